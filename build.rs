@@ -4,4 +4,12 @@ fn main() {
         std::env::var("CARGO_PKG_VERSION_OVERRIDE").unwrap_or(String::from("0.0.0"))
     );
     println!("cargo:rerun-if-env-changed=CARGO_PKG_VERSION_OVERRIDE");
+    println!(
+        "cargo:rustc-env=BUILD_TARGET={}",
+        std::env::var("TARGET").unwrap_or_default()
+    );
+    println!(
+        "cargo:rustc-env=BUILD_PROFILE={}",
+        std::env::var("PROFILE").unwrap_or_default()
+    );
 }
@@ -0,0 +1,175 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Retry of long running operations with jittered exponential backoff.
+
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Delay before the first retry.
+const INITIAL_DELAY: Duration = Duration::from_millis(500);
+/// Upper bound of the delay between retries.
+const MAX_DELAY: Duration = Duration::from_secs(60);
+/// Share of each delay that is randomized.
+const JITTER: f64 = 0.5;
+
+/// Tuning of all watches. See [configure].
+static SETTINGS: OnceLock<WatchSettings> = OnceLock::new();
+
+/// Tuning of the retries and listings of all watches.
+#[derive(Debug, Clone)]
+pub struct WatchSettings {
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between retries.
+    pub max_backoff: Duration,
+    /// Share (0.0-1.0) of each delay that is randomized.
+    pub jitter: f64,
+    /// Maximum number of objects in each page when listing or `None` for the client default.
+    pub page_size: Option<u32>,
+    /// `true` if the API server should send bookmarks to make restarted watches cheaper.
+    pub bookmarks: bool,
+}
+
+impl Default for WatchSettings {
+    fn default() -> Self {
+        Self {
+            initial_backoff: INITIAL_DELAY,
+            max_backoff: MAX_DELAY,
+            jitter: JITTER,
+            page_size: None,
+            bookmarks: true,
+        }
+    }
+}
+
+/**
+Apply the `settings` to all watches and retries started after this call.
+
+Only the first call has any effect, since the settings are process wide.
+ */
+pub fn configure(settings: WatchSettings) {
+    if SETTINGS.set(settings).is_err() {
+        log::debug!("Watch settings are already configured.");
+    }
+}
+
+/// Return the process wide settings of all watches.
+pub fn settings() -> &'static WatchSettings {
+    SETTINGS.get_or_init(WatchSettings::default)
+}
+
+/**
+Exponential backoff with "equal jitter".
+
+Each delay is randomly chosen between the current delay reduced by the jitter
+share (half by default) and the full current delay, which is doubled for
+every attempt until the maximum is reached.
+ */
+pub struct ExponentialBackoff {
+    /// Delay before the first retry.
+    initial: Duration,
+    /// Upper bound of the delay between retries.
+    max: Duration,
+    /// Share (0.0-1.0) of each delay that is randomized.
+    jitter: f64,
+    /// Delay before the next retry (before jitter is applied).
+    current: Duration,
+}
+
+impl Default for ExponentialBackoff {
+    fn default() -> Self {
+        let settings = settings();
+        Self::new(settings.initial_backoff, settings.max_backoff).with_jitter(settings.jitter)
+    }
+}
+
+impl ExponentialBackoff {
+    /// Return a new instance.
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            jitter: JITTER,
+            current: initial,
+        }
+    }
+
+    /// Return this instance with the share (0.0-1.0) of each delay that is randomized.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Return the jittered delay before the next attempt and increase the delay.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = std::cmp::min(self.current.saturating_mul(2), self.max);
+        delay.mul_f64(1.0 - self.jitter) + delay.mul_f64(Self::random_fraction() * self.jitter)
+    }
+
+    /// Start over from the initial delay.
+    pub fn reset(&mut self) {
+        self.current = self.initial;
+    }
+
+    /// Return the upper bound of the delay between retries.
+    pub fn max_delay(&self) -> Duration {
+        self.max
+    }
+
+    /// Return a pseudo random value in the range `[0.0, 1.0)`.
+    fn random_fraction() -> f64 {
+        // Each RandomState is seeded differently, which is good enough for jitter
+        let random = RandomState::new().build_hasher().finish();
+        (random % 1_000_000) as f64 / 1_000_000.0
+    }
+}
+
+/**
+Run the `operation` until it completes successfully and retry with jittered
+exponential backoff whenever it fails or panics.
+
+The backoff is reset if the `operation` was running for longer than the
+maximum delay before it failed, since the failure was then most likely
+unrelated to the previous one.
+ */
+pub async fn retry_with_backoff<F, Fut, E>(description: &str, mut operation: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+    E: std::fmt::Debug,
+{
+    let mut backoff = ExponentialBackoff::default();
+    loop {
+        let started = Instant::now();
+        let failure = match crate::supervisor::catch_panic(operation()).await {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => format!("error: {e:?}"),
+            Err(message) => format!("panic: {message}"),
+        };
+        if started.elapsed() > backoff.max_delay() {
+            backoff.reset();
+        }
+        let delay = backoff.next_delay();
+        log::warn!("Restarting {description} in {delay:?} due to {failure}");
+        tokio::time::sleep(delay).await;
+    }
+}
@@ -0,0 +1,195 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+//! Typed async client for the REST API.
+//!
+//! Requires the `client` feature.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), reqwest::Error> {
+//! let client = microfefind::client::Client::new("http://microfefind:8080").with_api_key("s3cr3t");
+//! for entry in client.list().await? {
+//!     println!("{} -> {:?}", entry.url, entry.annotations);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use futures::Stream;
+use std::time::Duration;
+
+use crate::ingress_monitor::ResourceError;
+use crate::notifier::ChangeFeedPage;
+use crate::rest_api::CHANGE_CURSOR_HEADER;
+
+pub use crate::rest_api::model::HostResponse;
+pub use crate::rest_api::model::IngressHostPathResponse;
+pub use crate::rest_api::model::VariantResponse;
+
+/// Client of a `microfefind` REST API.
+#[derive(Clone, Debug)]
+pub struct Client {
+    /// Underlying HTTP client.
+    http_client: reqwest::Client,
+    /// Base URL of the API server without trailing slash, like `http://microfefind:8080`.
+    base_url: String,
+    /// API key of the tenant sent with each request, if any.
+    api_key: Option<String>,
+}
+
+impl Client {
+    /// Return a new instance using a default HTTP client.
+    pub fn new(base_url: &str) -> Self {
+        Self::with_http_client(base_url, reqwest::Client::new())
+    }
+
+    /// Return a new instance using a pre-configured HTTP client (e.g. with timeouts or TLS trust).
+    pub fn with_http_client(base_url: &str, http_client: reqwest::Client) -> Self {
+        Self {
+            http_client,
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            api_key: None,
+        }
+    }
+
+    /// Return the instance authenticating each request with the API key of a tenant.
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_owned());
+        self
+    }
+
+    /// Return all currently known entries.
+    pub async fn list(&self) -> Result<Vec<IngressHostPathResponse>, reqwest::Error> {
+        Ok(self.list_with_cursor().await?.0)
+    }
+
+    /// Return all currently known entries and the cursor of the latest change.
+    async fn list_with_cursor(
+        &self,
+    ) -> Result<(Vec<IngressHostPathResponse>, u64), reqwest::Error> {
+        let response = self.get_response("/api/v1/all", &[]).await?;
+        let cursor = response
+            .headers()
+            .get(CHANGE_CURSOR_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default();
+        Ok((response.json().await?, cursor))
+    }
+
+    /// Return all currently known entries grouped by hostname.
+    pub async fn list_hosts(&self) -> Result<Vec<HostResponse>, reqwest::Error> {
+        self.get_json("/api/v1/hosts", &[]).await
+    }
+
+    /**
+      Return the entry of the combined hostname and path (like
+      `shop.example.com/checkout`) in the cluster or `None` if unknown.
+    */
+    pub async fn get(
+        &self,
+        cluster: &str,
+        host_path: &str,
+    ) -> Result<Option<IngressHostPathResponse>, reqwest::Error> {
+        let path = format!("/api/v1/entry/{cluster}/{host_path}");
+        match self.get_response(&path, &[]).await {
+            Ok(response) => Ok(Some(response.json().await?)),
+            Err(e) if e.status() == Some(reqwest::StatusCode::NOT_FOUND) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Return non-fatal problems of watched resources, optionally limited to a namespace.
+    pub async fn errors(
+        &self,
+        namespace: Option<&str>,
+    ) -> Result<Vec<ResourceError>, reqwest::Error> {
+        let query = namespace
+            .map(|namespace| vec![("namespace", namespace)])
+            .unwrap_or_default();
+        self.get_json("/api/v1/errors", &query).await
+    }
+
+    /**
+      Return a stream of all entries that yields the current entries first and
+      then every time they change.
+
+      The change feed of the API is polled with the `interval` and the entries
+      are only loaded again when changes were published or missed. Errors are
+      yielded without ending the stream, so the caller decides when to give up.
+    */
+    pub fn watch(
+        &self,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<Vec<IngressHostPathResponse>, reqwest::Error>> {
+        let client = self.clone();
+        futures::stream::unfold(
+            (client, None::<u64>, true),
+            move |(client, mut cursor, mut first)| async move {
+                loop {
+                    if !first {
+                        tokio::time::sleep(interval).await;
+                    }
+                    first = false;
+                    if let Some(since) = cursor {
+                        let since = since.to_string();
+                        match client
+                            .get_json::<ChangeFeedPage>("/api/v1/changes", &[("cursor", &since)])
+                            .await
+                        {
+                            Ok(page) if !page.reset && page.events.is_empty() => {
+                                cursor = Some(page.cursor);
+                                continue;
+                            }
+                            Ok(_) => {}
+                            Err(e) => return Some((Err(e), (client, cursor, false))),
+                        }
+                    }
+                    // Read the cursor with the entries, so no change after them is skipped
+                    return match client.list_with_cursor().await {
+                        Ok((entries, latest)) => Some((Ok(entries), (client, Some(latest), false))),
+                        Err(e) => Some((Err(e), (client, cursor, false))),
+                    };
+                }
+            },
+        )
+    }
+
+    /// Send a `GET` request and return the response if it has a success status.
+    async fn get_response(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let mut request = self
+            .http_client
+            .get(self.base_url.to_owned() + path)
+            .query(query);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        request.send().await?.error_for_status()
+    }
+
+    /// Send a `GET` request and parse the JSON response body.
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T, reqwest::Error> {
+        self.get_response(path, query).await?.json::<T>().await
+    }
+}
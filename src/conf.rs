@@ -17,23 +17,79 @@
 
 //! Parsing of application configuration.
 
+mod admission_config;
 mod api_config;
+mod catalogue_config;
+mod cluster_config;
+mod debug_config;
+mod federation_config;
 mod filter_config;
+mod health_config;
+mod kube_config;
 mod limits_config;
+mod notifier_config;
+mod openapi_config;
+mod operator_config;
+mod plugin_config;
+mod probe_config;
+mod secret_files;
+mod source_config;
+mod validation;
+mod watcher_config;
 
 use config::builder::BuilderState;
 use config::{Config, ConfigBuilder, Environment, File};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 
-use self::api_config::ApiConfig;
-use self::filter_config::IngressFilterConfig;
-use self::limits_config::ResourceLimitsConfig;
+pub use self::admission_config::AdmissionConfig;
+pub use self::api_config::ApiConfig;
+pub use self::catalogue_config::CatalogueConfig;
+pub use self::catalogue_config::ConflictPolicy;
+pub use self::cluster_config::AdditionalCluster;
+pub use self::cluster_config::ClusterConfig;
+pub use self::debug_config::DebugConfig;
+pub use self::federation_config::FederationConfig;
+pub use self::filter_config::IngressFilterConfig;
+pub use self::filter_config::NamespaceSelector;
+pub use self::health_config::HealthConfig;
+pub use self::health_config::ReadinessPolicy;
+pub use self::kube_config::KubeClientConfig;
+pub use self::limits_config::ResourceLimitsConfig;
+pub use self::notifier_config::NotifierConfig;
+pub use self::openapi_config::OpenApiConfig;
+pub use self::operator_config::OperatorConfig;
+pub use self::plugin_config::PluginConfig;
+pub use self::probe_config::ProbeConfig;
+pub use self::source_config::SourceConfig;
+pub use self::validation::ConfigError;
+pub use self::validation::ConfigProblem;
+pub use self::watcher_config::WatcherConfig;
+
+use self::secret_files::SecretFiles;
+use self::validation::ConfigProblems;
 
 /// Package name reported by Cargo at build time.
 const CARGO_PKG_NAME: &str = env!("CARGO_PKG_NAME");
 /// Package version reported by Cargo at build time.
 const CARGO_PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/**
+Key paths of the values that take effect when the configuration is reloaded.
+A key path ending with `.` covers the whole section.
+
+Everything else determines what is watched or how the server is bound and
+only takes effect at the next restart.
+ */
+pub const RELOADABLE_KEYS: &[&str] = &[
+    "api.tenants",
+    "ingress.annotationallow",
+    "ingress.annotationdeny",
+    "ingress.hostallow",
+    "ingress.hostdeny",
+    "plugin.",
+];
+
 /// Static trait for tracking implementations.
 trait AppConfigDefaults {
     fn set_defaults<T: BuilderState>(
@@ -42,6 +98,12 @@ trait AppConfigDefaults {
     ) -> ConfigBuilder<T>;
 }
 
+/// Static trait for validating each part of the loaded configuration.
+trait AppConfigValidation {
+    /// Report all invalid values of this part of the configuration.
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems);
+}
+
 /**
 Application configration root.
 
@@ -53,19 +115,53 @@ Configuration will be loaded from
 1. the file `{application name}.json` in the current working directory.
 2. environment variable overrides in the form
     `{APPLICATION_NAME}_MODULE_CONFIGKEYWITHOUTSPACES`
+3. files referenced by environment variables in the form
+    `{APPLICATION_NAME}_MODULE_CONFIGKEYWITHOUTSPACES_FILE`
+4. explicit overrides, e.g. from command line arguments.
  */
 #[derive(Debug, Deserialize, Serialize)]
 pub struct AppConfig {
+    /// Admission webhooks for labeled `Ingress`es.
+    pub admission: AdmissionConfig,
     /// Configuration of the exposed REST API.
     pub api: ApiConfig,
+    /// Exposed catalogue of entries.
+    pub catalogue: CatalogueConfig,
+    /// Kubernetes clusters to monitor.
+    pub clusters: ClusterConfig,
+    /// Debugging facilities.
+    pub debug: DebugConfig,
+    /// Federation with upstream `microfefind` instances.
+    pub federation: FederationConfig,
+    /// Health checks.
+    pub health: HealthConfig,
     /// Ingress detection and annotation filtering configuration.
     pub ingress: IngressFilterConfig,
+    /// Clients for Kubernetes API servers.
+    pub kube: KubeClientConfig,
     /// Resource detection and configuration overrides.
     pub limits: ResourceLimitsConfig,
+    /// Publishing of catalogue changes.
+    pub notifier: NotifierConfig,
+    /// Server and contact information of the OpenAPI document.
+    pub openapi: OpenApiConfig,
+    /// `MicroFrontend` custom resource operator configuration.
+    pub operator: OperatorConfig,
+    /// WebAssembly plugin transforming entries.
+    pub plugin: PluginConfig,
+    /// Reachability probing of entries.
+    pub probe: ProbeConfig,
+    /// Source of monitored resources.
+    pub source: SourceConfig,
+    /// Retries and listings of watches.
+    pub watcher: WatcherConfig,
 
     /// Lower case application name. Ignored when loading configuration.
     #[serde(skip_deserializing)]
     app_name: String,
+    /// Key paths of values that must not be exposed. Ignored when loading configuration.
+    #[serde(skip)]
+    redacted_keys: Vec<String>,
 }
 
 impl Default for AppConfig {
@@ -105,40 +201,177 @@ impl AppConfig {
     /**
        Creates a new instance pre-populated with defaults, an optional
        configrations file and environment variable overrides.
+
+       Panics with a description of all problems if the configuration is
+       invalid. Use [Self::try_new] to handle this gracefully.
     */
     pub fn new() -> Self {
+        Self::try_new().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /**
+       Creates a new instance pre-populated with defaults, an optional
+       configrations file and environment variable overrides.
+
+       All invalid values are reported together with their key path.
+    */
+    pub fn try_new() -> Result<Self, ConfigError> {
+        Self::try_with_overrides(None, &[])
+    }
+
+    /**
+       Like [Self::try_new], but loads the required `config_file` (if present)
+       instead of the optional default file and applies the `overrides` as
+       pairs of key path and value on top of all other sources.
+    */
+    pub fn try_with_overrides(
+        config_file: Option<&Path>,
+        overrides: &[(String, String)],
+    ) -> Result<Self, ConfigError> {
         let app_name = Self::read_app_name_lowercase();
         let config_filename = app_name.to_owned() + ".json";
         let config_env_prefix = &app_name.to_uppercase();
         let mut config_builder = Config::builder();
+        config_builder = AdmissionConfig::set_defaults(config_builder, "admission");
         config_builder = ApiConfig::set_defaults(config_builder, "api");
-        config_builder = IngressFilterConfig::set_defaults(config_builder, "ingressfilter");
+        config_builder = CatalogueConfig::set_defaults(config_builder, "catalogue");
+        config_builder = ClusterConfig::set_defaults(config_builder, "clusters");
+        config_builder = DebugConfig::set_defaults(config_builder, "debug");
+        config_builder = FederationConfig::set_defaults(config_builder, "federation");
+        config_builder = HealthConfig::set_defaults(config_builder, "health");
+        config_builder = IngressFilterConfig::set_defaults(config_builder, "ingress");
+        config_builder = KubeClientConfig::set_defaults(config_builder, "kube");
         config_builder = ResourceLimitsConfig::set_defaults(config_builder, "limits");
-        let conf_file = std::env::current_dir().unwrap().join(config_filename);
+        config_builder = NotifierConfig::set_defaults(config_builder, "notifier");
+        config_builder = OpenApiConfig::set_defaults(config_builder, "openapi");
+        config_builder = OperatorConfig::set_defaults(config_builder, "operator");
+        config_builder = PluginConfig::set_defaults(config_builder, "plugin");
+        config_builder = ProbeConfig::set_defaults(config_builder, "probe");
+        config_builder = SourceConfig::set_defaults(config_builder, "source");
+        config_builder = WatcherConfig::set_defaults(config_builder, "watcher");
+        let conf_file = config_file.map(Path::to_path_buf).unwrap_or_else(|| {
+            std::env::current_dir()
+                .unwrap_or_default()
+                .join(config_filename)
+        });
         if log::log_enabled!(log::Level::Debug) {
             log::debug!(
                 "Will load '{}' configuration if present.",
                 conf_file.display()
             );
         }
-        let config = config_builder
-            .add_source(File::with_name(conf_file.as_os_str().to_str().unwrap()).required(false))
+        let mut problems = ConfigProblems::default();
+        let secret_files = SecretFiles::load(config_env_prefix, &mut problems);
+        config_builder = config_builder
+            .add_source(
+                File::with_name(&conf_file.to_string_lossy()).required(config_file.is_some()),
+            )
             .add_source(
                 Environment::with_prefix(config_env_prefix)
+                    .source(Some(SecretFiles::plain_env_vars()))
                     //.try_parsing(true)
                     .separator("_")
                     .list_separator(","),
-            )
-            .build()
-            .unwrap();
-        let mut app_config: AppConfig = config.try_deserialize().unwrap();
+            );
+        for (key, value) in secret_files.values().iter().chain(overrides) {
+            match config_builder.clone().set_override(key, value.as_str()) {
+                Ok(overridden) => config_builder = overridden,
+                Err(e) => problems.add(key, "", e.to_string()),
+            }
+        }
+        let mut app_config: AppConfig =
+            match config_builder.build().and_then(Config::try_deserialize) {
+                Ok(app_config) => app_config,
+                Err(e) => {
+                    problems.add(&Self::config_error_key(&e), "", e.to_string());
+                    return Err(problems.into_error(config_env_prefix));
+                }
+            };
         app_config.app_name = app_name;
+        app_config.redacted_keys = secret_files.keys();
+        app_config.validate(&mut problems);
+        problems.into_result(config_env_prefix)?;
         if log::log_enabled!(log::Level::Debug) {
-            log::info!(
-                "Running with configuration: {}",
-                serde_json::to_string(&app_config).unwrap()
-            );
+            log::info!("Running with configuration: {}", app_config.to_json());
+        }
+        Ok(app_config)
+    }
+
+    /// Return the configuration as JSON with secrets and values loaded from files redacted.
+    pub fn to_json(&self) -> String {
+        let mut config_json = serde_json::to_value(self).unwrap();
+        secret_files::redact(&mut config_json, &self.redacted_keys);
+        config_json.to_string()
+    }
+
+    /// Return the key paths of all values that differ from the `other` configuration.
+    pub fn changed_keys(&self, other: &AppConfig) -> Vec<String> {
+        Self::changed_keys_of(
+            &serde_json::to_value(self).unwrap_or_default(),
+            &serde_json::to_value(other).unwrap_or_default(),
+            "",
+        )
+    }
+
+    /// Return `true` if changes of the value at the `key` path take effect when reloaded.
+    pub fn is_reloadable(key: &str) -> bool {
+        RELOADABLE_KEYS.iter().any(|reloadable| {
+            key == *reloadable || (reloadable.ends_with('.') && key.starts_with(reloadable))
+        })
+    }
+
+    /// Return the key paths of all values that differ between `old` and `new`.
+    fn changed_keys_of(
+        old: &serde_json::Value,
+        new: &serde_json::Value,
+        prefix: &str,
+    ) -> Vec<String> {
+        match (old, new) {
+            (serde_json::Value::Object(old), serde_json::Value::Object(new)) => {
+                let mut keys = old.keys().chain(new.keys()).collect::<Vec<_>>();
+                keys.sort();
+                keys.dedup();
+                keys.into_iter()
+                    .flat_map(|key| {
+                        Self::changed_keys_of(
+                            old.get(key).unwrap_or(&serde_json::Value::Null),
+                            new.get(key).unwrap_or(&serde_json::Value::Null),
+                            &(prefix.to_owned() + if prefix.is_empty() { "" } else { "." } + key),
+                        )
+                    })
+                    .collect()
+            }
+            (old, new) if old != new => vec![prefix.to_owned()],
+            _ => vec![],
         }
-        app_config
+    }
+
+    /// Return the key path of a loading or deserialization error if known.
+    fn config_error_key(e: &config::ConfigError) -> String {
+        match e {
+            config::ConfigError::Type { key: Some(key), .. } => key.to_owned(),
+            config::ConfigError::NotFound(key) => key.to_owned(),
+            _ => String::new(),
+        }
+    }
+
+    /// Validate all parts of the configuration.
+    fn validate(&self, problems: &mut ConfigProblems) {
+        self.admission.validate("admission", problems);
+        self.api.validate("api", problems);
+        self.catalogue.validate("catalogue", problems);
+        self.clusters.validate("clusters", problems);
+        self.debug.validate("debug", problems);
+        self.federation.validate("federation", problems);
+        self.health.validate("health", problems);
+        self.ingress.validate("ingress", problems);
+        self.kube.validate("kube", problems);
+        self.limits.validate("limits", problems);
+        self.notifier.validate("notifier", problems);
+        self.openapi.validate("openapi", problems);
+        self.plugin.validate("plugin", problems);
+        self.probe.validate("probe", problems);
+        self.source.validate("source", problems);
+        self.watcher.validate("watcher", problems);
     }
 }
@@ -0,0 +1,158 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Parsing of configuration for the admission webhook server.
+
+use config::builder::BuilderState;
+use config::ConfigBuilder;
+use serde::{Deserialize, Serialize};
+
+use super::validation::ConfigProblems;
+use super::AppConfigDefaults;
+use super::AppConfigValidation;
+
+/// Configuration of admission webhooks for labeled `Ingress`es served over HTTPS.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AdmissionConfig {
+    /// Serve admission webhooks.
+    enabled: bool,
+    /// IP port to serve admission webhooks on over HTTPS.
+    port: u16,
+    /// Path of the PEM encoded certificate chain.
+    tlscert: String,
+    /// Path of the PEM encoded private key.
+    tlskey: String,
+    /// Comma separated list of annotation keys (without prefix) that labeled `Ingress`es must have.
+    requiredkeys: String,
+    /// Comma separated list of `key=value` default annotations (without prefix) to inject.
+    defaults: String,
+}
+
+impl AppConfigDefaults for AdmissionConfig {
+    /// Provide defaults for this part of the configuration
+    fn set_defaults<T: BuilderState>(
+        config_builder: ConfigBuilder<T>,
+        prefix: &str,
+    ) -> ConfigBuilder<T> {
+        config_builder
+            .set_default(prefix.to_string() + "." + "enabled", "false")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "port", "8443")
+            .unwrap()
+            .set_default(
+                prefix.to_string() + "." + "tlscert",
+                "/etc/microfefind/tls/tls.crt",
+            )
+            .unwrap()
+            .set_default(
+                prefix.to_string() + "." + "tlskey",
+                "/etc/microfefind/tls/tls.key",
+            )
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "requiredkeys", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "defaults", "")
+            .unwrap()
+    }
+}
+
+impl AppConfigValidation for AdmissionConfig {
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems) {
+        if !self.enabled {
+            return;
+        }
+        if self.port == 0 {
+            problems.add(prefix, "port", "Must be in the range 1-65535.".to_string());
+        }
+        if self.tlscert.trim().is_empty() {
+            problems.add(prefix, "tlscert", "Must not be empty.".to_string());
+        }
+        if self.tlskey.trim().is_empty() {
+            problems.add(prefix, "tlskey", "Must not be empty.".to_string());
+        }
+        for entry in self.default_entries() {
+            if !entry
+                .split_once('=')
+                .is_some_and(|(key, _)| !key.trim().is_empty())
+            {
+                problems.add(
+                    prefix,
+                    "defaults",
+                    format!("'{entry}' must be formatted as 'key=value'."),
+                );
+            }
+        }
+    }
+}
+
+impl AdmissionConfig {
+    /**
+      Return `true` if admission webhooks should be served over HTTPS on the
+      admission [port](Self::port). Defaults to `false`.
+
+      The Kubernetes API server only calls webhooks over HTTPS.
+    */
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// IP port to serve admission webhooks on. Defaults to `8443`.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Path of the PEM encoded certificate chain, e.g. mounted from a `kubernetes.io/tls` `Secret`.
+    pub fn tls_cert_path(&self) -> &str {
+        self.tlscert.trim()
+    }
+
+    /// Path of the PEM encoded private key, e.g. mounted from a `kubernetes.io/tls` `Secret`.
+    pub fn tls_key_path(&self) -> &str {
+        self.tlskey.trim()
+    }
+
+    /// Annotation keys (without prefix) that labeled `Ingress`es must have to be admitted.
+    pub fn required_keys(&self) -> Vec<&str> {
+        self.requiredkeys
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .collect()
+    }
+
+    /**
+      Annotation keys (without prefix) and value templates that are injected
+      into labeled `Ingress`es that don't already have them.
+
+      `{namespace}` and `{name}` in values are replaced with the namespace and
+      name of the `Ingress`, e.g. `team={namespace}` or `module=@{namespace}/{name}`.
+    */
+    pub fn defaults(&self) -> Vec<(&str, &str)> {
+        self.default_entries()
+            .filter_map(|entry| entry.split_once('='))
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .collect()
+    }
+
+    /// Return the non-empty entries of the comma separated list of defaults.
+    fn default_entries(&self) -> impl Iterator<Item = &str> {
+        self.defaults
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+    }
+}
@@ -20,16 +20,59 @@
 use config::builder::BuilderState;
 use config::ConfigBuilder;
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
+use crate::rest_api::Tenants;
+
+use super::validation::ConfigProblems;
 use super::AppConfigDefaults;
+use super::AppConfigValidation;
 
 /// Configuration for the application's exposed REST API.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ApiConfig {
-    /// IP address to bind to.
+    /// Comma separated list of IP addresses to bind to, each optionally with a port.
     address: String,
     /// IP port to bind to.
     port: u16,
+    /// Enable administrative resources.
+    admin: bool,
+    /// Enable reverse-proxying of asset requests to the `Service` of each entry.
+    proxy: bool,
+    /// Milliseconds to wait for a proxied `Service` to respond.
+    proxytimeout: u64,
+    /// Semicolon separated list of `endpoint=directives` `Cache-Control` headers.
+    cachecontrol: String,
+    /// Path of a file mapping API keys to the namespaces and groups they may see.
+    tenants: String,
+    /// Seconds to keep idle connections open. `0` disables keep-alive.
+    keepalive: u64,
+    /// Milliseconds to wait for the request headers. `0` disables the timeout.
+    requesttimeout: u64,
+    /// Maximum size of a request body in bytes.
+    maxpayload: usize,
+    /// Maximum number of pending connections. `0` to derive from the number of workers.
+    backlog: u32,
+    /// Number of HTTP worker threads. `0` to use the available parallelism.
+    workers: usize,
+    /// Number of HTTP worker threads per assigned CPU core when `workers` is `0`.
+    workerspercpu: f64,
+    /// Number of parallel requests that can be served for each assigned CPU core.
+    workerspercore: usize,
+    /// Number of in-flight API requests above which new ones are rejected. `0` disables the limit.
+    maxinflight: usize,
+    /// Milliseconds of event loop lag above which API requests are rejected. `0` disables the limit.
+    maxlag: u64,
+    /// UDP port to serve the API on over HTTP/3. `0` disables HTTP/3.
+    http3port: u16,
+    /// Path to the PEM encoded certificate chain of the HTTP/3 listener.
+    tlscert: String,
+    /// Path to the PEM encoded private key of the HTTP/3 listener.
+    tlskey: String,
+    /// Tenants loaded from [Self::tenants_path] during validation.
+    #[serde(skip)]
+    loaded_tenants: OnceLock<Arc<Tenants>>,
 }
 
 impl AppConfigDefaults for ApiConfig {
@@ -43,17 +86,350 @@ impl AppConfigDefaults for ApiConfig {
             .unwrap()
             .set_default(prefix.to_string() + "." + "port", "8083")
             .unwrap()
+            .set_default(prefix.to_string() + "." + "admin", "false")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "proxy", "false")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "proxytimeout", "30000")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "cachecontrol", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "tenants", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "keepalive", "5")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "requesttimeout", "5000")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "maxpayload", "2097152")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "backlog", "0")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "workers", "0")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "workerspercpu", "1.0")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "workerspercore", "256")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "maxinflight", "0")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "maxlag", "0")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "http3port", "0")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "tlscert", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "tlskey", "")
+            .unwrap()
+    }
+}
+
+impl AppConfigValidation for ApiConfig {
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems) {
+        let entries = self
+            .address
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .collect::<Vec<_>>();
+        if entries.is_empty() {
+            problems.add(prefix, "address", "Must not be empty.".to_string());
+        }
+        for entry in entries {
+            if Self::parse_bind_address(entry, self.port).is_none() {
+                problems.add(
+                    prefix,
+                    "address",
+                    format!("'{entry}' is not formatted like 'address', 'address:port' or '[ipv6]:port'."),
+                );
+            }
+        }
+        if self.port == 0 {
+            problems.add(prefix, "port", "Must be in the range 1-65535.".to_string());
+        }
+        if self.http3port > 0 {
+            if !cfg!(feature = "http3") {
+                problems.add(
+                    prefix,
+                    "http3port",
+                    "Requires a build with the 'http3' feature.".to_string(),
+                );
+            }
+            if self.tlscert.trim().is_empty() {
+                problems.add(prefix, "tlscert", "Required by HTTP/3.".to_string());
+            }
+            if self.tlskey.trim().is_empty() {
+                problems.add(prefix, "tlskey", "Required by HTTP/3.".to_string());
+            }
+        }
+        if let Some(path) = self.tenants_path() {
+            match Tenants::load(path) {
+                Ok(tenants) => {
+                    let _ = self.loaded_tenants.set(Arc::new(tenants));
+                }
+                Err(e) => problems.add(prefix, "tenants", e),
+            }
+        }
+        if self.admin && self.tenants_path().is_none() {
+            problems.add(
+                prefix,
+                "admin",
+                "Requires API keys configured with 'tenants'.".to_string(),
+            );
+        }
+        if self.proxy && !cfg!(feature = "proxy") {
+            problems.add(
+                prefix,
+                "proxy",
+                "Requires a build with the 'proxy' feature.".to_string(),
+            );
+        }
+        for entry in self.cache_control_entries() {
+            match entry.split_once('=') {
+                Some((endpoint, directives))
+                    if endpoint.trim().starts_with('/') && !directives.trim().is_empty() => {}
+                _ => problems.add(
+                    prefix,
+                    "cachecontrol",
+                    format!("'{entry}' must be formatted as 'endpoint=directives', e.g. '/all=max-age=10'."),
+                ),
+            }
+        }
+        if self.maxpayload == 0 {
+            problems.add(
+                prefix,
+                "maxpayload",
+                "Must be a positive number of bytes.".to_string(),
+            );
+        }
+        if self.workerspercpu.is_nan() || self.workerspercpu <= 0.0 {
+            problems.add(
+                prefix,
+                "workerspercpu",
+                format!("{} is not a positive number.", self.workerspercpu),
+            );
+        }
+        if self.workerspercore == 0 {
+            problems.add(
+                prefix,
+                "workerspercore",
+                "Must be a positive number.".to_string(),
+            );
+        }
     }
 }
 
 impl ApiConfig {
-    /// IP address to bind to. Defaults to the IPv4 address `0.0.0.0`.
-    pub fn bind_address(&self) -> String {
-        self.address.clone()
+    /**
+      Addresses and ports to bind to. Defaults to the IPv4 address `0.0.0.0`.
+
+      Addresses without a port are bound to [Self::bind_port], so
+      `0.0.0.0,::1` serves on both on the same port.
+    */
+    pub fn bind_addresses(&self) -> Vec<(String, u16)> {
+        self.address
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .filter_map(|entry| Self::parse_bind_address(entry, self.port))
+            .collect()
     }
 
-    /// IP port to bind to. Defaults to the unpriviliged port `8083`.
+    /// IP port to bind addresses without an explicit port to. Defaults to the unpriviliged port `8083`.
     pub fn bind_port(&self) -> u16 {
         self.port
     }
+
+    /**
+      Return the address and port of an `entry` like `0.0.0.0`, `::`,
+      `0.0.0.0:8083` or `[::]:8083`, using the `default_port` when there is
+      none.
+    */
+    fn parse_bind_address(entry: &str, default_port: u16) -> Option<(String, u16)> {
+        let (address, port) = if let Some(bracketed) = entry.strip_prefix('[') {
+            let (address, rest) = bracketed.split_once(']')?;
+            match rest {
+                "" => (address, None),
+                rest => (address, Some(rest.strip_prefix(':')?)),
+            }
+        } else if entry.matches(':').count() == 1 {
+            let (address, port) = entry.split_once(':')?;
+            (address, Some(port))
+        } else {
+            // A bare IPv6 address or an address without port
+            (entry, None)
+        };
+        let port = match port {
+            Some(port) => port.parse::<u16>().ok().filter(|port| *port != 0)?,
+            None => default_port,
+        };
+        Some((address.to_owned(), port)).filter(|(address, _)| !address.is_empty())
+    }
+
+    /**
+    Return `true` if administrative resources like state export and import
+    should be available. Defaults to `false`.
+
+    The resources require API requests to be authenticated by an API key that
+    is allowed to see everything, see [Self::tenants_path].
+     */
+    pub fn admin_enabled(&self) -> bool {
+        self.admin
+    }
+
+    /**
+    Return `true` if asset requests to `/proxy/{host}/{path}` should be
+    forwarded to the `Service` of the matching entry. Defaults to `false`.
+     */
+    pub fn proxy_enabled(&self) -> bool {
+        self.proxy
+    }
+
+    /// Time to wait for a proxied `Service` to respond. Defaults to 30 seconds.
+    pub fn proxy_timeout(&self) -> Duration {
+        Duration::from_millis(std::cmp::max(self.proxytimeout, 1))
+    }
+
+    /**
+    Return the `Cache-Control` header value to add to successful responses of
+    the `endpoint` (relative to `/api/v1`, like `/all`) or `None` if no header
+    is configured.
+
+    Headers are configured as a semicolon separated list of
+    `endpoint=directives`, e.g.
+    `/all=public, max-age=10, stale-while-revalidate=60;/importmap=public, max-age=60`.
+     */
+    pub fn cache_control(&self, endpoint: &str) -> Option<&str> {
+        self.cache_control_entries().find_map(|entry| {
+            entry
+                .split_once('=')
+                .filter(|(configured, _)| configured.trim() == endpoint)
+                .map(|(_, directives)| directives.trim())
+        })
+    }
+
+    /// Return the non-empty entries of the `Cache-Control` configuration.
+    fn cache_control_entries(&self) -> impl Iterator<Item = &str> {
+        self.cachecontrol
+            .split(';')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+    }
+
+    /**
+    Return the path of the file that maps API keys to tenant scopes or `None`
+    if API requests are not authenticated. Defaults to `None`.
+
+    Each line of the file is an API key followed by whitespace and a comma
+    separated list of namespaces, `group:<name>` for entries with the `group`
+    (or `team`) annotation or `*` for everything. Empty lines and lines
+    starting with `#` are ignored.
+     */
+    pub fn tenants_path(&self) -> Option<&str> {
+        Some(self.tenants.trim()).filter(|path| !path.is_empty())
+    }
+
+    /**
+      Return the tenants loaded from [Self::tenants_path] or `None` if API
+      requests are not authenticated.
+
+      The file is loaded when the configuration is validated, so a missing or
+      invalid file is reported with the other configuration problems.
+    */
+    pub fn tenants(&self) -> Option<Arc<Tenants>> {
+        self.loaded_tenants.get().cloned()
+    }
+
+    /// Time to keep idle connections open or `None` to disable keep-alive. Defaults to 5 seconds.
+    pub fn keep_alive(&self) -> Option<Duration> {
+        Some(Duration::from_secs(self.keepalive)).filter(|duration| !duration.is_zero())
+    }
+
+    /// Time to wait for the request headers. Zero disables the timeout. Defaults to 5 seconds.
+    pub fn client_request_timeout(&self) -> Duration {
+        Duration::from_millis(self.requesttimeout)
+    }
+
+    /// Maximum size of a request body in bytes. Defaults to 2 MiB.
+    pub fn max_payload_bytes(&self) -> usize {
+        self.maxpayload
+    }
+
+    /// Maximum number of pending connections or `None` to derive from the number of workers.
+    pub fn backlog(&self) -> Option<u32> {
+        Some(self.backlog).filter(|backlog| *backlog > 0)
+    }
+
+    /// Number of HTTP worker threads or `None` to derive from the assigned CPU cores.
+    pub fn workers(&self) -> Option<usize> {
+        Some(self.workers).filter(|workers| *workers > 0)
+    }
+
+    /**
+      Return the number of HTTP worker threads for the (possibly fractional)
+      number of assigned CPU cores.
+
+      Unless configured explicitly, this is the assigned cores multiplied by
+      the workers per CPU (default `1.0`) rounded up, so a pod with a 0.5 CPU
+      quota gets a single worker and a pod with 1.5 CPUs gets two.
+    */
+    pub fn workers_for_cpus(&self, cpus: f64) -> usize {
+        self.workers()
+            .unwrap_or_else(|| std::cmp::max((cpus * self.workerspercpu).ceil() as usize, 1))
+    }
+
+    /// Number of parallel requests that can be served for each CPU core. Defaults to `256`.
+    pub fn workers_per_core(&self) -> usize {
+        self.workerspercore
+    }
+
+    /**
+      Return the total number of parallel requests that can be served with the
+      (possibly fractional) number of assigned CPU cores.
+
+      A pod with a 0.5 CPU quota serves half as many requests as one with a
+      full core, regardless of the number of workers. With an explicit number
+      of workers, each worker counts as a full core.
+    */
+    pub fn max_connections_for_cpus(&self, cpus: f64) -> usize {
+        let cores = match self.workers() {
+            Some(workers) => workers as f64,
+            None => cpus,
+        };
+        std::cmp::max((cores * self.workerspercore as f64).round() as usize, 1)
+    }
+
+    /**
+      Return the number of in-flight API requests above which new requests are
+      rejected with `503 Service Unavailable` or `None` if unlimited. Defaults
+      to `None`.
+    */
+    pub fn max_in_flight(&self) -> Option<usize> {
+        Some(self.maxinflight).filter(|max_in_flight| *max_in_flight > 0)
+    }
+
+    /**
+      Return the event loop lag above which API requests are rejected with
+      `503 Service Unavailable` or `None` if unlimited. Defaults to `None`.
+    */
+    pub fn max_lag(&self) -> Option<Duration> {
+        Some(Duration::from_millis(self.maxlag)).filter(|max_lag| !max_lag.is_zero())
+    }
+
+    /**
+      UDP port to serve the API on over HTTP/3 (advertised with `Alt-Svc`) or
+      `None` if HTTP/3 is disabled (the default).
+    */
+    pub fn http3_port(&self) -> Option<u16> {
+        Some(self.http3port).filter(|port| *port > 0)
+    }
+
+    /// Path to the PEM encoded certificate chain of the HTTP/3 listener.
+    pub fn tls_cert_path(&self) -> &str {
+        self.tlscert.trim()
+    }
+
+    /// Path to the PEM encoded private key of the HTTP/3 listener.
+    pub fn tls_key_path(&self) -> &str {
+        self.tlskey.trim()
+    }
 }
@@ -0,0 +1,288 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Parsing of configuration for the exposed catalogue of entries.
+
+use config::builder::BuilderState;
+use config::ConfigBuilder;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use crate::ingress_monitor::AnnotationSchema;
+
+use super::validation::ConfigProblems;
+use super::AppConfigDefaults;
+use super::AppConfigValidation;
+
+/// Configuration for the exposed catalogue of entries.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CatalogueConfig {
+    /// Seconds to keep deleted entries as tombstones.
+    tombstonettl: u64,
+    /// Path to a JSON Schema file that annotations must conform to.
+    annotationschema: String,
+    /// How to handle entries with annotations that fail validation.
+    invalidannotations: String,
+    /// Expose annotation values that are JSON objects or arrays as nested structures.
+    structuredannotations: bool,
+    /// How to resolve multiple resources declaring the same hostname + path.
+    conflictpolicy: String,
+    /// Seconds between revalidations of fetched asset manifests. `0` disables fetching.
+    manifestrefresh: u64,
+    /// Maximum size in bytes of a fetched asset manifest.
+    manifestmaxsize: u64,
+    /// Number of recent changes retained for the change feed.
+    changefeedsize: usize,
+    /// Name of a `ConfigMap` to publish the catalogue to. Empty to disable.
+    publishconfigmap: String,
+    /// Namespace of the published `ConfigMap`. Empty for the namespace of the local cluster context.
+    publishnamespace: String,
+    /// Maximum number of cataloged entries. `0` for unlimited.
+    maxentries: usize,
+    /// Maximum number of cataloged entries per namespace. `0` for unlimited.
+    maxentriespernamespace: usize,
+    /// Comma separated list of other resources to collect prefixed annotations from: `service` and/or `deployment`.
+    annotationsources: String,
+    /// Schema compiled from [Self::annotation_schema_path] during validation.
+    #[serde(skip)]
+    annotation_schema: OnceLock<Arc<AnnotationSchema>>,
+}
+
+/// How to resolve multiple resources declaring the same hostname + path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// The resource that was created first is cataloged.
+    FirstWins,
+    /// The resource that was created last is cataloged.
+    NewestWins,
+    /// None of the conflicting resources are cataloged.
+    RejectBoth,
+}
+
+impl AppConfigDefaults for CatalogueConfig {
+    /// Provide defaults for this part of the configuration
+    fn set_defaults<T: BuilderState>(
+        config_builder: ConfigBuilder<T>,
+        prefix: &str,
+    ) -> ConfigBuilder<T> {
+        config_builder
+            .set_default(prefix.to_string() + "." + "tombstonettl", "60")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "annotationschema", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "invalidannotations", "flag")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "structuredannotations", "false")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "conflictpolicy", "first-wins")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "manifestrefresh", "300")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "manifestmaxsize", "1048576")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "changefeedsize", "1000")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "publishconfigmap", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "publishnamespace", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "maxentries", "0")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "maxentriespernamespace", "0")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "annotationsources", "service")
+            .unwrap()
+    }
+}
+
+impl AppConfigValidation for CatalogueConfig {
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems) {
+        if let Some(path) = self.annotation_schema_path() {
+            match AnnotationSchema::load(path) {
+                Ok(annotation_schema) => {
+                    let _ = self.annotation_schema.set(Arc::new(annotation_schema));
+                }
+                Err(e) => problems.add(prefix, "annotationschema", e),
+            }
+        }
+        for annotation_source in self.annotation_sources() {
+            problems.check_one_of(
+                prefix,
+                "annotationsources",
+                &annotation_source,
+                &["service", "deployment"],
+            );
+        }
+        problems.check_one_of(
+            prefix,
+            "invalidannotations",
+            &self.invalidannotations,
+            &["flag", "reject"],
+        );
+        problems.check_one_of(
+            prefix,
+            "conflictpolicy",
+            &self.conflictpolicy,
+            &["first-wins", "newest-wins", "reject-both"],
+        );
+        if self.manifestmaxsize == 0 {
+            problems.add(
+                prefix,
+                "manifestmaxsize",
+                "Must be a positive number.".to_string(),
+            );
+        }
+        if let Some(namespace) = self.publish_namespace() {
+            problems.check_namespace_name(prefix, "publishnamespace", namespace);
+        }
+    }
+}
+
+impl CatalogueConfig {
+    /**
+      Return the number of seconds a deleted entry is still exposed, marked as
+      deleted, before it is removed from the catalogue. Defaults to `60`.
+
+      `0` removes deleted entries immediately.
+    */
+    pub fn tombstone_ttl_secs(&self) -> u64 {
+        self.tombstonettl
+    }
+
+    /**
+      Return the path of a JSON Schema file that the annotations of each entry
+      must conform to or `None` if annotations should not be validated.
+    */
+    pub fn annotation_schema_path(&self) -> Option<&str> {
+        Some(self.annotationschema.as_str()).filter(|path| !path.is_empty())
+    }
+
+    /**
+      Return the JSON Schema loaded from [Self::annotation_schema_path] or
+      `None` if annotations should not be validated.
+
+      The schema is loaded when the configuration is validated, so a missing
+      or invalid schema is reported with the other configuration problems.
+    */
+    pub fn annotation_schema(&self) -> Option<Arc<AnnotationSchema>> {
+        self.annotation_schema.get().cloned()
+    }
+
+    /**
+      Return `true` if entries with annotations that fail validation should be
+      omitted from the catalogue. Otherwise (the default `flag`) such entries
+      are exposed, but flagged as invalid.
+    */
+    pub fn reject_invalid_annotations(&self) -> bool {
+        self.invalidannotations.eq_ignore_ascii_case("reject")
+    }
+
+    /**
+      Return `true` if annotation values that parse as JSON objects or arrays
+      should be exposed as nested structures instead of strings. Defaults to
+      `false`.
+    */
+    pub fn structured_annotations(&self) -> bool {
+        self.structuredannotations
+    }
+
+    /**
+      Return how to resolve multiple resources declaring the same hostname +
+      path. One of `first-wins` (default), `newest-wins` or `reject-both`.
+      Unknown values are treated as `first-wins`.
+    */
+    pub fn conflict_policy(&self) -> ConflictPolicy {
+        match self.conflictpolicy.to_ascii_lowercase().as_str() {
+            "newest-wins" => ConflictPolicy::NewestWins,
+            "reject-both" => ConflictPolicy::RejectBoth,
+            _ => ConflictPolicy::FirstWins,
+        }
+    }
+
+    /**
+      Time between revalidations of the asset manifests referenced by entries
+      or `None` if manifests should not be fetched. Defaults to 5 minutes.
+    */
+    pub fn manifest_refresh(&self) -> Option<Duration> {
+        Some(self.manifestrefresh)
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// Maximum size of a fetched asset manifest. Defaults to 1 MiB.
+    pub fn manifest_max_size(&self) -> u64 {
+        self.manifestmaxsize
+    }
+
+    /// Number of recent changes retained for clients of the change feed. Defaults to `1000`.
+    pub fn change_feed_size(&self) -> usize {
+        self.changefeedsize
+    }
+
+    /**
+      Name of a `ConfigMap` that the catalogue and import map should be
+      written to on every change, or `None` to not publish the catalogue.
+
+      This serves workloads that can mount `ConfigMap`s but cannot make HTTP
+      calls, like static nginx shells.
+    */
+    pub fn publish_config_map(&self) -> Option<&str> {
+        Some(self.publishconfigmap.trim()).filter(|name| !name.is_empty())
+    }
+
+    /// Namespace of the published `ConfigMap` or `None` for the namespace of the local cluster context.
+    pub fn publish_namespace(&self) -> Option<&str> {
+        Some(self.publishnamespace.trim()).filter(|namespace| !namespace.is_empty())
+    }
+
+    /**
+      Maximum number of cataloged entries or `None` if unlimited (the default).
+
+      Entries declared when the limit is reached are not cataloged until other
+      entries are removed and are reported as resource errors.
+    */
+    pub fn max_entries(&self) -> Option<usize> {
+        Some(self.maxentries).filter(|max_entries| *max_entries > 0)
+    }
+
+    /**
+      Maximum number of cataloged entries in a single namespace or `None` if
+      unlimited (the default).
+
+      This protects the catalogue against a single tenant generating thousands
+      of paths.
+    */
+    pub fn max_entries_per_namespace(&self) -> Option<usize> {
+        Some(self.maxentriespernamespace).filter(|max_entries| *max_entries > 0)
+    }
+
+    /**
+      Return the other resources that prefixed annotations are collected from
+      besides the declaring resource. Defaults to `service`.
+
+      Backend teams often own the `Service` and `Deployment`, but not the
+      `Ingress`.
+    */
+    pub fn annotation_sources(&self) -> Vec<String> {
+        self.annotationsources
+            .split(',')
+            .map(|x| x.trim().to_lowercase())
+            .filter(|x| !x.is_empty())
+            .collect()
+    }
+}
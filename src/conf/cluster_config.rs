@@ -0,0 +1,150 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Parsing of configuration for monitoring of multiple Kubernetes clusters.
+
+use config::builder::BuilderState;
+use config::ConfigBuilder;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::validation::ConfigProblems;
+use super::AppConfigDefaults;
+use super::AppConfigValidation;
+
+/// Configuration of the Kubernetes clusters to monitor.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ClusterConfig {
+    /// Identifier of the cluster the app is running in.
+    id: String,
+    /// Comma separated list of `id=kubeconfig-path[#context]` for additional clusters.
+    additional: Option<String>,
+    /// Seconds to retry connecting to the local cluster at startup. `0` to retry forever.
+    startuptimeout: u64,
+}
+
+/// An additional Kubernetes cluster to monitor.
+#[derive(Clone, Debug)]
+pub struct AdditionalCluster {
+    /// Identifier of the cluster exposed to API clients.
+    pub id: String,
+    /// Path of a kubeconfig file (e.g. from a mounted `Secret`).
+    pub kubeconfig_path: String,
+    /// Context in the kubeconfig to use. Current context when `None`.
+    pub context: Option<String>,
+}
+
+impl AppConfigDefaults for ClusterConfig {
+    /// Provide defaults for this part of the configuration
+    fn set_defaults<T: BuilderState>(
+        config_builder: ConfigBuilder<T>,
+        prefix: &str,
+    ) -> ConfigBuilder<T> {
+        config_builder
+            .set_default(prefix.to_string() + "." + "id", "local")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "additional", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "startuptimeout", "300")
+            .unwrap()
+    }
+}
+
+impl AppConfigValidation for ClusterConfig {
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems) {
+        if self.id.trim().is_empty() {
+            problems.add(prefix, "id", "Must not be empty.".to_string());
+        }
+        let mut ids = vec![self.id.trim()];
+        for entry in self
+            .additional
+            .iter()
+            .flat_map(|additional| additional.split(','))
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            match entry.split_once('=') {
+                Some((id, location)) if !id.trim().is_empty() && !location.trim().is_empty() => {
+                    if ids.contains(&id.trim()) {
+                        problems.add(
+                            prefix,
+                            "additional",
+                            format!("Duplicate cluster identifier '{}'.", id.trim()),
+                        );
+                    }
+                    ids.push(id.trim());
+                }
+                _ => problems.add(
+                    prefix,
+                    "additional",
+                    format!("'{entry}' is not in the form 'id=kubeconfig-path[#context]'."),
+                ),
+            }
+        }
+    }
+}
+
+impl ClusterConfig {
+    /// Identifier of the cluster the app is running in. Defaults to `local`.
+    pub fn local_id(&self) -> &str {
+        &self.id
+    }
+
+    /**
+      How long to retry connecting to the local cluster at startup before
+      giving up. `None` to retry forever.
+    */
+    pub fn startup_timeout(&self) -> Option<Duration> {
+        Some(self.startuptimeout)
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+
+    /**
+      Additional clusters to monitor, parsed from a comma separated list of
+      `id=kubeconfig-path` or `id=kubeconfig-path#context`.
+
+      Malformed entries are logged and ignored.
+    */
+    pub fn additional(&self) -> Vec<AdditionalCluster> {
+        let Some(additional) = &self.additional else {
+            return vec![];
+        };
+        additional
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .filter_map(|entry| {
+                let Some((id, location)) = entry.split_once('=') else {
+                    log::warn!("Ignoring additional cluster '{entry}' without 'id='.");
+                    return None;
+                };
+                let (kubeconfig_path, context) = match location.split_once('#') {
+                    Some((kubeconfig_path, context)) => {
+                        (kubeconfig_path, Some(context.trim().to_owned()))
+                    }
+                    None => (location, None),
+                };
+                Some(AdditionalCluster {
+                    id: id.trim().to_owned(),
+                    kubeconfig_path: kubeconfig_path.trim().to_owned(),
+                    context,
+                })
+            })
+            .collect()
+    }
+}
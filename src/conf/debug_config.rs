@@ -0,0 +1,139 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Parsing of configuration for debugging facilities.
+
+use config::builder::BuilderState;
+use config::ConfigBuilder;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::validation::ConfigProblems;
+use super::AppConfigDefaults;
+use super::AppConfigValidation;
+
+/// Configuration of debugging facilities.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DebugConfig {
+    /// JSONL file to record all received watch events to. Empty to disable.
+    recordevents: String,
+    /// JSONL file of recorded watch events to replay instead of watching. Empty to disable.
+    replayevents: String,
+    /// Enable the profiling resources.
+    profiling: bool,
+    /// Serve instrumentation for `tokio-console`.
+    console: bool,
+    /// Address and port to serve `tokio-console` instrumentation on.
+    consoleaddress: String,
+}
+
+impl AppConfigDefaults for DebugConfig {
+    /// Provide defaults for this part of the configuration
+    fn set_defaults<T: BuilderState>(
+        config_builder: ConfigBuilder<T>,
+        prefix: &str,
+    ) -> ConfigBuilder<T> {
+        config_builder
+            .set_default(prefix.to_string() + "." + "recordevents", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "replayevents", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "profiling", "false")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "console", "false")
+            .unwrap()
+            .set_default(
+                prefix.to_string() + "." + "consoleaddress",
+                "127.0.0.1:6669",
+            )
+            .unwrap()
+    }
+}
+
+impl AppConfigValidation for DebugConfig {
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems) {
+        if self.record_events_path().is_some() && self.replay_events_path().is_some() {
+            problems.add(
+                prefix,
+                "recordevents",
+                "Must not be combined with replayevents.".to_string(),
+            );
+        }
+        if self.console && self.console_address().is_none() {
+            problems.add(
+                prefix,
+                "consoleaddress",
+                format!(
+                    "'{}' is not formatted like 'address:port'.",
+                    self.consoleaddress
+                ),
+            );
+        }
+        if let Some(path) = self.replay_events_path() {
+            if !path.is_file() {
+                problems.add(
+                    prefix,
+                    "replayevents",
+                    format!("'{}' is not a file.", self.replayevents),
+                );
+            }
+        }
+    }
+}
+
+impl DebugConfig {
+    /// JSONL file to record all received watch events to, if enabled.
+    pub fn record_events_path(&self) -> Option<&Path> {
+        Some(self.recordevents.trim())
+            .filter(|path| !path.is_empty())
+            .map(Path::new)
+    }
+
+    /// JSONL file of recorded watch events to replay instead of watching, if enabled.
+    pub fn replay_events_path(&self) -> Option<&Path> {
+        Some(self.replayevents.trim())
+            .filter(|path| !path.is_empty())
+            .map(Path::new)
+    }
+
+    /**
+      Return `true` if the `/api/v1/debug/pprof` resources should be
+      available. Defaults to `false`.
+
+      The resources also require API requests to be authenticated, see
+      [super::ApiConfig::tenants_path].
+    */
+    pub fn profiling_enabled(&self) -> bool {
+        self.profiling
+    }
+
+    /**
+      Return `true` if instrumentation for `tokio-console` should be served.
+      Defaults to `false`.
+
+      Only has an effect when built with the `console` feature and
+      `RUSTFLAGS="--cfg tokio_unstable"`.
+    */
+    pub fn console_enabled(&self) -> bool {
+        self.console
+    }
+
+    /// Address and port to serve `tokio-console` instrumentation on. Defaults to `127.0.0.1:6669`.
+    pub fn console_address(&self) -> Option<std::net::SocketAddr> {
+        self.consoleaddress.trim().parse().ok()
+    }
+}
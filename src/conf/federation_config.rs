@@ -0,0 +1,114 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Parsing of configuration for federation with other `microfefind` instances.
+
+use config::builder::BuilderState;
+use config::ConfigBuilder;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::validation::ConfigProblems;
+use super::AppConfigDefaults;
+use super::AppConfigValidation;
+
+/// Configuration of upstream `microfefind` instances whose catalogues are merged into the local one.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FederationConfig {
+    /// Comma separated list of `origin=url` upstream instances.
+    peers: String,
+    /// Seconds between fetches of each upstream catalogue.
+    interval: u64,
+    /// Milliseconds to wait for an upstream instance to respond.
+    timeout: u64,
+}
+
+impl AppConfigDefaults for FederationConfig {
+    /// Provide defaults for this part of the configuration
+    fn set_defaults<T: BuilderState>(
+        config_builder: ConfigBuilder<T>,
+        prefix: &str,
+    ) -> ConfigBuilder<T> {
+        config_builder
+            .set_default(prefix.to_string() + "." + "peers", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "interval", "30")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "timeout", "5000")
+            .unwrap()
+    }
+}
+
+impl AppConfigValidation for FederationConfig {
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems) {
+        for entry in self.peer_entries() {
+            match entry.split_once('=') {
+                Some((origin, url))
+                    if !origin.trim().is_empty()
+                        && (url.trim().starts_with("http://")
+                            || url.trim().starts_with("https://")) => {}
+                _ => problems.add(
+                    prefix,
+                    "peers",
+                    format!("'{entry}' must be formatted as 'origin=http(s)://host[:port]'."),
+                ),
+            }
+        }
+        if self.interval == 0 {
+            problems.add(prefix, "interval", "Must be a positive number.".to_string());
+        }
+        if self.timeout == 0 {
+            problems.add(prefix, "timeout", "Must be a positive number.".to_string());
+        }
+    }
+}
+
+impl FederationConfig {
+    /**
+      Return the origin tag and base URL of each upstream `microfefind`
+      instance, e.g. `("eu", "https://microfefind.eu.example.com")`.
+    */
+    pub fn peers(&self) -> Vec<(String, String)> {
+        self.peer_entries()
+            .filter_map(|entry| entry.split_once('='))
+            .map(|(origin, url)| {
+                (
+                    origin.trim().to_owned(),
+                    url.trim().trim_end_matches('/').to_owned(),
+                )
+            })
+            .collect()
+    }
+
+    /// Time between fetches of each upstream catalogue. Defaults to 30 seconds.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(std::cmp::max(self.interval, 1))
+    }
+
+    /// Time to wait for an upstream instance to respond. Defaults to 5 seconds.
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(std::cmp::max(self.timeout, 1))
+    }
+
+    /// Return the non-empty entries of the comma separated list of peers.
+    fn peer_entries(&self) -> impl Iterator<Item = &str> {
+        self.peers
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+    }
+}
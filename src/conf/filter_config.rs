@@ -21,7 +21,9 @@ use config::builder::BuilderState;
 use config::ConfigBuilder;
 use serde::{Deserialize, Serialize};
 
+use super::validation::ConfigProblems;
 use super::AppConfigDefaults;
+use super::AppConfigValidation;
 
 /// Configuration for detection of labeled Kubernetes `Ingress`es.
 #[derive(Debug, Deserialize, Serialize)]
@@ -32,6 +34,29 @@ pub struct IngressFilterConfig {
     annotationprefix: String,
     /// Comma separated list of namespaces. None to use context namespace.
     namespaces: Option<String>,
+    /// Comma separated list of `key=value` labels of `Namespace`s to monitor.
+    namespacelabels: Option<String>,
+    /// Semicolon separated list of `namespace:labels:annotationprefix` overrides.
+    namespaceselectors: Option<String>,
+    /// Comma separated list of annotation keys (`*` matches anything) to expose. Empty to expose all.
+    annotationallow: String,
+    /// Comma separated list of annotation keys (`*` matches anything) to never expose.
+    annotationdeny: String,
+    /// Comma separated list of hostnames (`*` matches anything) to expose. Empty to expose all.
+    hostallow: String,
+    /// Comma separated list of hostnames (`*` matches anything) to never expose.
+    hostdeny: String,
+    /// `true` to use prefixed annotations of each `Namespace` as defaults for its entries.
+    namespacedefaults: bool,
+}
+
+/// Label selector and annotation prefix that apply to a single `Namespace`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NamespaceSelector<'a> {
+    /// Comma separated list of `key=value` labels to match
+    pub labels: &'a str,
+    /// Prefix for `Ingress` annotations that will be exposed to API clients.
+    pub annotation_prefix: &'a str,
 }
 
 impl AppConfigDefaults for IngressFilterConfig {
@@ -47,18 +72,62 @@ impl AppConfigDefaults for IngressFilterConfig {
             .unwrap()
             .set_default(prefix.to_string() + "." + "namespaces", "")
             .unwrap()
+            .set_default(prefix.to_string() + "." + "namespacelabels", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "namespaceselectors", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "annotationallow", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "annotationdeny", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "namespacedefaults", "false")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "hostallow", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "hostdeny", "")
+            .unwrap()
+    }
+}
+
+impl AppConfigValidation for IngressFilterConfig {
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems) {
+        problems.check_label_selector(prefix, "labels", &self.labels);
+        problems.check_annotation_prefix(prefix, "annotationprefix", &self.annotationprefix);
+        for namespace in self.namespaces() {
+            problems.check_namespace_name(prefix, "namespaces", &namespace);
+        }
+        if let Some(namespace_labels) = self.namespace_labels() {
+            problems.check_label_selector(prefix, "namespacelabels", namespace_labels);
+        }
+        for entry in self
+            .namespaceselectors
+            .iter()
+            .flat_map(|namespace_selectors| namespace_selectors.split(';'))
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let mut parts = entry.splitn(3, ':').map(str::trim);
+            let namespace = parts.next().unwrap_or_default();
+            problems.check_namespace_name(prefix, "namespaceselectors", namespace);
+            if let Some(labels) = parts.next().filter(|labels| !labels.is_empty()) {
+                problems.check_label_selector(prefix, "namespaceselectors", labels);
+            }
+            if let Some(annotation_prefix) = parts.next().filter(|prefix| !prefix.is_empty()) {
+                problems.check_annotation_prefix(prefix, "namespaceselectors", annotation_prefix);
+            }
+        }
     }
 }
 
 impl IngressFilterConfig {
     /// Comma separated list of `key=value` labels to match
-    pub fn match_labels(&self) -> String {
-        self.labels.clone()
+    pub fn match_labels(&self) -> &str {
+        &self.labels
     }
 
     /// Prefix for `Ingress` annotations that will be exposed to API clients (without the `prefix/`).
-    pub fn annotation_prefix(&self) -> String {
-        self.annotationprefix.clone()
+    pub fn annotation_prefix(&self) -> &str {
+        &self.annotationprefix
     }
 
     /// Comma separated list of namespaces. Empty to use context namespace.
@@ -74,4 +143,122 @@ impl IngressFilterConfig {
         }
         ret
     }
+
+    /**
+      Comma separated list of `key=value` labels that `Namespace`s must have to
+      be monitored.
+
+      When present, `Namespace`s are discovered dynamically and the static list
+      of [namespaces](Self::namespaces) is ignored.
+    */
+    pub fn namespace_labels(&self) -> Option<&str> {
+        self.namespacelabels
+            .as_deref()
+            .filter(|namespace_labels| !namespace_labels.is_empty())
+    }
+
+    /**
+      `true` if prefixed annotations of each monitored `Namespace` should be
+      merged (with the lowest precedence) into the annotations of its entries.
+
+      This requires permissions to get and watch `Namespace`s.
+    */
+    pub fn namespace_defaults(&self) -> bool {
+        self.namespacedefaults
+    }
+
+    /**
+      Label selector and annotation prefix to use for `Ingress`es in the
+      `namespace`.
+
+      Per-namespace overrides are configured as a semicolon separated list of
+      `namespace:labels:annotationprefix` entries, e.g.
+      `tenant-a:team=a,microfe=true:tenant-a.example.com/;tenant-b::mfe/`.
+      An empty (or omitted) labels or annotation prefix part falls back to the
+      global [match labels](Self::match_labels) and
+      [annotation prefix](Self::annotation_prefix).
+    */
+    pub fn namespace_selector(&self, namespace: &str) -> NamespaceSelector<'_> {
+        let mut ret = NamespaceSelector {
+            labels: self.match_labels(),
+            annotation_prefix: self.annotation_prefix(),
+        };
+        let Some(namespace_selectors) = &self.namespaceselectors else {
+            return ret;
+        };
+        let Some(entry) = namespace_selectors
+            .split(';')
+            .map(str::trim)
+            .find(|entry| entry.split(':').next().map(str::trim) == Some(namespace))
+        else {
+            return ret;
+        };
+        let mut parts = entry.splitn(3, ':').skip(1).map(str::trim);
+        if let Some(labels) = parts.next().filter(|labels| !labels.is_empty()) {
+            ret.labels = labels;
+        }
+        if let Some(annotation_prefix) = parts.next().filter(|prefix| !prefix.is_empty()) {
+            ret.annotation_prefix = annotation_prefix;
+        }
+        ret
+    }
+
+    /**
+      Return `true` if the annotation `key` (without prefix) should be exposed
+      to API clients.
+
+      A key is exposed if it matches any pattern of the allowlist (or the
+      allowlist is empty) and does not match any pattern of the denylist.
+      Patterns are comma separated keys where `*` matches any characters, e.g.
+      `*internal*`.
+    */
+    pub fn is_annotation_exposed(&self, key: &str) -> bool {
+        let mut allow = Self::patterns(&self.annotationallow).peekable();
+        (allow.peek().is_none() || allow.any(|pattern| Self::matches(pattern, key)))
+            && !Self::patterns(&self.annotationdeny).any(|pattern| Self::matches(pattern, key))
+    }
+
+    /**
+      Return `true` if entries of the `host` should be exposed to API clients.
+
+      Hosts are matched (case-insensitively) after the label matching in the
+      same way as [annotation keys](Self::is_annotation_exposed), e.g.
+      `*.apps.example.com`, so internal-only hosts routed by the same
+      `Ingress` never leak into the catalogue.
+    */
+    pub fn is_host_exposed(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        let mut allow = Self::patterns(&self.hostallow).peekable();
+        (allow.peek().is_none()
+            || allow.any(|pattern| Self::matches(&pattern.to_ascii_lowercase(), &host)))
+            && !Self::patterns(&self.hostdeny)
+                .any(|pattern| Self::matches(&pattern.to_ascii_lowercase(), &host))
+    }
+
+    /// Return the non-empty patterns of a comma separated list.
+    fn patterns(list: &str) -> impl Iterator<Item = &str> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|pattern| !pattern.is_empty())
+    }
+
+    /// Return `true` if the `key` matches the `pattern` where `*` matches any characters.
+    fn matches(pattern: &str, key: &str) -> bool {
+        let mut parts = pattern.split('*');
+        let first = parts.next().unwrap_or_default();
+        let Some(mut rest) = key.strip_prefix(first) else {
+            return false;
+        };
+        let parts = parts.collect::<Vec<_>>();
+        let Some((last, middle)) = parts.split_last() else {
+            return rest.is_empty();
+        };
+        for part in middle {
+            match rest.find(part) {
+                Some(i) => rest = &rest[i + part.len()..],
+                None => return false,
+            }
+        }
+        rest.len() >= last.len() && rest.ends_with(last)
+    }
 }
@@ -0,0 +1,109 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Parsing of configuration for the health checks.
+
+use config::builder::BuilderState;
+use config::ConfigBuilder;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::validation::ConfigProblems;
+use super::AppConfigDefaults;
+use super::AppConfigValidation;
+
+/// Configuration of the health checks.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HealthConfig {
+    /// When the catalogue is complete enough to be ready: `all`, `any` or a number of namespaces.
+    readiness: String,
+    /// Seconds after startup to report ready once any namespace is listed. `0` to disable.
+    readinesstimeout: u64,
+}
+
+/// When the catalogue is complete enough to report ready.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadinessPolicy {
+    /// All monitored namespaces have been listed.
+    All,
+    /// Any monitored namespace has been listed.
+    Any,
+    /// At least this number of monitored namespaces (or all if fewer) have been listed.
+    AtLeast(usize),
+}
+
+impl AppConfigDefaults for HealthConfig {
+    /// Provide defaults for this part of the configuration
+    fn set_defaults<T: BuilderState>(
+        config_builder: ConfigBuilder<T>,
+        prefix: &str,
+    ) -> ConfigBuilder<T> {
+        config_builder
+            .set_default(prefix.to_string() + "." + "readiness", "all")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "readinesstimeout", "0")
+            .unwrap()
+    }
+}
+
+impl AppConfigValidation for HealthConfig {
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems) {
+        let readiness = self.readiness.trim();
+        if !readiness.eq_ignore_ascii_case("all")
+            && !readiness.eq_ignore_ascii_case("any")
+            && !readiness.parse::<usize>().is_ok_and(|count| count > 0)
+        {
+            problems.add(
+                prefix,
+                "readiness",
+                format!("'{readiness}' is not 'all', 'any' or a positive number of namespaces."),
+            );
+        }
+    }
+}
+
+impl HealthConfig {
+    /**
+      Return when the catalogue is complete enough to report ready. Defaults
+      to [ReadinessPolicy::All].
+
+      Unknown values are treated as `all`.
+    */
+    pub fn readiness_policy(&self) -> ReadinessPolicy {
+        let readiness = self.readiness.trim();
+        if readiness.eq_ignore_ascii_case("any") {
+            return ReadinessPolicy::Any;
+        }
+        readiness
+            .parse::<usize>()
+            .ok()
+            .filter(|count| *count > 0)
+            .map(ReadinessPolicy::AtLeast)
+            .unwrap_or(ReadinessPolicy::All)
+    }
+
+    /**
+      Time after startup when ready is reported regardless of the readiness
+      policy, as long as any namespace has been listed. `None` if disabled
+      (the default).
+    */
+    pub fn readiness_timeout(&self) -> Option<Duration> {
+        Some(self.readinesstimeout)
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+}
@@ -0,0 +1,200 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Parsing of configuration for the Kubernetes API clients.
+
+use config::builder::BuilderState;
+use config::ConfigBuilder;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::validation::ConfigProblems;
+use super::AppConfigDefaults;
+use super::AppConfigValidation;
+
+/// Configuration of all clients for Kubernetes API servers.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct KubeClientConfig {
+    /// Path of the kubeconfig file of the local cluster.
+    kubeconfig: String,
+    /// Context in the kubeconfig of the local cluster.
+    context: String,
+    /// Namespace of the local cluster used when no namespaces are configured.
+    namespace: String,
+    /// Seconds to wait for a connection. `0` disables the timeout.
+    connecttimeout: u64,
+    /// Seconds to wait for a response. `0` disables the timeout.
+    readtimeout: u64,
+    /// URL of a proxy for all API requests.
+    proxyurl: String,
+    /// Path to PEM encoded CA certificates trusted in addition to the kubeconfig's.
+    cabundle: String,
+    /// `User-Agent` of all API requests.
+    useragent: String,
+    /// Average number of requests per second to an API server. `0` disables throttling.
+    qps: f64,
+    /// Number of requests that may exceed the average rate in short bursts.
+    burst: u64,
+}
+
+impl AppConfigDefaults for KubeClientConfig {
+    /// Provide defaults for this part of the configuration
+    fn set_defaults<T: BuilderState>(
+        config_builder: ConfigBuilder<T>,
+        prefix: &str,
+    ) -> ConfigBuilder<T> {
+        config_builder
+            .set_default(prefix.to_string() + "." + "kubeconfig", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "context", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "namespace", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "connecttimeout", "30")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "readtimeout", "295")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "proxyurl", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "cabundle", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "useragent", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "qps", "0")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "burst", "10")
+            .unwrap()
+    }
+}
+
+impl AppConfigValidation for KubeClientConfig {
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems) {
+        if let Some(path) = self.kubeconfig_path() {
+            if !std::path::Path::new(path).is_file() {
+                problems.add(
+                    prefix,
+                    "kubeconfig",
+                    format!("The file '{path}' does not exist."),
+                );
+            }
+        }
+        if self.readtimeout > 0 && self.readtimeout < 290 {
+            problems.add(
+                prefix,
+                "readtimeout",
+                "Must be at least 290 seconds to not interrupt watches.".to_string(),
+            );
+        }
+        if !self.proxyurl.is_empty() && self.proxyurl.parse::<k8s_openapi::http::Uri>().is_err() {
+            problems.add(
+                prefix,
+                "proxyurl",
+                format!("'{}' is not a valid URL.", self.proxyurl),
+            );
+        }
+        if let Some(path) = self.ca_bundle_path() {
+            if !std::path::Path::new(path).is_file() {
+                problems.add(
+                    prefix,
+                    "cabundle",
+                    format!("The file '{path}' does not exist."),
+                );
+            }
+        }
+        if self.qps.is_nan() || self.qps < 0.0 {
+            problems.add(
+                prefix,
+                "qps",
+                format!("{} is not a positive number.", self.qps),
+            );
+        }
+        if self.qps > 0.0 && self.burst == 0 {
+            problems.add(
+                prefix,
+                "burst",
+                "Must be at least 1 when throttling.".to_string(),
+            );
+        }
+    }
+}
+
+impl KubeClientConfig {
+    /**
+      Path of the kubeconfig file of the local cluster or `None` to infer the
+      cluster from the environment (in-cluster service account or the
+      `KUBECONFIG` of the user).
+    */
+    pub fn kubeconfig_path(&self) -> Option<&str> {
+        Some(self.kubeconfig.as_str()).filter(|path| !path.is_empty())
+    }
+
+    /// Context in the kubeconfig of the local cluster or `None` for the current context.
+    pub fn context(&self) -> Option<&str> {
+        Some(self.context.as_str()).filter(|context| !context.is_empty())
+    }
+
+    /**
+      Namespace of the local cluster used when no namespaces are configured or
+      `None` for the namespace of the service account or kubeconfig context.
+    */
+    pub fn namespace(&self) -> Option<&str> {
+        Some(self.namespace.as_str()).filter(|namespace| !namespace.is_empty())
+    }
+
+    /// Time to wait for a connection or `None` to wait forever. Defaults to 30 seconds.
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        Some(Duration::from_secs(self.connecttimeout)).filter(|duration| !duration.is_zero())
+    }
+
+    /**
+      Time to wait for a response or `None` to wait forever. Defaults to 295
+      seconds, which is longer than the server side timeout of watches.
+    */
+    pub fn read_timeout(&self) -> Option<Duration> {
+        Some(Duration::from_secs(self.readtimeout)).filter(|duration| !duration.is_zero())
+    }
+
+    /// URL of a proxy for all API requests or `None` to connect directly.
+    pub fn proxy_url(&self) -> Option<&str> {
+        Some(self.proxyurl.as_str()).filter(|url| !url.is_empty())
+    }
+
+    /// Path to PEM encoded CA certificates trusted in addition to the ones in the kubeconfig.
+    pub fn ca_bundle_path(&self) -> Option<&str> {
+        Some(self.cabundle.as_str()).filter(|path| !path.is_empty())
+    }
+
+    /// `User-Agent` of all API requests or `None` to use the client library's default.
+    pub fn user_agent(&self) -> Option<&str> {
+        Some(self.useragent.as_str()).filter(|user_agent| !user_agent.is_empty())
+    }
+
+    /**
+      Return the number of requests allowed per period to throttle requests to
+      an average of `qps` with bursts of up to `burst` requests, or `None` if
+      requests should not be throttled (the default).
+    */
+    pub fn rate_limit(&self) -> Option<(u64, Duration)> {
+        if self.qps <= 0.0 || self.burst == 0 {
+            return None;
+        }
+        Some((
+            self.burst,
+            Duration::from_secs_f64(self.burst as f64 / self.qps),
+        ))
+    }
+}
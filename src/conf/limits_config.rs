@@ -21,7 +21,9 @@ use config::builder::BuilderState;
 use config::ConfigBuilder;
 use serde::{Deserialize, Serialize};
 
+use super::validation::ConfigProblems;
 use super::AppConfigDefaults;
+use super::AppConfigValidation;
 
 /// Resource limitations override configuration.
 #[derive(Debug, Deserialize, Serialize)]
@@ -40,35 +42,7 @@ impl AppConfigDefaults for ResourceLimitsConfig {
         mut config_builder: ConfigBuilder<T>,
         prefix: &str,
     ) -> ConfigBuilder<T> {
-        let mut cpu_quota = None;
-        let mut cpu_period = None;
-        let mut memory_max = None;
-        cgroups_rs::hierarchies::auto()
-            .subsystems()
-            .iter()
-            .for_each(|subsystem| match subsystem.controller_name().as_str() {
-                "cpu" => {
-                    let cpu_controller: &cgroups_rs::cpu::CpuController = subsystem.into();
-                    cpu_quota = cpu_controller
-                        .cfs_quota()
-                        .ok()
-                        .and_then(|cfs_quota| u64::try_from(std::cmp::max(cfs_quota, 0)).ok());
-                    cpu_period = cpu_controller.cfs_period().ok();
-                }
-                "memory" => {
-                    let memory_controller: &cgroups_rs::memory::MemController = subsystem.into();
-                    if let Ok(mem) = memory_controller.get_mem() {
-                        if let Some(cgroups_rs::MaxValue::Value(mem_max_value)) = mem.max {
-                            memory_max = u64::try_from(std::cmp::max(mem_max_value, 0)).ok();
-                        }
-                    }
-                }
-                _ => {
-                    if log::log_enabled!(log::Level::Trace) {
-                        log::trace!("Ignoring cgroup {}", subsystem.controller_name());
-                    }
-                }
-            });
+        let (cpu_quota, cpu_period, memory_max) = cgroup_limits();
         let mut cpus = std::thread::available_parallelism().unwrap().get() as f64;
         if let Some(cpu_quota) = cpu_quota {
             if let Some(cpu_period) = cpu_period {
@@ -89,14 +63,33 @@ impl AppConfigDefaults for ResourceLimitsConfig {
     }
 }
 
+impl AppConfigValidation for ResourceLimitsConfig {
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems) {
+        if self.cpus.is_nan() || self.cpus <= 0.0 {
+            problems.add(
+                prefix,
+                "cpus",
+                format!("{} is not a positive number.", self.cpus),
+            );
+        }
+        if self.memory == Some(0) {
+            problems.add(
+                prefix,
+                "memory",
+                "Must be a positive number of bytes.".to_string(),
+            );
+        }
+    }
+}
+
 impl ResourceLimitsConfig {
     /** Supported level of parallelism.
 
-       This roughly matches the number of full cores assigned to the app, but
-       always returns at least 1.
+       This is the number of (possibly fractional) cores assigned to the app
+       rounded up, so a 1.5 CPU quota can use two threads. Always at least 1.
     */
     pub fn available_parallelism(&self) -> usize {
-        std::cmp::max(self.cpus as usize, 1)
+        std::cmp::max(self.cpus.ceil() as usize, 1)
     }
 
     /// CPU cores assigned to the app.
@@ -108,4 +101,70 @@ impl ResourceLimitsConfig {
     pub fn memory_bytes(&self) -> Option<u64> {
         self.memory
     }
+
+    /**
+      Return the bytes that retained changes may use, which is 1/32 of the
+      assigned memory, or `None` when the memory is unlimited.
+    */
+    pub fn change_feed_budget_bytes(&self) -> Option<usize> {
+        self.memory_share_bytes(32)
+    }
+
+    /**
+      Return the bytes that cached asset manifests may use, which is 1/8 of
+      the assigned memory, or `None` when the memory is unlimited.
+    */
+    pub fn manifest_cache_budget_bytes(&self) -> Option<usize> {
+        self.memory_share_bytes(8)
+    }
+
+    /// Return the assigned memory divided by `divisor`.
+    fn memory_share_bytes(&self, divisor: u64) -> Option<usize> {
+        self.memory
+            .map(|memory| usize::try_from(memory / divisor).unwrap_or(usize::MAX))
+    }
+}
+
+/**
+Return the CPU quota, CPU period and memory limit of the cgroup of the
+process, if limited.
+ */
+#[cfg(target_os = "linux")]
+fn cgroup_limits() -> (Option<u64>, Option<u64>, Option<u64>) {
+    let mut cpu_quota = None;
+    let mut cpu_period = None;
+    let mut memory_max = None;
+    cgroups_rs::hierarchies::auto()
+        .subsystems()
+        .iter()
+        .for_each(|subsystem| match subsystem.controller_name().as_str() {
+            "cpu" => {
+                let cpu_controller: &cgroups_rs::cpu::CpuController = subsystem.into();
+                cpu_quota = cpu_controller
+                    .cfs_quota()
+                    .ok()
+                    .and_then(|cfs_quota| u64::try_from(std::cmp::max(cfs_quota, 0)).ok());
+                cpu_period = cpu_controller.cfs_period().ok();
+            }
+            "memory" => {
+                let memory_controller: &cgroups_rs::memory::MemController = subsystem.into();
+                if let Ok(mem) = memory_controller.get_mem() {
+                    if let Some(cgroups_rs::MaxValue::Value(mem_max_value)) = mem.max {
+                        memory_max = u64::try_from(std::cmp::max(mem_max_value, 0)).ok();
+                    }
+                }
+            }
+            _ => {
+                if log::log_enabled!(log::Level::Trace) {
+                    log::trace!("Ignoring cgroup {}", subsystem.controller_name());
+                }
+            }
+        });
+    (cpu_quota, cpu_period, memory_max)
+}
+
+/// Return no limits, since cgroups are only available on Linux.
+#[cfg(not(target_os = "linux"))]
+fn cgroup_limits() -> (Option<u64>, Option<u64>, Option<u64>) {
+    (None, None, None)
 }
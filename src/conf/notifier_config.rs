@@ -0,0 +1,136 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+//! Parsing of configuration for publishing of catalogue changes.
+
+use config::builder::BuilderState;
+use config::ConfigBuilder;
+use serde::{Deserialize, Serialize};
+
+use super::validation::ConfigProblems;
+use super::AppConfigDefaults;
+use super::AppConfigValidation;
+
+/// Configuration of where catalogue changes are published.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NotifierConfig {
+    /// Comma separated list of notifiers: `log`, `audit`, `webhook` and/or `nats`.
+    types: String,
+    /// URL that changes are `POST`ed to by the `webhook` notifier.
+    webhookurl: String,
+    /// URL of the NATS server used by the `nats` notifier.
+    natsurl: String,
+    /// NATS subject that changes are published to.
+    natssubject: String,
+    /// URL that panics and watch errors are `POST`ed to.
+    errorwebhookurl: String,
+    /// Publish Kubernetes `Event`s on the resources declaring entries.
+    kubernetesevents: bool,
+}
+
+impl AppConfigDefaults for NotifierConfig {
+    /// Provide defaults for this part of the configuration
+    fn set_defaults<T: BuilderState>(
+        config_builder: ConfigBuilder<T>,
+        prefix: &str,
+    ) -> ConfigBuilder<T> {
+        config_builder
+            .set_default(prefix.to_string() + "." + "types", "log")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "webhookurl", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "natsurl", "")
+            .unwrap()
+            .set_default(
+                prefix.to_string() + "." + "natssubject",
+                "microfefind.changes",
+            )
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "errorwebhookurl", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "kubernetesevents", "false")
+            .unwrap()
+    }
+}
+
+impl AppConfigValidation for NotifierConfig {
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems) {
+        for notifier_type in self.types() {
+            problems.check_one_of(
+                prefix,
+                "types",
+                &notifier_type,
+                &["log", "audit", "webhook", "nats"],
+            );
+        }
+        if self.types().iter().any(|x| x == "webhook") && self.webhook_url().is_none() {
+            problems.add(
+                prefix,
+                "webhookurl",
+                "Required by the 'webhook' notifier.".to_string(),
+            );
+        }
+        if self.types().iter().any(|x| x == "nats") && self.nats_url().is_none() {
+            problems.add(
+                prefix,
+                "natsurl",
+                "Required by the 'nats' notifier.".to_string(),
+            );
+        }
+    }
+}
+
+impl NotifierConfig {
+    /// Return the names of the enabled notifiers. Defaults to `log`.
+    pub fn types(&self) -> Vec<String> {
+        self.types
+            .split(',')
+            .map(|x| x.trim().to_lowercase())
+            .filter(|x| !x.is_empty())
+            .collect()
+    }
+
+    /// Return the URL that changes are `POST`ed to by the `webhook` notifier.
+    pub fn webhook_url(&self) -> Option<&str> {
+        Some(self.webhookurl.as_str()).filter(|url| !url.is_empty())
+    }
+
+    /// Return the URL of the NATS server used by the `nats` notifier.
+    pub fn nats_url(&self) -> Option<&str> {
+        Some(self.natsurl.as_str()).filter(|url| !url.is_empty())
+    }
+
+    /// Return the NATS subject that changes are published to. Defaults to `microfefind.changes`.
+    pub fn nats_subject(&self) -> &str {
+        &self.natssubject
+    }
+
+    /// Return the URL that panics and watch errors are `POST`ed to, if any.
+    pub fn error_webhook_url(&self) -> Option<&str> {
+        Some(self.errorwebhookurl.as_str()).filter(|url| !url.is_empty())
+    }
+
+    /**
+      Return `true` if Kubernetes `Event`s are published on the resources
+      declaring entries when they are cataloged, updated, rejected or removed.
+      Defaults to `false`.
+
+      This requires permission to create `Event`s in the monitored namespaces.
+    */
+    pub fn kubernetes_events(&self) -> bool {
+        self.kubernetesevents
+    }
+}
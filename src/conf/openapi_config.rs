@@ -0,0 +1,129 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Parsing of configuration for the published OpenAPI document.
+
+use config::builder::BuilderState;
+use config::ConfigBuilder;
+use serde::{Deserialize, Serialize};
+
+use super::validation::ConfigProblems;
+use super::AppConfigDefaults;
+use super::AppConfigValidation;
+
+/// Configuration of the server and contact information in the OpenAPI document.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OpenApiConfig {
+    /// Public URL of the server, like `https://microfefind.example.com`. Empty to omit.
+    serverurl: String,
+    /// Name of the contact person or team. Empty to omit.
+    contactname: String,
+    /// Email address of the contact. Empty to omit.
+    contactemail: String,
+    /// URL with contact information. Empty to omit.
+    contacturl: String,
+    /// Name of the license of the API. Empty for the license of the crate.
+    licensename: String,
+    /// URL of the license of the API. Empty to omit.
+    licenseurl: String,
+}
+
+impl AppConfigDefaults for OpenApiConfig {
+    /// Provide defaults for this part of the configuration
+    fn set_defaults<T: BuilderState>(
+        config_builder: ConfigBuilder<T>,
+        prefix: &str,
+    ) -> ConfigBuilder<T> {
+        config_builder
+            .set_default(prefix.to_string() + "." + "serverurl", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "contactname", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "contactemail", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "contacturl", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "licensename", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "licenseurl", "")
+            .unwrap()
+    }
+}
+
+impl AppConfigValidation for OpenApiConfig {
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems) {
+        for (key, url) in [
+            ("serverurl", self.server_url()),
+            ("contacturl", self.contact_url()),
+            ("licenseurl", self.license_url()),
+        ] {
+            if let Some(url) = url {
+                if !url.starts_with("http://") && !url.starts_with("https://") {
+                    problems.add(
+                        prefix,
+                        key,
+                        format!("'{url}' is not an absolute HTTP(S) URL."),
+                    );
+                }
+            }
+        }
+        if self.license_url().is_some() && self.license_name().is_none() {
+            problems.add(
+                prefix,
+                "licensename",
+                "Required when 'licenseurl' is set.".to_string(),
+            );
+        }
+    }
+}
+
+/// Return the trimmed value or `None` if empty.
+fn non_empty(value: &str) -> Option<&str> {
+    Some(value.trim()).filter(|value| !value.is_empty())
+}
+
+impl OpenApiConfig {
+    /// Public URL of the server that API clients should use or `None` to omit it. Defaults to `None`.
+    pub fn server_url(&self) -> Option<&str> {
+        non_empty(&self.serverurl)
+    }
+
+    /// Name of the contact person or team or `None` to omit it. Defaults to `None`.
+    pub fn contact_name(&self) -> Option<&str> {
+        non_empty(&self.contactname)
+    }
+
+    /// Email address of the contact or `None` to omit it. Defaults to `None`.
+    pub fn contact_email(&self) -> Option<&str> {
+        non_empty(&self.contactemail)
+    }
+
+    /// URL with contact information or `None` to omit it. Defaults to `None`.
+    pub fn contact_url(&self) -> Option<&str> {
+        non_empty(&self.contacturl)
+    }
+
+    /// Name of the license of the API or `None` for the license of the crate. Defaults to `None`.
+    pub fn license_name(&self) -> Option<&str> {
+        non_empty(&self.licensename)
+    }
+
+    /// URL of the license of the API or `None` to omit it. Defaults to `None`.
+    pub fn license_url(&self) -> Option<&str> {
+        non_empty(&self.licenseurl)
+    }
+}
@@ -0,0 +1,55 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Parsing of configuration for the `MicroFrontend` custom resource operator mode.
+
+use config::builder::BuilderState;
+use config::ConfigBuilder;
+use serde::{Deserialize, Serialize};
+
+use super::AppConfigDefaults;
+
+/// Configuration for watching `MicroFrontend` custom resources.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OperatorConfig {
+    /// Watch `MicroFrontend` custom resources in addition to `Ingress`es.
+    enabled: bool,
+}
+
+impl AppConfigDefaults for OperatorConfig {
+    /// Provide defaults for this part of the configuration
+    fn set_defaults<T: BuilderState>(
+        config_builder: ConfigBuilder<T>,
+        prefix: &str,
+    ) -> ConfigBuilder<T> {
+        config_builder
+            .set_default(prefix.to_string() + "." + "enabled", "false")
+            .unwrap()
+    }
+}
+
+impl OperatorConfig {
+    /**
+      Return `true` if `MicroFrontend` custom resources should be watched and
+      have their status updated. Defaults to `false`.
+
+      The `MicroFrontend` CRD must be installed in the cluster when enabled.
+    */
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+}
@@ -0,0 +1,169 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Parsing of configuration for plugins and scripts transforming entries.
+
+use config::builder::BuilderState;
+use config::ConfigBuilder;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+
+use crate::plugin::EntryPlugin;
+
+use super::validation::ConfigProblems;
+use super::AppConfigDefaults;
+use super::AppConfigValidation;
+
+/// Configuration of the WebAssembly plugin and Rhai script invoked for each entry.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PluginConfig {
+    /// Path of the WebAssembly module. Empty disables the plugin.
+    path: String,
+    /// Fuel (roughly instructions) that each invocation may consume.
+    fuel: u64,
+    /// Maximum size of the linear memory of each invocation in bytes.
+    maxmemory: usize,
+    /// Path of the Rhai script. Empty disables the script.
+    script: String,
+    /// Maximum number of operations of each script evaluation.
+    maxoperations: u64,
+    /// Plugin and script loaded from [Self::path] and [Self::script_path] during validation.
+    #[serde(skip)]
+    entry_plugin: OnceLock<Arc<dyn EntryPlugin>>,
+}
+
+impl AppConfigDefaults for PluginConfig {
+    /// Provide defaults for this part of the configuration
+    fn set_defaults<T: BuilderState>(
+        config_builder: ConfigBuilder<T>,
+        prefix: &str,
+    ) -> ConfigBuilder<T> {
+        config_builder
+            .set_default(prefix.to_string() + "." + "path", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "fuel", "10000000")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "maxmemory", "16777216")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "script", "")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "maxoperations", "100000")
+            .unwrap()
+    }
+}
+
+impl AppConfigValidation for PluginConfig {
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems) {
+        if self.fuel == 0 {
+            problems.add(prefix, "fuel", "Must be a positive number.".to_string());
+        }
+        if self.maxmemory < 65536 {
+            problems.add(
+                prefix,
+                "maxmemory",
+                "Must be at least one WebAssembly page (65536 bytes).".to_string(),
+            );
+        }
+        #[cfg_attr(not(any(feature = "wasm", feature = "rhai")), allow(unused_mut))]
+        let mut plugins: Vec<Arc<dyn EntryPlugin>> = vec![];
+        if let Some(path) = self.path() {
+            #[cfg(feature = "wasm")]
+            match crate::plugin::WasmPlugin::load(path, self.fuel, self.maxmemory) {
+                Ok(plugin) => plugins.push(Arc::new(plugin)),
+                Err(e) => problems.add(prefix, "path", format!("Failed to load '{path}': {e}")),
+            }
+            #[cfg(not(feature = "wasm"))]
+            problems.add(
+                prefix,
+                "path",
+                format!("Can't load '{path}', since this build lacks the 'wasm' feature."),
+            );
+        }
+        if self.maxoperations == 0 {
+            problems.add(
+                prefix,
+                "maxoperations",
+                "Must be a positive number.".to_string(),
+            );
+        }
+        if let Some(path) = self.script_path() {
+            #[cfg(feature = "rhai")]
+            match crate::plugin::RhaiPlugin::load(path, self.maxoperations) {
+                Ok(plugin) => plugins.push(Arc::new(plugin)),
+                Err(e) => problems.add(prefix, "script", format!("Failed to load '{path}': {e}")),
+            }
+            #[cfg(not(feature = "rhai"))]
+            problems.add(
+                prefix,
+                "script",
+                format!("Can't load '{path}', since this build lacks the 'rhai' feature."),
+            );
+        }
+        if let Some(entry_plugin) = crate::plugin::compose(plugins) {
+            let _ = self.entry_plugin.set(entry_plugin);
+        }
+    }
+}
+
+impl PluginConfig {
+    /**
+      Return the path of the WebAssembly module invoked for each entry or
+      `None` if entries are not transformed. Defaults to `None`.
+
+      The plugin is only loaded when built with the `wasm` feature.
+    */
+    pub fn path(&self) -> Option<&str> {
+        Some(self.path.trim()).filter(|path| !path.is_empty())
+    }
+
+    /// Fuel (roughly instructions) that each invocation may consume. Defaults to `10000000`.
+    pub fn fuel(&self) -> u64 {
+        self.fuel
+    }
+
+    /// Maximum size of the linear memory of each invocation in bytes. Defaults to 16 MiB.
+    pub fn max_memory_bytes(&self) -> usize {
+        self.maxmemory
+    }
+
+    /**
+      Return the path of the Rhai script evaluated for each entry or `None`
+      if entries are not shaped by a script. Defaults to `None`.
+
+      The script is only loaded when built with the `rhai` feature.
+    */
+    pub fn script_path(&self) -> Option<&str> {
+        Some(self.script.trim()).filter(|path| !path.is_empty())
+    }
+
+    /// Maximum number of operations of each script evaluation. Defaults to `100000`.
+    pub fn max_operations(&self) -> u64 {
+        self.maxoperations
+    }
+
+    /**
+      Return the WebAssembly plugin followed by the Rhai script as a single
+      [EntryPlugin] or `None` if neither is configured.
+
+      Both are loaded when the configuration is validated, so a plugin or
+      script that fails to load is reported with the other configuration
+      problems instead of exposing unfiltered entries.
+    */
+    pub fn entry_plugin(&self) -> Option<Arc<dyn EntryPlugin>> {
+        self.entry_plugin.get().cloned()
+    }
+}
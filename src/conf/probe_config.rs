@@ -0,0 +1,112 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Parsing of configuration for reachability probing of entries.
+
+use config::builder::BuilderState;
+use config::ConfigBuilder;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::validation::ConfigProblems;
+use super::AppConfigDefaults;
+use super::AppConfigValidation;
+
+/// Configuration of periodic requests to the URL and health endpoint of each entry.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ProbeConfig {
+    /// Periodically request the URL of each entry.
+    enabled: bool,
+    /// Seconds between probes of each entry.
+    interval: u64,
+    /// Milliseconds to wait for a response before the entry is considered unreachable.
+    timeout: u64,
+    /// HTTP method of the probes: `HEAD` or `GET`.
+    method: String,
+    /// Probe health endpoints declared by entries through the mapped `Service`.
+    healthpaths: bool,
+}
+
+impl AppConfigDefaults for ProbeConfig {
+    /// Provide defaults for this part of the configuration
+    fn set_defaults<T: BuilderState>(
+        config_builder: ConfigBuilder<T>,
+        prefix: &str,
+    ) -> ConfigBuilder<T> {
+        config_builder
+            .set_default(prefix.to_string() + "." + "enabled", "false")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "interval", "60")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "timeout", "5000")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "method", "HEAD")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "healthpaths", "true")
+            .unwrap()
+    }
+}
+
+impl AppConfigValidation for ProbeConfig {
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems) {
+        if self.interval == 0 {
+            problems.add(prefix, "interval", "Must be a positive number.".to_string());
+        }
+        if self.timeout == 0 {
+            problems.add(prefix, "timeout", "Must be a positive number.".to_string());
+        }
+        let method = self.method.trim();
+        if !method.eq_ignore_ascii_case("HEAD") && !method.eq_ignore_ascii_case("GET") {
+            problems.add(
+                prefix,
+                "method",
+                format!("'{method}' is not 'HEAD' or 'GET'."),
+            );
+        }
+    }
+}
+
+impl ProbeConfig {
+    /// Return `true` if the URL of each entry should be probed. Defaults to `false`.
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /**
+      Return `true` if health endpoints declared by the `health-path`
+      annotation should be probed through the mapped `Service`. Defaults to
+      `true`.
+    */
+    pub fn health_paths(&self) -> bool {
+        self.healthpaths
+    }
+
+    /// Time between probes of each entry. Defaults to 60 seconds.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(std::cmp::max(self.interval, 1))
+    }
+
+    /// Time to wait for a response to a probe. Defaults to 5 seconds.
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(std::cmp::max(self.timeout, 1))
+    }
+
+    /// Return `true` if probes should use `GET` instead of `HEAD` (the default).
+    pub fn use_get(&self) -> bool {
+        self.method.trim().eq_ignore_ascii_case("GET")
+    }
+}
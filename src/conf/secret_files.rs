@@ -0,0 +1,133 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Loading of configuration values from files referenced by environment variables.
+
+use serde_json::Value;
+
+use super::validation::ConfigProblems;
+
+/// Suffix of environment variables that reference a file holding the value.
+const FILE_SUFFIX: &str = "_FILE";
+
+/// Placeholder for values loaded from files when the configuration is logged.
+const REDACTED: &str = "<redacted>";
+
+/**
+Key paths of values that may hold credentials, like URLs with tokens or user
+information, which are always redacted regardless of their source.
+ */
+pub const SECRET_KEYS: &[&str] = &[
+    "federation.peers",
+    "kube.proxyurl",
+    "notifier.errorwebhookurl",
+    "notifier.natsurl",
+    "notifier.webhookurl",
+];
+
+/**
+Configuration values loaded from files.
+
+An environment variable like `MICROFEFIND_NOTIFIER_WEBHOOKURL_FILE` loads the
+value of `notifier.webhookurl` from the referenced file, e.g. a mounted
+`Secret`. This keeps credentials out of the process environment.
+ */
+#[derive(Debug, Default)]
+pub struct SecretFiles {
+    /// Key paths and values loaded from files.
+    values: Vec<(String, String)>,
+}
+
+impl SecretFiles {
+    /**
+      Load all values referenced by `{env_prefix}_..._FILE` environment
+      variables. Unreadable files are reported as problems.
+    */
+    pub fn load(env_prefix: &str, problems: &mut ConfigProblems) -> Self {
+        let mut values = vec![];
+        for (env_var, path) in std::env::vars() {
+            let Some(key) = env_var
+                .strip_prefix(env_prefix)
+                .and_then(|key| key.strip_prefix('_'))
+                .and_then(|key| key.strip_suffix(FILE_SUFFIX))
+                .filter(|key| !key.is_empty())
+                .map(|key| key.to_lowercase().replace('_', "."))
+            else {
+                continue;
+            };
+            if std::env::var_os(env_prefix.to_owned() + "_" + &key.to_uppercase().replace('.', "_"))
+                .is_some()
+            {
+                problems.add(
+                    &key,
+                    "",
+                    format!("Both {env_var} and the plain variable are set."),
+                );
+                continue;
+            }
+            match std::fs::read_to_string(&path) {
+                Ok(value) => {
+                    let value = value.strip_suffix('\n').unwrap_or(&value);
+                    let value = value.strip_suffix('\r').unwrap_or(value);
+                    values.push((key, value.to_owned()));
+                }
+                Err(e) => problems.add(
+                    &key,
+                    "",
+                    format!("Unable to read '{path}' referenced by {env_var}: {e}"),
+                ),
+            }
+        }
+        Self { values }
+    }
+
+    /// Return all environment variables except references to files.
+    pub fn plain_env_vars() -> config::Map<String, String> {
+        std::env::vars()
+            .filter(|(env_var, _)| !env_var.ends_with(FILE_SUFFIX))
+            .collect()
+    }
+
+    /// Return the key paths and values loaded from files.
+    pub fn values(&self) -> &[(String, String)] {
+        &self.values
+    }
+
+    /// Return the key paths of all values loaded from files.
+    pub fn keys(&self) -> Vec<String> {
+        self.values.iter().map(|(key, _)| key.to_owned()).collect()
+    }
+}
+
+/**
+Replace the values of all [SECRET_KEYS] and other `keys` in the serialized
+configuration. Unset and empty values are kept to show that they are unset.
+ */
+pub fn redact(config: &mut Value, keys: &[String]) {
+    let keys = SECRET_KEYS
+        .iter()
+        .copied()
+        .chain(keys.iter().map(String::as_str));
+    for key in keys {
+        let pointer = "/".to_owned() + &key.replace('.', "/");
+        if let Some(value) = config.pointer_mut(&pointer) {
+            if !value.is_null() && value.as_str() != Some("") {
+                *value = Value::String(REDACTED.to_owned());
+            }
+        }
+    }
+}
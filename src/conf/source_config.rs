@@ -0,0 +1,78 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Parsing of configuration for where monitored resources are loaded from.
+
+use config::builder::BuilderState;
+use config::ConfigBuilder;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::validation::ConfigProblems;
+use super::AppConfigDefaults;
+use super::AppConfigValidation;
+
+/// Configuration of the source of monitored resources.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SourceConfig {
+    /// `cluster` to watch Kubernetes API servers or `static` to load local fixture files.
+    kind: String,
+    /// Directory of YAML or JSON fixture files used by the `static` source.
+    fixtures: String,
+}
+
+impl AppConfigDefaults for SourceConfig {
+    /// Provide defaults for this part of the configuration
+    fn set_defaults<T: BuilderState>(
+        config_builder: ConfigBuilder<T>,
+        prefix: &str,
+    ) -> ConfigBuilder<T> {
+        config_builder
+            .set_default(prefix.to_string() + "." + "kind", "cluster")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "fixtures", "fixtures")
+            .unwrap()
+    }
+}
+
+impl AppConfigValidation for SourceConfig {
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems) {
+        problems.check_one_of(prefix, "kind", &self.kind, &["cluster", "static"]);
+        if self.is_static() && !self.fixtures_path().is_dir() {
+            problems.add(
+                prefix,
+                "fixtures",
+                format!("'{}' is not a directory.", self.fixtures),
+            );
+        }
+    }
+}
+
+impl SourceConfig {
+    /**
+      Return `true` if resources are loaded from local fixture files instead
+      of Kubernetes API servers. Defaults to `false`.
+    */
+    pub fn is_static(&self) -> bool {
+        self.kind.eq_ignore_ascii_case("static")
+    }
+
+    /// Directory of YAML or JSON fixture files. Defaults to `fixtures`.
+    pub fn fixtures_path(&self) -> &Path {
+        Path::new(&self.fixtures)
+    }
+}
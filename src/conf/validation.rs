@@ -0,0 +1,185 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Validation of application configuration with aggregated problems.
+
+use std::fmt;
+
+use crate::kubers_util::LabelSelector;
+
+/// A single invalid configuration value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigProblem {
+    /// Path of the offending configuration key like `ingress.labels`.
+    pub key: String,
+    /// Description of what is wrong with the value.
+    pub message: String,
+}
+
+/**
+All problems found while loading and validating the configuration.
+
+Each problem is reported with the key path and the corresponding environment
+variable, so that all typos can be fixed at once.
+ */
+#[derive(Debug)]
+pub struct ConfigError {
+    /// Upper case prefix of the environment variables.
+    env_prefix: String,
+    problems: Vec<ConfigProblem>,
+}
+
+impl ConfigError {
+    /// Return all found problems.
+    pub fn problems(&self) -> &[ConfigProblem] {
+        &self.problems
+    }
+
+    /// Return the environment variable that overrides the key path.
+    fn env_var(&self, key: &str) -> String {
+        self.env_prefix.to_owned() + "_" + &key.replace('.', "_").to_uppercase()
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid configuration ({} problems):",
+            self.problems.len()
+        )?;
+        for problem in &self.problems {
+            if problem.key.is_empty() {
+                write!(f, "\n  {}", problem.message)?;
+            } else {
+                write!(
+                    f,
+                    "\n  {} ({}): {}",
+                    problem.key,
+                    self.env_var(&problem.key),
+                    problem.message
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Collector of problems while validating the configuration.
+#[derive(Debug, Default)]
+pub struct ConfigProblems {
+    problems: Vec<ConfigProblem>,
+}
+
+impl ConfigProblems {
+    /// Report a problem with the value of `key` in the section `prefix`.
+    pub fn add(&mut self, prefix: &str, key: &str, message: String) {
+        let key = [prefix, key]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(".");
+        self.problems.push(ConfigProblem { key, message });
+    }
+
+    /// Return all reported problems as an error or `Ok` if there were none.
+    pub fn into_result(self, env_prefix: &str) -> Result<(), ConfigError> {
+        if self.problems.is_empty() {
+            return Ok(());
+        }
+        Err(self.into_error(env_prefix))
+    }
+
+    /// Return all reported problems as an error.
+    pub fn into_error(self, env_prefix: &str) -> ConfigError {
+        ConfigError {
+            env_prefix: env_prefix.to_owned(),
+            problems: self.problems,
+        }
+    }
+
+    /// Report `value` unless it is a valid label selector.
+    pub fn check_label_selector(&mut self, prefix: &str, key: &str, value: &str) {
+        if let Err(e) = value.parse::<LabelSelector>() {
+            self.add(prefix, key, format!("Invalid label selector: {e}"));
+        }
+    }
+
+    /// Report `value` unless it is a valid `Namespace` name (RFC 1123 label).
+    pub fn check_namespace_name(&mut self, prefix: &str, key: &str, value: &str) {
+        if !is_dns_label(value) {
+            self.add(
+                prefix,
+                key,
+                format!("'{value}' is not a valid namespace name (lower case alphanumerics and '-', at most 63 characters)."),
+            );
+        }
+    }
+
+    /**
+      Report `value` unless it is a valid annotation prefix like `microfe/` or
+      `example.com/mfe-`: a DNS subdomain, a `/` and an optional start of the
+      annotation name.
+    */
+    pub fn check_annotation_prefix(&mut self, prefix: &str, key: &str, value: &str) {
+        let valid = value.split_once('/').is_some_and(|(domain, name)| {
+            is_dns_subdomain(domain)
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+        });
+        if !valid {
+            self.add(
+                prefix,
+                key,
+                format!("'{value}' is not a valid annotation prefix like 'microfe/'."),
+            );
+        }
+    }
+
+    /// Report `value` unless it is one of the `allowed` values (ignoring case).
+    pub fn check_one_of(&mut self, prefix: &str, key: &str, value: &str, allowed: &[&str]) {
+        if !allowed
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(value))
+        {
+            self.add(
+                prefix,
+                key,
+                format!("'{value}' is not one of {}.", allowed.join(", ")),
+            );
+        }
+    }
+}
+
+/// Return `true` if `value` is a RFC 1123 DNS label.
+fn is_dns_label(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= 63
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !value.starts_with('-')
+        && !value.ends_with('-')
+}
+
+/// Return `true` if `value` is a RFC 1123 DNS subdomain.
+fn is_dns_subdomain(value: &str) -> bool {
+    value.len() <= 253 && value.split('.').all(is_dns_label)
+}
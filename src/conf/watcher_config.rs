@@ -0,0 +1,138 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Parsing of configuration for watches of Kubernetes resources.
+
+use config::builder::BuilderState;
+use config::ConfigBuilder;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::validation::ConfigProblems;
+use super::AppConfigDefaults;
+use super::AppConfigValidation;
+
+/// Configuration of retries and listings of all watches.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WatcherConfig {
+    /// Milliseconds to wait before the first retry of a failed watch.
+    initialbackoff: u64,
+    /// Upper bound of milliseconds to wait between retries.
+    maxbackoff: u64,
+    /// Share (0.0-1.0) of each delay that is randomized.
+    jitter: f64,
+    /// Maximum number of objects in each page when listing. `0` for the client default.
+    pagesize: u32,
+    /// Request bookmarks from the API server.
+    bookmarks: bool,
+    /// Seconds without events or errors after which a watch is restarted. `0` to disable.
+    stalethreshold: u64,
+}
+
+impl AppConfigDefaults for WatcherConfig {
+    /// Provide defaults for this part of the configuration
+    fn set_defaults<T: BuilderState>(
+        config_builder: ConfigBuilder<T>,
+        prefix: &str,
+    ) -> ConfigBuilder<T> {
+        config_builder
+            .set_default(prefix.to_string() + "." + "initialbackoff", "500")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "maxbackoff", "60000")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "jitter", "0.5")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "pagesize", "0")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "bookmarks", "true")
+            .unwrap()
+            .set_default(prefix.to_string() + "." + "stalethreshold", "900")
+            .unwrap()
+    }
+}
+
+impl AppConfigValidation for WatcherConfig {
+    fn validate(&self, prefix: &str, problems: &mut ConfigProblems) {
+        if self.initialbackoff == 0 {
+            problems.add(
+                prefix,
+                "initialbackoff",
+                "Must be a positive number.".to_string(),
+            );
+        }
+        if self.maxbackoff < self.initialbackoff {
+            problems.add(
+                prefix,
+                "maxbackoff",
+                format!(
+                    "Must not be less than initialbackoff ({}).",
+                    self.initialbackoff
+                ),
+            );
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            problems.add(
+                prefix,
+                "jitter",
+                format!("{} is not in the range 0.0-1.0.", self.jitter),
+            );
+        }
+    }
+}
+
+impl WatcherConfig {
+    /// Delay before the first retry of a failed watch. Defaults to 500 ms.
+    pub fn initial_backoff(&self) -> Duration {
+        Duration::from_millis(self.initialbackoff)
+    }
+
+    /// Upper bound of the delay between retries. Defaults to 60 seconds.
+    pub fn max_backoff(&self) -> Duration {
+        Duration::from_millis(self.maxbackoff)
+    }
+
+    /// Share (0.0-1.0) of each delay that is randomized. Defaults to `0.5`.
+    pub fn jitter(&self) -> f64 {
+        self.jitter
+    }
+
+    /**
+      Maximum number of objects in each page when (re-)listing resources or
+      `None` to use the client default. Smaller pages reduce the memory spikes
+      of the API server in large clusters at the cost of more requests.
+    */
+    pub fn page_size(&self) -> Option<u32> {
+        Some(self.pagesize).filter(|page_size| *page_size > 0)
+    }
+
+    /// Return `true` if bookmarks should be requested from the API server. Defaults to `true`.
+    pub fn bookmarks(&self) -> bool {
+        self.bookmarks
+    }
+
+    /**
+      Time without any events or errors after which a watch is considered
+      stale and restarted, or `None` if disabled. Defaults to 15 minutes.
+
+      Quiet namespaces are re-listed once per threshold, which is cheap.
+    */
+    pub fn stale_threshold(&self) -> Option<Duration> {
+        Some(self.stalethreshold)
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+}
@@ -0,0 +1,203 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+//! Reporting of panics and failing watches to an error webhook.
+//!
+//! Reports are `POST`ed as JSON to the URL configured with
+//! `MICROFEFIND_NOTIFIER_ERRORWEBHOOKURL`, so a dead or failing monitor is
+//! noticed before users see a stale catalogue.
+
+use crossbeam_skiplist::SkipMap;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// The process wide reporter. See [configure].
+static REPORTER: OnceLock<ErrorReporter> = OnceLock::new();
+
+/// Minimum time between two reports of the same kind from the same origin.
+const REPORT_INTERVAL_MILLIS: u64 = 60_000;
+/// Upper bound of the time a panicking thread waits for its report to be delivered.
+const PANIC_REPORT_TIMEOUT: Duration = Duration::from_secs(2);
+
+tokio::task_local! {
+    /// Kind and name of the resource that is currently processed by the task.
+    static SOURCE: String;
+}
+
+/// A single error with the context it occurred in.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorReport {
+    /// Kind of error: `panic`, `task_died` or `watch_error`.
+    pub kind: &'static str,
+    /// Human readable description of the error.
+    pub message: String,
+    /// Name of the supervised task where the error occurred, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    /// Identifier of the cluster where the error occurred, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cluster: Option<String>,
+    /// Kubernetes namespace where the error occurred, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Kind and name of the resource declaring the affected entries, like `Ingress/checkout`, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Source code location of a panic.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    /// Version of the reporting application.
+    pub release: &'static str,
+    /// Time of the error in milliseconds since Unix Epoch.
+    pub timestamp: u64,
+}
+
+/// `POST`s reports to the error webhook.
+struct ErrorReporter {
+    /// Shared HTTP client.
+    http_client: reqwest::Client,
+    /// URL to `POST` reports to.
+    url: String,
+    /// Version of the reporting application.
+    release: &'static str,
+    /// Time of the last report by kind and origin.
+    last_reported: SkipMap<String, u64>,
+}
+
+impl ErrorReporter {
+    /// Return `true` if no report of the same kind from the same origin was sent recently.
+    fn should_report(&self, report: &ErrorReport) -> bool {
+        let key = [
+            report.kind,
+            report.task.as_deref().unwrap_or_default(),
+            report.cluster.as_deref().unwrap_or_default(),
+            report.namespace.as_deref().unwrap_or_default(),
+        ]
+        .join("/");
+        if self
+            .last_reported
+            .get(&key)
+            .is_some_and(|entry| report.timestamp < entry.value() + REPORT_INTERVAL_MILLIS)
+        {
+            return false;
+        }
+        self.last_reported.insert(key, report.timestamp);
+        true
+    }
+}
+
+/**
+Report errors to the webhook at `url` from now on and report all panics.
+
+Only the first call has any effect, since the reporter is process wide.
+ */
+pub fn configure(url: Option<&str>, release: &'static str) {
+    let Some(url) = url else {
+        return;
+    };
+    let reporter = ErrorReporter {
+        http_client: reqwest::Client::new(),
+        url: url.to_owned(),
+        release,
+        last_reported: SkipMap::new(),
+    };
+    if REPORTER.set(reporter).is_err() {
+        log::debug!("Error reporting is already configured.");
+        return;
+    }
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |panic_info| {
+        previous_hook(panic_info);
+        report_panic(ErrorReport {
+            kind: "panic",
+            message: crate::supervisor::panic_message(panic_info.payload()),
+            task: crate::supervisor::current_task_name(),
+            source: SOURCE.try_with(Clone::clone).ok(),
+            location: panic_info.location().map(ToString::to_string),
+            ..ErrorReport::default()
+        });
+    }));
+}
+
+/**
+Report the error in the background.
+
+Does nothing unless an error webhook is configured.
+ */
+pub fn report(mut report: ErrorReport) {
+    let Some(reporter) = REPORTER.get() else {
+        return;
+    };
+    report.release = reporter.release;
+    report.timestamp = crate::time::now_as_millis();
+    if !reporter.should_report(&report) {
+        return;
+    }
+    let request = reporter.http_client.post(&reporter.url).json(&report);
+    let url = reporter.url.to_owned();
+    tokio::spawn(async move {
+        let result = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = result {
+            log::warn!("Failed to report error to webhook '{url}': {e:?}");
+        }
+    });
+}
+
+/**
+Report a panic and wait (bounded) for the delivery.
+
+The report is sent from a separate thread with its own runtime, since a panic
+outside of a task ends the process as soon as the panic hook returns.
+ */
+fn report_panic(mut report: ErrorReport) {
+    let Some(reporter) = REPORTER.get() else {
+        return;
+    };
+    report.release = reporter.release;
+    report.timestamp = crate::time::now_as_millis();
+    let url = reporter.url.to_owned();
+    let delivery = std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| e.to_string())?;
+        runtime.block_on(async {
+            let request = reqwest::Client::new().post(&url).json(&report).send();
+            match tokio::time::timeout(PANIC_REPORT_TIMEOUT, request).await {
+                Ok(Ok(response)) => response
+                    .error_for_status()
+                    .map(|_| ())
+                    .map_err(|e| e.to_string()),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err(format!("No response within {PANIC_REPORT_TIMEOUT:?}.")),
+            }
+        })
+    });
+    if let Ok(Err(e)) = delivery.join() {
+        log::warn!("Failed to report panic to webhook: {e}");
+    }
+}
+
+/// Run the `future` with the kind and name of the resource it processes as context for panic reports.
+pub async fn with_source<Fut: Future>(source: String, future: Fut) -> Fut::Output {
+    SOURCE.scope(source, future).await
+}
@@ -0,0 +1,500 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Offline source of Kubernetes resources loaded from local fixture files.
+//!
+//! Front end developers can run the real discovery API on a laptop without
+//! access to a cluster.
+
+use k8s_openapi::http::{Method, Request, Response, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+
+use crate::kubers_util::LabelSelector;
+
+/// Interval between checks of the fixture directory for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Longest time a watch request is held open when the client has no preference.
+const MAX_WATCH_DURATION: Duration = Duration::from_secs(290);
+
+/// A single object loaded from a fixture file.
+struct FixtureObject {
+    /// URL path of the collection of the object.
+    collection: String,
+    /// URL path of the collection of the object across all namespaces.
+    all_namespaces_collection: String,
+    /// The object without a `resourceVersion`.
+    value: Value,
+}
+
+impl FixtureObject {
+    /// Return the object as served at the `revision`.
+    fn served(&self, revision: u64) -> Value {
+        let mut value = self.value.clone();
+        value["metadata"]["resourceVersion"] = revision.to_string().into();
+        value
+    }
+}
+
+/// All objects loaded from the fixture files at one point in time.
+struct Fixtures {
+    /// Revision of the snapshot, exposed as `resourceVersion`.
+    revision: u64,
+    /// Objects by URL path, like `/api/v1/namespaces/default/services/checkout`.
+    objects: BTreeMap<String, FixtureObject>,
+}
+
+impl Fixtures {
+    /// Return all objects in the collection with the URL path `collection`.
+    fn in_collection<'a>(
+        &'a self,
+        collection: &'a str,
+    ) -> impl Iterator<Item = (&'a String, &'a FixtureObject)> {
+        self.objects.iter().filter(move |(_, object)| {
+            object.collection == collection || object.all_namespaces_collection == collection
+        })
+    }
+}
+
+/**
+Stand-in for a Kubernetes API server that serves list, get and watch requests
+from YAML or JSON manifests in a local directory.
+
+Each file may contain multiple documents and `List`s of objects. Namespaced
+objects without a namespace are placed in `default`. The directory is polled
+for changes and the difference is streamed to open watches.
+ */
+pub struct FixtureApiServer {
+    /// Directory of the fixture files.
+    directory: PathBuf,
+    /// Latest snapshot of the loaded objects.
+    fixtures: watch::Sender<Arc<Fixtures>>,
+}
+
+impl FixtureApiServer {
+    /// Return a new instance serving the fixture files in the `directory`.
+    pub fn new(directory: &Path) -> Arc<Self> {
+        let fixtures = Self::load(directory, 1);
+        Arc::new(Self {
+            directory: directory.to_path_buf(),
+            fixtures: watch::channel(Arc::new(fixtures)).0,
+        })
+    }
+
+    /// Return a new instance without any objects.
+    pub fn empty() -> Arc<Self> {
+        let fixtures = Fixtures {
+            revision: 1,
+            objects: BTreeMap::new(),
+        };
+        Arc::new(Self {
+            directory: PathBuf::new(),
+            fixtures: watch::channel(Arc::new(fixtures)).0,
+        })
+    }
+
+    /// Return a client that sends all requests to this instance.
+    pub fn client(self: &Arc<Self>) -> kube::Client {
+        let self_clone = Arc::clone(self);
+        let service = tower::service_fn(move |request: Request<kube::client::Body>| {
+            let self_clone = Arc::clone(&self_clone);
+            async move { Ok::<_, Infallible>(self_clone.handle(request).await) }
+        });
+        kube::Client::new(service, "default")
+    }
+
+    /// Reload the fixture files whenever they change. Never returns.
+    pub async fn watch_directory(self: Arc<Self>) {
+        let mut fingerprint = Self::fingerprint(&self.directory);
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let current = Self::fingerprint(&self.directory);
+            if current != fingerprint {
+                fingerprint = current;
+                let revision = self.fixtures.borrow().revision + 1;
+                let fixtures = Self::load(&self.directory, revision);
+                self.fixtures.send_replace(Arc::new(fixtures));
+            }
+        }
+    }
+
+    /// Return the YAML and JSON files in the `directory` in name order.
+    fn fixture_files(directory: &Path) -> Vec<PathBuf> {
+        let mut ret = std::fs::read_dir(directory)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| {
+                        path.extension()
+                            .and_then(|extension| extension.to_str())
+                            .is_some_and(|extension| ["yaml", "yml", "json"].contains(&extension))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_else(|e| {
+                log::warn!("Unable to list fixtures in '{}': {e}", directory.display());
+                vec![]
+            });
+        ret.sort();
+        ret
+    }
+
+    /// Return the name, modification time and size of each fixture file.
+    fn fingerprint(directory: &Path) -> Vec<(PathBuf, Option<SystemTime>, u64)> {
+        Self::fixture_files(directory)
+            .into_iter()
+            .map(|path| {
+                let metadata = std::fs::metadata(&path).ok();
+                let modified = metadata
+                    .as_ref()
+                    .and_then(|metadata| metadata.modified().ok());
+                let len = metadata.map(|metadata| metadata.len()).unwrap_or_default();
+                (path, modified, len)
+            })
+            .collect()
+    }
+
+    /// Load all objects from the fixture files in the `directory`.
+    fn load(directory: &Path, revision: u64) -> Fixtures {
+        let mut objects = BTreeMap::new();
+        for path in Self::fixture_files(directory) {
+            let content = match std::fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(e) => {
+                    log::warn!("Unable to read fixture '{}': {e}", path.display());
+                    continue;
+                }
+            };
+            // JSON is valid YAML, so all files are parsed the same way
+            for document in serde_yaml::Deserializer::from_str(&content) {
+                match Value::deserialize(document) {
+                    Ok(Value::Null) => {}
+                    Ok(value) => Self::add_objects(&mut objects, value, &path),
+                    Err(e) => log::warn!(
+                        "Ignoring invalid document in fixture '{}': {e}",
+                        path.display()
+                    ),
+                }
+            }
+        }
+        log::info!(
+            "Loaded {} objects from fixtures in '{}'.",
+            objects.len(),
+            directory.display()
+        );
+        Fixtures { revision, objects }
+    }
+
+    /// Add the object or all items of a `List` to the `objects`.
+    fn add_objects(objects: &mut BTreeMap<String, FixtureObject>, mut value: Value, path: &Path) {
+        if value["kind"]
+            .as_str()
+            .is_some_and(|kind| kind.ends_with("List"))
+        {
+            if let Value::Array(items) = value["items"].take() {
+                for item in items {
+                    Self::add_objects(objects, item, path);
+                }
+            }
+            return;
+        }
+        let (Some(api_version), Some(kind), Some(name)) = (
+            value["apiVersion"].as_str(),
+            value["kind"].as_str(),
+            value["metadata"]["name"].as_str(),
+        ) else {
+            log::warn!(
+                "Ignoring object without apiVersion, kind or metadata.name in fixture '{}'.",
+                path.display()
+            );
+            return;
+        };
+        let api_prefix = if api_version.contains('/') {
+            "/apis/".to_owned() + api_version
+        } else {
+            "/api/".to_owned() + api_version
+        };
+        let plural = Self::plural(kind);
+        let name = name.to_owned();
+        let all_namespaces_collection = api_prefix.to_owned() + "/" + &plural;
+        let collection = if kind == "Namespace" {
+            all_namespaces_collection.to_owned()
+        } else {
+            let namespace = value["metadata"]["namespace"]
+                .as_str()
+                .unwrap_or("default")
+                .to_owned();
+            value["metadata"]["namespace"] = namespace.to_owned().into();
+            api_prefix + "/namespaces/" + &namespace + "/" + &plural
+        };
+        if let Some(metadata) = value["metadata"].as_object_mut() {
+            // The UID must be stable across reloads to track the declaring resource
+            metadata
+                .entry("uid")
+                .or_insert_with(|| format!("fixture:{collection}/{name}").into());
+            metadata.remove("resourceVersion");
+        }
+        objects.insert(
+            collection.to_owned() + "/" + &name,
+            FixtureObject {
+                collection,
+                all_namespaces_collection,
+                value,
+            },
+        );
+    }
+
+    /// Return the resource name of the `kind` as used in URL paths.
+    fn plural(kind: &str) -> String {
+        let kind = kind.to_lowercase();
+        if kind.ends_with('s') {
+            kind + "es"
+        } else if let Some(stem) = kind.strip_suffix('y') {
+            stem.to_owned() + "ies"
+        } else {
+            kind + "s"
+        }
+    }
+
+    /// Serve a single request.
+    async fn handle(&self, request: Request<kube::client::Body>) -> Response<kube::client::Body> {
+        if request.method() != Method::GET {
+            return Self::status_response(
+                StatusCode::METHOD_NOT_ALLOWED,
+                "MethodNotAllowed",
+                "Fixtures are read-only.",
+            );
+        }
+        let path = request.uri().path().trim_end_matches('/');
+        let query = form_urlencoded::parse(request.uri().query().unwrap_or("").as_bytes())
+            .into_owned()
+            .collect::<BTreeMap<String, String>>();
+        let selector = match Selector::from_query(&query) {
+            Ok(selector) => selector,
+            Err(e) => return Self::status_response(StatusCode::BAD_REQUEST, "BadRequest", &e),
+        };
+        if query
+            .get("watch")
+            .is_some_and(|watch| watch == "true" || watch == "1")
+        {
+            let resource_version = query
+                .get("resourceVersion")
+                .and_then(|resource_version| resource_version.parse::<u64>().ok())
+                .filter(|resource_version| *resource_version != 0);
+            let timeout = query
+                .get("timeoutSeconds")
+                .and_then(|timeout| timeout.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(MAX_WATCH_DURATION);
+            return self.watch(path, &selector, resource_version, timeout).await;
+        }
+        let fixtures = Arc::clone(&self.fixtures.borrow());
+        // Paths below `/api/{version}` and `/apis/{group}/{version}` alternate
+        // between collections and names
+        let api_depth = if path.starts_with("/apis/") { 3 } else { 2 };
+        let depth = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .count();
+        if depth > api_depth && (depth - api_depth) % 2 == 0 {
+            return match fixtures.objects.get(path) {
+                Some(object) => Self::json_response(&object.served(fixtures.revision)),
+                None => Self::status_response(
+                    StatusCode::NOT_FOUND,
+                    "NotFound",
+                    &format!("'{path}' is not declared in any fixture."),
+                ),
+            };
+        }
+        let items = fixtures
+            .in_collection(path)
+            .filter(|(_, object)| selector.matches(&object.value))
+            .map(|(_, object)| object.served(fixtures.revision))
+            .collect::<Vec<_>>();
+        Self::json_response(&json!({
+            "apiVersion": "v1",
+            "kind": "List",
+            "metadata": {
+                "resourceVersion": fixtures.revision.to_string(),
+            },
+            "items": items,
+        }))
+    }
+
+    /**
+      Wait for the next change of the fixtures and respond with the changes of
+      matching objects in the `collection`.
+
+      Watches from any other `resource_version` than the current are expired,
+      so the client will list the collection again.
+    */
+    async fn watch(
+        &self,
+        collection: &str,
+        selector: &Selector,
+        resource_version: Option<u64>,
+        timeout: Duration,
+    ) -> Response<kube::client::Body> {
+        let mut receiver = self.fixtures.subscribe();
+        let before = Arc::clone(&receiver.borrow_and_update());
+        if resource_version.is_some_and(|resource_version| resource_version != before.revision) {
+            return Self::events_response(&[json!({
+                "type": "ERROR",
+                "object": {
+                    "apiVersion": "v1",
+                    "kind": "Status",
+                    "metadata": {},
+                    "status": "Failure",
+                    "message": "too old resource version",
+                    "reason": "Expired",
+                    "code": 410,
+                },
+            })]);
+        }
+        if !matches!(
+            tokio::time::timeout(timeout, receiver.changed()).await,
+            Ok(Ok(()))
+        ) {
+            return Self::events_response(&[]);
+        }
+        let after = Arc::clone(&receiver.borrow());
+        let paths = before
+            .in_collection(collection)
+            .chain(after.in_collection(collection))
+            .map(|(path, _)| path)
+            .collect::<BTreeSet<_>>();
+        let mut events = vec![];
+        for path in paths {
+            let old = before
+                .objects
+                .get(path)
+                .filter(|object| selector.matches(&object.value));
+            let new = after
+                .objects
+                .get(path)
+                .filter(|object| selector.matches(&object.value));
+            let (event_type, object) = match (old, new) {
+                (None, Some(new)) => ("ADDED", new),
+                (Some(old), Some(new)) if old.value != new.value => ("MODIFIED", new),
+                (Some(old), None) => ("DELETED", old),
+                _ => continue,
+            };
+            events.push(json!({"type": event_type, "object": object.served(after.revision)}));
+        }
+        // Let the client resume from the new revision even without any matching changes
+        events.push(json!({
+            "type": "BOOKMARK",
+            "object": {
+                "apiVersion": "v1",
+                "kind": "Bookmark",
+                "metadata": {
+                    "resourceVersion": after.revision.to_string(),
+                },
+            },
+        }));
+        Self::events_response(&events)
+    }
+
+    /// Return a response with the `value` as body.
+    fn json_response(value: &Value) -> Response<kube::client::Body> {
+        Response::new(kube::client::Body::from(value.to_string().into_bytes()))
+    }
+
+    /// Return a response with one line per watch event.
+    fn events_response(events: &[Value]) -> Response<kube::client::Body> {
+        let body = events
+            .iter()
+            .map(|event| event.to_string() + "\n")
+            .collect::<String>();
+        Response::new(kube::client::Body::from(body.into_bytes()))
+    }
+
+    /// Return an error response with a Kubernetes `Status` body.
+    fn status_response(
+        status: StatusCode,
+        reason: &str,
+        message: &str,
+    ) -> Response<kube::client::Body> {
+        let mut response = Self::json_response(&json!({
+            "apiVersion": "v1",
+            "kind": "Status",
+            "metadata": {},
+            "status": "Failure",
+            "message": message,
+            "reason": reason,
+            "code": status.as_u16(),
+        }));
+        *response.status_mut() = status;
+        response
+    }
+}
+
+/// Label and field selector of a list or watch request.
+struct Selector {
+    /// Requirements on the labels of the object.
+    labels: LabelSelector,
+    /// Required values of fields like `metadata.name`.
+    fields: Vec<(String, String)>,
+}
+
+impl Selector {
+    /// Parse the `labelSelector` and `fieldSelector` of the request.
+    fn from_query(query: &BTreeMap<String, String>) -> Result<Self, String> {
+        let labels = query
+            .get("labelSelector")
+            .map(|selector| selector.parse::<LabelSelector>())
+            .transpose()?
+            .unwrap_or_default();
+        let fields = query
+            .get("fieldSelector")
+            .into_iter()
+            .flat_map(|selector| selector.split(','))
+            .filter(|requirement| !requirement.is_empty())
+            .map(|requirement| {
+                if requirement.contains("!=") {
+                    return Err(format!("Unsupported field selector '{requirement}'."));
+                }
+                requirement
+                    .split_once('=')
+                    .map(|(key, value)| (key.to_owned(), value.trim_start_matches('=').to_owned()))
+                    .ok_or(format!("Invalid field selector '{requirement}'."))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { labels, fields })
+    }
+
+    /// Return `true` if the object matches both selectors.
+    fn matches(&self, object: &Value) -> bool {
+        let labels = serde_json::from_value::<BTreeMap<String, String>>(
+            object["metadata"]["labels"].to_owned(),
+        )
+        .unwrap_or_default();
+        self.labels.matches(&labels)
+            && self.fields.iter().all(|(key, value)| {
+                let field = key
+                    .split('.')
+                    .fold(object, |object, segment| &object[segment]);
+                field.as_str() == Some(value)
+            })
+    }
+}
@@ -17,21 +17,127 @@
 
 //! Monitor configured namespaces in Kubernetes for labeled `Ingress`es.
 
+mod admission;
+mod annotation_schema;
+mod compatibility;
+mod dependency_graph;
+mod dry_run;
+mod event_recorder;
+mod host_path_claims;
 mod ingress_host_path;
+mod ingress_rules;
+mod kube_cluster;
+mod manifest_cache;
+mod micro_frontend;
+mod namespace_defaults;
+mod namespace_discovery;
+mod namespace_health;
+mod path_rewrite;
+mod peer_catalogues;
+mod reachability;
+mod resource_errors;
+mod source_object;
+mod state_transfer;
+mod variant;
 
+use arc_swap::ArcSwap;
 use crossbeam_skiplist::SkipMap;
 use futures::TryStreamExt;
 use k8s_openapi::api::networking::v1::Ingress;
 use kube::api::ListParams;
-use kube::runtime::watcher::Config;
 use kube::Api;
 use kube::ResourceExt;
-use std::sync::atomic::AtomicBool;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use crate::backoff::WatchSettings;
 use crate::conf::AppConfig;
+use crate::conf::ReadinessPolicy;
+use crate::error_reporting::ErrorReport;
+use crate::fixtures::FixtureApiServer;
+use crate::kubers_util::LabelSelector;
+use crate::notifier::{ChangeFeed, ChangeFeedPage, ChangeKind, Notifier};
+use crate::supervisor::Supervisor;
+use crate::time::Clock;
 
+use self::event_recorder::EventRecorder;
+use self::host_path_claims::HostPathClaims;
+use self::ingress_host_path::AnnotationSources;
+use self::ingress_rules::SkippedRulePart;
+use self::manifest_cache::ManifestCache;
+use self::namespace_health::NamespaceHealthRegistry;
+use self::path_rewrite::PathRewrite;
+use self::peer_catalogues::PeerCatalogues;
+use self::reachability::Prober;
+use self::resource_errors::ResourceErrorRegistry;
+use self::variant::Variant;
+
+pub use self::annotation_schema::AnnotationSchema;
+pub use self::compatibility::CompatibilityReport;
+pub use self::compatibility::RequirementConflict;
+pub use self::compatibility::UnmetRequirement;
+pub use self::dependency_graph::DeclaredModule;
+pub use self::dependency_graph::DependencyGraph;
+pub use self::dependency_graph::ModuleNode;
+pub use self::dry_run::DryRunEntry;
+pub use self::dry_run::DryRunReport;
+pub use self::dry_run::DryRunSkipped;
+pub use self::ingress_host_path::DeploymentRollout;
+pub use self::ingress_host_path::EntryChange;
 pub use self::ingress_host_path::IngressHostPath;
+pub use self::ingress_host_path::LifecycleEvent;
+pub use self::ingress_host_path::LifecycleState;
+pub use self::ingress_host_path::LifecycleTransition;
+pub use self::ingress_host_path::PathType;
+pub use self::ingress_host_path::RolloutState;
+pub use self::ingress_host_path::GROUP_ANNOTATION;
+pub use self::ingress_host_path::PRIORITY_ANNOTATION;
+pub use self::ingress_host_path::TEAM_ANNOTATION;
+pub use self::kube_cluster::KubeCluster;
+pub use self::namespace_health::NamespaceHealth;
+pub use self::path_rewrite::RewrittenPath;
+pub use self::reachability::HealthProbeResult;
+pub use self::reachability::ProbeResult;
+pub use self::resource_errors::count_by_namespace;
+pub use self::resource_errors::ResourceError;
+pub use self::source_object::SourceObject;
+pub use self::state_transfer::HostPathState;
+pub use self::state_transfer::ImportReport;
+pub use self::state_transfer::MonitorState;
+pub use self::variant::PRIMARY_VARIANT;
+
+/// Declaration of a single hostname + path combination by a watched resource.
+struct HostPathDeclaration<'a> {
+    /// Hostname of the declaration.
+    host: &'a str,
+    /// Path of the declaration.
+    path: &'a str,
+    /// How the path is matched.
+    path_type: PathType,
+    /// Kubernetes namespace of the declaring resource.
+    namespace: &'a str,
+    /// Name of the `Service` serving the hostname + path.
+    service_name: &'a str,
+    /// Meta-data exposed to API clients.
+    annotations: &'a SkipMap<String, String>,
+    /// Prefixed labels exposed to API clients.
+    labels: &'a BTreeMap<String, String>,
+    /// Client-facing and backend path if the Ingress controller rewrites the path.
+    rewritten: Option<RewrittenPath>,
+    /// `true` if the hostname is served over HTTPS.
+    tls: bool,
+    /// Variant of the hostname + path served by the declaring resource.
+    variant: &'a Variant,
+    /// Kind and name of the declaring resource, like `Ingress/checkout`.
+    source: &'a str,
+    /// Identity and version of the declaring resource.
+    source_object: &'a SourceObject,
+    /// Creation time of the declaring resource in milliseconds since Unix Epoch.
+    created_millis: u64,
+}
 
 /**
 Object instance monitors (watches) configured namespaces in Kubernetes for
@@ -43,193 +149,1769 @@ also owns monitoring of related `Service`s and `Pod`s.
 pub struct IngressMonitor {
     /// Reference to the application's configuration.
     app_config: Arc<AppConfig>,
-    /// Thread safe boolean used to indicate application readyness.
+    /// Configuration applied by the last reload. See [Self::reload_config].
+    running_config: ArcSwap<AppConfig>,
+    /// Thread safe boolean used to indicate that any namespace has been listed.
     health_ready: AtomicBool,
-    /// Map of hostname + path combinations and the full meta-data object.
+    /// Monotonic time when the monitoring started.
+    started: Instant,
+    /// `false` if an `Ingress` watch is silent even after being restarted.
+    watches_live: AtomicBool,
+    /// `true` if connecting to the local cluster was given up at startup.
+    startup_failed: AtomicBool,
+    /// Notified when connecting to the local cluster was given up at startup.
+    startup_failure: tokio::sync::Notify,
+    /// Map of cluster + hostname + path combinations and the full meta-data object.
     monitored_ingress_host_paths: SkipMap<String, Arc<IngressHostPath>>,
+    /// Counter that is incremented whenever an entry is added, updated or removed.
+    catalogue_revision: Arc<AtomicU64>,
+    /// Names of namespace monitoring tasks started by namespace discovery by
+    /// cluster and namespace.
+    monitored_namespaces: SkipMap<String, Vec<String>>,
+    /// Owner of all long running monitoring tasks.
+    supervisor: Arc<Supervisor>,
+    /// Clusters where monitoring has started by cluster identifier.
+    clusters: SkipMap<String, Arc<KubeCluster>>,
+    /// Recently deleted entries and the monotonic time of deletion by the same
+    /// key as the monitored entries.
+    tombstones: SkipMap<String, (Instant, Arc<IngressHostPath>)>,
+    /// Source of the current time.
+    clock: Arc<dyn Clock>,
+    /// Schema that exposed annotations are validated against, if configured.
+    annotation_schema: Option<Arc<AnnotationSchema>>,
+    /// Non-fatal problems found in watched resources.
+    resource_errors: ResourceErrorRegistry,
+    /// Publishes Kubernetes `Event`s on the resources declaring entries.
+    event_recorder: EventRecorder,
+    /// Health of the monitoring of each namespace.
+    namespace_health: NamespaceHealthRegistry,
+    /// Declarations of each entry by all declaring resources.
+    host_path_claims: HostPathClaims,
+    /// Source and keys of the entries declared by each `Ingress` by cluster +
+    /// namespace + UID.
+    ingress_keys: SkipMap<String, (String, Vec<String>)>,
+    /// Sink for changes of entries.
+    notifier: Arc<dyn Notifier>,
+    /// Sequenced recent changes that also forwards to the configured notifiers.
+    change_feed: Arc<ChangeFeed>,
+    /// Asset manifests referenced by entries.
+    manifests: ManifestCache,
+    /// Catalogues fetched from upstream `microfefind` instances.
+    peers: PeerCatalogues,
+    /// Prefixed annotations of each monitored `Namespace` by cluster + namespace.
+    namespace_defaults: SkipMap<String, BTreeMap<String, String>>,
+    /// Declared entries that are not cataloged since an entry limit is reached
+    /// by the same key as the monitored entries.
+    entry_overflows: SkipMap<String, ResourceError>,
+    /// Total number of declared entries that were not cataloged due to an entry limit.
+    entry_overflows_total: AtomicU64,
 }
 
 impl IngressMonitor {
-    /// Return a new instance.
+    /// Return a new instance monitoring all configured clusters.
     pub fn new(app_config: Arc<AppConfig>) -> Arc<Self> {
+        Self::new_unstarted(app_config, crate::time::system_clock()).start_background_monitoring()
+    }
+
+    /**
+      Return a new instance monitoring only the provided `clusters` instead of
+      the configured ones.
+    */
+    pub fn with_clusters(app_config: Arc<AppConfig>, clusters: &[Arc<KubeCluster>]) -> Arc<Self> {
+        Self::with_clusters_and_clock(app_config, clusters, crate::time::system_clock())
+    }
+
+    /**
+      Return a new instance monitoring only the provided `clusters` that gets
+      the current time from the `clock`, e.g. a [MockClock](crate::time::MockClock)
+      in tests.
+    */
+    pub fn with_clusters_and_clock(
+        app_config: Arc<AppConfig>,
+        clusters: &[Arc<KubeCluster>],
+        clock: Arc<dyn Clock>,
+    ) -> Arc<Self> {
+        let ret = Self::new_unstarted(app_config, clock);
+        clusters
+            .iter()
+            .for_each(|cluster| ret.start_cluster_monitoring(cluster));
+        ret.start_tombstone_purging()
+            .start_watchdog()
+            .start_probing()
+            .start_manifest_fetching()
+            .start_peer_fetching()
+    }
+
+    /// Return a new instance without any started monitoring.
+    fn new_unstarted(app_config: Arc<AppConfig>, clock: Arc<dyn Clock>) -> Arc<Self> {
+        crate::backoff::configure(WatchSettings {
+            initial_backoff: app_config.watcher.initial_backoff(),
+            max_backoff: app_config.watcher.max_backoff(),
+            jitter: app_config.watcher.jitter(),
+            page_size: app_config.watcher.page_size(),
+            bookmarks: app_config.watcher.bookmarks(),
+        });
+        crate::error_reporting::configure(
+            app_config.notifier.error_webhook_url(),
+            app_config.app_version(),
+        );
+        crate::watch_recording::configure(
+            app_config.debug.record_events_path(),
+            app_config.debug.replay_events_path(),
+        );
+        let annotation_schema = app_config.catalogue.annotation_schema();
+        let change_feed = Arc::new(ChangeFeed::with_memory_budget(
+            crate::notifier::from_config(&app_config),
+            app_config.catalogue.change_feed_size(),
+            app_config.limits.change_feed_budget_bytes(),
+        ));
+        let notifier: Arc<dyn Notifier> = change_feed.clone();
+        let manifests = ManifestCache::new(
+            app_config.catalogue.manifest_max_size(),
+            app_config.limits.manifest_cache_budget_bytes(),
+        );
+        let peers = PeerCatalogues::new(app_config.federation.timeout());
+        let event_recorder = EventRecorder::new(app_config.notifier.kubernetes_events());
         Arc::new(Self {
+            running_config: ArcSwap::new(Arc::clone(&app_config)),
             app_config,
             health_ready: AtomicBool::new(false),
+            started: clock.instant(),
+            watches_live: AtomicBool::new(true),
+            startup_failed: AtomicBool::new(false),
+            startup_failure: tokio::sync::Notify::new(),
             monitored_ingress_host_paths: SkipMap::new(),
+            catalogue_revision: Arc::new(AtomicU64::new(0)),
+            monitored_namespaces: SkipMap::new(),
+            supervisor: Supervisor::new(),
+            clusters: SkipMap::new(),
+            tombstones: SkipMap::new(),
+            clock,
+            annotation_schema,
+            resource_errors: ResourceErrorRegistry::default(),
+            event_recorder,
+            namespace_health: NamespaceHealthRegistry::default(),
+            host_path_claims: HostPathClaims::default(),
+            ingress_keys: SkipMap::new(),
+            notifier,
+            change_feed,
+            manifests,
+            peers,
+            namespace_defaults: SkipMap::new(),
+            entry_overflows: SkipMap::new(),
+            entry_overflows_total: AtomicU64::new(0),
         })
-        .start_background_monitoring()
     }
 
-    /// Return true if the [IngressMonitor] has started.
+    /**
+       Return true if the [IngressMonitor] has started.
+
+       This is `true` while connecting to an unavailable Kubernetes API is
+       retried at startup, since the app is then running but not ready.
+    */
     pub fn is_health_started(self: &Arc<Self>) -> bool {
-        self.health_ready.load(std::sync::atomic::Ordering::Relaxed)
+        !self.startup_failed.load(Ordering::Relaxed)
     }
 
-    /// Return true if the [IngressMonitor] is ready to serve requests.
+    /**
+       Return true if the [IngressMonitor] is ready to serve requests.
+
+       This requires that enough of the monitored namespaces have been listed
+       according to the configured [ReadinessPolicy], or that any namespace has
+       been listed and the configured readiness timeout has passed since the
+       monitoring started.
+    */
     pub fn is_health_ready(self: &Arc<Self>) -> bool {
-        self.health_ready.load(std::sync::atomic::Ordering::Relaxed)
+        if !self.health_ready.load(Ordering::Relaxed) {
+            return false;
+        }
+        let namespace_health = self.namespace_health.get_all();
+        let total = namespace_health.len();
+        let listed = namespace_health
+            .iter()
+            .filter(|namespace_health| namespace_health.last_event_millis.is_some())
+            .count();
+        let policy_fulfilled = match self.app_config.health.readiness_policy() {
+            ReadinessPolicy::All => listed == total,
+            ReadinessPolicy::Any => true,
+            ReadinessPolicy::AtLeast(count) => listed >= std::cmp::min(count, total),
+        };
+        policy_fulfilled
+            || self
+                .app_config
+                .health
+                .readiness_timeout()
+                .is_some_and(|timeout| {
+                    self.clock.instant().saturating_duration_since(self.started) >= timeout
+                })
     }
 
     /**
        Return true if the [IngressMonitor] is still able to serve relevant data.
 
-       *NOTE: This always returns `true`, even if the application is locked out
-       of one of the configured namespaces to prevent a single µFE namespace
-       owner to DoS the entire application.*
+       This is `false` when a supervised monitoring task keeps dying or when
+       an `Ingress` watch is wedged, i.e. has been silent for longer than the
+       configured stale threshold even after a restart.
+
+       *NOTE: Being locked out of one of the configured namespaces does not
+       affect liveness to prevent a single µFE namespace owner to DoS the
+       entire application.*
     */
     pub fn is_health_live(self: &Arc<Self>) -> bool {
-        true
+        !self.startup_failed.load(Ordering::Relaxed)
+            && self.watches_live.load(Ordering::Relaxed)
+            && self.supervisor.is_healthy()
+    }
+
+    /**
+       Wait until connecting to the local cluster is given up, because the
+       configured startup timeout was exceeded.
+
+       This never completes when the local cluster is reachable.
+    */
+    pub async fn startup_failed(&self) {
+        self.startup_failure.notified().await
+    }
+
+    /// Return the health of the monitoring of each namespace ordered by cluster and namespace.
+    pub fn namespace_health(&self) -> Vec<NamespaceHealth> {
+        self.namespace_health.get_all()
     }
 
-    /// Start background monitoring of all configured namespaces
+    /// Return the owner of all long running monitoring tasks.
+    pub fn supervisor(&self) -> &Arc<Supervisor> {
+        &self.supervisor
+    }
+
+    /// Start background monitoring of all configured clusters.
     fn start_background_monitoring(self: Arc<Self>) -> Arc<Self> {
+        let local_id = self.app_config.clusters.local_id().to_owned();
+        if self.app_config.source.is_static() {
+            let fixtures = FixtureApiServer::new(self.app_config.source.fixtures_path());
+            let fixtures_clone = Arc::clone(&fixtures);
+            self.supervisor.spawn("fixtures", move || {
+                Arc::clone(&fixtures_clone).watch_directory()
+            });
+            log::info!(
+                "Serving fixtures from '{}' as cluster '{local_id}'.",
+                self.app_config.source.fixtures_path().display()
+            );
+            self.start_cluster_monitoring(&KubeCluster::from_client(&local_id, fixtures.client()));
+            return self
+                .start_tombstone_purging()
+                .start_watchdog()
+                .start_probing()
+                .start_manifest_fetching()
+                .start_peer_fetching();
+        }
+        if crate::watch_recording::is_replaying() {
+            // Only lists and gets outside of watches reach the (empty) API server
+            log::info!("Replaying recorded watch events as cluster '{local_id}'.");
+            let client = FixtureApiServer::empty().client();
+            self.start_cluster_monitoring(&KubeCluster::from_client(&local_id, client));
+            return self
+                .start_tombstone_purging()
+                .start_watchdog()
+                .start_probing()
+                .start_manifest_fetching()
+                .start_peer_fetching();
+        }
+        let self_clone = Arc::clone(&self);
+        self.supervisor
+            .spawn(&("cluster/".to_owned() + &local_id), move || {
+                let self_clone = Arc::clone(&self_clone);
+                let local_id = local_id.clone();
+                async move {
+                    match self_clone.connect_local_cluster(&local_id).await {
+                        Some(cluster) => self_clone.start_cluster_monitoring(&cluster),
+                        None => {
+                            self_clone.startup_failed.store(true, Ordering::Relaxed);
+                            self_clone.startup_failure.notify_one();
+                        }
+                    }
+                }
+            });
+        for additional in self.app_config.clusters.additional() {
+            let self_clone = Arc::clone(&self);
+            let task_name = "cluster/".to_owned() + &additional.id;
+            self.supervisor.spawn(&task_name, move || {
+                let self_clone = Arc::clone(&self_clone);
+                let additional = additional.clone();
+                async move {
+                    let cluster_result = KubeCluster::new(
+                        &additional.id,
+                        Some(&additional.kubeconfig_path),
+                        additional.context.as_deref(),
+                        &self_clone.app_config.kube,
+                    )
+                    .await;
+                    match cluster_result {
+                        Ok(cluster) => {
+                            log::info!("Monitoring additional cluster '{}'.", additional.id);
+                            self_clone.start_cluster_monitoring(&cluster)
+                        }
+                        Err(e) => {
+                            log::warn!("Unable to monitor cluster '{}': {e:?}", additional.id)
+                        }
+                    }
+                }
+            });
+        }
+        self.start_tombstone_purging()
+            .start_watchdog()
+            .start_probing()
+            .start_manifest_fetching()
+            .start_peer_fetching()
+    }
+
+    /**
+      Connect to the local cluster and retry with backoff while the Kubernetes
+      API is unavailable.
+
+      Returns `None` if the configured startup timeout is exceeded.
+    */
+    async fn connect_local_cluster(&self, local_id: &str) -> Option<Arc<KubeCluster>> {
+        let deadline = self
+            .app_config
+            .clusters
+            .startup_timeout()
+            .map(|timeout| Instant::now() + timeout);
+        let mut backoff = crate::backoff::ExponentialBackoff::default();
+        loop {
+            let e = match KubeCluster::local(local_id, &self.app_config.kube).await {
+                Ok(cluster) => match cluster.client().apiserver_version().await {
+                    Ok(info) => {
+                        log::info!("Kubernetes API version: {info:?}");
+                        return Some(cluster);
+                    }
+                    Err(e) => Box::new(e) as Box<dyn std::error::Error + Send + Sync>,
+                },
+                Err(e) => e,
+            };
+            let delay = backoff.next_delay();
+            if deadline.is_some_and(|deadline| Instant::now() + delay > deadline) {
+                log::error!(
+                    "Failed to access Kubernetes API of cluster '{local_id}'. Is this container deployed? {e:?}"
+                );
+                return None;
+            }
+            log::warn!(
+                "Retrying access to Kubernetes API of cluster '{local_id}' in {delay:?}: {e:?}"
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Start background removal of expired tombstones.
+    fn start_tombstone_purging(self: Arc<Self>) -> Arc<Self> {
+        let self_clone = Arc::clone(&self);
+        self.supervisor.spawn("tombstones", move || {
+            let self_clone = Arc::clone(&self_clone);
+            async move {
+                loop {
+                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                    self_clone.purge_expired_tombstones();
+                }
+            }
+        });
+        self
+    }
+
+    /**
+      Start background restarts of `Ingress` watches that have neither received
+      any events nor failed within the configured stale threshold.
+
+      A watch that is still silent a full threshold after the restart is
+      considered wedged and fails the liveness check.
+    */
+    fn start_watchdog(self: Arc<Self>) -> Arc<Self> {
+        let Some(threshold) = self.app_config.watcher.stale_threshold() else {
+            return self;
+        };
+        let self_clone = Arc::clone(&self);
+        self.supervisor.spawn("watchdog", move || {
+            let self_clone = Arc::clone(&self_clone);
+            async move {
+                let interval = std::cmp::max(threshold / 10, Duration::from_secs(1));
+                let mut restarted = HashMap::new();
+                loop {
+                    tokio::time::sleep(interval).await;
+                    self_clone.restart_stale_watches(threshold, &mut restarted);
+                }
+            }
+        });
+        self
+    }
+
+    /**
+      Start background probing of the URL of all entries and of health
+      endpoints declared by entries, if enabled.
+    */
+    fn start_probing(self: Arc<Self>) -> Arc<Self> {
+        if !self.app_config.probe.enabled() && !self.app_config.probe.health_paths() {
+            return self;
+        }
+        let prober = Arc::new(Prober::new(&self.app_config.probe));
+        let interval = self.app_config.probe.interval();
+        let self_clone = Arc::clone(&self);
+        self.supervisor.spawn("probes", move || {
+            let self_clone = Arc::clone(&self_clone);
+            let prober = Arc::clone(&prober);
+            async move {
+                loop {
+                    prober
+                        .probe_all(
+                            self_clone.get_all(),
+                            self_clone.app_config.clusters.local_id(),
+                            self_clone.clock.now_as_millis(),
+                        )
+                        .await;
+                    tokio::time::sleep(interval).await;
+                }
+            }
+        });
+        self
+    }
+
+    /**
+      Start background fetching and revalidation of the asset manifests
+      referenced by entries, if enabled.
+    */
+    fn start_manifest_fetching(self: Arc<Self>) -> Arc<Self> {
+        let Some(refresh) = self.app_config.catalogue.manifest_refresh() else {
+            return self;
+        };
+        let self_clone = Arc::clone(&self);
+        self.supervisor.spawn("manifests", move || {
+            let self_clone = Arc::clone(&self_clone);
+            async move {
+                let interval = std::cmp::min(refresh, Duration::from_secs(5));
+                loop {
+                    let mut urls = vec![];
+                    for entry in self_clone.get_all() {
+                        if let Some(url) = entry.manifest_url().await {
+                            if !urls.contains(&url) {
+                                urls.push(url);
+                            }
+                        }
+                    }
+                    self_clone.manifests.refresh(urls, refresh).await;
+                    tokio::time::sleep(interval).await;
+                }
+            }
+        });
+        self
+    }
+
+    /// Start periodic fetching of the catalogues of configured upstream instances.
+    fn start_peer_fetching(self: Arc<Self>) -> Arc<Self> {
+        let peers = self.app_config.federation.peers();
+        if peers.is_empty() {
+            return self;
+        }
+        let interval = self.app_config.federation.interval();
+        let self_clone = Arc::clone(&self);
+        self.supervisor.spawn("peers", move || {
+            let self_clone = Arc::clone(&self_clone);
+            let peers = peers.clone();
+            async move {
+                loop {
+                    self_clone.peers.refresh(&peers).await;
+                    tokio::time::sleep(interval).await;
+                }
+            }
+        });
+        self
+    }
+
+    /**
+      Return the entries most recently fetched from each upstream
+      `microfefind` instance by origin.
+    */
+    pub fn peer_entries(self: &Arc<Self>) -> Vec<(String, Vec<serde_json::Value>)> {
+        self.peers.get_all()
+    }
+
+    /**
+      Return the cached asset manifest referenced by the entry, if any has
+      been fetched.
+    */
+    pub async fn manifest(
+        self: &Arc<Self>,
+        entry: &Arc<IngressHostPath>,
+    ) -> Option<serde_json::Value> {
+        entry
+            .manifest_url()
+            .await
+            .and_then(|url| self.manifests.get(&url))
+    }
+
+    /**
+      Restart `Ingress` watches that have been silent for the `threshold`.
+
+      `restarted` tracks when each watch was restarted by task name.
+    */
+    fn restart_stale_watches(&self, threshold: Duration, restarted: &mut HashMap<String, u64>) {
+        let now_millis = self.clock.now_as_millis();
+        let threshold_millis = u64::try_from(threshold.as_millis()).unwrap_or(u64::MAX);
+        let mut wedged = false;
+        let mut tracked = vec![];
+        for namespace_health in self.namespace_health.get_all() {
+            let task_name = format!(
+                "ingresses/{}/{}",
+                namespace_health.cluster, namespace_health.namespace
+            );
+            tracked.push(task_name.to_owned());
+            let silent_millis = now_millis.saturating_sub(namespace_health.last_activity_millis());
+            if silent_millis < threshold_millis {
+                restarted.remove(&task_name);
+                continue;
+            }
+            match restarted.get(&task_name) {
+                Some(restarted_millis) => {
+                    if now_millis.saturating_sub(*restarted_millis) >= threshold_millis {
+                        log::error!(
+                            "Watch '{task_name}' is still silent {}s after a restart.",
+                            silent_millis / 1000
+                        );
+                        wedged = true;
+                    }
+                }
+                None => {
+                    log::warn!(
+                        "Restarting watch '{task_name}' that has been silent for {}s.",
+                        silent_millis / 1000
+                    );
+                    self.supervisor.restart(&task_name);
+                    restarted.insert(task_name, now_millis);
+                }
+            }
+        }
+        restarted.retain(|task_name, _| tracked.contains(task_name));
+        self.watches_live.store(!wedged, Ordering::Relaxed);
+    }
+
+    /// Return the local cluster once connected.
+    pub fn local_cluster(self: &Arc<Self>) -> Option<Arc<KubeCluster>> {
+        self.clusters
+            .get(self.app_config.clusters.local_id())
+            .map(|entry| Arc::clone(entry.value()))
+    }
+
+    /// Start background monitoring of all configured namespaces in the cluster.
+    fn start_cluster_monitoring(self: &Arc<Self>, cluster: &Arc<KubeCluster>) {
+        self.clusters
+            .insert(cluster.id().to_owned(), Arc::clone(cluster));
+        if let Some(namespace_labels) = self.app_config.ingress.namespace_labels() {
+            let namespace_labels = namespace_labels.to_owned();
+            let self_clone = Arc::clone(self);
+            let cluster_clone = Arc::clone(cluster);
+            self.supervisor
+                .spawn(&format!("namespaces/{}", cluster.id()), move || {
+                    let self_clone = Arc::clone(&self_clone);
+                    let cluster = Arc::clone(&cluster_clone);
+                    let namespace_labels = namespace_labels.clone();
+                    async move {
+                        self_clone
+                            .watch_namespaces(&cluster, &namespace_labels)
+                            .await
+                    }
+                });
+            return;
+        }
         let namespaces = self.app_config.ingress.namespaces();
         if namespaces.is_empty() {
-            let self_clone = Arc::clone(&self);
-            tokio::spawn(async move { self_clone.watch_ingresses(None).await });
+            self.start_namespace_monitoring(cluster, None);
         } else {
             for namespace in namespaces {
-                let self_clone = Arc::clone(&self);
-                tokio::spawn(async move {
+                self.start_namespace_monitoring(cluster, Some(namespace));
+            }
+        }
+    }
+
+    /**
+      Start background monitoring of a single namespace. `None` will monitor
+      the context namespace.
+
+      Returns the names of the supervised tasks that can be used to abort the
+      monitoring.
+    */
+    fn start_namespace_monitoring(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        namespace: Option<String>,
+    ) -> Vec<String> {
+        let mut task_names = vec![];
+        let ingress_namespace_name = namespace
+            .as_deref()
+            .unwrap_or(cluster.client().default_namespace())
+            .to_owned();
+        let task_suffix = cluster.id().to_owned() + "/" + &ingress_namespace_name;
+        let task_name = "ingresses/".to_owned() + &task_suffix;
+        let self_clone = Arc::clone(self);
+        let cluster_clone = Arc::clone(cluster);
+        let ingress_namespace = namespace.clone();
+        self.supervisor.spawn(&task_name, move || {
+            let self_clone = Arc::clone(&self_clone);
+            let cluster = Arc::clone(&cluster_clone);
+            let namespace = ingress_namespace.clone();
+            async move { self_clone.watch_ingresses(&cluster, namespace).await }
+        });
+        task_names.push(task_name);
+        if self.app_config.operator.enabled() {
+            let task_name = "microfrontends/".to_owned() + &task_suffix;
+            let self_clone = Arc::clone(self);
+            let cluster_clone = Arc::clone(cluster);
+            self.supervisor.spawn(&task_name, move || {
+                let self_clone = Arc::clone(&self_clone);
+                let cluster = Arc::clone(&cluster_clone);
+                let namespace = namespace.clone();
+                async move { self_clone.watch_micro_frontends(&cluster, namespace).await }
+            });
+            task_names.push(task_name);
+        }
+        if self.app_config.ingress.namespace_defaults() {
+            let task_name = "namespacedefaults/".to_owned() + &task_suffix;
+            let self_clone = Arc::clone(self);
+            let cluster_clone = Arc::clone(cluster);
+            let namespace = ingress_namespace_name.clone();
+            self.supervisor.spawn(&task_name, move || {
+                let self_clone = Arc::clone(&self_clone);
+                let cluster = Arc::clone(&cluster_clone);
+                let namespace = namespace.clone();
+                async move {
                     self_clone
-                        .watch_ingresses(Some(namespace.to_string()))
+                        .watch_namespace_defaults(&cluster, &namespace)
                         .await
-                });
-            }
+                }
+            });
+            task_names.push(task_name);
         }
-        self
+        task_names
     }
 
     /**
       Watch all `Ingress` objects for changes and load all pre-existing
       `Ingress`es in the namespace.
+
+      The watch is restarted with backoff on errors, which re-lists all
+      `Ingress`es in the namespace.
     */
-    async fn watch_ingresses(self: &Arc<Self>, namespace: Option<String>) {
-        let label_selector = &self.app_config.ingress.match_labels();
-        let client = kube::Client::try_default().await.unwrap();
-        let namespace = namespace.unwrap_or(client.default_namespace().to_owned());
+    async fn watch_ingresses(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        namespace: Option<String>,
+    ) {
+        let namespace = namespace.unwrap_or(cluster.client().default_namespace().to_owned());
+        let description = format!(
+            "monitoring of Ingresses in namespace '{namespace}' of cluster '{}'",
+            cluster.id()
+        );
+        self.namespace_health
+            .register(cluster.id(), &namespace, self.clock.now_as_millis());
+        crate::backoff::retry_with_backoff(&description, || async {
+            let result = self.watch_ingresses_once(cluster, &namespace).await;
+            if let Err(e) = &result {
+                self.namespace_health.error(
+                    cluster.id(),
+                    &namespace,
+                    self.clock.now_as_millis(),
+                    e.to_string(),
+                );
+                crate::error_reporting::report(ErrorReport {
+                    kind: "watch_error",
+                    message: e.to_string(),
+                    task: crate::supervisor::current_task_name(),
+                    cluster: Some(cluster.id().to_owned()),
+                    namespace: Some(namespace.to_owned()),
+                    ..ErrorReport::default()
+                });
+            }
+            result
+        })
+        .await;
+    }
+
+    /// Load all pre-existing `Ingress`es and watch for changes until an error occurs.
+    async fn watch_ingresses_once(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        namespace: &str,
+    ) -> Result<(), kube::runtime::watcher::Error> {
+        let label_selector = self.app_config.ingress.namespace_selector(namespace).labels;
+        // Never fall back to matching everything, since selectors are validated with the configuration
+        let selector = &label_selector
+            .parse::<LabelSelector>()
+            .unwrap_or_else(|e| panic!("Invalid label selector '{label_selector}': {e}"));
         // Prepare to watch for Ingress updates
-        let stream = kube::runtime::watcher(
-            Api::<Ingress>::namespaced(client.clone(), &namespace),
-            Config::default().labels(label_selector),
+        let stream = crate::watch_recording::watcher(
+            Api::<Ingress>::namespaced(cluster.client(), namespace),
+            crate::kubers_util::watcher_config().labels(label_selector),
         );
         // Process any already existing Ingress
-        let api = &Api::<Ingress>::namespaced(client.clone(), &namespace);
-        let lp = &ListParams::default().labels(label_selector);
         let self_clone = &self.clone();
-        let namespace = &namespace.to_owned();
-        match api.list(lp).await {
-            Ok(object_list) => {
-                for ingress in object_list {
-                    self_clone
-                        .update_ingress_host_paths(&Arc::new(ingress), namespace)
-                        .await;
-                }
-                self.health_ready
-                    .store(true, std::sync::atomic::Ordering::Relaxed);
-            }
-            Err(e) => {
-                log::warn!("Canceling monitoring of namespace '{namespace}' due to error: {e:?}");
-                return;
-            }
-        }
+        self.list_and_reconcile_ingresses(cluster, namespace)
+            .await
+            .map_err(kube::runtime::watcher::Error::InitialListFailed)?;
+        self.namespace_health
+            .event(cluster.id(), namespace, self.clock.now_as_millis());
+        self.health_ready
+            .store(true, std::sync::atomic::Ordering::Relaxed);
         // Watch for Ingress updates
         stream
             .try_for_each(|event| async move {
+                self_clone.namespace_health.event(
+                    cluster.id(),
+                    namespace,
+                    self_clone.clock.now_as_millis(),
+                );
                 match event {
                     kube::runtime::watcher::Event::Deleted(ingress) => {
                         // Ingress was deleted, so remove all host paths
-                        self_clone.remove_ingress_host_paths(&Arc::new(ingress), namespace);
+                        self_clone
+                            .remove_ingress_host_paths(cluster, &Arc::new(ingress), namespace)
+                            .await;
                     }
                     kube::runtime::watcher::Event::Applied(ingress) => {
                         //log::info!("MODIFIED ingress: {:?}", ingress);
                         // Ingress was modified, so check if labels still match, remove otherwise
-                        if let Ok(object_list) = api.list_metadata(lp).await {
-                            let still_present = object_list
-                                .into_iter()
-                                .any(|object| ingress.metadata.name == object.metadata.name);
-                            if still_present {
-                                self_clone
-                                    .update_ingress_host_paths(&Arc::new(ingress), namespace)
-                                    .await;
-                            } else {
-                                log::info!(
-                                    "ingress.metadata.labels change and no longer matches: {:?}",
-                                    ingress.metadata.labels
-                                );
-                                // Nuke it
-                                self_clone.remove_ingress_host_paths(&Arc::new(ingress), namespace);
-                            }
+                        if selector.matches(ingress.labels()) {
+                            self_clone.apply_ingress(cluster, ingress, namespace).await;
                         } else {
-                            // Just use any error, just make sure that we bail out of the stream
-                            return Err(kube::runtime::watcher::Error::NoResourceVersion);
+                            log::info!(
+                                "ingress.metadata.labels change and no longer matches: {:?}",
+                                ingress.metadata.labels
+                            );
+                            // Nuke it
+                            self_clone
+                                .remove_ingress_host_paths(cluster, &Arc::new(ingress), namespace)
+                                .await;
                         }
                     }
-                    kube::runtime::watcher::Event::Restarted(_) => {
-                        log::debug!("Ingress restarted");
+                    kube::runtime::watcher::Event::Restarted(ingresses) => {
+                        log::debug!("Ingress watch restarted with {} objects.", ingresses.len());
+                        self_clone
+                            .reconcile_ingresses(cluster, ingresses, namespace)
+                            .await;
                     }
                 }
                 Ok(())
             })
             .await
-            .map_err(|e| {
-                log::warn!("Canceling monitoring of namespace '{namespace}' due to error: {e:?}");
+    }
+
+    /// List all labeled `Ingress`es in the namespace and reconcile the entries with them.
+    async fn list_and_reconcile_ingresses(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        namespace: &str,
+    ) -> Result<(), kube::Error> {
+        let label_selector = self.app_config.ingress.namespace_selector(namespace).labels;
+        let object_list = Api::<Ingress>::namespaced(cluster.client(), namespace)
+            .list(&ListParams::default().labels(label_selector))
+            .await?;
+        self.reconcile_ingresses(cluster, object_list.items, namespace)
+            .await;
+        Ok(())
+    }
+
+    /**
+      Return the running configuration, which differs from the configuration
+      at startup in the [reloadable](crate::conf::RELOADABLE_KEYS) values
+      after [Self::reload_config].
+    */
+    pub fn running_config(self: &Arc<Self>) -> Arc<AppConfig> {
+        self.running_config.load_full()
+    }
+
+    /**
+      Apply the `reloaded` configuration and return the key paths of the
+      changed values.
+
+      The configuration is only applied if all changed values are
+      [reloadable](crate::conf::RELOADABLE_KEYS). Otherwise the key paths of
+      the values that require a restart are returned as the error. Existing
+      entries are filtered again by [Self::reconcile_all].
+    */
+    pub fn reload_config(
+        self: &Arc<Self>,
+        reloaded: AppConfig,
+    ) -> Result<Vec<String>, Vec<String>> {
+        let changed_keys = self.running_config().changed_keys(&reloaded);
+        let restart_keys = changed_keys
+            .iter()
+            .filter(|key| !AppConfig::is_reloadable(key))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !restart_keys.is_empty() {
+            return Err(restart_keys);
+        }
+        // Also when unchanged, so the tenants and plugin loaded during validation are applied
+        self.running_config.store(Arc::new(reloaded));
+        // Derived data depends on the reloaded filters and plugin
+        self.catalogue_revision.fetch_add(1, Ordering::Relaxed);
+        Ok(changed_keys)
+    }
+
+    /**
+      Reconcile the entries of every monitored namespace with a fresh listing
+      of the `Ingress`es and revalidate all cached manifests.
+
+      This repairs a catalogue that has drifted from the cluster, e.g. due to
+      missed watch events, without waiting for the watches to restart.
+    */
+    pub async fn reconcile_all(self: &Arc<Self>) {
+        self.manifests.expire_all();
+        for namespace_health in self.namespace_health.get_all() {
+            let Some(cluster) = self
+                .clusters
+                .get(&namespace_health.cluster)
+                .map(|entry| Arc::clone(entry.value()))
+            else {
+                continue;
+            };
+            let namespace = &namespace_health.namespace;
+            if let Err(e) = self.list_and_reconcile_ingresses(&cluster, namespace).await {
+                log::warn!(
+                    "Failed to reconcile namespace '{namespace}' of cluster '{}': {e:?}",
+                    cluster.id()
+                );
+            }
+        }
+        log::info!("Reconciled all monitored namespaces.");
+    }
+
+    /**
+      Add or update all `Ingress`es of a full listing of the namespace and
+      remove entries of tracked `Ingress`es that are no longer present, e.g.
+      since they were deleted while the watch was disconnected.
+    */
+    async fn reconcile_ingresses(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        ingresses: Vec<Ingress>,
+        namespace: &str,
+    ) {
+        let present_keys = ingresses
+            .iter()
+            .filter_map(|ingress| Self::ingress_keys_key(cluster, ingress, namespace))
+            .collect::<Vec<_>>();
+        let namespace_prefix = Self::ingress_keys_prefix(cluster, namespace);
+        let missing = self
+            .ingress_keys
+            .iter()
+            .filter(|entry| {
+                entry.key().starts_with(&namespace_prefix) && !present_keys.contains(entry.key())
             })
-            .ok();
+            .map(|entry| entry.key().to_owned())
+            .collect::<Vec<_>>();
+        for ingress_keys_key in missing {
+            let Some(entry) = self.ingress_keys.remove(&ingress_keys_key) else {
+                continue;
+            };
+            let (source, keys) = entry.value();
+            log::info!("'{source}' in 'ns/{namespace}' is no longer present.");
+            for key in keys {
+                self.release_host_path(cluster, key, namespace, source)
+                    .await;
+            }
+            if let Some(name) = source.strip_prefix("Ingress/") {
+                self.resource_errors
+                    .clear(cluster.id(), namespace, "Ingress", name);
+            }
+        }
+        for ingress in ingresses {
+            self.apply_ingress(cluster, ingress, namespace).await;
+        }
     }
 
     /// Remove [IngressHostPath] from local cache.
-    fn remove_ingress_host_paths(self: &Arc<Self>, ingress: &Arc<Ingress>, namespace: &str) {
-        let ingress_rules = ingress.spec.as_ref().unwrap().rules.as_ref().unwrap();
-        for ingress_rule in ingress_rules {
-            let host = ingress_rule.host.as_ref().unwrap();
-            for http_ingress_path in &ingress_rule.http.as_ref().unwrap().paths {
-                let path = http_ingress_path.path.as_ref().unwrap();
-                self.monitored_ingress_host_paths
-                    .remove(&IngressHostPath::identifier(host, path));
-                log::info!("Ingress path '{host}{path}' in 'ns/{namespace}' was deleted.");
-            }
-        }
-    }
-
-    /// Add or update [IngressHostPath] in local cache.
-    async fn update_ingress_host_paths(self: &Arc<Self>, ingress: &Arc<Ingress>, namespace: &str) {
-        let tag_prefix = self.app_config.ingress.annotation_prefix();
-        let ingress_rules = ingress.spec.as_ref().unwrap().rules.as_ref().unwrap();
-        for ingress_rule in ingress_rules {
-            let host = ingress_rule.host.as_ref().unwrap();
-            for http_ingress_path in &ingress_rule.http.as_ref().unwrap().paths {
-                let path = http_ingress_path.path.as_ref().unwrap();
-                let service_name = &http_ingress_path.backend.service.as_ref().unwrap().name;
-                let key = IngressHostPath::identifier(host, path);
-                if !self.monitored_ingress_host_paths.contains_key(&key) {
-                    log::info!("New labeled Ingress path '{host}{path}' in 'ns/{namespace}' ->  'svc/{service_name}'");
-                    let value = IngressHostPath::new(host, path, namespace, service_name).await;
-                    self.monitored_ingress_host_paths
-                        .insert(key.to_owned(), value);
+    async fn remove_ingress_host_paths(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        ingress: &Arc<Ingress>,
+        namespace: &str,
+    ) {
+        let tag_prefix = self
+            .app_config
+            .ingress
+            .namespace_selector(namespace)
+            .annotation_prefix;
+        let variant = Variant::from_ingress(ingress, tag_prefix);
+        let source = "Ingress/".to_owned() + &ingress.name_any();
+        let keys = match Self::ingress_keys_key(cluster, ingress, namespace)
+            .and_then(|ingress_keys_key| self.ingress_keys.remove(&ingress_keys_key))
+        {
+            Some(entry) => entry.value().1.to_owned(),
+            None => ingress_rules::ingress_rule_paths(ingress, namespace, None)
+                .iter()
+                .map(|rule_path| {
+                    IngressHostPath::key(
+                        cluster.id(),
+                        rule_path.host,
+                        rule_path.path,
+                        &variant.name,
+                    )
+                })
+                .collect(),
+        };
+        for key in keys {
+            self.release_host_path(cluster, &key, namespace, &source)
+                .await;
+        }
+        self.resource_errors
+            .clear(cluster.id(), namespace, "Ingress", &ingress.name_any());
+    }
+
+    /**
+      Add or update the entries declared by the `Ingress`.
+
+      A panic while processing is logged together with the offending
+      `Ingress`, which is then skipped, so a single odd resource can't stop
+      the monitoring of the whole namespace.
+    */
+    async fn apply_ingress(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        ingress: Ingress,
+        namespace: &str,
+    ) {
+        let ingress = Arc::new(ingress);
+        let source = "Ingress/".to_owned() + &ingress.name_any();
+        let result = crate::supervisor::catch_panic(crate::error_reporting::with_source(
+            source.to_owned(),
+            self.update_ingress_host_paths(cluster, &ingress, namespace),
+        ))
+        .await;
+        if let Err(message) = result {
+            log::error!(
+                "Skipped '{source}' in namespace '{namespace}' of cluster '{}' after panic '{message}': {ingress:?}",
+                cluster.id()
+            );
+        }
+    }
+
+    /**
+      Add or update [IngressHostPath] in local cache.
+
+      Entries that were previously declared by the same `Ingress` (by UID),
+      but are no longer declared, are removed.
+    */
+    async fn update_ingress_host_paths(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        ingress: &Arc<Ingress>,
+        namespace: &str,
+    ) {
+        let tag_prefix = self
+            .app_config
+            .ingress
+            .namespace_selector(namespace)
+            .annotation_prefix;
+        let annotations: SkipMap<String, String> = ingress
+            .annotations()
+            .iter()
+            .filter_map(|(annotation_key, annotation_value)| {
+                annotation_key
+                    .strip_prefix(tag_prefix)
+                    .map(|key| (key.to_owned(), annotation_value.to_owned()))
+            })
+            .collect();
+        let labels: BTreeMap<String, String> = ingress
+            .labels()
+            .iter()
+            .filter_map(|(label_key, label_value)| {
+                label_key
+                    .strip_prefix(tag_prefix)
+                    .map(|key| (key.to_owned(), label_value.to_owned()))
+            })
+            .collect();
+        let tls_hosts = Self::tls_hosts(ingress);
+        let variant = Variant::from_ingress(ingress, tag_prefix);
+        let path_rewrite = PathRewrite::from_ingress(ingress, tag_prefix);
+        let mut skipped = vec![];
+        let rule_paths = ingress_rules::ingress_rule_paths(ingress, namespace, Some(&mut skipped));
+        self.ingress_errors_update(cluster, ingress, namespace, skipped, &annotations);
+        let source = "Ingress/".to_owned() + &ingress.name_any();
+        let source_object = SourceObject::from_resource(ingress.as_ref(), namespace);
+        let created_millis = ingress
+            .creation_timestamp()
+            .and_then(|time| u64::try_from(time.0.timestamp_millis()).ok())
+            .unwrap_or_default();
+        let running_config = self.running_config();
+        let mut keys = vec![];
+        for rule_path in rule_paths {
+            if !running_config.ingress.is_host_exposed(rule_path.host) {
+                log::debug!(
+                    "Ignoring host '{}' of 'ingress/{}' in 'ns/{namespace}' that is not allowed.",
+                    rule_path.host,
+                    ingress.name_any()
+                );
+                continue;
+            }
+            let declaration = HostPathDeclaration {
+                host: rule_path.host,
+                path: rule_path.path,
+                path_type: rule_path.path_type,
+                namespace,
+                service_name: rule_path.service_name,
+                annotations: &annotations,
+                labels: &labels,
+                rewritten: path_rewrite
+                    .as_ref()
+                    .map(|path_rewrite| path_rewrite.apply(rule_path.path)),
+                tls: Self::is_tls_host(&tls_hosts, rule_path.host),
+                variant: &variant,
+                source: &source,
+                source_object: &source_object,
+                created_millis,
+            };
+            self.upsert_host_path(cluster, &declaration).await;
+            keys.push(IngressHostPath::key(
+                cluster.id(),
+                rule_path.host,
+                rule_path.path,
+                &variant.name,
+            ));
+        }
+        // Release entries that the Ingress no longer declares
+        if let Some(ingress_keys_key) = Self::ingress_keys_key(cluster, ingress, namespace) {
+            let previous_keys = self
+                .ingress_keys
+                .get(&ingress_keys_key)
+                .map(|entry| entry.value().1.to_owned())
+                .unwrap_or_default();
+            self.ingress_keys
+                .insert(ingress_keys_key, (source.to_owned(), keys.clone()));
+            for key in previous_keys {
+                if !keys.contains(&key) {
+                    log::debug!("Path '{key}' is no longer declared by '{source}'.");
+                    self.release_host_path(cluster, &key, namespace, &source)
+                        .await;
+                }
+            }
+        }
+    }
+
+    /// Return the key of the `Ingress` in the tracked entry keys if it has an UID.
+    fn ingress_keys_key(
+        cluster: &Arc<KubeCluster>,
+        ingress: &Ingress,
+        namespace: &str,
+    ) -> Option<String> {
+        ingress
+            .uid()
+            .map(|uid| Self::ingress_keys_prefix(cluster, namespace) + &uid)
+    }
+
+    /// Return the common prefix of keys of all `Ingress`es in the namespace in the tracked entry keys.
+    fn ingress_keys_prefix(cluster: &Arc<KubeCluster>, namespace: &str) -> String {
+        cluster.id().to_owned() + "/" + namespace + "/"
+    }
+
+    /**
+      Replace the known problems of the `Ingress` with the skipped parts and any
+      violations of the annotation schema.
+    */
+    fn ingress_errors_update(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        ingress: &Ingress,
+        namespace: &str,
+        skipped: Vec<SkippedRulePart>,
+        annotations: &SkipMap<String, String>,
+    ) {
+        let name = ingress.name_any();
+        let resource_error = |reason: &str, detail: String| ResourceError {
+            cluster: cluster.id().to_owned(),
+            namespace: namespace.to_owned(),
+            kind: "Ingress".to_owned(),
+            name: name.to_owned(),
+            reason: reason.to_owned(),
+            detail,
+        };
+        let mut errors = skipped
+            .into_iter()
+            .map(|skipped_part| resource_error(skipped_part.reason, skipped_part.detail))
+            .collect::<Vec<_>>();
+        let exposed_annotations = self.exposed_annotations(
+            annotations
+                .iter()
+                .map(|entry| (entry.key().to_owned(), entry.value().to_owned()))
+                .collect(),
+        );
+        errors.extend(
+            self.annotation_validation_errors(&exposed_annotations)
+                .into_iter()
+                .map(|validation_error| resource_error("invalid_annotations", validation_error)),
+        );
+        let rejections = errors
+            .iter()
+            .map(|error| match error.detail.as_str() {
+                "" => error.reason.to_owned(),
+                detail => detail.to_owned(),
+            })
+            .collect::<Vec<_>>();
+        let changed =
+            self.resource_errors
+                .replace(cluster.id(), namespace, "Ingress", &name, errors);
+        if changed && !rejections.is_empty() {
+            self.event_recorder.warning(
+                cluster,
+                Some(&SourceObject::from_resource(ingress, namespace)),
+                "Rejected",
+                rejections.join("; "),
+            );
+        }
+    }
+
+    /**
+      Return all known non-fatal problems of watched resources, ordered by
+      cluster, namespace, kind and name.
+
+      This includes parts of `Ingress`es that could not be cataloged, invalid
+      annotations and `Service`s without a selector that are not of type
+      `ExternalName`.
+    */
+    pub async fn resource_errors(self: &Arc<Self>) -> Vec<ResourceError> {
+        let mut ret = self.resource_errors.get_all();
+        for entry in self.monitored_ingress_host_paths.iter() {
+            let ingress_host_path = entry.value();
+            if !ingress_host_path.has_selectorless_service().await {
+                continue;
+            }
+            let service_name = ingress_host_path.service_name().await;
+            let already_reported = ret.iter().any(|resource_error| {
+                resource_error.kind == "Service"
+                    && resource_error.name == service_name
+                    && resource_error.namespace == ingress_host_path.namespace()
+                    && resource_error.cluster == ingress_host_path.cluster_id()
+            });
+            if !already_reported {
+                ret.push(ResourceError {
+                    cluster: ingress_host_path.cluster_id().to_owned(),
+                    namespace: ingress_host_path.namespace().to_owned(),
+                    kind: "Service".to_owned(),
+                    name: service_name,
+                    reason: "selectorless_service".to_owned(),
+                    detail: "Service has no selector and is not of type ExternalName.".to_owned(),
+                });
+            }
+        }
+        ret.extend(self.conflict_errors());
+        ret.extend(
+            self.entry_overflows
+                .iter()
+                .map(|entry| entry.value().clone()),
+        );
+        ret.sort_by(|a, b| {
+            (&a.cluster, &a.namespace, &a.kind, &a.name).cmp(&(
+                &b.cluster,
+                &b.namespace,
+                &b.kind,
+                &b.name,
+            ))
+        });
+        ret
+    }
+
+    /**
+      Return a problem for each declaration of a hostname + path combination
+      that is also declared by another resource and is not cataloged due to the
+      conflict policy.
+    */
+    fn conflict_errors(self: &Arc<Self>) -> Vec<ResourceError> {
+        let policy = self.app_config.catalogue.conflict_policy();
+        let mut ret = vec![];
+        for claims in self.host_path_claims.conflicts() {
+            let winner = HostPathClaims::winning_claim(&claims, policy);
+            for claim in &claims {
+                if winner.is_some_and(|winner| std::ptr::eq(winner, claim)) {
+                    continue;
                 }
-                let entry = self.monitored_ingress_host_paths.get(&key).unwrap();
-                let ingress_host_path = entry.value();
-                // Update backend service (if needed)
-                ingress_host_path.service_name_update(service_name).await;
-                let annotations: SkipMap<String, String> = ingress
-                    .annotations()
+                let others = claims
                     .iter()
-                    .filter_map(|(annotation_key, annotation_value)| {
-                        if annotation_key.starts_with(&tag_prefix) {
-                            Some((
-                                annotation_key.replacen(&tag_prefix, "", 1),
-                                annotation_value.to_owned(),
-                            ))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                // Update annotations (if needed)
-                ingress_host_path.annotations_update(&annotations);
+                    .filter(|other| !std::ptr::eq(*other, claim))
+                    .map(|other| format!("{}/{}", other.namespace, other.source))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let (kind, name) = claim.source.split_once('/').unwrap_or((&claim.source, ""));
+                ret.push(ResourceError {
+                    cluster: claim.cluster.to_owned(),
+                    namespace: claim.namespace.to_owned(),
+                    kind: kind.to_owned(),
+                    name: name.to_owned(),
+                    reason: "conflict".to_owned(),
+                    detail: format!(
+                        "'{}{}' is also declared by {others} and is not cataloged due to conflict policy {policy:?}.",
+                        claim.host, claim.path
+                    ),
+                });
             }
         }
+        ret
+    }
+
+    /// Return the (possibly wildcard) hosts listed in the `Ingress`'s `spec.tls`.
+    fn tls_hosts(ingress: &Ingress) -> Vec<&str> {
+        ingress
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.tls.as_ref())
+            .map(|ingress_tls_list| {
+                ingress_tls_list
+                    .iter()
+                    .filter_map(|ingress_tls| ingress_tls.hosts.as_ref())
+                    .flatten()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    }
+
+    /**
+      Return `true` if the `host` is listed in the `Ingress`'s `spec.tls`,
+      either explicitly or by a wildcard like `*.example.com`.
+    */
+    fn is_tls_host(tls_hosts: &[&str], host: &str) -> bool {
+        tls_hosts.iter().any(|tls_host| {
+            if *tls_host == host {
+                true
+            } else if let Some(domain) = tls_host.strip_prefix("*.") {
+                host.split_once('.')
+                    .is_some_and(|(_, host_domain)| host_domain == domain)
+            } else {
+                false
+            }
+        })
+    }
+
+    /**
+      Remove a single hostname + path combination from local cache by its
+      [IngressHostPath::key].
+    */
+    async fn remove_host_path(self: &Arc<Self>, key: &str, namespace: &str) {
+        self.entry_overflows.remove(key);
+        if let Some(entry) = self.monitored_ingress_host_paths.remove(key) {
+            self.catalogue_revision.fetch_add(1, Ordering::Relaxed);
+            entry.value().remove().await;
+            entry.value().publish_lifecycle(ChangeKind::Removed);
+            self.event_recorder.normal(
+                entry.value().cluster(),
+                entry.value().source_object().as_ref(),
+                "Removed",
+                format!(
+                    "Removed '{}' from the catalogue.",
+                    entry.value().host_path()
+                ),
+            );
+            if self.app_config.catalogue.tombstone_ttl_secs() > 0 {
+                self.tombstones.insert(
+                    key.to_owned(),
+                    (self.clock.instant(), Arc::clone(entry.value())),
+                );
+            }
+        }
+        log::info!("Path '{key}' in 'ns/{namespace}' was deleted.");
+        self.retry_entry_overflows().await;
+    }
+
+    /// Return the other resources that prefixed annotations of entries in the namespace are collected from.
+    fn annotation_sources(self: &Arc<Self>, namespace: &str) -> AnnotationSources {
+        let annotation_sources = self.app_config.catalogue.annotation_sources();
+        AnnotationSources {
+            prefix: self
+                .app_config
+                .ingress
+                .namespace_selector(namespace)
+                .annotation_prefix
+                .to_owned(),
+            service: annotation_sources.iter().any(|x| x == "service"),
+            deployment: annotation_sources.iter().any(|x| x == "deployment"),
+        }
+    }
+
+    /**
+      Return the reason why a new entry in the namespace would exceed an entry
+      limit or `None` if it can be cataloged.
+    */
+    fn entry_limit_exceeded(self: &Arc<Self>, namespace: &str) -> Option<String> {
+        let catalogue_config = &self.app_config.catalogue;
+        if let Some(max_entries) = catalogue_config.max_entries() {
+            if self.monitored_ingress_host_paths.len() >= max_entries {
+                return Some(format!("the limit of {max_entries} entries is reached"));
+            }
+        }
+        if let Some(max_entries) = catalogue_config.max_entries_per_namespace() {
+            let namespace_entries = self
+                .monitored_ingress_host_paths
+                .iter()
+                .filter(|entry| entry.value().namespace() == namespace)
+                .count();
+            if namespace_entries >= max_entries {
+                return Some(format!(
+                    "the limit of {max_entries} entries in the namespace is reached"
+                ));
+            }
+        }
+        None
+    }
+
+    /// Record that a declared entry is not cataloged since an entry limit is reached.
+    fn entry_overflow(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        key: &str,
+        state: &HostPathState,
+        reason: &str,
+    ) {
+        if !self.entry_overflows.contains_key(key) {
+            log::warn!(
+                "Path '{}{}' in 'ns/{}' is not cataloged since {reason}.",
+                state.host,
+                state.path,
+                state.namespace
+            );
+            self.entry_overflows_total.fetch_add(1, Ordering::Relaxed);
+            self.event_recorder.warning(
+                cluster,
+                state.source_object.as_ref(),
+                "Rejected",
+                format!(
+                    "'{}{}' is not cataloged since {reason}.",
+                    state.host, state.path
+                ),
+            );
+        }
+        let (kind, name) = state.source.split_once('/').unwrap_or((&state.source, ""));
+        self.entry_overflows.insert(
+            key.to_owned(),
+            ResourceError {
+                cluster: state.cluster.to_owned(),
+                namespace: state.namespace.to_owned(),
+                kind: kind.to_owned(),
+                name: name.to_owned(),
+                reason: "entry_limit".to_owned(),
+                detail: format!(
+                    "'{}{}' is not cataloged since {reason}.",
+                    state.host, state.path
+                ),
+            },
+        );
+    }
+
+    /// Try to catalog declared entries that previously exceeded an entry limit.
+    async fn retry_entry_overflows(self: &Arc<Self>) {
+        let policy = self.app_config.catalogue.conflict_policy();
+        for entry in self.entry_overflows.iter() {
+            let key = entry.key();
+            let cluster = self
+                .clusters
+                .get(&entry.value().cluster)
+                .map(|cluster| Arc::clone(cluster.value()));
+            match cluster.zip(self.host_path_claims.winner(key, policy)) {
+                Some((cluster, state)) => self.apply_host_path(&cluster, key, &state).await,
+                None => {
+                    entry.remove();
+                }
+            }
+        }
+    }
+
+    /**
+      Return the number of declared entries that are currently not cataloged
+      and the total number that were not cataloged since start, due to an
+      entry limit.
+    */
+    pub fn entry_overflow_counts(self: &Arc<Self>) -> (usize, u64) {
+        (
+            self.entry_overflows.len(),
+            self.entry_overflows_total.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Remove tombstones of deleted entries that have expired.
+    fn purge_expired_tombstones(self: &Arc<Self>) {
+        let ttl = Duration::from_secs(self.app_config.catalogue.tombstone_ttl_secs());
+        let now = self.clock.instant();
+        for entry in self.tombstones.iter() {
+            let (deleted, _) = entry.value();
+            if now.saturating_duration_since(*deleted) >= ttl {
+                log::debug!("Tombstone of '{}' expired.", entry.key());
+                entry.remove();
+                self.catalogue_revision.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Add or update the declaration of a single hostname + path combination.
+    async fn upsert_host_path(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        declaration: &HostPathDeclaration<'_>,
+    ) {
+        let state = HostPathState::from_declaration(cluster.id(), declaration);
+        self.claim_host_path(cluster, state).await;
+    }
+
+    /**
+      Add or replace the claim of a hostname + path combination by the declaring
+      resource and update the local cache according to the conflict policy.
+    */
+    async fn claim_host_path(self: &Arc<Self>, cluster: &Arc<KubeCluster>, state: HostPathState) {
+        let key = IngressHostPath::key(cluster.id(), &state.host, &state.path, &state.variant);
+        let namespace = state.namespace.to_owned();
+        self.host_path_claims.claim(&key, state);
+        self.resolve_host_path(cluster, &key, &namespace).await;
+    }
+
+    /**
+      Remove the claim of a hostname + path combination by the declaring
+      resource and update the local cache according to the conflict policy.
+    */
+    async fn release_host_path(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        key: &str,
+        namespace: &str,
+        source: &str,
+    ) {
+        self.host_path_claims.release(key, namespace, source);
+        self.resolve_host_path(cluster, key, namespace).await;
+    }
+
+    /**
+      Catalog the claim of a hostname + path combination that wins according
+      to the conflict policy or remove the entry if there is none.
+    */
+    async fn resolve_host_path(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        key: &str,
+        namespace: &str,
+    ) {
+        let policy = self.app_config.catalogue.conflict_policy();
+        match self.host_path_claims.winner(key, policy) {
+            Some(state) => self.apply_host_path(cluster, key, &state).await,
+            None => self.remove_host_path(key, namespace).await,
+        }
+    }
+
+    /// Add or update a single hostname + path combination in local cache.
+    async fn apply_host_path(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        key: &str,
+        state: &HostPathState,
+    ) {
+        let host = state.host.as_str();
+        let path = state.path.as_str();
+        let namespace = state.namespace.as_str();
+        let service_name = state.service_name.as_str();
+        let variant = state.variant();
+        let switched_namespace = self
+            .monitored_ingress_host_paths
+            .get(key)
+            .is_some_and(|entry| entry.value().namespace() != namespace);
+        if switched_namespace {
+            log::info!("Path '{host}{path}' is now declared in 'ns/{namespace}'.");
+            if let Some(entry) = self.monitored_ingress_host_paths.remove(key) {
+                entry.value().remove().await;
+            }
+        }
+        if !self.monitored_ingress_host_paths.contains_key(key) {
+            if let Some(reason) = self.entry_limit_exceeded(namespace) {
+                self.entry_overflow(cluster, key, state, &reason);
+                return;
+            }
+            self.entry_overflows.remove(key);
+            log::info!(
+                "New labeled path '{host}{path}' in 'ns/{namespace}' ->  'svc/{service_name}'"
+            );
+            let value = IngressHostPath::new(
+                cluster,
+                host,
+                path,
+                namespace,
+                service_name,
+                &variant.name,
+                Arc::clone(&self.notifier),
+                Arc::clone(&self.clock),
+                self.annotation_sources(namespace),
+                Arc::clone(&self.catalogue_revision),
+            )
+            .await;
+            value.source_update(&state.source);
+            value.source_object_update(state.source_object.to_owned());
+            value.publish_lifecycle(ChangeKind::Added);
+            self.event_recorder.normal(
+                cluster,
+                state.source_object.as_ref(),
+                "Cataloged",
+                format!("Cataloged '{host}{path}' served by 'svc/{service_name}'."),
+            );
+            self.monitored_ingress_host_paths
+                .insert(key.to_owned(), value);
+            self.tombstones.remove(key);
+            self.catalogue_revision.fetch_add(1, Ordering::Relaxed);
+        }
+        let Some(entry) = self.monitored_ingress_host_paths.get(key) else {
+            log::debug!("Path '{host}{path}' was removed while being updated.");
+            return;
+        };
+        let ingress_host_path = entry.value();
+        // Update declaring resource (if needed)
+        ingress_host_path.source_update(&state.source);
+        let previous_source_object =
+            ingress_host_path.source_object_update(state.source_object.to_owned());
+        if previous_source_object.is_some() && previous_source_object != state.source_object {
+            self.event_recorder.normal(
+                cluster,
+                state.source_object.as_ref(),
+                "Updated",
+                format!("Updated '{host}{path}' served by 'svc/{service_name}'."),
+            );
+        }
+        // Update backend service (if needed)
+        ingress_host_path.service_name_update(service_name).await;
+        // Update path type (if needed)
+        ingress_host_path.path_type_update(state.path_type);
+        // Update TLS (if needed)
+        ingress_host_path.tls_update(state.tls);
+        // Update variant weight (if needed)
+        ingress_host_path.weight_update(variant.weight);
+        // Update annotations (if needed)
+        ingress_host_path.annotations_update(&state.annotations());
+        ingress_host_path.labels_update(&state.labels);
+        ingress_host_path.rewritten_update(state.rewritten.to_owned());
+        ingress_host_path.hidden_update();
+        // Update referenced ConfigMap (if needed)
+        ingress_host_path.config_ref_update().await;
+    }
+
+    /**
+      Return a description of each violation of the configured annotation
+      schema. Always empty when no schema is configured.
+    */
+    pub fn annotation_validation_errors(
+        self: &Arc<Self>,
+        annotations: &HashMap<String, serde_json::Value>,
+    ) -> Vec<String> {
+        self.annotation_schema
+            .as_ref()
+            .map(|annotation_schema| annotation_schema.validate(annotations))
+            .unwrap_or_default()
+    }
+
+    /**
+      Return the annotation values as exposed to API clients.
+
+      Annotations that are not allowed by the configured allowlist or denylist
+      are omitted. Values are exposed as strings, unless structured annotations
+      are enabled and the value parses as a JSON object or array.
+    */
+    pub fn exposed_annotations(
+        self: &Arc<Self>,
+        annotations: HashMap<String, String>,
+    ) -> HashMap<String, serde_json::Value> {
+        let structured = self.app_config.catalogue.structured_annotations();
+        let running_config = self.running_config();
+        annotations
+            .into_iter()
+            .filter(|(key, _)| running_config.ingress.is_annotation_exposed(key))
+            .map(|(key, value)| {
+                let parsed = Some(value.trim_start())
+                    .filter(|trimmed| structured && trimmed.starts_with(['{', '[']))
+                    .and_then(|trimmed| serde_json::from_str::<serde_json::Value>(trimmed).ok());
+                (key, parsed.unwrap_or(serde_json::Value::String(value)))
+            })
+            .collect()
+    }
+
+    /// Return `true` if entries with invalid annotations should be omitted from the catalogue.
+    pub fn reject_invalid_annotations(self: &Arc<Self>) -> bool {
+        self.app_config.catalogue.reject_invalid_annotations()
+    }
+
+    /// Return `true` if the entry is omitted from the catalogue for annotations that fail validation.
+    async fn is_rejected(self: &Arc<Self>, entry: &Arc<IngressHostPath>) -> bool {
+        if self.annotation_schema.is_none() || !self.reject_invalid_annotations() {
+            return false;
+        }
+        let annotations = self.exposed_annotations(self.entry_annotations(entry).await);
+        !self.annotation_validation_errors(&annotations).is_empty()
+    }
+
+    /**
+      Return the modules declared by the `name` annotation of each entry that
+      is not hidden, with the `version`, `requires`, `entry` and `integrity`
+      annotations. Modules are ordered by the `priority` of their entries,
+      highest first.
+    */
+    pub async fn declared_modules(self: &Arc<Self>) -> Vec<DeclaredModule> {
+        self.declared_modules_of(self.get_all()).await
+    }
+
+    /// Return the modules declared by the given entries. See [Self::declared_modules].
+    pub async fn declared_modules_of(
+        self: &Arc<Self>,
+        entries: Vec<Arc<IngressHostPath>>,
+    ) -> Vec<DeclaredModule> {
+        let mut declared = vec![];
+        for entry in entries {
+            if entry.is_hidden() || self.is_rejected(&entry).await {
+                continue;
+            }
+            let annotations = self.entry_annotations(&entry).await;
+            let Some(name) = annotations
+                .get(dependency_graph::NAME_ANNOTATION)
+                .map(|name| name.trim())
+                .filter(|name| !name.is_empty())
+            else {
+                continue;
+            };
+            let priority = annotations
+                .get(ingress_host_path::PRIORITY_ANNOTATION)
+                .map_or(0, |value| IngressHostPath::parse_priority(value));
+            declared.push((
+                priority,
+                DeclaredModule {
+                    name: name.to_owned(),
+                    url: entry.url(),
+                    version: annotations
+                        .get(dependency_graph::VERSION_ANNOTATION)
+                        .map(|version| version.trim().to_owned()),
+                    requires: annotations
+                        .get(dependency_graph::REQUIRES_ANNOTATION)
+                        .map(|value| DependencyGraph::parse_requires(value))
+                        .unwrap_or_default(),
+                    entry_point: annotations
+                        .get(ingress_host_path::ENTRY_ANNOTATION)
+                        .and_then(|reference| entry.resolve_url(reference)),
+                    integrity: annotations
+                        .get(dependency_graph::INTEGRITY_ANNOTATION)
+                        .map(|integrity| integrity.trim().to_owned()),
+                },
+            ));
+        }
+        // Modules of entries with higher priority come first and win name conflicts
+        declared.sort_by_key(|(priority, _)| std::cmp::Reverse(*priority));
+        declared.into_iter().map(|(_, module)| module).collect()
+    }
+
+    /**
+      Return the [DependencyGraph] of the modules declared by entries and the
+      modules they require.
+    */
+    pub async fn dependency_graph(self: &Arc<Self>) -> DependencyGraph {
+        self.dependency_graph_of(self.get_all()).await
+    }
+
+    /// Return the [DependencyGraph] of the modules declared by the given entries.
+    pub async fn dependency_graph_of(
+        self: &Arc<Self>,
+        entries: Vec<Arc<IngressHostPath>>,
+    ) -> DependencyGraph {
+        let mut modules: BTreeMap<String, (Vec<String>, BTreeSet<String>)> = BTreeMap::new();
+        for declared in self.declared_modules_of(entries).await {
+            let (urls, requires) = modules.entry(declared.name).or_default();
+            urls.push(declared.url);
+            requires.extend(declared.requires.into_iter().map(|(required, _)| required));
+        }
+        DependencyGraph::build(modules)
+    }
+
+    /**
+      Return the [CompatibilityReport] of the semver requirements between the
+      modules declared by entries.
+    */
+    pub async fn compatibility_report(self: &Arc<Self>) -> CompatibilityReport {
+        self.compatibility_report_of(self.get_all()).await
+    }
+
+    /// Return the [CompatibilityReport] of the modules declared by the given entries.
+    pub async fn compatibility_report_of(
+        self: &Arc<Self>,
+        entries: Vec<Arc<IngressHostPath>>,
+    ) -> CompatibilityReport {
+        CompatibilityReport::evaluate(&self.declared_modules_of(entries).await)
+    }
+
+    /**
+      Return the entry of the local cluster with the longest hostname + path
+      that `host_path` (like `shop.example.com/checkout/main.js`) starts with
+      at a path segment boundary. The primary variant is preferred.
+
+      Hidden and wildcard entries are never returned.
+    */
+    pub fn find_local_entry(self: &Arc<Self>, host_path: &str) -> Option<Arc<IngressHostPath>> {
+        let local_id = self.app_config.clusters.local_id();
+        self.get_all()
+            .into_iter()
+            .filter(|entry| {
+                entry.cluster_id() == local_id && !entry.is_hidden() && !entry.is_wildcard()
+            })
+            .filter(|entry| {
+                let prefix = entry.host_path().trim_end_matches('/');
+                host_path.strip_prefix(prefix).is_some_and(|rest| {
+                    rest.is_empty() || rest.starts_with('/') || rest.starts_with('?')
+                })
+            })
+            .max_by_key(|entry| {
+                (
+                    entry.host_path().trim_end_matches('/').len(),
+                    entry.variant() == PRIMARY_VARIANT,
+                )
+            })
+    }
+
+    /**
+      Return the URLs of the JavaScript entry points declared by the `entry`
+      annotation of all entries that are not hidden, with entries of higher
+      `priority` first. Each URL is only returned once.
+    */
+    pub async fn entry_point_urls(self: &Arc<Self>) -> Vec<String> {
+        self.entry_point_urls_of(self.get_all()).await
+    }
+
+    /// Return the URLs of the entry points declared by the given entries. See [Self::entry_point_urls].
+    pub async fn entry_point_urls_of(
+        self: &Arc<Self>,
+        entries: Vec<Arc<IngressHostPath>>,
+    ) -> Vec<String> {
+        let mut prioritized = vec![];
+        for entry in entries {
+            if entry.is_hidden() || self.is_rejected(&entry).await {
+                continue;
+            }
+            if let Some(url) = entry.entry_point_url().await {
+                let priority = self
+                    .entry_annotations(&entry)
+                    .await
+                    .get(ingress_host_path::PRIORITY_ANNOTATION)
+                    .map_or(0, |value| IngressHostPath::parse_priority(value));
+                prioritized.push((priority, url));
+            }
+        }
+        // Stable, so entries of equal priority keep their order
+        prioritized.sort_by_key(|(priority, _)| std::cmp::Reverse(*priority));
+        let mut seen = HashSet::new();
+        prioritized
+            .into_iter()
+            .map(|(_, url)| url)
+            .filter(|url| seen.insert(url.to_owned()))
+            .collect()
+    }
+
+    /**
+      Return the approximate memory used by retained changes and cached asset
+      manifests in bytes, which are bounded by shares of the assigned memory.
+    */
+    pub fn cache_memory_usage(self: &Arc<Self>) -> (usize, usize) {
+        (
+            self.change_feed.memory_usage(),
+            self.manifests.memory_usage(),
+        )
     }
 
     /// Return all known [IngressHostPath]s from local cache.
@@ -239,4 +1921,55 @@ impl IngressMonitor {
             .map(|entry| Arc::clone(entry.value()))
             .collect()
     }
+
+    /// Return recently deleted [IngressHostPath]s that have not yet expired.
+    pub fn get_tombstones(self: &Arc<Self>) -> Vec<Arc<IngressHostPath>> {
+        self.tombstones
+            .iter()
+            .map(|entry| Arc::clone(&entry.value().1))
+            .collect()
+    }
+
+    /**
+      Return the entry with the `key` (see [IngressHostPath::key]), including
+      recently removed entries that are retained as tombstones.
+    */
+    pub fn find_entry(self: &Arc<Self>, key: &str) -> Option<Arc<IngressHostPath>> {
+        self.monitored_ingress_host_paths
+            .get(key)
+            .map(|entry| Arc::clone(entry.value()))
+            .or_else(|| {
+                self.tombstones
+                    .get(key)
+                    .map(|entry| Arc::clone(&entry.value().1))
+            })
+    }
+
+    /// Return the sequence number of the latest change of any entry.
+    pub fn change_cursor(self: &Arc<Self>) -> u64 {
+        self.change_feed.cursor()
+    }
+
+    /// Return the retained changes after the `cursor`. See [ChangeFeed].
+    pub fn changes_since(self: &Arc<Self>, cursor: u64) -> ChangeFeedPage {
+        self.change_feed.since(cursor)
+    }
+
+    /**
+      Return the revision of the current catalogue without visiting entries.
+
+      The revision changes whenever an entry is added, removed or updated
+      (including replica counts, reachability and fetched manifests) or the
+      configuration is reloaded, so it can be used to detect if previously
+      derived data is still valid.
+    */
+    pub fn catalogue_revision(self: &Arc<Self>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.catalogue_revision
+            .load(Ordering::Relaxed)
+            .hash(&mut hasher);
+        self.manifests.revision().hash(&mut hasher);
+        self.peers.revision().hash(&mut hasher);
+        hasher.finish()
+    }
 }
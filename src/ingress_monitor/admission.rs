@@ -0,0 +1,130 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Admission-time checks of labeled `Ingress`es.
+
+use k8s_openapi::api::networking::v1::Ingress;
+use kube::ResourceExt;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::ingress_rules;
+use super::IngressHostPath;
+use super::IngressMonitor;
+use super::Variant;
+use crate::kubers_util::LabelSelector;
+
+impl IngressMonitor {
+    /**
+      Return the reasons to reject the `Ingress` at admission time or an empty
+      list if it should be admitted.
+
+      `Ingress`es that don't match the label selector of their namespace are
+      always admitted. Labeled `Ingress`es are rejected if the prefixed
+      annotations violate the configured schema, if any of the required
+      annotation keys is missing or if a hostname + path is already declared
+      by another resource in the local cluster.
+    */
+    pub fn admission_problems(self: &Arc<Self>, ingress: &Ingress) -> Vec<String> {
+        let namespace = ingress.namespace().unwrap_or_default();
+        let Some(tag_prefix) = self.admission_tag_prefix(ingress, &namespace) else {
+            return vec![];
+        };
+        let annotations = ingress
+            .annotations()
+            .iter()
+            .filter_map(|(annotation_key, annotation_value)| {
+                annotation_key
+                    .strip_prefix(tag_prefix)
+                    .map(|key| (key.to_owned(), annotation_value.to_owned()))
+            })
+            .collect::<HashMap<_, _>>();
+        let mut problems = vec![];
+        for required_key in self.app_config.admission.required_keys() {
+            if !annotations.contains_key(required_key) {
+                problems.push(format!(
+                    "Missing required annotation '{tag_prefix}{required_key}'."
+                ));
+            }
+        }
+        problems.extend(
+            self.annotation_validation_errors(&self.exposed_annotations(annotations))
+                .into_iter()
+                .map(|validation_error| format!("Invalid annotations: {validation_error}")),
+        );
+        let local_id = self.app_config.clusters.local_id();
+        let source = "Ingress/".to_owned() + &ingress.name_any();
+        let variant = Variant::from_ingress(ingress, tag_prefix);
+        for rule_path in ingress_rules::ingress_rule_paths(ingress, &namespace, None) {
+            let key = IngressHostPath::key(local_id, rule_path.host, rule_path.path, &variant.name);
+            if let Some(claim) = self
+                .host_path_claims
+                .claims(&key)
+                .into_iter()
+                .find(|claim| claim.namespace != namespace || claim.source != source)
+            {
+                problems.push(format!(
+                    "'{}{}' is already declared by '{}' in 'ns/{}'.",
+                    rule_path.host, rule_path.path, claim.source, claim.namespace
+                ));
+            }
+        }
+        problems
+    }
+
+    /**
+      Return the prefixed default annotations to inject into the `Ingress` at
+      admission time.
+
+      Only annotations that the `Ingress` doesn't already declare are returned
+      and nothing is injected into `Ingress`es that don't match the label
+      selector of their namespace.
+    */
+    pub fn admission_defaults(&self, ingress: &Ingress) -> BTreeMap<String, String> {
+        let namespace = ingress.namespace().unwrap_or_default();
+        let Some(tag_prefix) = self.admission_tag_prefix(ingress, &namespace) else {
+            return BTreeMap::new();
+        };
+        let name = ingress.name_any();
+        self.app_config
+            .admission
+            .defaults()
+            .into_iter()
+            .map(|(key, value)| {
+                (
+                    tag_prefix.to_owned() + key,
+                    value
+                        .replace("{namespace}", &namespace)
+                        .replace("{name}", &name),
+                )
+            })
+            .filter(|(annotation_key, _)| !ingress.annotations().contains_key(annotation_key))
+            .collect()
+    }
+
+    /// Return the annotation prefix of the namespace if the `Ingress` matches its label selector.
+    fn admission_tag_prefix(&self, ingress: &Ingress, namespace: &str) -> Option<&str> {
+        let namespace_selector = self.app_config.ingress.namespace_selector(namespace);
+        namespace_selector
+            .labels
+            .parse::<LabelSelector>()
+            .ok()
+            .filter(|selector| selector.matches(ingress.labels()))
+            .map(|_| namespace_selector.annotation_prefix)
+    }
+}
@@ -0,0 +1,69 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Validation of exposed annotations against a JSON Schema.
+
+use jsonschema::JSONSchema;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Compiled JSON Schema that the annotations of each entry must conform to.
+pub struct AnnotationSchema {
+    /// The compiled schema.
+    schema: JSONSchema,
+}
+
+impl std::fmt::Debug for AnnotationSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AnnotationSchema").finish_non_exhaustive()
+    }
+}
+
+impl AnnotationSchema {
+    /// Load and compile the JSON Schema from the file at `path`.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read annotation schema '{path}': {e}"))?;
+        let schema_value = serde_json::from_str::<Value>(&content)
+            .map_err(|e| format!("Unable to parse annotation schema '{path}': {e}"))?;
+        let schema = JSONSchema::compile(&schema_value)
+            .map_err(|e| format!("Invalid annotation schema '{path}': {e}"))?;
+        log::info!("Annotations will be validated against the schema in '{path}'.");
+        Ok(Self { schema })
+    }
+
+    /**
+      Validate the annotations as a JSON object where each annotation is a
+      property with the value as exposed to API clients.
+
+      Returns a human readable description of each violation.
+    */
+    pub fn validate(&self, annotations: &HashMap<String, Value>) -> Vec<String> {
+        let instance = Value::Object(
+            annotations
+                .iter()
+                .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                .collect(),
+        );
+        match self.schema.validate(&instance) {
+            Ok(()) => vec![],
+            Err(errors) => errors
+                .map(|error| format!("{}: {error}", error.instance_path))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Semver compatibility checking between modules declared by entries.
+
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::dependency_graph::DeclaredModule;
+
+/// Requirement of a module that no provided version satisfies.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct UnmetRequirement {
+    /// Name of the requiring module.
+    pub module: String,
+    /// URL of the entry declaring the requirement.
+    pub url: String,
+    /// Name of the required module.
+    pub required: String,
+    /// Required semver range.
+    pub range: String,
+    /// Versions of the required module provided by entries.
+    pub available: Vec<String>,
+    /// Description of why the requirement is not met.
+    pub reason: String,
+}
+
+/// Requirements of the same module that no single provided version satisfies.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RequirementConflict {
+    /// Name of the required module.
+    pub required: String,
+    /// Required semver range by requiring module.
+    pub ranges: BTreeMap<String, String>,
+    /// Versions of the required module provided by entries.
+    pub available: Vec<String>,
+}
+
+/// Unmet and conflicting semver requirements across the catalogue.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CompatibilityReport {
+    /// Requirements that no provided version satisfies.
+    pub unmet: Vec<UnmetRequirement>,
+    /// Requirements that are satisfied one by one, but not by any single provided version.
+    pub conflicts: Vec<RequirementConflict>,
+}
+
+impl CompatibilityReport {
+    /// Return `true` if all requirements are met without conflicts.
+    pub fn is_compatible(&self) -> bool {
+        self.unmet.is_empty() && self.conflicts.is_empty()
+    }
+
+    /**
+      Evaluate the version ranges required by the `declared` modules against
+      the versions provided by other entries.
+
+      Requirements without a range and modules with a version that is not
+      valid semver are not considered.
+    */
+    pub fn evaluate(declared: &[DeclaredModule]) -> Self {
+        let mut provided: BTreeMap<&str, Vec<Version>> = BTreeMap::new();
+        for module in declared {
+            let versions = provided.entry(module.name.as_str()).or_default();
+            if let Some(version) = module
+                .version
+                .as_deref()
+                .and_then(|version| Version::parse(version.trim_start_matches('v')).ok())
+            {
+                if !versions.contains(&version) {
+                    versions.push(version);
+                }
+            }
+        }
+        provided.values_mut().for_each(|versions| versions.sort());
+        let available = |required: &str| -> Vec<String> {
+            provided
+                .get(required)
+                .map(|versions| versions.iter().map(Version::to_string).collect())
+                .unwrap_or_default()
+        };
+        let mut report = Self::default();
+        let mut satisfied: BTreeMap<&str, BTreeMap<&str, (&str, VersionReq)>> = BTreeMap::new();
+        for module in declared {
+            for (required, range) in &module.requires {
+                let Some(range) = range else {
+                    continue;
+                };
+                let reason = match (VersionReq::parse(range), provided.get(required.as_str())) {
+                    (Err(e), _) => format!("'{range}' is not a valid semver range: {e}"),
+                    (Ok(_), None) => "No entry provides the module.".to_owned(),
+                    (Ok(_), Some(versions)) if versions.is_empty() => {
+                        "No entry declares a valid version of the module.".to_owned()
+                    }
+                    (Ok(version_req), Some(versions)) => {
+                        if versions.iter().any(|version| version_req.matches(version)) {
+                            satisfied
+                                .entry(required.as_str())
+                                .or_default()
+                                .insert(module.name.as_str(), (range.as_str(), version_req));
+                            continue;
+                        }
+                        "No provided version satisfies the range.".to_owned()
+                    }
+                };
+                report.unmet.push(UnmetRequirement {
+                    module: module.name.to_owned(),
+                    url: module.url.to_owned(),
+                    required: required.to_owned(),
+                    range: range.to_owned(),
+                    available: available(required),
+                    reason,
+                });
+            }
+        }
+        for (required, ranges) in satisfied {
+            let versions = &provided[required];
+            let compatible = versions.iter().any(|version| {
+                ranges
+                    .values()
+                    .all(|(_, version_req)| version_req.matches(version))
+            });
+            if !compatible {
+                report.conflicts.push(RequirementConflict {
+                    required: required.to_owned(),
+                    ranges: ranges
+                        .iter()
+                        .map(|(module, (range, _))| (module.to_string(), range.to_string()))
+                        .collect(),
+                    available: available(required),
+                });
+            }
+        }
+        report.unmet.sort_by(|a, b| {
+            (&a.module, &a.required, &a.url).cmp(&(&b.module, &b.required, &b.url))
+        });
+        report
+    }
+}
@@ -0,0 +1,236 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Dependency graph of modules declared by entries.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Annotation (without prefix) with the name of the module provided by an entry.
+pub const NAME_ANNOTATION: &str = "name";
+/**
+Annotation (without prefix) with a comma separated list of required module
+names, each optionally followed by `@` and a semver range like `cart@^1.2`.
+*/
+pub const REQUIRES_ANNOTATION: &str = "requires";
+/// Annotation (without prefix) with the subresource integrity metadata of the entry point.
+pub const INTEGRITY_ANNOTATION: &str = "integrity";
+/// Annotation (without prefix) with the semver version of the module provided by an entry.
+pub const VERSION_ANNOTATION: &str = "version";
+
+/// Module declared by the annotations of a single entry.
+pub struct DeclaredModule {
+    /// Name of the module.
+    pub name: String,
+    /// URL of the entry.
+    pub url: String,
+    /// Declared version of the module, if any.
+    pub version: Option<String>,
+    /// Name and version range (if any) of each required module.
+    pub requires: Vec<(String, Option<String>)>,
+    /// Absolute URL of the JavaScript entry point of the module, if declared.
+    pub entry_point: Option<String>,
+    /// Subresource integrity metadata of the entry point, like `sha384-...`, if declared.
+    pub integrity: Option<String>,
+}
+
+/// Module provided by one or more entries.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ModuleNode {
+    /// Name of the module.
+    pub name: String,
+    /// URLs of the entries providing the module.
+    pub urls: Vec<String>,
+    /// Names of the modules required by this module.
+    pub requires: Vec<String>,
+    /// Names of required modules that no entry provides.
+    pub missing: Vec<String>,
+}
+
+/// Dependency graph of all modules in the catalogue.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DependencyGraph {
+    /// All modules ordered by name.
+    pub modules: Vec<ModuleNode>,
+    /// Groups of modules that (indirectly) require each other.
+    pub cycles: Vec<Vec<String>>,
+    /// Modules ordered so that each module comes after the modules it requires.
+    /// Modules that are part of or require a cycle are omitted.
+    pub load_order: Vec<String>,
+}
+
+impl DependencyGraph {
+    /**
+      Return the name and version range (if any) of each required module in
+      the value of a [REQUIRES_ANNOTATION].
+
+      Scoped names like `@shop/cart@^1` are supported.
+    */
+    pub fn parse_requires(value: &str) -> Vec<(String, Option<String>)> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|requirement| !requirement.is_empty())
+            .map(
+                |requirement| match requirement.rfind('@').filter(|i| *i > 0) {
+                    Some(i) => (
+                        requirement[..i].trim().to_owned(),
+                        Some(requirement[i + 1..].trim().to_owned()),
+                    ),
+                    None => (requirement.to_owned(), None),
+                },
+            )
+            .collect()
+    }
+
+    /**
+      Return the dependency graph of the `modules`, where each module name maps
+      to the URLs of the providing entries and the names of required modules.
+    */
+    pub fn build(modules: BTreeMap<String, (Vec<String>, BTreeSet<String>)>) -> Self {
+        let cycles = Self::cycles(&modules);
+        let in_cycle = cycles.iter().flatten().collect::<BTreeSet<_>>();
+        let load_order = Self::load_order(&modules, &in_cycle);
+        let modules = modules
+            .iter()
+            .map(|(name, (urls, requires))| ModuleNode {
+                name: name.to_owned(),
+                urls: urls.to_owned(),
+                requires: requires.iter().cloned().collect(),
+                missing: requires
+                    .iter()
+                    .filter(|required| !modules.contains_key(*required))
+                    .cloned()
+                    .collect(),
+            })
+            .collect();
+        Self {
+            modules,
+            cycles,
+            load_order,
+        }
+    }
+
+    /**
+      Return the strongly connected components (Tarjan's algorithm) that
+      contain more than one module or a module requiring itself.
+    */
+    fn cycles(modules: &BTreeMap<String, (Vec<String>, BTreeSet<String>)>) -> Vec<Vec<String>> {
+        struct Tarjan<'a> {
+            modules: &'a BTreeMap<String, (Vec<String>, BTreeSet<String>)>,
+            index: BTreeMap<&'a str, usize>,
+            low_link: BTreeMap<&'a str, usize>,
+            stack: Vec<&'a str>,
+            on_stack: BTreeSet<&'a str>,
+            cycles: Vec<Vec<String>>,
+        }
+        impl<'a> Tarjan<'a> {
+            fn visit(&mut self, name: &'a str) {
+                let index = self.index.len();
+                self.index.insert(name, index);
+                self.low_link.insert(name, index);
+                self.stack.push(name);
+                self.on_stack.insert(name);
+                let modules = self.modules;
+                let requires = &modules[name].1;
+                for required in requires {
+                    let required = required.as_str();
+                    if !modules.contains_key(required) {
+                        continue;
+                    }
+                    if !self.index.contains_key(required) {
+                        self.visit(required);
+                        let low_link = std::cmp::min(self.low_link[name], self.low_link[required]);
+                        self.low_link.insert(name, low_link);
+                    } else if self.on_stack.contains(required) {
+                        let low_link = std::cmp::min(self.low_link[name], self.index[required]);
+                        self.low_link.insert(name, low_link);
+                    }
+                }
+                if self.low_link[name] == self.index[name] {
+                    let mut component = vec![];
+                    while let Some(member) = self.stack.pop() {
+                        self.on_stack.remove(member);
+                        component.push(member.to_owned());
+                        if member == name {
+                            break;
+                        }
+                    }
+                    if component.len() > 1 || requires.contains(name) {
+                        component.sort();
+                        self.cycles.push(component);
+                    }
+                }
+            }
+        }
+        let mut tarjan = Tarjan {
+            modules,
+            index: BTreeMap::new(),
+            low_link: BTreeMap::new(),
+            stack: vec![],
+            on_stack: BTreeSet::new(),
+            cycles: vec![],
+        };
+        for name in modules.keys() {
+            if !tarjan.index.contains_key(name.as_str()) {
+                tarjan.visit(name);
+            }
+        }
+        tarjan.cycles.sort();
+        tarjan.cycles
+    }
+
+    /**
+      Return a topological order of the modules that neither are part of nor
+      (indirectly) require a cycle. Ties are broken by name. Missing modules
+      are ignored.
+    */
+    fn load_order(
+        modules: &BTreeMap<String, (Vec<String>, BTreeSet<String>)>,
+        in_cycle: &BTreeSet<&String>,
+    ) -> Vec<String> {
+        let mut remaining = modules
+            .iter()
+            .filter(|(name, _)| !in_cycle.contains(name))
+            .map(|(name, (_, requires))| {
+                let requires = requires
+                    .iter()
+                    .filter(|required| modules.contains_key(*required))
+                    .collect::<BTreeSet<_>>();
+                (name, requires)
+            })
+            .collect::<BTreeMap<_, _>>();
+        let mut load_order = vec![];
+        loop {
+            let ready = remaining
+                .iter()
+                .find(|(_, requires)| requires.is_empty())
+                .map(|(name, _)| *name);
+            let Some(ready) = ready else {
+                break;
+            };
+            remaining.remove(ready);
+            remaining.values_mut().for_each(|requires| {
+                requires.remove(ready);
+            });
+            load_order.push(ready.to_owned());
+        }
+        load_order
+    }
+}
@@ -0,0 +1,184 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Evaluation of `Ingress` manifests without cataloging them.
+
+use k8s_openapi::api::networking::v1::Ingress;
+use kube::ResourceExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::ingress_rules;
+use super::IngressHostPath;
+use super::IngressMonitor;
+use super::PathRewrite;
+use super::Variant;
+use crate::kubers_util::LabelSelector;
+
+/// Entry that would be cataloged for a hostname + path of an `Ingress` rule.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DryRunEntry {
+    /// Hostname of the rule.
+    pub host: String,
+    /// Declared path of the rule.
+    pub path: String,
+    /// Name of the variant.
+    pub variant: String,
+    /// Name of the backend `Service`.
+    pub service_name: String,
+    /// URL that the browser must request.
+    pub url: String,
+    /// Path that the backend receives after the rewrite.
+    pub backend_path: String,
+    /// `false` when the hostname is not allowed by the exposed host patterns.
+    pub exposed: bool,
+    /// Resource in the local cluster that already declares the hostname + path, if any.
+    pub declared_by: Option<String>,
+}
+
+/// Part of an `Ingress` that would be skipped since it cannot be cataloged.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DryRunSkipped {
+    /// Machine readable reason, like `missing_host`.
+    pub reason: String,
+    /// Hostname and/or path of the skipped part. Might be empty.
+    pub detail: String,
+}
+
+/// Whether and how an `Ingress` would be cataloged.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DryRunReport {
+    /// Namespace that the `Ingress` was evaluated for.
+    pub namespace: String,
+    /// Labels that the `Ingress` must have in the namespace.
+    pub label_selector: String,
+    /// `true` when the `Ingress` has all the required labels.
+    pub matched: bool,
+    /// Prefix of the annotations that are exposed in the namespace.
+    pub annotation_prefix: String,
+    /// Exposed annotations (without prefix).
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub annotations: HashMap<String, serde_json::Value>,
+    /// Violations of the configured annotation schema.
+    pub validation_errors: Vec<String>,
+    /// `true` when the entries would be omitted due to invalid annotations.
+    pub rejected: bool,
+    /// Entries that would be cataloged.
+    pub entries: Vec<DryRunEntry>,
+    /// Parts of the `Ingress` that would be skipped.
+    pub skipped: Vec<DryRunSkipped>,
+}
+
+impl IngressMonitor {
+    /**
+      Return whether and how the `Ingress` would be cataloged in its namespace
+      (or `default_namespace` if it has none) without cataloging it.
+
+      Annotations inherited from `Namespace` defaults or referenced
+      `ConfigMap`s are not included.
+    */
+    pub fn dry_run(self: &Arc<Self>, ingress: &Ingress, default_namespace: &str) -> DryRunReport {
+        let namespace = ingress
+            .namespace()
+            .unwrap_or_else(|| default_namespace.to_owned());
+        let namespace_selector = self.app_config.ingress.namespace_selector(&namespace);
+        let matched = namespace_selector
+            .labels
+            .parse::<LabelSelector>()
+            .is_ok_and(|selector| selector.matches(ingress.labels()));
+        let tag_prefix = namespace_selector.annotation_prefix;
+        let annotations = self.exposed_annotations(
+            ingress
+                .annotations()
+                .iter()
+                .filter_map(|(annotation_key, annotation_value)| {
+                    annotation_key
+                        .strip_prefix(tag_prefix)
+                        .map(|key| (key.to_owned(), annotation_value.to_owned()))
+                })
+                .collect(),
+        );
+        let validation_errors = self.annotation_validation_errors(&annotations);
+        let rejected = !validation_errors.is_empty() && self.reject_invalid_annotations();
+        let tls_hosts = Self::tls_hosts(ingress);
+        let variant = Variant::from_ingress(ingress, tag_prefix);
+        let path_rewrite = PathRewrite::from_ingress(ingress, tag_prefix);
+        let local_id = self.app_config.clusters.local_id();
+        let source = "Ingress/".to_owned() + &ingress.name_any();
+        let mut skipped = vec![];
+        let entries = ingress_rules::ingress_rule_paths(ingress, &namespace, Some(&mut skipped))
+            .into_iter()
+            .map(|rule_path| {
+                let rewritten = path_rewrite
+                    .as_ref()
+                    .map(|path_rewrite| path_rewrite.apply(rule_path.path));
+                let external_path = rewritten
+                    .as_ref()
+                    .map_or(rule_path.path, |rewritten| &rewritten.external_path);
+                let scheme = if Self::is_tls_host(&tls_hosts, rule_path.host) {
+                    "https"
+                } else {
+                    "http"
+                };
+                let key =
+                    IngressHostPath::key(local_id, rule_path.host, rule_path.path, &variant.name);
+                DryRunEntry {
+                    host: rule_path.host.to_owned(),
+                    path: rule_path.path.to_owned(),
+                    variant: variant.name.to_owned(),
+                    service_name: rule_path.service_name.to_owned(),
+                    url: scheme.to_owned() + "://" + rule_path.host + external_path,
+                    backend_path: rewritten
+                        .as_ref()
+                        .map_or(rule_path.path, |rewritten| &rewritten.backend_path)
+                        .to_owned(),
+                    exposed: self
+                        .running_config()
+                        .ingress
+                        .is_host_exposed(rule_path.host),
+                    declared_by: self
+                        .host_path_claims
+                        .claims(&key)
+                        .into_iter()
+                        .find(|claim| claim.namespace != namespace || claim.source != source)
+                        .map(|claim| claim.source + " in ns/" + &claim.namespace),
+                }
+            })
+            .collect();
+        DryRunReport {
+            label_selector: namespace_selector.labels.to_owned(),
+            matched,
+            annotation_prefix: tag_prefix.to_owned(),
+            annotations,
+            validation_errors,
+            rejected,
+            entries,
+            skipped: skipped
+                .into_iter()
+                .map(|skipped_part| DryRunSkipped {
+                    reason: skipped_part.reason.to_owned(),
+                    detail: skipped_part.detail,
+                })
+                .collect(),
+            namespace,
+        }
+    }
+}
@@ -0,0 +1,116 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+//! Publishing of Kubernetes `Event`s on the resources declaring entries.
+
+use kube::runtime::events::{Event, EventType, Recorder, Reporter};
+use std::sync::Arc;
+
+use super::KubeCluster;
+use super::SourceObject;
+
+/// Name of the controller reported as source of published `Event`s.
+const REPORTING_CONTROLLER: &str = "microfefind";
+
+/**
+Publishes Kubernetes `Event`s when entries of a resource are cataloged,
+updated, rejected or removed.
+
+This lets tenants see discovery feedback with `kubectl describe ingress`
+without access to the logs. `Event`s are published in the background and
+failures are only logged, since they are not essential.
+ */
+pub struct EventRecorder {
+    /// `false` if no `Event`s are published.
+    enabled: bool,
+    /// Identity of this instance as source of `Event`s.
+    reporter: Reporter,
+}
+
+impl EventRecorder {
+    /// Return a new instance.
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            reporter: Reporter {
+                controller: REPORTING_CONTROLLER.to_owned(),
+                instance: std::env::var("HOSTNAME").ok(),
+            },
+        }
+    }
+
+    /// Publish a `Normal` event on the declaring resource.
+    pub fn normal(
+        &self,
+        cluster: &Arc<KubeCluster>,
+        source_object: Option<&SourceObject>,
+        reason: &str,
+        note: String,
+    ) {
+        self.publish(cluster, source_object, EventType::Normal, reason, note);
+    }
+
+    /// Publish a `Warning` event on the declaring resource.
+    pub fn warning(
+        &self,
+        cluster: &Arc<KubeCluster>,
+        source_object: Option<&SourceObject>,
+        reason: &str,
+        note: String,
+    ) {
+        self.publish(cluster, source_object, EventType::Warning, reason, note);
+    }
+
+    /// Publish an event in the background unless disabled or the declaring resource is unknown.
+    fn publish(
+        &self,
+        cluster: &Arc<KubeCluster>,
+        source_object: Option<&SourceObject>,
+        type_: EventType,
+        reason: &str,
+        note: String,
+    ) {
+        let Some(source_object) = source_object.filter(|_| self.enabled) else {
+            return;
+        };
+        let recorder = Recorder::new(
+            cluster.client(),
+            self.reporter.clone(),
+            source_object.object_reference(),
+        );
+        let event = Event {
+            type_,
+            reason: reason.to_owned(),
+            note: Some(note),
+            action: reason.to_owned(),
+            secondary: None,
+        };
+        let reason = event.reason.to_owned();
+        let object = format!(
+            "{}/{}",
+            source_object.kind.to_lowercase(),
+            source_object.name
+        );
+        let namespace = source_object.namespace.to_owned();
+        tokio::spawn(async move {
+            if let Err(e) = recorder.publish(event).await {
+                log::warn!(
+                    "Failed to publish '{reason}' event on '{object}' in 'ns/{namespace}': {e}"
+                );
+            }
+        });
+    }
+}
@@ -0,0 +1,142 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+//! Tracking of resources that declare the same hostname + path combination.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::conf::ConflictPolicy;
+
+use super::HostPathState;
+
+/**
+Declared state of each hostname + path combination by every resource that
+declares it.
+
+More than one claim of the same entry is a conflict that is resolved by a
+[ConflictPolicy].
+ */
+#[derive(Default)]
+pub struct HostPathClaims {
+    /// Claims by the same key as the monitored entries.
+    claims: Mutex<BTreeMap<String, Vec<HostPathState>>>,
+}
+
+impl HostPathClaims {
+    /// Add or replace the claim of the declaring resource.
+    pub fn claim(&self, key: &str, state: HostPathState) {
+        let mut claims = self.claims.lock().unwrap();
+        let key_claims = claims.entry(key.to_owned()).or_default();
+        key_claims.retain(|claim| !Self::is_same_source(claim, &state.namespace, &state.source));
+        key_claims.push(state);
+        key_claims.sort_by(|a, b| {
+            (a.created_millis, &a.namespace, &a.source).cmp(&(
+                b.created_millis,
+                &b.namespace,
+                &b.source,
+            ))
+        });
+    }
+
+    /// Remove the claim of the declaring resource.
+    pub fn release(&self, key: &str, namespace: &str, source: &str) {
+        let mut claims = self.claims.lock().unwrap();
+        if let Some(key_claims) = claims.get_mut(key) {
+            key_claims.retain(|claim| !Self::is_same_source(claim, namespace, source));
+            if key_claims.is_empty() {
+                claims.remove(key);
+            }
+        }
+    }
+
+    /**
+      Return the claim that should be cataloged according to the `policy` or
+      `None` if the entry should not be cataloged at all.
+    */
+    pub fn winner(&self, key: &str, policy: ConflictPolicy) -> Option<HostPathState> {
+        let claims = self.claims.lock().unwrap();
+        Self::winning_claim(claims.get(key)?, policy).cloned()
+    }
+
+    /**
+      Return the claim that should be cataloged according to the `policy` from
+      all claims of the same entry ordered by creation time.
+    */
+    pub fn winning_claim(
+        key_claims: &[HostPathState],
+        policy: ConflictPolicy,
+    ) -> Option<&HostPathState> {
+        match policy {
+            ConflictPolicy::FirstWins => key_claims.first(),
+            ConflictPolicy::NewestWins => key_claims.last(),
+            ConflictPolicy::RejectBoth if key_claims.len() == 1 => key_claims.first(),
+            ConflictPolicy::RejectBoth => None,
+        }
+    }
+
+    /// Return the claims of the entry ordered by creation time.
+    pub fn claims(&self, key: &str) -> Vec<HostPathState> {
+        self.claims
+            .lock()
+            .unwrap()
+            .get(key)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Return all claims.
+    pub fn get_all(&self) -> Vec<HostPathState> {
+        self.claims
+            .lock()
+            .unwrap()
+            .values()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// Return all claims of entries that are claimed by more than one resource.
+    pub fn conflicts(&self) -> Vec<Vec<HostPathState>> {
+        self.claims
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|key_claims| key_claims.len() > 1)
+            .cloned()
+            .collect()
+    }
+
+    /// Return the key and source of every claim by resources in the namespace.
+    pub fn claims_in_namespace(&self, cluster_id: &str, namespace: &str) -> Vec<(String, String)> {
+        self.claims
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|(key, key_claims)| {
+                key_claims
+                    .iter()
+                    .filter(|claim| claim.cluster == cluster_id && claim.namespace == namespace)
+                    .map(|claim| (key.to_owned(), claim.source.to_owned()))
+            })
+            .collect()
+    }
+
+    /// Return `true` if the claim was made by the resource.
+    fn is_same_source(claim: &HostPathState, namespace: &str, source: &str) -> bool {
+        claim.namespace == namespace && claim.source == source
+    }
+}
@@ -17,15 +17,59 @@
 
 //! Home of [IngressHostPath] and related `Service` and `Pod` monitoring.
 
+mod config_map_monitor;
+mod entry_history;
+mod interned_keys;
+mod lifecycle;
+mod path_type;
 mod service_monitor;
+mod updated_millis;
 
 use crossbeam_skiplist::SkipMap;
 use futures::lock::Mutex;
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 
+use self::config_map_monitor::ConfigMapMonitor;
+use self::entry_history::EntryHistory;
 use self::service_monitor::ServiceMonitor;
+use self::updated_millis::UpdatedMillis;
+use super::HealthProbeResult;
+use super::KubeCluster;
+use super::ProbeResult;
+use super::RewrittenPath;
+use super::SourceObject;
+use super::PRIMARY_VARIANT;
+use crate::notifier::{ChangeEvent, ChangeKind, Notifier};
+use crate::time::Clock;
+
+pub use self::entry_history::EntryChange;
+pub use self::lifecycle::Lifecycle;
+pub use self::lifecycle::LifecycleEvent;
+pub use self::lifecycle::LifecycleState;
+pub use self::lifecycle::LifecycleTransition;
+pub use self::path_type::PathType;
+pub use self::service_monitor::AnnotationSources;
+pub use self::service_monitor::DeploymentRollout;
+pub use self::service_monitor::RolloutState;
+
+/// Annotation (without prefix) referencing a `ConfigMap` with extended meta-data.
+pub const CONFIG_REF_ANNOTATION: &str = "config-ref";
+/// Annotation (without prefix) that takes an entry out of rotation when `"true"`.
+pub const HIDDEN_ANNOTATION: &str = "hidden";
+/// Annotation (without prefix) with the path of a health endpoint served by the mapped `Service`.
+pub const HEALTH_PATH_ANNOTATION: &str = "health-path";
+/// Annotation (without prefix) with the (relative) URL of the entry's JavaScript entry point.
+pub const ENTRY_ANNOTATION: &str = "entry";
+/// Annotation (without prefix) with the (relative) URL of the entry's asset manifest.
+pub const MANIFEST_ANNOTATION: &str = "manifest";
+/// Annotation (without prefix) with the business domain the entry belongs to.
+pub const GROUP_ANNOTATION: &str = "group";
+/// Annotation (without prefix) with the owning team, used as group when no group is declared.
+pub const TEAM_ANNOTATION: &str = "team";
+/// Annotation (without prefix) with the mounting priority of the entry, where higher values come first.
+pub const PRIORITY_ANNOTATION: &str = "priority";
 
 /**
    Representation of a hostname + path mapped by an `Ingress` to a `Service` and
@@ -33,35 +77,209 @@ use self::service_monitor::ServiceMonitor;
 */
 pub struct IngressHostPath {
     /// Last update timestamp in milliseconds sinch Unix Epoch.
-    updated_millis: Arc<AtomicU64>,
+    updated_millis: Arc<UpdatedMillis>,
+    /// Cluster where the `Ingress` is declared.
+    cluster: Arc<KubeCluster>,
     /// Hostname defined in `Ingress`.
     host: String,
     /// Path defined in `Ingress`.
     path: String,
+    /// Concatinated hostname and path, computed once since it is used frequently.
+    host_path: Arc<str>,
+    /// How the path is matched according to the `Ingress`.
+    path_type: std::sync::Mutex<PathType>,
+    /// Kubernetes namespace of the `Ingress`.
+    namespace: String,
+    /// Name of the variant of the hostname + path served by the `Ingress`.
+    variant: String,
+    /// Share of traffic (0-100) served by the variant, if declared.
+    weight: std::sync::Mutex<Option<u32>>,
+    /// `true` if the hostname is served over HTTPS according to the `Ingress`.
+    tls: AtomicBool,
     /// Prefixed `Ingress` annotations with the prefix removed.
-    annotations: SkipMap<String, String>,
+    annotations: SkipMap<Arc<str>, String>,
+    /// Prefixed `Ingress` labels with the prefix removed.
+    labels: std::sync::Mutex<BTreeMap<String, String>>,
+    /// Client-facing and backend path if the Ingress controller rewrites the path.
+    rewritten: std::sync::Mutex<Option<RewrittenPath>>,
+    /// Kind and name of the resource declaring the entry, like `Ingress/checkout`.
+    source: std::sync::Mutex<String>,
+    /// Identity and version of the resource declaring the entry, unless imported.
+    source_object: std::sync::Mutex<Option<SourceObject>>,
+    /// Lifecycle state machine shared with the `Service` and `Pod` monitoring.
+    lifecycle: Arc<Lifecycle>,
     /// Reference to object responsible for montitoring of mapped `Service`.
     service_monitor: Arc<Mutex<Option<Arc<ServiceMonitor>>>>,
+    /// Reference to object responsible for montitoring of a referenced `ConfigMap`.
+    config_map_monitor: Arc<Mutex<Option<Arc<ConfigMapMonitor>>>>,
+    /// Outcome of the most recent reachability probe, if any.
+    probe_result: std::sync::Mutex<Option<ProbeResult>>,
+    /// Outcome of the most recent probe of the health endpoint, if any.
+    health_result: std::sync::Mutex<Option<HealthProbeResult>>,
+    /// Sink for changes of the entry.
+    notifier: Arc<dyn Notifier>,
+    /// Recent changes of the entry, including rollouts of backing `Deployment`s.
+    history: Arc<EntryHistory>,
+    /// Source of update timestamps.
+    clock: Arc<dyn Clock>,
+    /// Other resources that prefixed annotations are collected from.
+    annotation_sources: AnnotationSources,
 }
 
 impl IngressHostPath {
-    /// Return a new instance.
-    pub async fn new(host: &str, path: &str, namespace: &str, service_name: &str) -> Arc<Self> {
-        let updated_millis = Arc::new(AtomicU64::new(0));
+    /// Return a new instance that advances the `catalogue_revision` whenever it is updated.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn new(
+        cluster: &Arc<KubeCluster>,
+        host: &str,
+        path: &str,
+        namespace: &str,
+        service_name: &str,
+        variant: &str,
+        notifier: Arc<dyn Notifier>,
+        clock: Arc<dyn Clock>,
+        annotation_sources: AnnotationSources,
+        catalogue_revision: Arc<AtomicU64>,
+    ) -> Arc<Self> {
+        let updated_millis = Arc::new(UpdatedMillis::new(catalogue_revision));
+        let history = Arc::new(EntryHistory::default());
+        let lifecycle = Arc::new(Lifecycle::new(
+            &Self::identifier(host, path),
+            Arc::clone(&clock),
+        ));
         Arc::new(Self {
             updated_millis: Arc::clone(&updated_millis),
+            cluster: Arc::clone(cluster),
             host: host.to_owned(),
+            host_path: Arc::from(Self::identifier(host, path)),
             path: path.to_owned(),
+            path_type: std::sync::Mutex::new(PathType::ImplementationSpecific),
+            namespace: namespace.to_owned(),
+            variant: variant.to_owned(),
+            weight: std::sync::Mutex::new(None),
+            tls: AtomicBool::new(false),
             annotations: SkipMap::new(),
+            labels: std::sync::Mutex::new(BTreeMap::new()),
+            rewritten: std::sync::Mutex::new(None),
+            source: std::sync::Mutex::new(String::new()),
+            source_object: std::sync::Mutex::new(None),
+            lifecycle: Arc::clone(&lifecycle),
             service_monitor: Arc::new(Mutex::new(Some(
-                ServiceMonitor::new(namespace, service_name, updated_millis).await,
+                ServiceMonitor::new(
+                    cluster.client(),
+                    namespace,
+                    service_name,
+                    updated_millis,
+                    lifecycle,
+                    Arc::clone(&history),
+                    annotation_sources.clone(),
+                )
+                .await,
             ))),
+            config_map_monitor: Arc::new(Mutex::new(None)),
+            probe_result: std::sync::Mutex::new(None),
+            health_result: std::sync::Mutex::new(None),
+            notifier,
+            history,
+            clock,
+            annotation_sources,
         })
     }
 
+    /// Return a change of the entry.
+    pub fn change_event(self: &Arc<Self>, kind: ChangeKind, property: Option<&str>) -> ChangeEvent {
+        ChangeEvent {
+            kind,
+            cluster: self.cluster_id().to_owned(),
+            namespace: self.namespace.to_owned(),
+            host_path: self.host_path().to_owned(),
+            variant: self.variant.to_owned(),
+            property: property.map(str::to_owned),
+            source: Some(self.source.lock().unwrap().to_owned())
+                .filter(|source| !source.is_empty()),
+            old: None,
+            new: None,
+            timestamp: self.clock.now_as_millis(),
+            // Assigned by the ChangeFeed when published
+            sequence: 0,
+        }
+    }
+
+    /// Record the change in the history of the entry and publish it.
+    fn publish(self: &Arc<Self>, event: &ChangeEvent) {
+        self.history.record(EntryChange::from(event));
+        self.notifier.notify(event);
+    }
+
+    /// Record and publish that the entry was added to or removed from the catalogue.
+    pub fn publish_lifecycle(self: &Arc<Self>, kind: ChangeKind) {
+        self.publish(&self.change_event(kind, None));
+    }
+
+    /// Return the recent changes of the entry, oldest first.
+    pub fn history(self: &Arc<Self>) -> Vec<EntryChange> {
+        self.history.changes()
+    }
+
+    /// Mark the entry as updated and publish the change of the property.
+    fn changed(self: &Arc<Self>, property: &str) {
+        self.updated_millis.store(self.clock.now_as_millis());
+        self.publish(&self.change_event(ChangeKind::Updated, Some(property)));
+    }
+
+    /**
+      Mark the entry as updated and publish the change of the property with the
+      old and new value.
+    */
+    fn changed_from<T: serde::Serialize>(self: &Arc<Self>, property: &str, old: T, new: T) {
+        self.updated_millis.store(self.clock.now_as_millis());
+        let mut event = self.change_event(ChangeKind::Updated, Some(property));
+        event.old = serde_json::to_value(old).ok();
+        event.new = serde_json::to_value(new).ok();
+        self.publish(&event);
+    }
+
     /// Return the concatinated hostname and path.
-    pub fn host_path(self: &Arc<Self>) -> String {
-        Self::identifier(&self.host, &self.path)
+    pub fn host_path(self: &Arc<Self>) -> &str {
+        &self.host_path
+    }
+
+    /**
+      Return the key of an entry that is unique across all monitored clusters.
+
+      The key of the primary variant is the same as it would be without
+      variants, so entries are kept in order primary first.
+    */
+    pub fn key(cluster_id: &str, host: &str, path: &str, variant: &str) -> String {
+        if variant == PRIMARY_VARIANT {
+            cluster_id.to_owned() + "/" + host + path
+        } else {
+            cluster_id.to_owned() + "/" + host + path + "#" + variant
+        }
+    }
+
+    /**
+      Return the mounting priority declared by a [PRIORITY_ANNOTATION] value.
+
+      Entries without a valid integer priority have priority `0`.
+    */
+    pub fn parse_priority(value: &str) -> i64 {
+        value.trim().parse().unwrap_or_default()
+    }
+
+    /// Return the identifier of the cluster where the `Ingress` is declared.
+    pub fn cluster_id(self: &Arc<Self>) -> &str {
+        self.cluster.id()
+    }
+
+    /// Return the Kubernetes cluster where the entry was discovered.
+    pub fn cluster(self: &Arc<Self>) -> &Arc<KubeCluster> {
+        &self.cluster
+    }
+
+    /// Return the hostname and path as separate parts.
+    pub fn host_and_path(self: &Arc<Self>) -> (&str, &str) {
+        (&self.host, &self.path)
     }
 
     /// Return the concatinated hostname and path.
@@ -69,21 +287,356 @@ impl IngressHostPath {
         host.to_owned() + path
     }
 
+    /// Return how the path is matched according to the `Ingress`.
+    pub fn path_type(self: &Arc<Self>) -> PathType {
+        *self.path_type.lock().unwrap()
+    }
+
+    /// Return `https` if the hostname is served over TLS and `http` otherwise.
+    pub fn scheme(self: &Arc<Self>) -> &'static str {
+        if self.tls() {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    /// Return the fully qualified URL of the hostname + path.
+    pub fn url(self: &Arc<Self>) -> String {
+        self.scheme().to_owned() + "://" + &self.host + &self.external_path()
+    }
+
+    /**
+      Return the path that the browser must request, which differs from the
+      declared path when it is a regular expression.
+    */
+    pub fn external_path(self: &Arc<Self>) -> String {
+        self.rewritten
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|rewritten| rewritten.external_path.to_owned())
+            .unwrap_or_else(|| self.path.to_owned())
+    }
+
+    /// Return the path that the backend receives after rewrites by the Ingress controller.
+    pub fn backend_path(self: &Arc<Self>) -> String {
+        self.rewritten
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|rewritten| rewritten.backend_path.to_owned())
+            .unwrap_or_else(|| self.path.to_owned())
+    }
+
+    /// Return `true` if the hostname is served over TLS according to the `Ingress`.
+    pub fn tls(self: &Arc<Self>) -> bool {
+        self.tls.load(Ordering::Relaxed)
+    }
+
+    /// Return the name of the mapped `Service`.
+    pub async fn service_name(self: &Arc<Self>) -> String {
+        self.service_monitor
+            .lock()
+            .await
+            .as_ref()
+            .map(|service_monitor| service_monitor.service_name().to_owned())
+            .unwrap_or_default()
+    }
+
+    /**
+      Return the in-cluster URL of the health endpoint of the mapped `Service`
+      declared by the [HEALTH_PATH_ANNOTATION], if any.
+    */
+    pub async fn health_url(self: &Arc<Self>) -> Option<String> {
+        let annotations = self.annotations_map().await;
+        let health_path = annotations.get(HEALTH_PATH_ANNOTATION)?.trim();
+        Some(self.service_base_url().await? + "/" + health_path.trim_start_matches('/'))
+    }
+
+    /**
+      Return the in-cluster base URL of the mapped `Service`, like
+      `http://checkout.shop.svc:8080`, if it is known and not of type
+      `ExternalName`.
+    */
+    pub async fn service_base_url(self: &Arc<Self>) -> Option<String> {
+        let service_monitor = self.service_monitor.lock().await;
+        let service_monitor = service_monitor.as_ref()?;
+        if service_monitor.external_name().is_some() {
+            return None;
+        }
+        Some(format!(
+            "http://{}.{}.svc:{}",
+            service_monitor.service_name(),
+            service_monitor.namespace(),
+            service_monitor.port()?,
+        ))
+    }
+
+    /// Return the external target if the mapped `Service` is of type `ExternalName`.
+    pub async fn external_name(self: &Arc<Self>) -> Option<String> {
+        self.service_monitor
+            .lock()
+            .await
+            .as_ref()
+            .and_then(|service_monitor| service_monitor.external_name())
+    }
+
+    /**
+      Return `true` if the mapped `Service` has no selector and is not of type
+      `ExternalName`.
+    */
+    pub async fn has_selectorless_service(self: &Arc<Self>) -> bool {
+        self.service_monitor
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|service_monitor| service_monitor.is_selectorless())
+    }
+
+    /**
+      Return the number of replicas and ready replicas backing the mapped
+      `Service`, based on its endpoints.
+    */
+    pub async fn replica_counts(self: &Arc<Self>) -> (usize, usize) {
+        self.service_monitor
+            .lock()
+            .await
+            .as_ref()
+            .map(|service_monitor| service_monitor.endpoint_counts())
+            .unwrap_or_default()
+    }
+
+    /// Return the revision and rollout state of each `Deployment` backing the mapped `Service`.
+    pub async fn deployment_rollouts(self: &Arc<Self>) -> Vec<DeploymentRollout> {
+        match self.service_monitor.lock().await.as_ref() {
+            Some(service_monitor) => service_monitor.deployment_rollouts().await,
+            None => vec![],
+        }
+    }
+
+    /// Return `true` if the hostname is a wildcard like `*.example.com`.
+    pub fn is_wildcard(self: &Arc<Self>) -> bool {
+        self.host.starts_with("*.")
+    }
+
+    /// Return the identity and version of the resource declaring the entry, unless imported.
+    pub fn source_object(self: &Arc<Self>) -> Option<SourceObject> {
+        self.source_object.lock().unwrap().to_owned()
+    }
+
+    /// Return the Kubernetes namespace of the `Ingress`.
+    pub fn namespace(self: &Arc<Self>) -> &str {
+        &self.namespace
+    }
+
+    /// Return the name of the variant of the hostname + path served by the `Ingress`.
+    pub fn variant(self: &Arc<Self>) -> &str {
+        &self.variant
+    }
+
+    /// Return the share of traffic (0-100) served by the variant, if declared.
+    pub fn weight(self: &Arc<Self>) -> Option<u32> {
+        *self.weight.lock().unwrap()
+    }
+
     /**
       Last update of this `Ingress`, the `Service` mapped by the `Ingress` or
       change in ownership of any `Pod` backing the `Service`.
     */
     pub async fn updated_millis(self: &Arc<Self>) -> u64 {
-        self.updated_millis.load(Ordering::Relaxed)
+        self.updated_millis.load()
     }
 
-    /// Prefixed `Ingress` annotations with the prefix removed.
-    pub fn annotations_map(self: &Arc<Self>) -> HashMap<String, String> {
-        HashMap::from_iter(
+    /// Current [LifecycleState] of this entry.
+    pub fn lifecycle_state(self: &Arc<Self>) -> LifecycleState {
+        self.lifecycle.state()
+    }
+
+    /**
+      Return `true` if the mapped `Service` has at least one ready endpoint or
+      `None` if this is not yet known.
+    */
+    pub fn available(self: &Arc<Self>) -> Option<bool> {
+        self.lifecycle.backend_available()
+    }
+
+    /**
+      Return `true` if the URL of the entry responded to the most recent probe
+      or `None` if it has not been probed.
+    */
+    pub fn reachable(self: &Arc<Self>) -> Option<bool> {
+        self.probe_result
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|probe_result| probe_result.reachable)
+    }
+
+    /// Outcome of the most recent reachability probe, if any.
+    pub fn probe_result(self: &Arc<Self>) -> Option<ProbeResult> {
+        self.probe_result.lock().unwrap().clone()
+    }
+
+    /// Outcome of the most recent probe of the health endpoint, if any.
+    pub fn health_result(self: &Arc<Self>) -> Option<HealthProbeResult> {
+        self.health_result.lock().unwrap().clone()
+    }
+
+    /// Return `true` if the entry is monitored, but taken out of rotation.
+    pub fn is_hidden(self: &Arc<Self>) -> bool {
+        self.lifecycle.state() == LifecycleState::Disabled
+    }
+
+    /// Most recent [LifecycleTransition] of this entry, if any.
+    pub fn lifecycle_last_transition(self: &Arc<Self>) -> Option<LifecycleTransition> {
+        self.lifecycle.last_transition()
+    }
+
+    /**
+      Invoked when the `Ingress` path has been deleted to stop all related
+      monitoring and move the entry to its terminal [LifecycleState].
+    */
+    pub async fn remove(self: &Arc<Self>) {
+        self.lifecycle.handle(LifecycleEvent::Delete);
+        if let Some(service_monitor) = self.service_monitor.lock().await.as_ref() {
+            service_monitor.abort_background_tasks().await;
+        }
+        if let Some(config_map_monitor) = self.config_map_monitor.lock().await.as_ref() {
+            config_map_monitor.abort_background_tasks().await;
+        }
+        self.lifecycle.handle(LifecycleEvent::Remove);
+        self.updated_millis.store(self.clock.now_as_millis());
+    }
+
+    /**
+      Prefixed `Ingress` annotations with the prefix removed.
+
+      Prefixed annotations of the mapped `Service` and its backing
+      `Deployment`s (when collected) and keys from a `ConfigMap` referenced by
+      the [CONFIG_REF_ANNOTATION] are merged in. Precedence is, from lowest to
+      highest: `Deployment`, `Service`, `ConfigMap` and `Ingress`.
+    */
+    pub async fn annotations_map(self: &Arc<Self>) -> HashMap<String, String> {
+        let mut ret = HashMap::new();
+        if let Some(service_monitor) = self.service_monitor.lock().await.as_ref() {
+            ret.extend(service_monitor.annotations().await);
+        }
+        if let Some(config_map_monitor) = self.config_map_monitor.lock().await.as_ref() {
+            ret.extend(
+                config_map_monitor
+                    .data()
+                    .iter()
+                    .map(|entry| (entry.key().to_owned(), entry.value().to_owned())),
+            );
+        }
+        ret.extend(
             self.annotations
                 .iter()
-                .map(|entry| (entry.key().to_owned(), entry.value().to_owned())),
-        )
+                .map(|entry| (entry.key().to_string(), entry.value().to_owned())),
+        );
+        ret
+    }
+
+    /// Prefixed `Ingress` labels with the prefix removed.
+    pub fn labels_map(self: &Arc<Self>) -> BTreeMap<String, String> {
+        self.labels.lock().unwrap().clone()
+    }
+
+    /**
+      Return the absolute URL of the asset manifest referenced by the
+      [MANIFEST_ANNOTATION], if any.
+    */
+    pub async fn manifest_url(self: &Arc<Self>) -> Option<String> {
+        self.annotations_map()
+            .await
+            .get(MANIFEST_ANNOTATION)
+            .and_then(|reference| self.resolve_url(reference))
+    }
+
+    /**
+      Return the absolute URL of the JavaScript entry point (like
+      `remoteEntry.js`) referenced by the [ENTRY_ANNOTATION], if any.
+    */
+    pub async fn entry_point_url(self: &Arc<Self>) -> Option<String> {
+        self.annotations_map()
+            .await
+            .get(ENTRY_ANNOTATION)
+            .and_then(|reference| self.resolve_url(reference))
+    }
+
+    /**
+      Return the absolute URL of the `reference`. Relative references are
+      resolved against the entry's path, as if it ended with a slash.
+    */
+    pub fn resolve_url(self: &Arc<Self>, reference: &str) -> Option<String> {
+        let mut base = self.url();
+        if !base.ends_with('/') {
+            base.push('/');
+        }
+        reqwest::Url::parse(&base)
+            .and_then(|base| base.join(reference.trim()))
+            .ok()
+            .filter(|url| url.scheme() == "http" || url.scheme() == "https")
+            .map(|url| url.to_string())
+    }
+
+    /**
+      Invoked when `Ingress` has been modified to check if the `ConfigMap`
+      referenced by the [CONFIG_REF_ANNOTATION] has changed.
+    */
+    pub async fn config_ref_update(self: &Arc<Self>) {
+        let config_map_name = self
+            .annotations
+            .get(CONFIG_REF_ANNOTATION)
+            .map(|entry| entry.value().to_owned())
+            .filter(|config_map_name| !config_map_name.is_empty());
+        let mut config_map_monitor_opt = self.config_map_monitor.lock().await;
+        let current_name = config_map_monitor_opt
+            .as_ref()
+            .map(|config_map_monitor| config_map_monitor.config_map_name().to_owned());
+        if current_name == config_map_name {
+            return;
+        }
+        log::info!(
+            "Referenced ConfigMap for '{}' changes from {current_name:?} to {config_map_name:?}.",
+            self.host_path()
+        );
+        if let Some(config_map_monitor) = config_map_monitor_opt.take() {
+            config_map_monitor.abort_background_tasks().await;
+        }
+        let new_name = config_map_name.to_owned();
+        if let Some(config_map_name) = config_map_name {
+            config_map_monitor_opt.replace(
+                ConfigMapMonitor::new(
+                    self.cluster.client(),
+                    &self.namespace,
+                    &config_map_name,
+                    Arc::clone(&self.updated_millis),
+                )
+                .await,
+            );
+        }
+        self.changed_from("configRef", current_name, new_name);
+    }
+
+    /**
+      Invoked when `Ingress` has been modified to check if the entry was taken
+      out of or put back into rotation by the [HIDDEN_ANNOTATION].
+    */
+    pub fn hidden_update(self: &Arc<Self>) {
+        let hidden = self
+            .annotations
+            .get(HIDDEN_ANNOTATION)
+            .is_some_and(|entry| entry.value().eq_ignore_ascii_case("true"));
+        let event = if hidden {
+            LifecycleEvent::Disable
+        } else {
+            LifecycleEvent::Enable
+        };
+        if self.lifecycle.handle(event) {
+            self.changed_from("hidden", !hidden, hidden);
+        }
     }
 
     /**
@@ -94,22 +647,110 @@ impl IngressHostPath {
         let mutex = Arc::clone(&self.service_monitor);
         {
             let mut service_monitor_opt = mutex.lock().await;
-            let service_monitor = service_monitor_opt.as_ref().unwrap();
+            let Some(service_monitor) = service_monitor_opt.as_ref() else {
+                log::debug!("Ignoring Service of removed path '{}'.", self.host_path());
+                return;
+            };
             if service_monitor.service_name() != service_name {
                 log::info!(
                     "Service for Ingress changes from '{}' to '{service_name}'.",
                     &service_monitor.service_name()
                 );
+                let previous_name = service_monitor.service_name().to_owned();
                 service_monitor.abort_background_tasks().await;
                 let namespace = service_monitor.namespace().to_owned();
                 service_monitor_opt.replace(
-                    ServiceMonitor::new(&namespace, service_name, Arc::clone(&self.updated_millis))
-                        .await,
+                    ServiceMonitor::new(
+                        self.cluster.client(),
+                        &namespace,
+                        service_name,
+                        Arc::clone(&self.updated_millis),
+                        Arc::clone(&self.lifecycle),
+                        Arc::clone(&self.history),
+                        self.annotation_sources.clone(),
+                    )
+                    .await,
+                );
+                self.changed_from("service", previous_name.as_str(), service_name);
+            }
+        }
+    }
+
+    /// Invoked when `Ingress` has been modified to check if the path type has changed.
+    pub fn path_type_update(self: &Arc<Self>, path_type: PathType) {
+        let mut current = self.path_type.lock().unwrap();
+        if *current != path_type {
+            log::info!(
+                "Path type for '{}' changed from {:?} to {path_type:?}.",
+                self.host_path(),
+                *current
+            );
+            let previous = std::mem::replace(&mut *current, path_type);
+            drop(current);
+            self.changed_from("pathType", previous, path_type);
+        }
+    }
+
+    /// Invoked when `Ingress` has been modified to check if the variant's weight has changed.
+    pub fn weight_update(self: &Arc<Self>, weight: Option<u32>) {
+        let mut current = self.weight.lock().unwrap();
+        if *current != weight {
+            log::info!(
+                "Weight of variant '{}' for '{}' changed to {weight:?}.",
+                self.variant,
+                self.host_path()
+            );
+            let previous = std::mem::replace(&mut *current, weight);
+            drop(current);
+            self.changed_from("weight", previous, weight);
+        }
+    }
+
+    /**
+      Invoked when the URL of the entry has been probed.
+
+      Changes in reachability are logged, but do not count as updates of the
+      entry.
+    */
+    pub fn probe_update(self: &Arc<Self>, probe_result: ProbeResult) {
+        let mut current = self.probe_result.lock().unwrap();
+        let previous = current.as_ref().map(|previous| previous.reachable);
+        if previous != Some(probe_result.reachable) {
+            self.updated_millis.touch();
+            if probe_result.reachable {
+                log::info!("'{}' is reachable.", self.url());
+            } else {
+                log::warn!(
+                    "'{}' is unreachable (status: {:?}, error: {:?}).",
+                    self.url(),
+                    probe_result.status,
+                    probe_result.error
                 );
-                self.updated_millis
-                    .store(crate::time::now_as_millis(), Ordering::Relaxed);
             }
         }
+        *current = Some(probe_result);
+    }
+
+    /// Invoked when the health endpoint of the entry has been probed or is no longer declared.
+    pub fn health_update(self: &Arc<Self>, health_result: Option<HealthProbeResult>) {
+        let mut current = self.health_result.lock().unwrap();
+        let previous = current.as_ref().map(|previous| previous.healthy);
+        let healthy = health_result
+            .as_ref()
+            .map(|health_result| health_result.healthy);
+        if previous != healthy {
+            self.updated_millis.touch();
+            log::info!("Health of '{}' changed to {healthy:?}.", self.host_path());
+        }
+        *current = health_result;
+    }
+
+    /// Invoked when `Ingress` has been modified to check if TLS usage has changed.
+    pub fn tls_update(self: &Arc<Self>, tls: bool) {
+        if self.tls.swap(tls, Ordering::Relaxed) != tls {
+            log::info!("TLS for '{}' changed to {tls}.", self.host_path());
+            self.changed_from("tls", !tls, tls);
+        }
     }
 
     /**
@@ -122,7 +763,7 @@ impl IngressHostPath {
             change = true;
         } else {
             for entry in annotations.iter() {
-                if let Some(old_entry) = self.annotations.get(entry.key()) {
+                if let Some(old_entry) = self.annotations.get(entry.key().as_str()) {
                     if entry.value() != old_entry.value() {
                         change = true;
                     }
@@ -140,14 +781,105 @@ impl IngressHostPath {
                     .map(|entry| { entry.key().to_string() + "=" + entry.value() })
                     .collect::<Vec<_>>()
             );
-            // TODO: Fix race condition here and avoid String creations
-            self.annotations.clear();
+            let previous = self
+                .annotations
+                .iter()
+                .map(|entry| (entry.key().to_string(), entry.value().to_owned()))
+                .collect::<BTreeMap<_, _>>();
+            // Only touch what changed, so readers never observe an empty map
+            self.annotations
+                .iter()
+                .filter(|entry| !annotations.contains_key(&**entry.key()))
+                .for_each(|entry| {
+                    entry.remove();
+                });
             annotations.iter().for_each(|entry| {
-                self.annotations
-                    .insert(entry.key().to_owned(), entry.value().to_owned());
+                let unchanged = self
+                    .annotations
+                    .get(entry.key().as_str())
+                    .is_some_and(|old_entry| old_entry.value() == entry.value());
+                if !unchanged {
+                    self.annotations
+                        .insert(interned_keys::intern(entry.key()), entry.value().to_owned());
+                }
             });
-            self.updated_millis
-                .store(crate::time::now_as_millis(), Ordering::Relaxed);
+            let current = self
+                .annotations
+                .iter()
+                .map(|entry| (entry.key().to_string(), entry.value().to_owned()))
+                .collect::<BTreeMap<_, _>>();
+            self.changed_from("annotations", previous, current);
+        }
+    }
+
+    /// Invoked when `Ingress` has been modified to check if the path rewrite has changed.
+    pub fn rewritten_update(self: &Arc<Self>, rewritten: Option<RewrittenPath>) {
+        let mut current = self.rewritten.lock().unwrap();
+        if *current != rewritten {
+            log::info!(
+                "Path rewrite for '{}' changed to {rewritten:?}.",
+                self.host_path()
+            );
+            let previous = std::mem::replace(&mut *current, rewritten.clone());
+            drop(current);
+            self.changed_from("rewrite", previous, rewritten);
+        }
+    }
+
+    /**
+      Invoked when `Ingress` has been modified to check if another resource
+      now declares the entry.
+    */
+    pub fn source_update(self: &Arc<Self>, source: &str) {
+        let mut current = self.source.lock().unwrap();
+        if *current != source {
+            let previous = std::mem::replace(&mut *current, source.to_owned());
+            drop(current);
+            // The first declaration is part of adding the entry
+            if !previous.is_empty() {
+                log::info!(
+                    "'{}' is now declared by '{source}' instead of '{previous}'.",
+                    self.host_path()
+                );
+                self.changed_from("source", previous.as_str(), source);
+            }
+        }
+    }
+
+    /**
+      Invoked when the declaring resource has been modified. Returns the
+      previous identity and version of the declaring resource.
+
+      A new `resourceVersion` alone is not published as a change, since it
+      changes with every write to the resource, including status updates.
+    */
+    pub fn source_object_update(
+        self: &Arc<Self>,
+        source_object: Option<SourceObject>,
+    ) -> Option<SourceObject> {
+        std::mem::replace(&mut *self.source_object.lock().unwrap(), source_object)
+    }
+
+    /// Invoked when the defaults inherited from the `Namespace` have changed.
+    pub fn namespace_defaults_changed(self: &Arc<Self>) {
+        self.changed("namespace-defaults");
+    }
+
+    /**
+      Invoked when `Ingress` has been modified to check if prefixed
+      labels on the `Ingress` has changed.
+    */
+    pub fn labels_update(self: &Arc<Self>, labels: &BTreeMap<String, String>) {
+        let mut current = self.labels.lock().unwrap();
+        if *current != *labels {
+            log::info!(
+                "Prefixed labels for '{}' changed to {:?}.",
+                self.host_path(),
+                labels
+            );
+            let previous = std::mem::replace(&mut *current, labels.clone());
+            drop(current);
+            self.changed_from("labels", &previous, labels);
         }
     }
 }
@@ -0,0 +1,156 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Monitor a named Kubernetes `ConfigMap` holding extended meta-data.
+
+use crossbeam_skiplist::SkipMap;
+use futures::lock::Mutex;
+use futures::TryStreamExt;
+use k8s_openapi::api::core::v1::ConfigMap;
+use kube::runtime::watcher::Event;
+use kube::{Api, Client};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::UpdatedMillis;
+
+/// Tracks the `data` of a `ConfigMap` referenced by an `Ingress` annotation.
+pub struct ConfigMapMonitor {
+    /// Handle used to abort the background monitoring.
+    abort_handle: Arc<Mutex<Option<tokio::task::AbortHandle>>>,
+    /// Shared atomic counter used to communicate potential changes.
+    updated_millis: Arc<UpdatedMillis>,
+    /// Client for the API server of the cluster.
+    client: Client,
+    /// The Kubernetes namespace to monitor.
+    namespace: String,
+    /// The name of the `ConfigMap` to monitor.
+    config_map_name: String,
+    /// Current `data` of the `ConfigMap`.
+    data: SkipMap<String, String>,
+}
+
+impl ConfigMapMonitor {
+    /// Return a new instance.
+    pub async fn new(
+        client: Client,
+        namespace: &str,
+        config_map_name: &str,
+        updated_millis: Arc<UpdatedMillis>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            abort_handle: Arc::new(Mutex::new(None)),
+            updated_millis,
+            client,
+            namespace: namespace.to_owned(),
+            config_map_name: config_map_name.to_owned(),
+            data: SkipMap::new(),
+        })
+        .start_background_tasks()
+        .await
+    }
+
+    /// Return the `ConfigMap`'s name.
+    pub fn config_map_name(&self) -> &str {
+        &self.config_map_name
+    }
+
+    /// Return the current `data` of the `ConfigMap`.
+    pub fn data(&self) -> SkipMap<String, String> {
+        self.data
+            .iter()
+            .map(|entry| (entry.key().to_owned(), entry.value().to_owned()))
+            .collect()
+    }
+
+    /// Start background monitoring of the named `ConfigMap`.
+    async fn start_background_tasks(self: Arc<Self>) -> Arc<Self> {
+        let self_clone = Arc::clone(&self);
+        let join_handle = tokio::spawn(async move {
+            let description = format!(
+                "monitoring of config map '{}' in namespace '{}'",
+                self_clone.config_map_name, self_clone.namespace
+            );
+            crate::backoff::retry_with_backoff(&description, || self_clone.watch_config_map())
+                .await;
+        });
+        Arc::clone(&self.abort_handle)
+            .lock()
+            .await
+            .replace(join_handle.abort_handle());
+        self
+    }
+
+    /// Watch the named `ConfigMap` until an error occurs.
+    async fn watch_config_map(self: &Arc<Self>) -> Result<(), kube::runtime::watcher::Error> {
+        let field_selector = "metadata.name=".to_string() + &self.config_map_name;
+        crate::watch_recording::watcher(
+            Api::<ConfigMap>::namespaced(self.client.clone(), &self.namespace),
+            crate::kubers_util::watcher_config().fields(&field_selector),
+        )
+        .try_for_each(|event| async move {
+            match event {
+                Event::Applied(config_map) => {
+                    self.handle_update(config_map.data.unwrap_or_default());
+                }
+                Event::Deleted(_) => {
+                    self.handle_update(BTreeMap::new());
+                }
+                Event::Restarted(config_maps) => {
+                    let data = config_maps
+                        .into_iter()
+                        .next()
+                        .and_then(|config_map| config_map.data)
+                        .unwrap_or_default();
+                    self.handle_update(data);
+                }
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Abort background monitoring of the named `ConfigMap`.
+    pub async fn abort_background_tasks(self: &Arc<Self>) {
+        if let Some(abort_handle) = Arc::clone(&self.abort_handle).lock().await.as_mut() {
+            abort_handle.abort();
+        }
+    }
+
+    /// Replace the known `data` if it has changed.
+    fn handle_update(self: &Arc<Self>, data: BTreeMap<String, String>) {
+        let unchanged = data.len() == self.data.len()
+            && data.iter().all(|(key, value)| {
+                self.data
+                    .get(key)
+                    .is_some_and(|old_entry| old_entry.value() == value)
+            });
+        if unchanged {
+            return;
+        }
+        log::info!(
+            "Data of 'configmap/{}' in 'ns/{}' changed.",
+            self.config_map_name,
+            self.namespace
+        );
+        self.data.clear();
+        data.into_iter().for_each(|(key, value)| {
+            self.data.insert(key, value);
+        });
+        self.updated_millis.store(crate::time::now_as_millis());
+    }
+}
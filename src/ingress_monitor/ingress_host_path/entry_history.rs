@@ -0,0 +1,86 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+//! Recent changes of a single entry.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use crate::notifier::{ChangeEvent, ChangeKind};
+
+/// Number of recent changes retained for each entry.
+pub const HISTORY_SIZE: usize = 20;
+
+/// A recorded change of an entry.
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct EntryChange {
+    /// Kind of change.
+    pub kind: ChangeKind,
+    /// Name of the changed property, like `annotations`, `service` or `rollout`, for updates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub property: Option<String>,
+    /// Kind and name of the resource declaring the entry, like `Ingress/checkout`, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Value of the changed property before the update, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Object>))]
+    pub old: Option<serde_json::Value>,
+    /// Value of the changed property after the update, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Object>))]
+    pub new: Option<serde_json::Value>,
+    /// Time of the change in milliseconds since Unix Epoch.
+    pub timestamp: u64,
+}
+
+impl From<&ChangeEvent> for EntryChange {
+    fn from(event: &ChangeEvent) -> Self {
+        Self {
+            kind: event.kind,
+            property: event.property.to_owned(),
+            source: event.source.to_owned(),
+            old: event.old.to_owned(),
+            new: event.new.to_owned(),
+            timestamp: event.timestamp,
+        }
+    }
+}
+
+/// The [HISTORY_SIZE] most recent changes of an entry.
+#[derive(Default)]
+pub struct EntryHistory {
+    changes: Mutex<VecDeque<EntryChange>>,
+}
+
+impl EntryHistory {
+    /// Record the `change` and forget the oldest change when full.
+    pub fn record(&self, change: EntryChange) {
+        let mut changes = self.changes.lock().unwrap();
+        if changes.len() >= HISTORY_SIZE {
+            changes.pop_front();
+        }
+        changes.push_back(change);
+    }
+
+    /// Return the retained changes, oldest first.
+    pub fn changes(&self) -> Vec<EntryChange> {
+        self.changes.lock().unwrap().iter().cloned().collect()
+    }
+}
@@ -0,0 +1,45 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Interning of annotation keys shared by all entries.
+
+use crossbeam_skiplist::SkipSet;
+use std::sync::{Arc, OnceLock};
+
+/// Maximum number of interned keys, so unique keys of tenants can't grow the set forever.
+const MAX_INTERNED_KEYS: usize = 10_000;
+
+/// Keys interned so far.
+static INTERNED_KEYS: OnceLock<SkipSet<Arc<str>>> = OnceLock::new();
+
+/**
+Return the shared instance of the annotation `key`.
+
+Most entries declare the same few annotation keys, so sharing them avoids an
+allocation per key and entry. Once [MAX_INTERNED_KEYS] are interned, new keys
+are allocated without being interned.
+ */
+pub fn intern(key: &str) -> Arc<str> {
+    let interned_keys = INTERNED_KEYS.get_or_init(SkipSet::new);
+    if let Some(entry) = interned_keys.get(key) {
+        return Arc::clone(entry.value());
+    }
+    if interned_keys.len() >= MAX_INTERNED_KEYS {
+        return Arc::from(key);
+    }
+    Arc::clone(interned_keys.get_or_insert(Arc::from(key)).value())
+}
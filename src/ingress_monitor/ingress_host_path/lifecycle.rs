@@ -0,0 +1,329 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Lifecycle state machine of a monitored hostname + path combination.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use crate::time::Clock;
+
+/**
+Lifecycle states of an [IngressHostPath](super::IngressHostPath).
+
+```text
+Discovered ─┬─> Ready <──> Degraded ─┬─> Deleting ──> Removed
+            └────────┴──> Disabled ──┘
+```
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LifecycleState {
+    /// Detected via a labeled `Ingress`, but backend availability is still unknown.
+    Discovered,
+    /// The mapped `Service` has at least one ready endpoint.
+    Ready,
+    /// The mapped `Service` currently has no ready endpoints.
+    Degraded,
+    /// Still monitored, but intentionally taken out of rotation.
+    Disabled,
+    /// The `Ingress` path is gone and monitoring is being torn down.
+    Deleting,
+    /// Terminal state. All monitoring has stopped.
+    Removed,
+}
+
+/// Events that drive transitions between [LifecycleState]s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LifecycleEvent {
+    /// The mapped `Service` has at least one ready endpoint.
+    BackendAvailable,
+    /// The mapped `Service` has no ready endpoints.
+    BackendUnavailable,
+    /// The entry was taken out of rotation.
+    Disable,
+    /// The entry was put back into rotation.
+    Enable,
+    /// The `Ingress` path was deleted or no longer matches.
+    Delete,
+    /// All monitoring of the entry has stopped.
+    Remove,
+}
+
+/// A transition between two [LifecycleState]s.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LifecycleTransition {
+    /// State before the transition.
+    from: LifecycleState,
+    /// State after the transition.
+    to: LifecycleState,
+    /// The event that caused the transition.
+    event: LifecycleEvent,
+    /// Timestamp of the transition in milliseconds since Unix Epoch.
+    timestamp: u64,
+}
+
+impl LifecycleTransition {
+    /// Return the state before the transition.
+    pub fn from(&self) -> LifecycleState {
+        self.from
+    }
+
+    /// Return the state after the transition.
+    pub fn to(&self) -> LifecycleState {
+        self.to
+    }
+
+    /// Return the event that caused the transition.
+    pub fn event(&self) -> LifecycleEvent {
+        self.event
+    }
+
+    /// Return the timestamp of the transition in milliseconds since Unix Epoch.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+}
+
+/// Mutable part of the [Lifecycle].
+struct LifecycleInner {
+    /// Current state.
+    state: LifecycleState,
+    /// Last known backend availability, if any.
+    backend_available: Option<bool>,
+    /// The most recent transition, if any.
+    last_transition: Option<LifecycleTransition>,
+}
+
+/// Thread safe lifecycle state machine of a single entry.
+pub struct Lifecycle {
+    /// Hostname + path used for logging.
+    host_path: String,
+    /// Source of transition timestamps.
+    clock: Arc<dyn Clock>,
+    /// Current state, guarded for atomic transitions.
+    inner: Mutex<LifecycleInner>,
+}
+
+impl Lifecycle {
+    /// Return a new instance in the [LifecycleState::Discovered] state.
+    pub fn new(host_path: &str, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            host_path: host_path.to_owned(),
+            clock,
+            inner: Mutex::new(LifecycleInner {
+                state: LifecycleState::Discovered,
+                backend_available: None,
+                last_transition: None,
+            }),
+        }
+    }
+
+    /// Return the current state.
+    pub fn state(&self) -> LifecycleState {
+        self.inner.lock().unwrap().state
+    }
+
+    /**
+      Return `true` if the mapped `Service` has at least one ready endpoint.
+
+      Returns `None` until the availability is known.
+    */
+    pub fn backend_available(&self) -> Option<bool> {
+        self.inner.lock().unwrap().backend_available
+    }
+
+    /// Return the most recent transition, if any.
+    pub fn last_transition(&self) -> Option<LifecycleTransition> {
+        self.inner.lock().unwrap().last_transition
+    }
+
+    /**
+      Apply the `event` to the state machine.
+
+      Returns `true` if the event caused a transition to a new state.
+    */
+    pub fn handle(&self, event: LifecycleEvent) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        match event {
+            LifecycleEvent::BackendAvailable => inner.backend_available = Some(true),
+            LifecycleEvent::BackendUnavailable => inner.backend_available = Some(false),
+            _ => {}
+        }
+        let from = inner.state;
+        let Some(to) = Self::next_state(from, event, inner.backend_available) else {
+            return false;
+        };
+        if from == to {
+            return false;
+        }
+        log::info!(
+            "Lifecycle of '{}' changed from {from:?} to {to:?} due to {event:?}.",
+            self.host_path
+        );
+        inner.state = to;
+        inner.last_transition = Some(LifecycleTransition {
+            from,
+            to,
+            event,
+            timestamp: self.clock.now_as_millis(),
+        });
+        true
+    }
+
+    /// Transition table. Returns `None` when the event is ignored in the current state.
+    fn next_state(
+        state: LifecycleState,
+        event: LifecycleEvent,
+        backend_available: Option<bool>,
+    ) -> Option<LifecycleState> {
+        match (state, event) {
+            (LifecycleState::Removed, _) => None,
+            (LifecycleState::Deleting, LifecycleEvent::Remove) => Some(LifecycleState::Removed),
+            (LifecycleState::Deleting, _) => None,
+            (_, LifecycleEvent::Delete) => Some(LifecycleState::Deleting),
+            (_, LifecycleEvent::Remove) => Some(LifecycleState::Removed),
+            (LifecycleState::Disabled, LifecycleEvent::Enable) => match backend_available {
+                Some(true) => Some(LifecycleState::Ready),
+                Some(false) => Some(LifecycleState::Degraded),
+                None => Some(LifecycleState::Discovered),
+            },
+            (LifecycleState::Disabled, _) => None,
+            (_, LifecycleEvent::Disable) => Some(LifecycleState::Disabled),
+            (_, LifecycleEvent::Enable) => None,
+            (_, LifecycleEvent::BackendAvailable) => Some(LifecycleState::Ready),
+            (_, LifecycleEvent::BackendUnavailable) => Some(LifecycleState::Degraded),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LifecycleEvent::*;
+    use super::LifecycleState::*;
+    use super::*;
+    use crate::time::MockClock;
+
+    /// Return a new lifecycle with a fixed clock.
+    fn lifecycle() -> Lifecycle {
+        Lifecycle::new("example.com/app", MockClock::new(1_000))
+    }
+
+    #[test]
+    fn transition_table() {
+        // (state, event, last known backend availability, expected next state)
+        let table = [
+            (Discovered, BackendAvailable, None, Some(Ready)),
+            (Discovered, BackendUnavailable, None, Some(Degraded)),
+            (Discovered, Disable, None, Some(Disabled)),
+            (Discovered, Enable, None, None),
+            (Discovered, Delete, None, Some(Deleting)),
+            (Discovered, Remove, None, Some(Removed)),
+            (Ready, BackendAvailable, Some(true), Some(Ready)),
+            (Ready, BackendUnavailable, Some(true), Some(Degraded)),
+            (Ready, Disable, Some(true), Some(Disabled)),
+            (Ready, Enable, Some(true), None),
+            (Ready, Delete, Some(true), Some(Deleting)),
+            (Ready, Remove, Some(true), Some(Removed)),
+            (Degraded, BackendAvailable, Some(false), Some(Ready)),
+            (Degraded, BackendUnavailable, Some(false), Some(Degraded)),
+            (Degraded, Disable, Some(false), Some(Disabled)),
+            (Degraded, Delete, Some(false), Some(Deleting)),
+            (Disabled, BackendAvailable, Some(true), None),
+            (Disabled, BackendUnavailable, Some(true), None),
+            (Disabled, Disable, Some(true), None),
+            (Disabled, Enable, None, Some(Discovered)),
+            (Disabled, Enable, Some(true), Some(Ready)),
+            (Disabled, Enable, Some(false), Some(Degraded)),
+            (Disabled, Delete, Some(true), Some(Deleting)),
+            (Disabled, Remove, Some(true), Some(Removed)),
+            (Deleting, BackendAvailable, Some(true), None),
+            (Deleting, Disable, Some(true), None),
+            (Deleting, Enable, Some(true), None),
+            (Deleting, Delete, Some(true), None),
+            (Deleting, Remove, Some(true), Some(Removed)),
+            (Removed, BackendAvailable, Some(true), None),
+            (Removed, Enable, Some(true), None),
+            (Removed, Delete, Some(true), None),
+            (Removed, Remove, Some(true), None),
+        ];
+        for (state, event, backend_available, expected) in table {
+            assert_eq!(
+                Lifecycle::next_state(state, event, backend_available),
+                expected,
+                "{state:?} + {event:?} with backend available {backend_available:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn handle_records_transitions() {
+        let lifecycle = lifecycle();
+        assert_eq!(lifecycle.state(), Discovered);
+        assert_eq!(lifecycle.backend_available(), None);
+        assert!(lifecycle.last_transition().is_none());
+
+        assert!(lifecycle.handle(BackendAvailable));
+        assert_eq!(lifecycle.state(), Ready);
+        assert_eq!(lifecycle.backend_available(), Some(true));
+        let transition = lifecycle.last_transition().unwrap();
+        assert_eq!(transition.from(), Discovered);
+        assert_eq!(transition.to(), Ready);
+        assert_eq!(transition.event(), BackendAvailable);
+        assert_eq!(transition.timestamp(), 1_000);
+
+        // Repeating the event is not a transition
+        assert!(!lifecycle.handle(BackendAvailable));
+        assert_eq!(lifecycle.last_transition().unwrap().from(), Discovered);
+    }
+
+    #[test]
+    fn availability_is_tracked_while_disabled() {
+        let lifecycle = lifecycle();
+        assert!(lifecycle.handle(BackendAvailable));
+        assert!(lifecycle.handle(Disable));
+        assert!(!lifecycle.handle(BackendUnavailable));
+        assert_eq!(lifecycle.state(), Disabled);
+        assert_eq!(lifecycle.backend_available(), Some(false));
+        assert!(lifecycle.handle(Enable));
+        assert_eq!(lifecycle.state(), Degraded);
+    }
+
+    #[test]
+    fn removed_is_terminal() {
+        let lifecycle = lifecycle();
+        assert!(lifecycle.handle(Delete));
+        assert!(!lifecycle.handle(BackendAvailable));
+        assert_eq!(lifecycle.state(), Deleting);
+        assert!(lifecycle.handle(Remove));
+        for event in [
+            BackendAvailable,
+            BackendUnavailable,
+            Disable,
+            Enable,
+            Delete,
+        ] {
+            assert!(!lifecycle.handle(event));
+            assert_eq!(lifecycle.state(), Removed);
+        }
+    }
+}
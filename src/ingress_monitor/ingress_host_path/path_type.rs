@@ -0,0 +1,47 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! `Ingress` path matching semantics.
+
+use serde::{Deserialize, Serialize};
+
+/**
+How the path of an `Ingress` rule is matched, as declared by `pathType`.
+
+See also [Kubernetes Ingress path types](https://kubernetes.io/docs/concepts/services-networking/ingress/#path-types).
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum PathType {
+    /// Matches the URL path exactly.
+    Exact,
+    /// Matches based on a URL path prefix split by `/`.
+    Prefix,
+    /// Matching is up to the `IngressClass`.
+    ImplementationSpecific,
+}
+
+impl PathType {
+    /// Parse the `pathType` of an `Ingress` path. Unknown values are treated as implementation specific.
+    pub fn from_path_type(path_type: &str) -> Self {
+        match path_type {
+            "Exact" => Self::Exact,
+            "Prefix" => Self::Prefix,
+            _ => Self::ImplementationSpecific,
+        }
+    }
+}
@@ -17,42 +17,118 @@
 
 //! Monitor a named Kubernetes `Service`.
 
-mod pod_monitor;
+mod deployment_monitor;
+mod endpoint_slice_monitor;
+mod replica_set_monitor;
 
 use futures::lock::Mutex;
 use futures::TryStreamExt;
 use k8s_openapi::api::core::v1::Service;
-use std::sync::atomic::{AtomicU64, Ordering};
+use kube::ResourceExt;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-use self::pod_monitor::PodMonitor;
+use self::deployment_monitor::DeploymentMonitor;
+use self::endpoint_slice_monitor::EndpointSliceMonitor;
+use self::replica_set_monitor::ReplicaSetMonitor;
+use super::EntryHistory;
+use super::Lifecycle;
+use super::UpdatedMillis;
+
+pub use self::deployment_monitor::DeploymentRollout;
+pub use self::deployment_monitor::RolloutState;
+
+/// Other resources than the declaring resource that prefixed annotations of an entry are collected from.
+#[derive(Clone, Debug, Default)]
+pub struct AnnotationSources {
+    /// Prefix of the collected annotations, like `microfe/`.
+    pub prefix: String,
+    /// Collect prefixed annotations of the `Service`.
+    pub service: bool,
+    /// Collect prefixed annotations of the `Deployment`s backing the `Service`.
+    pub deployment: bool,
+}
+
+impl AnnotationSources {
+    /// Return the prefixed `annotations` with the prefix removed.
+    pub fn strip_prefix(&self, annotations: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+        annotations
+            .iter()
+            .filter_map(|(annotation_key, annotation_value)| {
+                annotation_key
+                    .strip_prefix(&self.prefix)
+                    .map(|key| (key.to_owned(), annotation_value.to_owned()))
+            })
+            .collect()
+    }
+}
 
 pub struct ServiceMonitor {
     /// Handle used to abort the background monitoring.
     abort_handle: Arc<Mutex<Option<tokio::task::AbortHandle>>>,
     /// Shared atomic counter used to communicate potential changes.
-    updated_millis: Arc<AtomicU64>,
+    updated_millis: Arc<UpdatedMillis>,
+    /// Client for the API server of the cluster.
+    client: kube::Client,
     /// The Kubernetes namespace to monitor.
     namespace: String,
     /// The name of the `Service` to monitor.
     service_name: String,
-    /// Reference to object responsible for montitoring of labeled `Pod`s.
-    pod_monitor: Arc<Mutex<Option<Arc<PodMonitor>>>>,
+    /// Reference to object responsible for montitoring of rollouts of labeled `ReplicaSet`s.
+    replica_set_monitor: Arc<Mutex<Option<Arc<ReplicaSetMonitor>>>>,
+    /// Reference to object responsible for montitoring of backing `Deployment`s.
+    deployment_monitor: Arc<Mutex<Option<Arc<DeploymentMonitor>>>>,
+    /// External target of an `ExternalName` `Service`.
+    external_name: std::sync::Mutex<Option<String>>,
+    /// `true` if the `Service` is neither an `ExternalName` nor has a selector.
+    selectorless: AtomicBool,
+    /// Port of the `Service` used for in-cluster requests, if any.
+    port: std::sync::Mutex<Option<i32>>,
+    /// Reference to object responsible for montitoring of ready endpoints.
+    endpoint_slice_monitor: Arc<EndpointSliceMonitor>,
+    /// Recent changes of the entry that rollouts are recorded in.
+    history: Arc<EntryHistory>,
+    /// Other resources that prefixed annotations are collected from.
+    annotation_sources: AnnotationSources,
+    /// Prefixed annotations of the `Service` (without the prefix part), if collected.
+    annotations: std::sync::Mutex<BTreeMap<String, String>>,
 }
 
 impl ServiceMonitor {
     /// Return a new instance.
     pub async fn new(
+        client: kube::Client,
         namespace: &str,
         service_name: &str,
-        updated_millis: Arc<AtomicU64>,
+        updated_millis: Arc<UpdatedMillis>,
+        lifecycle: Arc<Lifecycle>,
+        history: Arc<EntryHistory>,
+        annotation_sources: AnnotationSources,
     ) -> Arc<Self> {
+        let endpoint_slice_monitor = EndpointSliceMonitor::new(
+            client.clone(),
+            namespace,
+            service_name,
+            Arc::clone(&updated_millis),
+            lifecycle,
+        )
+        .await;
         Arc::new(Self {
             abort_handle: Arc::new(Mutex::new(None)),
             updated_millis,
+            client,
             namespace: namespace.to_owned(),
             service_name: service_name.to_owned(),
-            pod_monitor: Arc::new(Mutex::new(None)),
+            replica_set_monitor: Arc::new(Mutex::new(None)),
+            deployment_monitor: Arc::new(Mutex::new(None)),
+            external_name: std::sync::Mutex::new(None),
+            selectorless: AtomicBool::new(false),
+            port: std::sync::Mutex::new(None),
+            endpoint_slice_monitor,
+            history,
+            annotation_sources,
+            annotations: std::sync::Mutex::new(BTreeMap::new()),
         })
         .start_background_tasks()
         .await
@@ -63,6 +139,62 @@ impl ServiceMonitor {
         &self.service_name
     }
 
+    /// Return the external target if this is an `ExternalName` `Service`.
+    pub fn external_name(&self) -> Option<String> {
+        self.external_name.lock().unwrap().to_owned()
+    }
+
+    /**
+      Return `true` if the `Service` has no selector, but is not of type
+      `ExternalName`. Such `Service`s rely on manually managed endpoints.
+    */
+    pub fn is_selectorless(&self) -> bool {
+        self.selectorless.load(Ordering::Relaxed)
+    }
+
+    /**
+      Return the port of the `Service` used for in-cluster requests: the port
+      named `http` or else the first port, if any.
+    */
+    pub fn port(&self) -> Option<i32> {
+        *self.port.lock().unwrap()
+    }
+
+    /// Return the number of endpoints and ready endpoints of the `Service`.
+    pub fn endpoint_counts(&self) -> (usize, usize) {
+        (
+            self.endpoint_slice_monitor.endpoints(),
+            self.endpoint_slice_monitor.ready_endpoints(),
+        )
+    }
+
+    /// Return the rollout of each `Deployment` backing the `Service`.
+    pub async fn deployment_rollouts(&self) -> Vec<DeploymentRollout> {
+        self.deployment_monitor
+            .lock()
+            .await
+            .as_ref()
+            .map(|deployment_monitor| deployment_monitor.rollouts())
+            .unwrap_or_default()
+    }
+
+    /**
+      Return the collected prefixed annotations of the backing `Deployment`s
+      and the `Service` (without the prefix part), where annotations of the
+      `Service` take precedence.
+    */
+    pub async fn annotations(&self) -> BTreeMap<String, String> {
+        let mut ret = self
+            .deployment_monitor
+            .lock()
+            .await
+            .as_ref()
+            .map(|deployment_monitor| deployment_monitor.annotations())
+            .unwrap_or_default();
+        ret.extend(self.annotations.lock().unwrap().clone());
+        ret
+    }
+
     /// Return the `Service`'s namespace.
     pub fn namespace(&self) -> &str {
         &self.namespace
@@ -72,24 +204,11 @@ impl ServiceMonitor {
     async fn start_background_tasks(self: Arc<Self>) -> Arc<Self> {
         let self_clone = Arc::clone(&self);
         let join_handle = tokio::spawn(async move {
-            let field_selector = "metadata.name=".to_string() + &self_clone.service_name;
-            let client = kube::Client::try_default().await.unwrap();
-            let k8s_resource_stream = crate::kubers_util::reflector_stream::<Service>(
-                kube::Api::namespaced(client, &self_clone.namespace),
-                kube::runtime::watcher::Config::default().fields(&field_selector),
-            )
-            .await;
-            let self_clone = &self_clone.clone();
-            k8s_resource_stream
-                .try_for_each(|resource| async move {
-                    self_clone.handle_update(&resource).await;
-                    Ok(())
-                })
-                .await
-                .map_err(|e| {
-                    log::warn!("Canceling monitoring of service due to error: {e:?}");
-                })
-                .ok();
+            let description = format!(
+                "monitoring of service '{}' in namespace '{}'",
+                self_clone.service_name, self_clone.namespace
+            );
+            crate::backoff::retry_with_backoff(&description, || self_clone.watch_service()).await;
         });
         Arc::clone(&self.abort_handle)
             .lock()
@@ -98,28 +217,73 @@ impl ServiceMonitor {
         self
     }
 
+    /// Watch the named `Service` until an error occurs.
+    async fn watch_service(self: &Arc<Self>) -> Result<(), kube::runtime::watcher::Error> {
+        let field_selector = "metadata.name=".to_string() + &self.service_name;
+        crate::kubers_util::reflector_stream::<Service>(
+            kube::Api::namespaced(self.client.clone(), &self.namespace),
+            crate::kubers_util::watcher_config().fields(&field_selector),
+        )
+        .await
+        .try_for_each(|resource| async move {
+            self.handle_update(&resource).await;
+            Ok(())
+        })
+        .await
+    }
+
     /// Abort background monitoring of the named `Service`.
     pub async fn abort_background_tasks(self: &Arc<Self>) {
         if let Some(abort_handle) = Arc::clone(&self.abort_handle).lock().await.as_mut() {
             abort_handle.abort();
         }
-        // Also abort the related monitoring of Pods
-        let mutex = Arc::clone(&self.pod_monitor);
+        self.endpoint_slice_monitor.abort_background_tasks().await;
+        // Also abort the related monitoring of ReplicaSets
+        let mutex = Arc::clone(&self.replica_set_monitor);
         {
-            let pod_monitor_opt = mutex.lock().await;
-            if let Some(pod_monitor) = pod_monitor_opt.as_ref() {
-                pod_monitor.abort_background_tasks().await;
+            let replica_set_monitor_opt = mutex.lock().await;
+            if let Some(replica_set_monitor) = replica_set_monitor_opt.as_ref() {
+                replica_set_monitor.abort_background_tasks().await;
             }
         }
+        if let Some(deployment_monitor) = self.deployment_monitor.lock().await.as_ref() {
+            deployment_monitor.abort_background_tasks().await;
+        }
     }
 
     /**
       If the `Service` update also changed the selector labels, we need to
-      update the `Pod` monitoring as well.
+      update the `ReplicaSet` monitoring as well.
+
+      `ExternalName` and other selector-less `Service`s have no workload to
+      monitor, so any such monitoring is stopped instead.
     */
     async fn handle_update(self: &Arc<Self>, service: &Arc<Service>) {
-        let service_spec = service.as_ref().spec.as_ref().unwrap();
-        let pod_selector = service_spec.selector.as_ref().unwrap();
+        if self.annotation_sources.service {
+            self.annotations_update(service.annotations());
+        }
+        let Some(service_spec) = service.as_ref().spec.as_ref() else {
+            log::debug!("svc/{} has no spec.", self.service_name);
+            return;
+        };
+        self.external_name_update(service_spec.external_name.as_deref());
+        let ports = service_spec.ports.as_deref().unwrap_or_default();
+        *self.port.lock().unwrap() = ports
+            .iter()
+            .find(|port| port.name.as_deref() == Some("http"))
+            .or(ports.first())
+            .map(|port| port.port);
+        let Some(pod_selector) = service_spec
+            .selector
+            .as_ref()
+            .filter(|pod_selector| !pod_selector.is_empty())
+        else {
+            self.selectorless
+                .store(service_spec.external_name.is_none(), Ordering::Relaxed);
+            self.stop_workload_monitoring().await;
+            return;
+        };
+        self.selectorless.store(false, Ordering::Relaxed);
         // Transform into a label_selector "key1=value1,key2=value2" etc
         let mut label_selector = String::new();
         for (i, (key, value)) in pod_selector.iter().enumerate() {
@@ -130,32 +294,95 @@ impl ServiceMonitor {
                 label_selector.push(',');
             }
         }
-        // Check if current PodMonitor uses this label-selector
+        // Check if current ReplicaSetMonitor uses this label-selector
         let mut changed = true;
-        let mutex = Arc::clone(&self.pod_monitor);
+        let mutex = Arc::clone(&self.replica_set_monitor);
         {
-            let mut pod_monitor_opt = mutex.lock().await;
-            if let Some(pod_montor) = pod_monitor_opt.as_ref() {
-                if pod_montor.clone().label_selector() == label_selector {
+            let mut replica_set_monitor_opt = mutex.lock().await;
+            if let Some(replica_set_monitor) = replica_set_monitor_opt.as_ref() {
+                if replica_set_monitor.label_selector() == label_selector {
                     changed = false;
                 }
             }
             if changed {
-                let old_pod_monitor = pod_monitor_opt.insert(
-                    PodMonitor::new(
+                let old_replica_set_monitor = replica_set_monitor_opt.replace(
+                    ReplicaSetMonitor::new(
+                        self.client.clone(),
                         &self.namespace,
                         &label_selector,
                         Arc::clone(&self.updated_millis),
                     )
                     .await,
                 );
-                old_pod_monitor.abort_background_tasks().await;
+                if let Some(old_replica_set_monitor) = old_replica_set_monitor {
+                    old_replica_set_monitor.abort_background_tasks().await;
+                }
+                let old_deployment_monitor = self.deployment_monitor.lock().await.replace(
+                    DeploymentMonitor::new(
+                        self.client.clone(),
+                        &self.namespace,
+                        pod_selector,
+                        Arc::clone(&self.updated_millis),
+                        Arc::clone(&self.history),
+                        self.annotation_sources.clone(),
+                    )
+                    .await,
+                );
+                if let Some(old_deployment_monitor) = old_deployment_monitor {
+                    old_deployment_monitor.abort_background_tasks().await;
+                }
             }
         }
         if changed {
             log::info!("New service label_selector: '{label_selector}'.");
-            self.updated_millis
-                .store(crate::time::now_as_millis(), Ordering::Relaxed);
+            self.updated_millis.store(crate::time::now_as_millis());
+        }
+    }
+
+    /// Stop monitoring of `ReplicaSet`s and `Deployment`s if active.
+    async fn stop_workload_monitoring(self: &Arc<Self>) {
+        let replica_set_monitor = self.replica_set_monitor.lock().await.take();
+        let deployment_monitor = self.deployment_monitor.lock().await.take();
+        if replica_set_monitor.is_none() && deployment_monitor.is_none() {
+            return;
+        }
+        log::info!(
+            "svc/{} has no selector. Stopping workload monitoring.",
+            self.service_name
+        );
+        if let Some(replica_set_monitor) = replica_set_monitor {
+            replica_set_monitor.abort_background_tasks().await;
+        }
+        if let Some(deployment_monitor) = deployment_monitor {
+            deployment_monitor.abort_background_tasks().await;
+        }
+        self.updated_millis.store(crate::time::now_as_millis());
+    }
+
+    /// Track the prefixed annotations of the `Service`.
+    fn annotations_update(self: &Arc<Self>, annotations: &BTreeMap<String, String>) {
+        let annotations = self.annotation_sources.strip_prefix(annotations);
+        let mut current = self.annotations.lock().unwrap();
+        if *current != annotations {
+            log::info!(
+                "Prefixed annotations of svc/{} changed to {annotations:?}.",
+                self.service_name
+            );
+            *current = annotations;
+            self.updated_millis.store(crate::time::now_as_millis());
+        }
+    }
+
+    /// Track the external target of an `ExternalName` `Service`.
+    fn external_name_update(self: &Arc<Self>, external_name: Option<&str>) {
+        let mut current = self.external_name.lock().unwrap();
+        if current.as_deref() != external_name {
+            log::info!(
+                "svc/{} external name changed to {external_name:?}.",
+                self.service_name
+            );
+            *current = external_name.map(str::to_owned);
+            self.updated_millis.store(crate::time::now_as_millis());
         }
     }
 }
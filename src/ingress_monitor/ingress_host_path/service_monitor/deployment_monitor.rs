@@ -0,0 +1,383 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Monitor the rollout state of `Deployment`s backing a Kubernetes `Service`.
+
+use crossbeam_skiplist::SkipMap;
+use futures::lock::Mutex;
+use futures::TryStreamExt;
+use k8s_openapi::api::apps::v1::Deployment;
+use kube::runtime::watcher::Event;
+use kube::{Api, Client, ResourceExt};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::super::EntryChange;
+use super::super::EntryHistory;
+use super::super::UpdatedMillis;
+use super::AnnotationSources;
+use crate::notifier::ChangeKind;
+
+/// Annotation set by the `Deployment` controller with the current revision.
+const REVISION_ANNOTATION: &str = "deployment.kubernetes.io/revision";
+/// Recommended label with the version of the application.
+const VERSION_LABEL: &str = "app.kubernetes.io/version";
+/// Reason of the `Progressing` condition when the progress deadline was exceeded.
+const PROGRESS_DEADLINE_EXCEEDED: &str = "ProgressDeadlineExceeded";
+
+/// Rollout state of a `Deployment`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RolloutState {
+    /// A new revision is being rolled out.
+    Progressing,
+    /// All replicas run the current revision and are available.
+    Complete,
+    /// The rollout exceeded its progress deadline.
+    Failed,
+}
+
+/// Revision and rollout state of a single `Deployment`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DeploymentRollout {
+    /// Name of the `Deployment`.
+    name: String,
+    /// Value of the `deployment.kubernetes.io/revision` annotation, if any.
+    revision: Option<u64>,
+    /// Current rollout state.
+    state: RolloutState,
+    /// Container images of the `Pod` template, like `registry.example.com/checkout:1.4.2`.
+    #[serde(default)]
+    images: Vec<String>,
+    /// Value of the `app.kubernetes.io/version` label of the `Pod` template or the `Deployment`, if any.
+    #[serde(default)]
+    version: Option<String>,
+}
+
+impl DeploymentRollout {
+    /// Return the name of the `Deployment`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the revision of the `Deployment`, if any.
+    pub fn revision(&self) -> Option<u64> {
+        self.revision
+    }
+
+    /// Return the current rollout state.
+    pub fn state(&self) -> RolloutState {
+        self.state
+    }
+
+    /// Return the container images of the `Pod` template.
+    pub fn images(&self) -> &[String] {
+        &self.images
+    }
+
+    /// Return the version of the application according to the `app.kubernetes.io/version` label, if any.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+}
+
+/**
+Tracks revision and rollout state of the `Deployment`s whose `Pod` template
+labels match the selector of a `Service`.
+ */
+pub struct DeploymentMonitor {
+    /// Handle used to abort the background monitoring.
+    abort_handle: Arc<Mutex<Option<tokio::task::AbortHandle>>>,
+    /// Shared atomic counter used to communicate potential changes.
+    updated_millis: Arc<UpdatedMillis>,
+    /// Client for the API server of the cluster.
+    client: Client,
+    /// The Kubernetes namespace to monitor.
+    namespace: String,
+    /// `Pod` selector of the `Service`.
+    pod_selector: BTreeMap<String, String>,
+    /// Rollout of each matching `Deployment` by name.
+    rollouts: SkipMap<String, DeploymentRollout>,
+    /// Recent changes of the entry that rollouts are recorded in.
+    history: Arc<EntryHistory>,
+    /// Other resources that prefixed annotations are collected from.
+    annotation_sources: AnnotationSources,
+    /// Prefixed annotations (without the prefix part) of each matching `Deployment` by name, if collected.
+    annotations: SkipMap<String, BTreeMap<String, String>>,
+}
+
+impl DeploymentMonitor {
+    /// Return a new instance.
+    pub async fn new(
+        client: Client,
+        namespace: &str,
+        pod_selector: &BTreeMap<String, String>,
+        updated_millis: Arc<UpdatedMillis>,
+        history: Arc<EntryHistory>,
+        annotation_sources: AnnotationSources,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            abort_handle: Arc::new(Mutex::new(None)),
+            updated_millis,
+            client,
+            namespace: namespace.to_owned(),
+            pod_selector: pod_selector.to_owned(),
+            rollouts: SkipMap::new(),
+            history,
+            annotation_sources,
+            annotations: SkipMap::new(),
+        })
+        .start_background_tasks()
+        .await
+    }
+
+    /// Return the rollout of each `Deployment` backing the `Service`.
+    pub fn rollouts(&self) -> Vec<DeploymentRollout> {
+        self.rollouts
+            .iter()
+            .map(|entry| entry.value().to_owned())
+            .collect()
+    }
+
+    /**
+      Return the collected prefixed annotations (without the prefix part) of
+      the `Deployment`s backing the `Service`, merged in order of their names.
+    */
+    pub fn annotations(&self) -> BTreeMap<String, String> {
+        self.annotations
+            .iter()
+            .flat_map(|entry| entry.value().to_owned())
+            .collect()
+    }
+
+    /// Start background monitoring of `Deployment`s.
+    async fn start_background_tasks(self: Arc<Self>) -> Arc<Self> {
+        let self_clone = Arc::clone(&self);
+        let join_handle = tokio::spawn(async move {
+            let description = format!(
+                "monitoring of Deployments in namespace '{}'",
+                self_clone.namespace
+            );
+            crate::backoff::retry_with_backoff(&description, || self_clone.watch_deployments())
+                .await;
+        });
+        Arc::clone(&self.abort_handle)
+            .lock()
+            .await
+            .replace(join_handle.abort_handle());
+        self
+    }
+
+    /// Abort the background monitoring of `Deployment`s.
+    pub async fn abort_background_tasks(self: &Arc<Self>) {
+        if let Some(abort_handle) = Arc::clone(&self.abort_handle).lock().await.as_mut() {
+            abort_handle.abort();
+        }
+    }
+
+    /// Watch `Deployment`s in the namespace until an error occurs.
+    async fn watch_deployments(self: &Arc<Self>) -> Result<(), kube::runtime::watcher::Error> {
+        crate::watch_recording::watcher(
+            Api::<Deployment>::namespaced(self.client.clone(), &self.namespace),
+            crate::kubers_util::watcher_config(),
+        )
+        .try_for_each(|event| async move {
+            match event {
+                Event::Applied(deployment) => {
+                    self.handle_update(&deployment);
+                }
+                Event::Deleted(deployment) => {
+                    self.remove(&deployment.name_any());
+                }
+                Event::Restarted(deployments) => {
+                    let names = deployments
+                        .iter()
+                        .map(ResourceExt::name_any)
+                        .collect::<Vec<_>>();
+                    self.rollouts
+                        .iter()
+                        .filter(|entry| !names.contains(entry.key()))
+                        .for_each(|entry| {
+                            entry.remove();
+                        });
+                    self.annotations
+                        .iter()
+                        .filter(|entry| !names.contains(entry.key()))
+                        .for_each(|entry| {
+                            entry.remove();
+                        });
+                    deployments
+                        .iter()
+                        .for_each(|deployment| self.handle_update(deployment));
+                }
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Return `true` if the `Pod` template labels match the `Service` selector.
+    fn is_backing_service(&self, deployment: &Deployment) -> bool {
+        let template_labels = deployment
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.template.metadata.as_ref())
+            .and_then(|metadata| metadata.labels.as_ref());
+        !self.pod_selector.is_empty()
+            && template_labels.is_some_and(|labels| {
+                self.pod_selector
+                    .iter()
+                    .all(|(key, value)| labels.get(key) == Some(value))
+            })
+    }
+
+    /// Update the tracked rollout and signal a change if revision or state changed.
+    fn handle_update(self: &Arc<Self>, deployment: &Deployment) {
+        let name = deployment.name_any();
+        if !self.is_backing_service(deployment) {
+            self.remove(&name);
+            return;
+        }
+        if self.annotation_sources.deployment {
+            self.annotations_update(&name, deployment.annotations());
+        }
+        let rollout = DeploymentRollout {
+            name: name.to_owned(),
+            revision: deployment
+                .annotations()
+                .get(REVISION_ANNOTATION)
+                .and_then(|value| value.parse::<u64>().ok()),
+            state: Self::rollout_state(deployment),
+            images: Self::images(deployment),
+            version: Self::version(deployment),
+        };
+        let previous = self
+            .rollouts
+            .get(&name)
+            .map(|entry| entry.value().to_owned());
+        if previous.as_ref() != Some(&rollout) {
+            log::info!(
+                "Deployment '{name}' is at revision {:?} ({:?}) with rollout state {:?}.",
+                rollout.revision,
+                rollout.images,
+                rollout.state
+            );
+            self.history.record(EntryChange {
+                kind: ChangeKind::Updated,
+                property: Some("rollout".to_owned()),
+                source: Some("Deployment/".to_owned() + &name),
+                old: previous.and_then(|previous| serde_json::to_value(previous).ok()),
+                new: serde_json::to_value(&rollout).ok(),
+                timestamp: crate::time::now_as_millis(),
+            });
+            self.rollouts.insert(name, rollout);
+            self.updated_millis.store(crate::time::now_as_millis());
+        }
+    }
+
+    /// Forget a `Deployment` that no longer backs the `Service` and signal a change if it did.
+    fn remove(self: &Arc<Self>, name: &str) {
+        let removed_rollout = self.rollouts.remove(name).is_some();
+        let removed_annotations = self
+            .annotations
+            .remove(name)
+            .is_some_and(|entry| !entry.value().is_empty());
+        if removed_rollout || removed_annotations {
+            self.updated_millis.store(crate::time::now_as_millis());
+        }
+    }
+
+    /// Track the prefixed annotations of a `Deployment` and signal a change if they changed.
+    fn annotations_update(self: &Arc<Self>, name: &str, annotations: &BTreeMap<String, String>) {
+        let annotations = self.annotation_sources.strip_prefix(annotations);
+        let unchanged = self
+            .annotations
+            .get(name)
+            .map(|entry| entry.value() == &annotations)
+            .unwrap_or(annotations.is_empty());
+        if unchanged {
+            return;
+        }
+        log::info!("Prefixed annotations of deployment/{name} changed to {annotations:?}.");
+        self.annotations.insert(name.to_owned(), annotations);
+        self.updated_millis.store(crate::time::now_as_millis());
+    }
+
+    /// Return the image of each container in the `Pod` template.
+    fn images(deployment: &Deployment) -> Vec<String> {
+        deployment
+            .spec
+            .iter()
+            .flat_map(|spec| spec.template.spec.iter())
+            .flat_map(|pod_spec| pod_spec.containers.iter())
+            .filter_map(|container| container.image.to_owned())
+            .collect()
+    }
+
+    /**
+      Return the `app.kubernetes.io/version` label of the `Pod` template or
+      of the `Deployment` itself.
+    */
+    fn version(deployment: &Deployment) -> Option<String> {
+        deployment
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.template.metadata.as_ref())
+            .and_then(|metadata| metadata.labels.as_ref())
+            .and_then(|labels| labels.get(VERSION_LABEL))
+            .or_else(|| deployment.labels().get(VERSION_LABEL))
+            .cloned()
+    }
+
+    /**
+      Derive the rollout state in the same way as `kubectl rollout status`.
+
+      The rollout is complete when the controller has observed the latest
+      generation and all replicas are updated and available.
+    */
+    fn rollout_state(deployment: &Deployment) -> RolloutState {
+        let Some(status) = deployment.status.as_ref() else {
+            return RolloutState::Progressing;
+        };
+        let deadline_exceeded = status.conditions.iter().flatten().any(|condition| {
+            condition.type_ == "Progressing"
+                && condition.reason.as_deref() == Some(PROGRESS_DEADLINE_EXCEEDED)
+        });
+        if deadline_exceeded {
+            return RolloutState::Failed;
+        }
+        let desired = deployment
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.replicas)
+            .unwrap_or(1);
+        let updated = status.updated_replicas.unwrap_or_default();
+        let observed = status.observed_generation >= deployment.metadata.generation;
+        if observed
+            && updated >= desired
+            && status.replicas.unwrap_or_default() <= updated
+            && status.available_replicas.unwrap_or_default() >= updated
+        {
+            RolloutState::Complete
+        } else {
+            RolloutState::Progressing
+        }
+    }
+}
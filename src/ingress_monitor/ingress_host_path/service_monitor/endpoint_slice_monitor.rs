@@ -0,0 +1,192 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Monitor the `EndpointSlice`s of a named Kubernetes `Service`.
+
+use crossbeam_skiplist::SkipMap;
+use futures::lock::Mutex;
+use futures::TryStreamExt;
+use k8s_openapi::api::discovery::v1::EndpointSlice;
+use kube::runtime::watcher::Event;
+use kube::{Api, Client, ResourceExt};
+use std::sync::Arc;
+
+use super::super::Lifecycle;
+use super::super::LifecycleEvent;
+use super::super::UpdatedMillis;
+
+/// Label set by Kubernetes on `EndpointSlice`s to reference the owning `Service`.
+const SERVICE_NAME_LABEL: &str = "kubernetes.io/service-name";
+
+/// Tracks the number of ready endpoints of a `Service`.
+pub struct EndpointSliceMonitor {
+    /// Handle used to abort the background monitoring.
+    abort_handle: Arc<Mutex<Option<tokio::task::AbortHandle>>>,
+    /// Shared atomic counter used to communicate potential changes.
+    updated_millis: Arc<UpdatedMillis>,
+    /// Client for the API server of the cluster.
+    client: Client,
+    /// Shared lifecycle state machine of the entry.
+    lifecycle: Arc<Lifecycle>,
+    /// The Kubernetes namespace to monitor.
+    namespace: String,
+    /// The name of the `Service` that owns the `EndpointSlice`s.
+    service_name: String,
+    /// Number of ready endpoints by `EndpointSlice` name.
+    ready_endpoints: SkipMap<String, usize>,
+    /// Number of endpoints regardless of readiness by `EndpointSlice` name.
+    endpoints: SkipMap<String, usize>,
+}
+
+impl EndpointSliceMonitor {
+    /// Return a new instance.
+    pub async fn new(
+        client: Client,
+        namespace: &str,
+        service_name: &str,
+        updated_millis: Arc<UpdatedMillis>,
+        lifecycle: Arc<Lifecycle>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            abort_handle: Arc::new(Mutex::new(None)),
+            updated_millis,
+            client,
+            lifecycle,
+            namespace: namespace.to_owned(),
+            service_name: service_name.to_owned(),
+            ready_endpoints: SkipMap::new(),
+            endpoints: SkipMap::new(),
+        })
+        .start_background_tasks()
+        .await
+    }
+
+    /// Return the total number of ready endpoints of the `Service`.
+    pub fn ready_endpoints(&self) -> usize {
+        self.ready_endpoints
+            .iter()
+            .map(|entry| *entry.value())
+            .sum()
+    }
+
+    /// Return the total number of endpoints of the `Service` regardless of readiness.
+    pub fn endpoints(&self) -> usize {
+        self.endpoints.iter().map(|entry| *entry.value()).sum()
+    }
+
+    /// Start background monitoring of the `Service`'s `EndpointSlice`s.
+    async fn start_background_tasks(self: Arc<Self>) -> Arc<Self> {
+        let self_clone = Arc::clone(&self);
+        let join_handle = tokio::spawn(async move {
+            let description = format!(
+                "monitoring of endpoint slices of service '{}' in namespace '{}'",
+                self_clone.service_name, self_clone.namespace
+            );
+            crate::backoff::retry_with_backoff(&description, || self_clone.watch_endpoint_slices())
+                .await;
+        });
+        Arc::clone(&self.abort_handle)
+            .lock()
+            .await
+            .replace(join_handle.abort_handle());
+        self
+    }
+
+    /// Watch the `Service`'s `EndpointSlice`s until an error occurs.
+    async fn watch_endpoint_slices(self: &Arc<Self>) -> Result<(), kube::runtime::watcher::Error> {
+        let label_selector = SERVICE_NAME_LABEL.to_owned() + "=" + &self.service_name;
+        crate::watch_recording::watcher(
+            Api::<EndpointSlice>::namespaced(self.client.clone(), &self.namespace),
+            crate::kubers_util::watcher_config().labels(&label_selector),
+        )
+        .try_for_each(|event| async move {
+            match event {
+                Event::Applied(endpoint_slice) => {
+                    self.insert_counts(&endpoint_slice);
+                }
+                Event::Deleted(endpoint_slice) => {
+                    self.ready_endpoints.remove(&endpoint_slice.name_any());
+                    self.endpoints.remove(&endpoint_slice.name_any());
+                }
+                Event::Restarted(endpoint_slices) => {
+                    self.ready_endpoints.clear();
+                    self.endpoints.clear();
+                    for endpoint_slice in &endpoint_slices {
+                        self.insert_counts(endpoint_slice);
+                    }
+                }
+            }
+            self.handle_update();
+            Ok(())
+        })
+        .await
+    }
+
+    /// Abort the background monitoring of the `EndpointSlice`s.
+    pub async fn abort_background_tasks(self: &Arc<Self>) {
+        if let Some(abort_handle) = Arc::clone(&self.abort_handle).lock().await.as_mut() {
+            abort_handle.abort();
+        }
+    }
+
+    /// Track the number of ready and total endpoints of the `EndpointSlice`.
+    fn insert_counts(&self, endpoint_slice: &EndpointSlice) {
+        let name = endpoint_slice.name_any();
+        self.endpoints
+            .insert(name.to_owned(), endpoint_slice.endpoints.len());
+        self.ready_endpoints
+            .insert(name, Self::count_ready_endpoints(endpoint_slice));
+    }
+
+    /**
+      Count endpoints that are ready. A missing `ready` condition should be
+      interpreted as ready according to the Kubernetes API documentation.
+    */
+    fn count_ready_endpoints(endpoint_slice: &EndpointSlice) -> usize {
+        endpoint_slice
+            .endpoints
+            .iter()
+            .filter(|endpoint| {
+                endpoint
+                    .conditions
+                    .as_ref()
+                    .and_then(|conditions| conditions.ready)
+                    .unwrap_or(true)
+            })
+            .count()
+    }
+
+    /// Report the backend availability to the lifecycle of the entry.
+    fn handle_update(self: &Arc<Self>) {
+        let ready_endpoints = self.ready_endpoints();
+        log::trace!(
+            "svc/{} has {ready_endpoints} ready endpoints.",
+            self.service_name
+        );
+        let event = if ready_endpoints == 0 {
+            LifecycleEvent::BackendUnavailable
+        } else {
+            LifecycleEvent::BackendAvailable
+        };
+        if self.lifecycle.handle(event) {
+            self.updated_millis.store(crate::time::now_as_millis());
+        } else {
+            // Replica counts may still have changed
+            self.updated_millis.touch();
+        }
+    }
+}
@@ -0,0 +1,195 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Monitor `ReplicaSet`s backing a Kubernetes `Service` to detect rollouts.
+
+use crossbeam_skiplist::SkipMap;
+use futures::lock::Mutex;
+use futures::TryStreamExt;
+use k8s_openapi::api::apps::v1::ReplicaSet;
+use kube::core::PartialObjectMeta;
+use kube::runtime::watcher::Event;
+use kube::{Api, Client, ResourceExt};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use super::super::UpdatedMillis;
+
+/// Annotation set by the `Deployment` controller on each `ReplicaSet` it owns.
+const REVISION_ANNOTATION: &str = "deployment.kubernetes.io/revision";
+
+/**
+Detects rollouts of the workload behind a `Service`.
+
+`ReplicaSet`s carry the labels of their `Pod` template, so the `Service`'s
+selector also matches the `ReplicaSet`s of the backing `Deployment`. A new
+rollout is detected when the revision of the owning `Deployment` increases,
+so scaling and `Pod` churn do not count as new releases.
+
+Only the metadata of the `ReplicaSet`s is watched, since the owner and
+revision is all that is needed and `Pod` templates can be large (e.g. with
+injected service mesh sidecars).
+ */
+pub struct ReplicaSetMonitor {
+    /// Handle used to abort the background monitoring.
+    abort_handle: Arc<Mutex<Option<tokio::task::AbortHandle>>>,
+    /// Shared atomic counter used to communicate potential changes.
+    updated_millis: Arc<UpdatedMillis>,
+    /// Client for the API server of the cluster.
+    client: Client,
+    /// The Kubernetes namespace to monitor.
+    namespace: String,
+    /// The labels to use when monitoring `ReplicaSet`s for updates.
+    label_selector: String,
+    /// Latest known revision by owner (`Deployment` or standalone `ReplicaSet`).
+    revisions: SkipMap<String, u64>,
+    /// `true` once the initial listing has been processed.
+    initialized: AtomicBool,
+}
+
+impl ReplicaSetMonitor {
+    /// Return a new instance.
+    pub async fn new(
+        client: Client,
+        namespace: &str,
+        label_selector: &str,
+        updated_millis: Arc<UpdatedMillis>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            abort_handle: Arc::new(Mutex::new(None)),
+            updated_millis,
+            client,
+            namespace: namespace.to_owned(),
+            label_selector: label_selector.to_owned(),
+            revisions: SkipMap::new(),
+            initialized: AtomicBool::new(false),
+        })
+        .start_background_tasks()
+        .await
+    }
+
+    /// Return the current label selector as a comma separated `key=value` pairs.
+    pub fn label_selector(&self) -> &str {
+        &self.label_selector
+    }
+
+    /// Start background monitoring of the labeled `ReplicaSet`s.
+    async fn start_background_tasks(self: Arc<Self>) -> Arc<Self> {
+        let self_clone = Arc::clone(&self);
+        let join_handle = tokio::spawn(async move {
+            let description = format!(
+                "monitoring of ReplicaSets labeled '{}' in namespace '{}'",
+                self_clone.label_selector, self_clone.namespace
+            );
+            crate::backoff::retry_with_backoff(&description, || self_clone.watch_replica_sets())
+                .await;
+        });
+        Arc::clone(&self.abort_handle)
+            .lock()
+            .await
+            .replace(join_handle.abort_handle());
+        self
+    }
+
+    /// Abort the background monitoring of the labeled `ReplicaSet`s.
+    pub async fn abort_background_tasks(self: &Arc<Self>) {
+        if let Some(abort_handle) = Arc::clone(&self.abort_handle).lock().await.as_mut() {
+            abort_handle.abort();
+        }
+    }
+
+    /// Watch the metadata of the labeled `ReplicaSet`s until an error occurs.
+    async fn watch_replica_sets(self: &Arc<Self>) -> Result<(), kube::runtime::watcher::Error> {
+        crate::watch_recording::metadata_watcher(
+            Api::<ReplicaSet>::namespaced(self.client.clone(), &self.namespace),
+            crate::kubers_util::watcher_config().labels(&self.label_selector),
+        )
+        .try_for_each(|event| async move {
+            match event {
+                Event::Applied(replica_set) => {
+                    let (owner, revision) = Self::owner_and_revision(&replica_set);
+                    self.handle_revision(&owner, revision);
+                }
+                Event::Deleted(_) => {
+                    // Older ReplicaSets are garbage collected, which is not a rollout
+                }
+                Event::Restarted(replica_sets) => {
+                    let mut latest = HashMap::<String, u64>::new();
+                    for replica_set in &replica_sets {
+                        let (owner, revision) = Self::owner_and_revision(replica_set);
+                        let current = latest.entry(owner).or_default();
+                        *current = std::cmp::max(*current, revision);
+                    }
+                    for (owner, revision) in &latest {
+                        self.handle_revision(owner, *revision);
+                    }
+                    // Forget owners that are gone
+                    self.revisions
+                        .iter()
+                        .filter(|entry| !latest.contains_key(entry.key()))
+                        .for_each(|entry| {
+                            entry.remove();
+                        });
+                    self.initialized.store(true, Ordering::Relaxed);
+                }
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /**
+      Return the owning `Deployment` (or the `ReplicaSet` itself when it has no
+      such owner) and the revision of the `ReplicaSet`.
+    */
+    fn owner_and_revision(replica_set: &PartialObjectMeta<ReplicaSet>) -> (String, u64) {
+        let owner = replica_set
+            .owner_references()
+            .iter()
+            .find(|owner_reference| owner_reference.kind == "Deployment")
+            .map(|owner_reference| "Deployment/".to_owned() + &owner_reference.name)
+            .unwrap_or_else(|| "ReplicaSet/".to_owned() + &replica_set.name_any());
+        let revision = replica_set
+            .annotations()
+            .get(REVISION_ANNOTATION)
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or_default();
+        (owner, revision)
+    }
+
+    /**
+      Record the revision of an owner and signal a change if this is a new
+      rollout.
+
+      Owners seen during the initial listing are not considered new rollouts.
+    */
+    fn handle_revision(self: &Arc<Self>, owner: &str, revision: u64) {
+        let previous = self.revisions.get(owner).map(|entry| *entry.value());
+        let new_rollout = match previous {
+            Some(previous) => revision > previous,
+            None => self.initialized.load(Ordering::Relaxed),
+        };
+        if previous.map_or(true, |previous| revision > previous) {
+            self.revisions.insert(owner.to_owned(), revision);
+        }
+        if new_rollout {
+            log::info!("Rollout of revision {revision} detected for '{owner}'.");
+            self.updated_millis.store(crate::time::now_as_millis());
+        }
+    }
+}
@@ -0,0 +1,65 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Time of the last update of an entry.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/**
+Time of the last update of an entry, shared with the monitors of the resources
+backing the entry.
+
+Every update also advances the revision of the whole catalogue, so derived
+data can be checked for staleness without visiting each entry.
+ */
+#[derive(Default)]
+pub struct UpdatedMillis {
+    /// Epoch milliseconds of the last update.
+    millis: AtomicU64,
+    /// Revision of the catalogue shared by all entries.
+    catalogue_revision: Arc<AtomicU64>,
+}
+
+impl UpdatedMillis {
+    /// Return a new instance advancing the shared `catalogue_revision`.
+    pub fn new(catalogue_revision: Arc<AtomicU64>) -> Self {
+        Self {
+            millis: AtomicU64::new(0),
+            catalogue_revision,
+        }
+    }
+
+    /// Return the epoch milliseconds of the last update.
+    pub fn load(&self) -> u64 {
+        self.millis.load(Ordering::Relaxed)
+    }
+
+    /// Record an update at the epoch milliseconds `millis`.
+    pub fn store(&self, millis: u64) {
+        self.millis.store(millis, Ordering::Relaxed);
+        self.touch();
+    }
+
+    /**
+      Advance the catalogue revision without recording an update, e.g. when
+      only derived values like replica counts changed.
+    */
+    pub fn touch(&self) {
+        self.catalogue_revision.fetch_add(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,110 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Extraction of hostname + path combinations from `Ingress` rules.
+
+use k8s_openapi::api::networking::v1::Ingress;
+use kube::ResourceExt;
+
+use super::PathType;
+
+/// A well-formed hostname + path combination of an `Ingress` rule.
+pub struct IngressRulePath<'a> {
+    /// Hostname of the rule. Might be a wildcard like `*.example.com`.
+    pub host: &'a str,
+    /// Path of the rule.
+    pub path: &'a str,
+    /// Name of the backend `Service`.
+    pub service_name: &'a str,
+    /// How the path is matched.
+    pub path_type: PathType,
+}
+
+/// Part of an `Ingress` that was skipped since it could not be cataloged.
+pub struct SkippedRulePart {
+    /// Machine readable reason, like `missing_host`.
+    pub reason: &'static str,
+    /// Hostname and/or path of the skipped part. Might be empty.
+    pub detail: String,
+}
+
+/// Path used when an `ImplementationSpecific` rule path is omitted.
+const DEFAULT_PATH: &str = "/";
+
+/**
+Return all hostname + path combinations of the `Ingress` that can be cataloged.
+
+Rules without a hostname (or that only use a default backend), without `http`
+paths or with a non-`Service` backend are skipped. When `skipped` is present
+each skipped part is logged as a warning and collected.
+ */
+pub fn ingress_rule_paths<'a>(
+    ingress: &'a Ingress,
+    namespace: &str,
+    mut skipped: Option<&mut Vec<SkippedRulePart>>,
+) -> Vec<IngressRulePath<'a>> {
+    let mut ret = vec![];
+    let name = ingress.name_any();
+    let mut warn = |reason: &'static str, detail: &str| {
+        if let Some(skipped) = skipped.as_mut() {
+            log::warn!(
+                "Skipping part of Ingress. namespace={namespace} ingress={name} reason={reason} detail={detail:?}"
+            );
+            skipped.push(SkippedRulePart {
+                reason,
+                detail: detail.to_owned(),
+            });
+        }
+    };
+    let Some(spec) = ingress.spec.as_ref() else {
+        warn("missing_spec", "");
+        return ret;
+    };
+    let rules = spec.rules.as_deref().unwrap_or_default();
+    if rules.is_empty() {
+        if spec.default_backend.is_some() {
+            warn("default_backend_only", "");
+        } else {
+            warn("missing_rules", "");
+        }
+        return ret;
+    }
+    for rule in rules {
+        let Some(host) = rule.host.as_deref().filter(|host| !host.is_empty()) else {
+            warn("missing_host", "");
+            continue;
+        };
+        let Some(http) = rule.http.as_ref() else {
+            warn("missing_http", host);
+            continue;
+        };
+        for http_ingress_path in &http.paths {
+            let path = http_ingress_path.path.as_deref().unwrap_or(DEFAULT_PATH);
+            let Some(service) = http_ingress_path.backend.service.as_ref() else {
+                warn("non_service_backend", &(host.to_owned() + path));
+                continue;
+            };
+            ret.push(IngressRulePath {
+                host,
+                path,
+                service_name: &service.name,
+                path_type: PathType::from_path_type(&http_ingress_path.path_type),
+            });
+        }
+    }
+    ret
+}
@@ -0,0 +1,166 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Kubernetes cluster connection with a stable identifier.
+
+use k8s_openapi::http::{header::USER_AGENT, HeaderValue, Uri};
+use kube::client::ClientBuilder;
+use kube::config::{KubeConfigOptions, Kubeconfig};
+use kube::Client;
+use std::sync::Arc;
+use tower::limit::RateLimitLayer;
+
+use crate::conf::KubeClientConfig;
+
+/**
+A Kubernetes cluster that is monitored.
+
+Entries discovered in the cluster are tagged with its identifier.
+ */
+pub struct KubeCluster {
+    /// Identifier of the cluster exposed to API clients.
+    id: String,
+    /// Client for the cluster's API server.
+    client: Client,
+}
+
+impl KubeCluster {
+    /**
+      Return a new instance connected using the `kubeconfig_path` and optional
+      `context`. Without a path, the cluster is inferred from the environment
+      (in-cluster service account or local kubeconfig).
+
+      The client is tuned according to the `kube_client_config`.
+    */
+    pub async fn new(
+        id: &str,
+        kubeconfig_path: Option<&str>,
+        context: Option<&str>,
+        kube_client_config: &KubeClientConfig,
+    ) -> Result<Arc<Self>, Box<dyn std::error::Error + Send + Sync>> {
+        let config = Self::load_config(kubeconfig_path, context).await?;
+        Self::connect(id, config, kube_client_config)
+    }
+
+    /**
+      Return a new instance connected to the local cluster selected by the
+      kubeconfig path, context and namespace of the `kube_client_config`.
+    */
+    pub async fn local(
+        id: &str,
+        kube_client_config: &KubeClientConfig,
+    ) -> Result<Arc<Self>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut config = Self::load_config(
+            kube_client_config.kubeconfig_path(),
+            kube_client_config.context(),
+        )
+        .await?;
+        if let Some(namespace) = kube_client_config.namespace() {
+            config.default_namespace = namespace.to_owned();
+        }
+        Self::connect(id, config, kube_client_config)
+    }
+
+    /**
+      Return the client configuration from the kubeconfig at the path or the
+      default kubeconfig when only a `context` is selected. Otherwise the
+      cluster is inferred from the environment.
+    */
+    async fn load_config(
+        kubeconfig_path: Option<&str>,
+        context: Option<&str>,
+    ) -> Result<kube::Config, Box<dyn std::error::Error + Send + Sync>> {
+        let options = KubeConfigOptions {
+            context: context.map(str::to_owned),
+            ..KubeConfigOptions::default()
+        };
+        Ok(match kubeconfig_path {
+            Some(kubeconfig_path) => {
+                let kubeconfig = Kubeconfig::read_from(kubeconfig_path)?;
+                kube::Config::from_custom_kubeconfig(kubeconfig, &options).await?
+            }
+            None if context.is_some() => kube::Config::from_kubeconfig(&options).await?,
+            None => kube::Config::infer().await?,
+        })
+    }
+
+    /// Return a new instance with a client tuned according to the `kube_client_config`.
+    fn connect(
+        id: &str,
+        mut config: kube::Config,
+        kube_client_config: &KubeClientConfig,
+    ) -> Result<Arc<Self>, Box<dyn std::error::Error + Send + Sync>> {
+        Self::tune(&mut config, kube_client_config)?;
+        let rate_limit = kube_client_config
+            .rate_limit()
+            .map(|(num, per)| RateLimitLayer::new(num, per));
+        let client = ClientBuilder::try_from(config)?
+            .with_layer(&tower::util::option_layer(rate_limit))
+            .build();
+        Ok(Arc::new(Self {
+            id: id.to_owned(),
+            client,
+        }))
+    }
+
+    /// Apply timeouts, proxy, additional trusted CAs and `User-Agent` to the client `config`.
+    fn tune(
+        config: &mut kube::Config,
+        kube_client_config: &KubeClientConfig,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        config.connect_timeout = kube_client_config.connect_timeout();
+        config.read_timeout = kube_client_config.read_timeout();
+        if let Some(proxy_url) = kube_client_config.proxy_url() {
+            config.proxy_url = Some(proxy_url.parse::<Uri>()?);
+        }
+        if let Some(ca_bundle_path) = kube_client_config.ca_bundle_path() {
+            let ca_bundle = std::fs::read(ca_bundle_path)?;
+            config.root_cert.get_or_insert_with(Vec::new).extend(
+                pem::parse_many(ca_bundle)?
+                    .into_iter()
+                    .map(pem::Pem::into_contents),
+            );
+        }
+        if let Some(user_agent) = kube_client_config.user_agent() {
+            config
+                .headers
+                .push((USER_AGENT, HeaderValue::from_str(user_agent)?));
+        }
+        Ok(())
+    }
+
+    /**
+      Return a new instance using an existing `client`, e.g. one that is
+      already configured by an application embedding the monitoring.
+    */
+    pub fn from_client(id: &str, client: Client) -> Arc<Self> {
+        Arc::new(Self {
+            id: id.to_owned(),
+            client,
+        })
+    }
+
+    /// Return the identifier of the cluster.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Return a client for the cluster's API server.
+    pub fn client(&self) -> Client {
+        self.client.clone()
+    }
+}
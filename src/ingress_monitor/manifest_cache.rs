@@ -0,0 +1,248 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Fetching and caching of asset manifests referenced by entries.
+
+use crossbeam_skiplist::SkipMap;
+use futures::StreamExt;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Maximum number of concurrent manifest requests.
+const CONCURRENT_FETCHES: usize = 4;
+
+/// Most recently fetched version of an asset manifest.
+struct CachedManifest {
+    /// Parsed manifest, if any version has been fetched successfully.
+    content: Option<Value>,
+    /// Entity tag of the fetched version used for revalidation.
+    etag: Option<String>,
+    /// Modification time of the fetched version used for revalidation.
+    last_modified: Option<String>,
+    /// Monotonic time of the most recent fetch attempt.
+    fetched: Instant,
+    /// Size of the fetched manifest in bytes, used to account for memory.
+    size: usize,
+}
+
+/**
+Cache of asset manifests (e.g. `asset-manifest.json` or the metadata of a
+`remoteEntry.js`) by URL.
+
+Cached manifests are revalidated with conditional requests and the last
+successfully fetched version is kept when a revalidation fails.
+*/
+pub struct ManifestCache {
+    /// Client used for all manifest requests.
+    http_client: reqwest::Client,
+    /// Maximum size in bytes of a manifest.
+    max_size: u64,
+    /// Cached manifests by URL.
+    manifests: SkipMap<String, Arc<CachedManifest>>,
+    /// Counter that is incremented whenever a cached manifest changes.
+    revision: AtomicU64,
+    /// Maximum total size in bytes of the cached manifests, if limited.
+    budget_bytes: Option<usize>,
+    /// Total size in bytes of the cached manifests.
+    bytes: AtomicUsize,
+    /// Manifests fetched before this monotonic time are revalidated on the next refresh.
+    expired_before: Mutex<Option<Instant>>,
+}
+
+impl ManifestCache {
+    /**
+      Return a new instance that caches manifests of up to `max_size` bytes
+      each and up to `budget_bytes` in total.
+    */
+    pub fn new(max_size: u64, budget_bytes: Option<usize>) -> Self {
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        Self {
+            http_client,
+            max_size,
+            manifests: SkipMap::new(),
+            revision: AtomicU64::new(0),
+            budget_bytes,
+            bytes: AtomicUsize::new(0),
+            expired_before: Mutex::new(None),
+        }
+    }
+
+    /**
+      Revalidate all cached manifests on the next refresh. The cached versions
+      are served until then.
+    */
+    pub fn expire_all(&self) {
+        self.expired_before.lock().unwrap().replace(Instant::now());
+    }
+
+    /// Return the total size of the cached manifests in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// Return the cached manifest at the `url`, if any.
+    pub fn get(&self, url: &str) -> Option<Value> {
+        self.manifests
+            .get(url)
+            .and_then(|entry| entry.value().content.clone())
+    }
+
+    /// Return a counter that changes whenever a cached manifest changes.
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Relaxed)
+    }
+
+    /**
+      Fetch manifests at the `urls` that are not cached or were fetched more
+      than `refresh` ago and forget manifests that are no longer referenced.
+    */
+    pub async fn refresh(&self, urls: Vec<String>, refresh: Duration) {
+        for entry in self.manifests.iter() {
+            if !urls.contains(entry.key()) && entry.remove() {
+                self.bytes.fetch_sub(entry.value().size, Ordering::Relaxed);
+            }
+        }
+        futures::stream::iter(urls)
+            .for_each_concurrent(CONCURRENT_FETCHES, |url| async move {
+                let previous = self
+                    .manifests
+                    .get(&url)
+                    .map(|entry| Arc::clone(entry.value()));
+                let expired_before = *self.expired_before.lock().unwrap();
+                if previous.as_ref().is_some_and(|previous| {
+                    previous.fetched.elapsed() < refresh
+                        && !expired_before
+                            .is_some_and(|expired_before| previous.fetched <= expired_before)
+                }) {
+                    return;
+                }
+                let previous_content = previous
+                    .as_ref()
+                    .and_then(|previous| previous.content.clone());
+                let previous_size = previous.as_ref().map_or(0, |previous| previous.size);
+                let mut fetched = self.fetch(&url, previous).await;
+                if self.budget_bytes.is_some_and(|budget| {
+                    self.memory_usage().saturating_sub(previous_size) + fetched.size > budget
+                }) {
+                    log::warn!("Not caching manifest '{url}' that exceeds the memory budget.");
+                    fetched.content = None;
+                    fetched.size = 0;
+                }
+                if fetched.content != previous_content {
+                    self.revision.fetch_add(1, Ordering::Relaxed);
+                }
+                self.bytes.fetch_add(fetched.size, Ordering::Relaxed);
+                self.bytes.fetch_sub(previous_size, Ordering::Relaxed);
+                self.manifests.insert(url, Arc::new(fetched));
+            })
+            .await;
+    }
+
+    /// Fetch or revalidate the manifest at the `url`.
+    async fn fetch(&self, url: &str, previous: Option<Arc<CachedManifest>>) -> CachedManifest {
+        let mut request = self.http_client.get(url);
+        if let Some(previous) = previous
+            .as_ref()
+            .filter(|previous| previous.content.is_some())
+        {
+            if let Some(etag) = &previous.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &previous.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let stale = |previous: Option<Arc<CachedManifest>>| CachedManifest {
+            content: previous
+                .as_ref()
+                .and_then(|previous| previous.content.clone()),
+            etag: previous.as_ref().and_then(|previous| previous.etag.clone()),
+            last_modified: previous
+                .as_ref()
+                .and_then(|previous| previous.last_modified.clone()),
+            fetched: Instant::now(),
+            size: previous.map_or(0, |previous| previous.size),
+        };
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                log::warn!("Failed to fetch manifest '{url}': {}", e.without_url());
+                return stale(previous);
+            }
+        };
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return stale(previous);
+        }
+        if !response.status().is_success() {
+            log::warn!(
+                "Failed to fetch manifest '{url}': HTTP status {}",
+                response.status()
+            );
+            return stale(previous);
+        }
+        if response
+            .content_length()
+            .is_some_and(|length| length > self.max_size)
+        {
+            log::warn!("Manifest '{url}' exceeds {} bytes.", self.max_size);
+            return stale(previous);
+        }
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        let body = match response.bytes().await {
+            Ok(body) if body.len() as u64 <= self.max_size => body,
+            Ok(_) => {
+                log::warn!("Manifest '{url}' exceeds {} bytes.", self.max_size);
+                return stale(previous);
+            }
+            Err(e) => {
+                log::warn!("Failed to read manifest '{url}': {}", e.without_url());
+                return stale(previous);
+            }
+        };
+        match serde_json::from_slice::<Value>(&body) {
+            Ok(content) => {
+                log::debug!("Fetched manifest '{url}'.");
+                CachedManifest {
+                    content: Some(content),
+                    etag,
+                    last_modified,
+                    fetched: Instant::now(),
+                    size: body.len(),
+                }
+            }
+            Err(e) => {
+                log::warn!("Manifest '{url}' is not valid JSON: {e}");
+                stale(previous)
+            }
+        }
+    }
+}
@@ -0,0 +1,268 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! `MicroFrontend` custom resource definition and monitoring.
+
+use crossbeam_skiplist::SkipMap;
+use futures::TryStreamExt;
+use k8s_openapi::chrono::{SecondsFormat, Utc};
+use kube::api::{Patch, PatchParams};
+use kube::runtime::watcher::Event;
+use kube::{Api, CustomResource, ResourceExt};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::variant::Variant;
+use super::HostPathDeclaration;
+use super::IngressHostPath;
+use super::IngressMonitor;
+use super::KubeCluster;
+use super::PathType;
+use super::SourceObject;
+use super::PRIMARY_VARIANT;
+
+/**
+Declaration of a micro front end for teams that prefer a dedicated object over
+labeling an `Ingress`.
+
+```yaml
+apiVersion: microfe.mydriatech.com/v1alpha1
+kind: MicroFrontend
+metadata:
+  name: checkout
+spec:
+  name: checkout
+  host: shop.example.com
+  path: /checkout
+  service: checkout-svc
+  moduleMetadata:
+    entry: remoteEntry.js
+  dependencies:
+  - cart
+  tls: true
+```
+ */
+#[derive(CustomResource, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[kube(
+    group = "microfe.mydriatech.com",
+    version = "v1alpha1",
+    kind = "MicroFrontend",
+    namespaced,
+    status = "MicroFrontendStatus",
+    shortname = "mfe"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct MicroFrontendSpec {
+    /// Name of the module provided by the micro front end.
+    pub name: String,
+    /// Hostname the micro front end is served from.
+    pub host: String,
+    /// Path (route) prefix the micro front end is served from.
+    pub path: String,
+    /// Name of the `Service` in the same namespace that serves the micro front end.
+    pub service: String,
+    /// Module meta-data exposed to API clients like prefixed `Ingress` annotations.
+    #[serde(default)]
+    pub module_metadata: BTreeMap<String, String>,
+    /// Names of other modules that this micro front end depends on.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// `true` if the hostname is served over HTTPS.
+    #[serde(default)]
+    pub tls: bool,
+}
+
+/// Status of a `MicroFrontend` as reported by `microfefind`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MicroFrontendStatus {
+    /// The `metadata.generation` that was last processed.
+    pub observed_generation: Option<i64>,
+    /// Current conditions of the resource.
+    #[serde(default)]
+    pub conditions: Vec<MicroFrontendCondition>,
+}
+
+/// Condition in the style of `metav1.Condition`.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MicroFrontendCondition {
+    /// Type of condition, e.g. `Cataloged`.
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// One of `True`, `False` or `Unknown`.
+    pub status: String,
+    /// Machine readable reason for the condition's last transition.
+    pub reason: String,
+    /// Human readable details about the transition.
+    pub message: String,
+    /// RFC 3339 timestamp of the last transition.
+    pub last_transition_time: String,
+}
+
+impl IngressMonitor {
+    /**
+      Watch all `MicroFrontend` custom resources in the namespace for changes,
+      including pre-existing ones.
+
+      The watch is restarted with backoff on errors.
+    */
+    pub(super) async fn watch_micro_frontends(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        namespace: Option<String>,
+    ) {
+        let namespace = namespace.unwrap_or(cluster.client().default_namespace().to_owned());
+        let description = format!(
+            "monitoring of MicroFrontends in namespace '{namespace}' of cluster '{}'",
+            cluster.id()
+        );
+        crate::backoff::retry_with_backoff(&description, || {
+            self.watch_micro_frontends_once(cluster, &namespace)
+        })
+        .await;
+    }
+
+    /// Watch `MicroFrontend` custom resources in the namespace until an error occurs.
+    async fn watch_micro_frontends_once(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        namespace: &str,
+    ) -> Result<(), kube::runtime::watcher::Error> {
+        let api = &Api::<MicroFrontend>::namespaced(cluster.client(), namespace);
+        crate::watch_recording::watcher(api.clone(), crate::kubers_util::watcher_config())
+            .try_for_each(|event| async move {
+                match event {
+                    Event::Applied(micro_frontend) => {
+                        self.update_micro_frontend(cluster, api, &micro_frontend, namespace)
+                            .await;
+                    }
+                    Event::Deleted(micro_frontend) => {
+                        let spec = &micro_frontend.spec;
+                        let key = IngressHostPath::key(
+                            cluster.id(),
+                            &spec.host,
+                            &spec.path,
+                            PRIMARY_VARIANT,
+                        );
+                        let source = Self::micro_frontend_source(&micro_frontend);
+                        self.release_host_path(cluster, &key, namespace, &source)
+                            .await;
+                    }
+                    Event::Restarted(micro_frontends) => {
+                        for micro_frontend in &micro_frontends {
+                            self.update_micro_frontend(cluster, api, micro_frontend, namespace)
+                                .await;
+                        }
+                    }
+                }
+                Ok(())
+            })
+            .await
+    }
+
+    /// Return the kind and name of the `MicroFrontend` as the source of declarations.
+    fn micro_frontend_source(micro_frontend: &MicroFrontend) -> String {
+        "MicroFrontend/".to_owned() + &micro_frontend.name_any()
+    }
+
+    /// Add or update the declared micro front end and report back via the status.
+    async fn update_micro_frontend(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        api: &Api<MicroFrontend>,
+        micro_frontend: &MicroFrontend,
+        namespace: &str,
+    ) {
+        let spec = &micro_frontend.spec;
+        let annotations = SkipMap::new();
+        spec.module_metadata.iter().for_each(|(key, value)| {
+            annotations.insert(key.to_owned(), value.to_owned());
+        });
+        annotations.insert("name".to_owned(), spec.name.to_owned());
+        if !spec.dependencies.is_empty() {
+            annotations.insert("requires".to_owned(), spec.dependencies.join(","));
+        }
+        let declaration = HostPathDeclaration {
+            host: &spec.host,
+            path: &spec.path,
+            path_type: PathType::Prefix,
+            namespace,
+            service_name: &spec.service,
+            annotations: &annotations,
+            labels: &BTreeMap::new(),
+            rewritten: None,
+            tls: spec.tls,
+            variant: &Variant::default(),
+            source: &Self::micro_frontend_source(micro_frontend),
+            source_object: &SourceObject::from_resource(micro_frontend, namespace),
+            created_millis: micro_frontend
+                .creation_timestamp()
+                .and_then(|time| u64::try_from(time.0.timestamp_millis()).ok())
+                .unwrap_or_default(),
+        };
+        if self.running_config().ingress.is_host_exposed(&spec.host) {
+            self.upsert_host_path(cluster, &declaration).await;
+        } else {
+            log::debug!(
+                "Ignoring host '{}' of 'microfrontend/{}' in 'ns/{namespace}' that is not allowed.",
+                spec.host,
+                micro_frontend.name_any()
+            );
+            let key = IngressHostPath::key(cluster.id(), &spec.host, &spec.path, PRIMARY_VARIANT);
+            self.release_host_path(cluster, &key, namespace, declaration.source)
+                .await;
+        }
+        // Only write the status once per generation, since the status update itself is a change
+        let generation = micro_frontend.metadata.generation;
+        let observed_generation = micro_frontend
+            .status
+            .as_ref()
+            .and_then(|status| status.observed_generation);
+        if generation == observed_generation {
+            return;
+        }
+        let status = MicroFrontendStatus {
+            observed_generation: generation,
+            conditions: vec![MicroFrontendCondition {
+                type_: "Cataloged".to_owned(),
+                status: "True".to_owned(),
+                reason: "Discovered".to_owned(),
+                message: format!(
+                    "Serving '{}{}' via 'svc/{}'.",
+                    spec.host, spec.path, spec.service
+                ),
+                last_transition_time: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            }],
+        };
+        let name = micro_frontend.name_any();
+        if let Err(e) = api
+            .patch_status(
+                &name,
+                &PatchParams::default(),
+                &Patch::Merge(serde_json::json!({ "status": status })),
+            )
+            .await
+        {
+            log::warn!(
+                "Failed to update status of 'microfrontend/{name}' in 'ns/{namespace}': {e:?}"
+            );
+        }
+    }
+}
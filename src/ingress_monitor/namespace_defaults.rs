@@ -0,0 +1,162 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Defaults for entries declared by prefixed annotations on the `Namespace`.
+
+use futures::TryStreamExt;
+use k8s_openapi::api::core::v1::Namespace;
+use kube::runtime::watcher::Event;
+use kube::{Api, ResourceExt};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use super::IngressHostPath;
+use super::IngressMonitor;
+use super::KubeCluster;
+
+impl IngressMonitor {
+    /**
+      Watch the `Namespace` object itself for prefixed annotations that are
+      inherited by every entry from the namespace.
+
+      This requires permissions to get and watch `Namespace`s in the cluster.
+    */
+    pub(super) async fn watch_namespace_defaults(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        namespace: &str,
+    ) {
+        let description = format!(
+            "monitoring of defaults of namespace '{namespace}' in cluster '{}'",
+            cluster.id()
+        );
+        crate::backoff::retry_with_backoff(&description, || {
+            self.watch_namespace_defaults_once(cluster, namespace)
+        })
+        .await;
+    }
+
+    /// Watch the `Namespace` object until an error occurs.
+    async fn watch_namespace_defaults_once(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        namespace: &str,
+    ) -> Result<(), kube::runtime::watcher::Error> {
+        let field_selector = "metadata.name=".to_string() + namespace;
+        crate::watch_recording::watcher(
+            Api::<Namespace>::all(cluster.client()),
+            crate::kubers_util::watcher_config().fields(&field_selector),
+        )
+        .try_for_each(|event| async move {
+            match event {
+                Event::Applied(namespace_object) => {
+                    self.namespace_defaults_update(cluster, namespace, &namespace_object);
+                }
+                Event::Deleted(_) => {
+                    self.namespace_defaults_update(cluster, namespace, &Namespace::default());
+                }
+                Event::Restarted(namespace_objects) => {
+                    let namespace_object = namespace_objects.into_iter().next().unwrap_or_default();
+                    self.namespace_defaults_update(cluster, namespace, &namespace_object);
+                }
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Replace the defaults of the `namespace` and touch its entries if they changed.
+    fn namespace_defaults_update(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        namespace: &str,
+        namespace_object: &Namespace,
+    ) {
+        let tag_prefix = self
+            .app_config
+            .ingress
+            .namespace_selector(namespace)
+            .annotation_prefix;
+        let defaults = namespace_object
+            .annotations()
+            .iter()
+            .filter_map(|(annotation_key, annotation_value)| {
+                annotation_key
+                    .strip_prefix(tag_prefix)
+                    .map(|key| (key.to_owned(), annotation_value.to_owned()))
+            })
+            .collect::<BTreeMap<_, _>>();
+        let key = Self::namespace_defaults_key(cluster.id(), namespace);
+        let unchanged = self
+            .namespace_defaults
+            .get(&key)
+            .map(|entry| entry.value() == &defaults)
+            .unwrap_or(defaults.is_empty());
+        if unchanged {
+            return;
+        }
+        log::info!(
+            "Defaults of namespace '{namespace}' in cluster '{}' changed to {:?}.",
+            cluster.id(),
+            defaults
+        );
+        self.namespace_defaults.insert(key, defaults);
+        self.monitored_ingress_host_paths
+            .iter()
+            .map(|entry| Arc::clone(entry.value()))
+            .filter(|entry| entry.cluster_id() == cluster.id() && entry.namespace() == namespace)
+            .for_each(|entry| entry.namespace_defaults_changed());
+    }
+
+    /// Forget the defaults of a `namespace` that is no longer monitored.
+    pub(super) fn namespace_defaults_remove(self: &Arc<Self>, cluster_id: &str, namespace: &str) {
+        self.namespace_defaults
+            .remove(&Self::namespace_defaults_key(cluster_id, namespace));
+    }
+
+    /// Return the key of the defaults of a namespace in a cluster.
+    fn namespace_defaults_key(cluster_id: &str, namespace: &str) -> String {
+        cluster_id.to_owned() + "/" + namespace
+    }
+
+    /**
+      Return the prefixed annotations of the `entry` (with the prefix removed)
+      merged with the defaults of its `Namespace`, where annotations of the
+      entry take precedence.
+    */
+    pub async fn entry_annotations(
+        self: &Arc<Self>,
+        entry: &Arc<IngressHostPath>,
+    ) -> HashMap<String, String> {
+        let mut ret = self
+            .namespace_defaults
+            .get(&Self::namespace_defaults_key(
+                entry.cluster_id(),
+                entry.namespace(),
+            ))
+            .map(|defaults| {
+                defaults
+                    .value()
+                    .iter()
+                    .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                    .collect::<HashMap<_, _>>()
+            })
+            .unwrap_or_default();
+        ret.extend(entry.annotations_map().await);
+        ret
+    }
+}
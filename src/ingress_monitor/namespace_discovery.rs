@@ -0,0 +1,154 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Dynamic discovery of labeled `Namespace`s to monitor.
+
+use futures::TryStreamExt;
+use k8s_openapi::api::core::v1::Namespace;
+use kube::runtime::watcher::Event;
+use kube::{Api, ResourceExt};
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use super::IngressMonitor;
+use super::KubeCluster;
+
+impl IngressMonitor {
+    /**
+      Watch all `Namespace`s with matching labels and start or stop monitoring
+      of each `Namespace` as labels are added or removed.
+
+      This requires permissions to list and watch `Namespace`s in the cluster.
+    */
+    pub(super) async fn watch_namespaces(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        namespace_labels: &str,
+    ) {
+        let description = format!("discovery of namespaces in cluster '{}'", cluster.id());
+        crate::backoff::retry_with_backoff(&description, || {
+            self.watch_namespaces_once(cluster, namespace_labels)
+        })
+        .await;
+    }
+
+    /// Watch `Namespace`s with matching labels until an error occurs.
+    async fn watch_namespaces_once(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        namespace_labels: &str,
+    ) -> Result<(), kube::runtime::watcher::Error> {
+        crate::watch_recording::watcher(
+            Api::<Namespace>::all(cluster.client()),
+            crate::kubers_util::watcher_config().labels(namespace_labels),
+        )
+        .try_for_each(|event| async move {
+            match event {
+                Event::Applied(namespace) => {
+                    self.namespace_monitoring_start(cluster, &namespace.name_any());
+                }
+                Event::Deleted(namespace) => {
+                    self.namespace_monitoring_stop(cluster, &namespace.name_any())
+                        .await;
+                }
+                Event::Restarted(namespaces) => {
+                    let names = namespaces
+                        .iter()
+                        .map(ResourceExt::name_any)
+                        .collect::<HashSet<_>>();
+                    let key_prefix = Self::monitored_namespace_key(cluster, "");
+                    let stale_names = self
+                        .monitored_namespaces
+                        .iter()
+                        .filter_map(|entry| {
+                            entry.key().strip_prefix(&key_prefix).map(str::to_owned)
+                        })
+                        .filter(|name| !names.contains(name))
+                        .collect::<Vec<_>>();
+                    for name in stale_names {
+                        self.namespace_monitoring_stop(cluster, &name).await;
+                    }
+                    for name in &names {
+                        self.namespace_monitoring_start(cluster, name);
+                    }
+                    if names.is_empty() {
+                        // Nothing to list, so an empty catalogue is accurate
+                        self.health_ready.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Return the key of a monitored namespace in a cluster.
+    fn monitored_namespace_key(cluster: &Arc<KubeCluster>, namespace: &str) -> String {
+        cluster.id().to_owned() + "/" + namespace
+    }
+
+    /// Start monitoring of the discovered `Namespace` unless already monitored.
+    fn namespace_monitoring_start(self: &Arc<Self>, cluster: &Arc<KubeCluster>, namespace: &str) {
+        let key = Self::monitored_namespace_key(cluster, namespace);
+        if self.monitored_namespaces.contains_key(&key) {
+            return;
+        }
+        log::info!(
+            "Starting monitoring of discovered namespace '{namespace}' in cluster '{}'.",
+            cluster.id()
+        );
+        let task_names = self.start_namespace_monitoring(cluster, Some(namespace.to_owned()));
+        self.monitored_namespaces.insert(key, task_names);
+    }
+
+    /**
+      Stop monitoring of a `Namespace` that no longer matches and remove all
+      entries originating from it.
+    */
+    async fn namespace_monitoring_stop(
+        self: &Arc<Self>,
+        cluster: &Arc<KubeCluster>,
+        namespace: &str,
+    ) {
+        let key = Self::monitored_namespace_key(cluster, namespace);
+        let Some(entry) = self.monitored_namespaces.remove(&key) else {
+            return;
+        };
+        log::info!("Stopping monitoring of namespace '{namespace}' that no longer matches.");
+        entry
+            .value()
+            .iter()
+            .for_each(|task_name| self.supervisor.abort(task_name));
+        let stale_claims = self
+            .host_path_claims
+            .claims_in_namespace(cluster.id(), namespace);
+        for (key, source) in stale_claims {
+            self.release_host_path(cluster, &key, namespace, &source)
+                .await;
+        }
+        self.namespace_health.remove(cluster.id(), namespace);
+        self.namespace_defaults_remove(cluster.id(), namespace);
+        let ingress_keys_prefix = Self::ingress_keys_prefix(cluster, namespace);
+        self.ingress_keys
+            .iter()
+            .filter(|entry| entry.key().starts_with(&ingress_keys_prefix))
+            .for_each(|entry| {
+                entry.remove();
+            });
+    }
+}
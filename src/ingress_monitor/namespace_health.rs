@@ -0,0 +1,136 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Health of the monitoring of each namespace.
+
+use crossbeam_skiplist::SkipMap;
+use std::sync::Mutex;
+
+/// Health of the monitoring of `Ingress`es in a single namespace.
+#[derive(Clone, Debug)]
+pub struct NamespaceHealth {
+    /// Identifier of the cluster of the namespace.
+    pub cluster: String,
+    /// Kubernetes namespace.
+    pub namespace: String,
+    /// Time when tracking of the namespace started in milliseconds since Unix Epoch.
+    pub since_millis: u64,
+    /// Time of the last successful listing or received event in milliseconds since Unix Epoch.
+    pub last_event_millis: Option<u64>,
+    /// Time of the last failure in milliseconds since Unix Epoch.
+    pub last_error_millis: Option<u64>,
+    /// Description of the last failure.
+    pub last_error: Option<String>,
+}
+
+impl NamespaceHealth {
+    /**
+      Return `true` if the namespace has been listed and the monitoring
+      recovered from the last failure (if any).
+    */
+    pub fn is_healthy(&self) -> bool {
+        self.last_event_millis.is_some_and(|last_event_millis| {
+            !self
+                .last_error_millis
+                .is_some_and(|last_error_millis| last_error_millis > last_event_millis)
+        })
+    }
+
+    /**
+      Return the time of the last event, error or the start of tracking
+      in milliseconds since Unix Epoch, whichever is latest.
+    */
+    pub fn last_activity_millis(&self) -> u64 {
+        [self.last_event_millis, self.last_error_millis]
+            .into_iter()
+            .flatten()
+            .fold(self.since_millis, u64::max)
+    }
+}
+
+/// Health of the monitoring of each namespace.
+#[derive(Default)]
+pub struct NamespaceHealthRegistry {
+    /// Health by cluster + namespace.
+    namespaces: SkipMap<String, Mutex<NamespaceHealth>>,
+}
+
+impl NamespaceHealthRegistry {
+    /// Unique key of a namespace.
+    fn key(cluster: &str, namespace: &str) -> String {
+        cluster.to_owned() + "/" + namespace
+    }
+
+    /**
+      Apply the `update` to the health of the namespace, which is added at
+      `now_millis` if unknown.
+    */
+    fn update<F: FnOnce(&mut NamespaceHealth)>(
+        &self,
+        cluster: &str,
+        namespace: &str,
+        now_millis: u64,
+        update: F,
+    ) {
+        let entry = self
+            .namespaces
+            .get_or_insert_with(Self::key(cluster, namespace), || {
+                Mutex::new(NamespaceHealth {
+                    cluster: cluster.to_owned(),
+                    namespace: namespace.to_owned(),
+                    since_millis: now_millis,
+                    last_event_millis: None,
+                    last_error_millis: None,
+                    last_error: None,
+                })
+            });
+        update(&mut entry.value().lock().unwrap());
+    }
+
+    /// Start tracking a namespace that has not been listed yet at `now_millis`.
+    pub fn register(&self, cluster: &str, namespace: &str, now_millis: u64) {
+        self.update(cluster, namespace, now_millis, |_| {});
+    }
+
+    /// Record a successful listing or received event at `now_millis`.
+    pub fn event(&self, cluster: &str, namespace: &str, now_millis: u64) {
+        self.update(cluster, namespace, now_millis, |health| {
+            health.last_event_millis = Some(now_millis)
+        });
+    }
+
+    /// Record a failure of the monitoring at `now_millis`.
+    pub fn error(&self, cluster: &str, namespace: &str, now_millis: u64, message: String) {
+        self.update(cluster, namespace, now_millis, |health| {
+            health.last_error_millis = Some(now_millis);
+            health.last_error = Some(message);
+        });
+    }
+
+    /// Stop tracking the namespace, e.g. when it is no longer monitored.
+    pub fn remove(&self, cluster: &str, namespace: &str) {
+        self.namespaces.remove(&Self::key(cluster, namespace));
+    }
+
+    /// Return the health of all tracked namespaces ordered by cluster and namespace.
+    pub fn get_all(&self) -> Vec<NamespaceHealth> {
+        self.namespaces
+            .iter()
+            .map(|entry| entry.value().lock().unwrap().clone())
+            .collect()
+    }
+}
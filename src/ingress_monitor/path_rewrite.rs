@@ -0,0 +1,154 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Detection of path rewrites between the client-facing and the backend path.
+
+use k8s_openapi::api::networking::v1::Ingress;
+use kube::ResourceExt;
+use serde::{Deserialize, Serialize};
+
+/// Annotation (without prefix) with the path that the backend actually serves.
+const BACKEND_PATH_ANNOTATION: &str = "backend-path";
+/// Annotation used by the NGINX Ingress controller to rewrite the request path.
+const NGINX_REWRITE_TARGET_ANNOTATION: &str = "nginx.ingress.kubernetes.io/rewrite-target";
+/// Annotation used by the NGINX Ingress controller to match paths as regular expressions.
+const NGINX_USE_REGEX_ANNOTATION: &str = "nginx.ingress.kubernetes.io/use-regex";
+/// Annotation used by Traefik 1.x to select how paths are matched and forwarded.
+const TRAEFIK_RULE_TYPE_ANNOTATION: &str = "traefik.ingress.kubernetes.io/rule-type";
+/// Annotation used by Traefik 2.x and later to apply `Middleware`s to a router.
+const TRAEFIK_MIDDLEWARES_ANNOTATION: &str = "traefik.ingress.kubernetes.io/router.middlewares";
+
+/// Client-facing and backend path of an entry when these differ.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RewrittenPath {
+    /// Path that the browser must request.
+    pub external_path: String,
+    /// Path that the backend receives after the rewrite.
+    pub backend_path: String,
+}
+
+/**
+Rewrite of request paths applied by the Ingress controller of an `Ingress`.
+
+Detected from the NGINX `rewrite-target` annotation, Traefik `PathPrefixStrip`
+rule type or a `Middleware` named like `*stripprefix*`, or declared explicitly
+with the `microfe/backend-path` annotation (using the configured prefix).
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathRewrite {
+    /// `true` if paths are regular expressions that need to be reduced to a literal prefix.
+    regex: bool,
+    /// Backend path where capture group references have been removed or
+    /// `None` if the matched prefix is stripped.
+    target: Option<String>,
+}
+
+impl PathRewrite {
+    /// Detect the path rewrite of the `Ingress`, if any.
+    pub fn from_ingress(ingress: &Ingress, annotation_prefix: &str) -> Option<Self> {
+        let annotations = ingress.annotations();
+        let nginx_regex = annotations
+            .get(NGINX_USE_REGEX_ANNOTATION)
+            .is_some_and(|value| value.eq_ignore_ascii_case("true"));
+        if let Some(backend_path) = annotations
+            .get(&(annotation_prefix.to_owned() + BACKEND_PATH_ANNOTATION))
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+        {
+            return Some(Self {
+                regex: nginx_regex,
+                target: Some(backend_path.to_owned()),
+            });
+        }
+        if let Some(rewrite_target) = annotations
+            .get(NGINX_REWRITE_TARGET_ANNOTATION)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+        {
+            let captures = rewrite_target.contains('$');
+            return Some(Self {
+                regex: nginx_regex || captures,
+                target: Some(Self::without_captures(rewrite_target)),
+            });
+        }
+        let traefik_strip = annotations
+            .get(TRAEFIK_RULE_TYPE_ANNOTATION)
+            .is_some_and(|value| value.eq_ignore_ascii_case("PathPrefixStrip"))
+            || annotations
+                .get(TRAEFIK_MIDDLEWARES_ANNOTATION)
+                .is_some_and(|value| {
+                    value.split(',').any(|middleware| {
+                        let middleware = middleware.to_ascii_lowercase().replace('-', "");
+                        middleware.contains("stripprefix")
+                    })
+                });
+        traefik_strip.then_some(Self {
+            regex: false,
+            target: None,
+        })
+    }
+
+    /// Return the client-facing and backend path of the `path` declared by the `Ingress`.
+    pub fn apply(&self, path: &str) -> RewrittenPath {
+        let external_path = if self.regex {
+            Self::literal_prefix(path)
+        } else {
+            path.to_owned()
+        };
+        let backend_path = self.target.to_owned().unwrap_or_else(|| "/".to_owned());
+        RewrittenPath {
+            external_path,
+            backend_path,
+        }
+    }
+
+    /// Return the `target` with `$1`-style capture group references removed.
+    fn without_captures(target: &str) -> String {
+        let mut ret = String::with_capacity(target.len());
+        let mut chars = target.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == '$' && chars.peek().is_some_and(char::is_ascii_digit) {
+                while chars.peek().is_some_and(char::is_ascii_digit) {
+                    chars.next();
+                }
+                continue;
+            }
+            if c == '/' && ret.ends_with('/') {
+                continue;
+            }
+            ret.push(c);
+        }
+        if ret.is_empty() {
+            ret.push('/');
+        }
+        ret
+    }
+
+    /// Return the literal prefix of a path that is a regular expression, like `/app` for `/app(/|$)(.*)`.
+    fn literal_prefix(path: &str) -> String {
+        let end = path
+            .find(['(', '[', '*', '+', '?', '{', '|', '$', '^', '\\'])
+            .unwrap_or(path.len());
+        let prefix = &path[..end];
+        if prefix.len() > 1 {
+            prefix.trim_end_matches('/').to_owned()
+        } else {
+            "/".to_owned()
+        }
+    }
+}
@@ -0,0 +1,102 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Fetching of catalogues of upstream `microfefind` instances.
+
+use crossbeam_skiplist::SkipMap;
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/**
+Most recently fetched catalogues of upstream `microfefind` instances by origin.
+
+The last successfully fetched catalogue of a peer is kept when a fetch fails,
+so a flaky peer doesn't make its entries flap in the merged catalogue.
+*/
+pub struct PeerCatalogues {
+    /// Client used for all peer requests.
+    http_client: reqwest::Client,
+    /// Entries of the most recent `GET /api/v1/all` response by origin.
+    catalogues: SkipMap<String, Vec<Value>>,
+    /// Counter that is incremented whenever a fetched catalogue changes.
+    revision: AtomicU64,
+}
+
+impl PeerCatalogues {
+    /// Return a new instance.
+    pub fn new(timeout: Duration) -> Self {
+        let http_client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .unwrap_or_default();
+        Self {
+            http_client,
+            catalogues: SkipMap::new(),
+            revision: AtomicU64::new(0),
+        }
+    }
+
+    /// Return the fetched entries of each peer by origin.
+    pub fn get_all(&self) -> Vec<(String, Vec<Value>)> {
+        self.catalogues
+            .iter()
+            .map(|entry| (entry.key().to_owned(), entry.value().to_owned()))
+            .collect()
+    }
+
+    /// Return a counter that changes whenever a fetched catalogue changes.
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Relaxed)
+    }
+
+    /// Fetch the catalogue of each `(origin, base_url)` peer.
+    pub async fn refresh(&self, peers: &[(String, String)]) {
+        for (origin, base_url) in peers {
+            match self.fetch(base_url).await {
+                Ok(entries) => {
+                    let unchanged = self
+                        .catalogues
+                        .get(origin)
+                        .is_some_and(|entry| entry.value() == &entries);
+                    if !unchanged {
+                        log::debug!(
+                            "Catalogue of peer '{origin}' changed to {} entries.",
+                            entries.len()
+                        );
+                        self.catalogues.insert(origin.to_owned(), entries);
+                        self.revision.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+                Err(e) => {
+                    log::warn!("Failed to fetch catalogue of peer '{origin}' at '{base_url}': {e}");
+                }
+            }
+        }
+    }
+
+    /// Return the entries of the peer's catalogue.
+    async fn fetch(&self, base_url: &str) -> Result<Vec<Value>, reqwest::Error> {
+        self.http_client
+            .get(base_url.to_owned() + "/api/v1/all")
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Vec<Value>>()
+            .await
+    }
+}
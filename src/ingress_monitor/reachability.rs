@@ -0,0 +1,157 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Active probing of the URL and the health endpoint of each entry.
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::IngressHostPath;
+use crate::conf::ProbeConfig;
+
+/// Maximum number of concurrent probes.
+const CONCURRENT_PROBES: usize = 8;
+
+/// Outcome of the most recent request to the URL of an entry.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProbeResult {
+    /// `true` if the URL responded with a status that indicates a working route.
+    pub reachable: bool,
+    /// HTTP status of the response, if any.
+    pub status: Option<u16>,
+    /// Description of why no response was received, if any.
+    pub error: Option<String>,
+    /// Time of the probe in milliseconds since Unix Epoch.
+    pub probed: u64,
+}
+
+impl ProbeResult {
+    /**
+      Return `true` if the `status` indicates that the route through the edge
+      load balancer works.
+
+      Successful responses, redirects and authentication challenges count as
+      reachable, while `404 Not Found` and server errors are what a load
+      balancer without a working route to the backend typically responds.
+    */
+    fn is_reachable_status(status: u16) -> bool {
+        status < 400 || status == 401 || status == 403
+    }
+}
+
+/// Outcome of the most recent request to the health endpoint of an entry.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HealthProbeResult {
+    /// `true` if the health endpoint responded with a successful (`2xx`) status.
+    pub healthy: bool,
+    /// HTTP status of the response, if any.
+    pub status: Option<u16>,
+    /// Description of why no response was received, if any.
+    pub error: Option<String>,
+    /// Time of the probe in milliseconds since Unix Epoch.
+    pub probed: u64,
+}
+
+/// Periodically requests the URL and the health endpoint of each entry.
+pub struct Prober {
+    /// Client used for all probes.
+    http_client: reqwest::Client,
+    /// `true` to probe the URL of each entry.
+    reachability: bool,
+    /// `true` to probe health endpoints declared by entries.
+    health_paths: bool,
+    /// `true` to use `GET` instead of `HEAD` for the URL of each entry.
+    use_get: bool,
+}
+
+impl Prober {
+    /// Return a new instance.
+    pub fn new(probe_config: &ProbeConfig) -> Self {
+        let http_client = reqwest::Client::builder()
+            .timeout(probe_config.timeout())
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap_or_default();
+        Self {
+            http_client,
+            reachability: probe_config.enabled(),
+            health_paths: probe_config.health_paths(),
+            use_get: probe_config.use_get(),
+        }
+    }
+
+    /**
+      Probe all `entries` and store the results in each entry.
+
+      Health endpoints are requested through the mapped `Service`, so they are
+      only probed for entries in the local cluster.
+    */
+    pub async fn probe_all(
+        &self,
+        entries: Vec<Arc<IngressHostPath>>,
+        local_cluster_id: &str,
+        now_millis: u64,
+    ) {
+        futures::stream::iter(entries)
+            .for_each_concurrent(CONCURRENT_PROBES, |entry| async move {
+                if self.reachability && !entry.is_wildcard() {
+                    let (status, error) = self.request(&entry.url(), self.use_get).await;
+                    entry.probe_update(ProbeResult {
+                        reachable: status.is_some_and(ProbeResult::is_reachable_status),
+                        status,
+                        error,
+                        probed: now_millis,
+                    });
+                }
+                let health_url = if self.health_paths && entry.cluster_id() == local_cluster_id {
+                    entry.health_url().await
+                } else {
+                    None
+                };
+                let health_result = match health_url {
+                    Some(health_url) => {
+                        let (status, error) = self.request(&health_url, true).await;
+                        Some(HealthProbeResult {
+                            healthy: status.is_some_and(|status| (200..300).contains(&status)),
+                            status,
+                            error,
+                            probed: now_millis,
+                        })
+                    }
+                    None => None,
+                };
+                entry.health_update(health_result);
+            })
+            .await;
+    }
+
+    /// Request the `url` once and return the status or why no response was received.
+    async fn request(&self, url: &str, use_get: bool) -> (Option<u16>, Option<String>) {
+        let request = if use_get {
+            self.http_client.get(url)
+        } else {
+            self.http_client.head(url)
+        };
+        match request.send().await {
+            Ok(response) => (Some(response.status().as_u16()), None),
+            Err(e) => (None, Some(e.without_url().to_string())),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+//! Registry of non-fatal problems found in watched resources.
+
+use crossbeam_skiplist::SkipMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A non-fatal problem that prevents (part of) a resource from being cataloged.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceError {
+    /// Identifier of the cluster where the resource was found.
+    pub cluster: String,
+    /// Kubernetes namespace of the resource.
+    pub namespace: String,
+    /// Kind of the resource, like `Ingress` or `Service`.
+    pub kind: String,
+    /// Name of the resource.
+    pub name: String,
+    /// Machine readable reason, like `missing_host`.
+    pub reason: String,
+    /// Human readable details. Might be empty.
+    pub detail: String,
+}
+
+/**
+Problems of each watched resource.
+
+Problems are replaced every time a resource is processed, so resolving the
+problem in the resource also clears it here.
+ */
+#[derive(Default)]
+pub struct ResourceErrorRegistry {
+    /// Problems by cluster + namespace + kind + name of the resource.
+    errors: SkipMap<String, Vec<ResourceError>>,
+}
+
+impl ResourceErrorRegistry {
+    /// Unique key of a resource.
+    fn key(cluster: &str, namespace: &str, kind: &str, name: &str) -> String {
+        format!("{cluster}/{namespace}/{kind}/{name}")
+    }
+
+    /// Replace all known problems of a resource and return `true` if they changed.
+    pub fn replace(
+        &self,
+        cluster: &str,
+        namespace: &str,
+        kind: &str,
+        name: &str,
+        errors: Vec<ResourceError>,
+    ) -> bool {
+        let key = Self::key(cluster, namespace, kind, name);
+        let changed = self
+            .errors
+            .get(&key)
+            .map_or(!errors.is_empty(), |entry| *entry.value() != errors);
+        if errors.is_empty() {
+            self.errors.remove(&key);
+        } else {
+            self.errors.insert(key, errors);
+        }
+        changed
+    }
+
+    /// Forget all problems of a resource, e.g. when it was deleted.
+    pub fn clear(&self, cluster: &str, namespace: &str, kind: &str, name: &str) {
+        self.errors
+            .remove(&Self::key(cluster, namespace, kind, name));
+    }
+
+    /// Return all known problems ordered by cluster, namespace, kind and name.
+    pub fn get_all(&self) -> Vec<ResourceError> {
+        self.errors
+            .iter()
+            .flat_map(|entry| entry.value().clone())
+            .collect()
+    }
+}
+
+/// Return the number of problems by `cluster/namespace`.
+pub fn count_by_namespace(errors: &[ResourceError]) -> BTreeMap<String, usize> {
+    let mut ret = BTreeMap::new();
+    for error in errors {
+        *ret.entry(error.cluster.to_owned() + "/" + &error.namespace)
+            .or_default() += 1;
+    }
+    ret
+}
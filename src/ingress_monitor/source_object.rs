@@ -0,0 +1,86 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+//! Identity of the Kubernetes object declaring an entry.
+
+use k8s_openapi::api::core::v1::ObjectReference;
+use kube::Resource;
+use kube::ResourceExt;
+use serde::{Deserialize, Serialize};
+
+/**
+Identity and version of the Kubernetes object declaring an entry.
+
+Consumers can use the `uid` to correlate entries with cluster objects and the
+`resourceVersion` and `generation` to tell if the catalogue lags behind the
+cluster.
+ */
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct SourceObject {
+    /// API group and version of the declaring object.
+    #[cfg_attr(feature = "openapi", schema(example = "networking.k8s.io/v1"))]
+    pub api_version: String,
+    /// Kind of the declaring object.
+    #[cfg_attr(feature = "openapi", schema(example = "Ingress"))]
+    pub kind: String,
+    /// Name of the declaring object.
+    #[cfg_attr(feature = "openapi", schema(example = "checkout"))]
+    pub name: String,
+    /// Kubernetes namespace of the declaring object.
+    pub namespace: String,
+    /// Unique identifier of the declaring object.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
+    /// Generation of the desired state of the declaring object.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generation: Option<i64>,
+    /// Version of the declaring object that the entry was last updated from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_version: Option<String>,
+}
+
+impl SourceObject {
+    /// Return the identity of the declaring Kubernetes `resource`.
+    pub fn from_resource<K>(resource: &K, namespace: &str) -> Self
+    where
+        K: Resource<DynamicType = ()>,
+    {
+        Self {
+            api_version: K::api_version(&()).into_owned(),
+            kind: K::kind(&()).into_owned(),
+            name: resource.name_any(),
+            namespace: namespace.to_owned(),
+            uid: resource.uid(),
+            generation: resource.meta().generation,
+            resource_version: resource.resource_version(),
+        }
+    }
+
+    /// Return a reference to the declaring object, e.g. for Kubernetes `Event`s.
+    pub fn object_reference(&self) -> ObjectReference {
+        ObjectReference {
+            api_version: Some(self.api_version.to_owned()),
+            kind: Some(self.kind.to_owned()),
+            name: Some(self.name.to_owned()),
+            namespace: Some(self.namespace.to_owned()),
+            uid: self.uid.to_owned(),
+            resource_version: self.resource_version.to_owned(),
+            ..ObjectReference::default()
+        }
+    }
+}
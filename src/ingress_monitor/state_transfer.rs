@@ -0,0 +1,211 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Export and import of the full monitoring state.
+
+use crossbeam_skiplist::SkipMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::path_rewrite::RewrittenPath;
+use super::source_object::SourceObject;
+use super::variant::Variant;
+use super::HostPathDeclaration;
+use super::IngressMonitor;
+use super::PathType;
+use super::PRIMARY_VARIANT;
+
+/// Version of the exported state format.
+const STATE_FORMAT_VERSION: u32 = 1;
+
+/// Declared state of a single hostname + path combination.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct HostPathState {
+    /// Identifier of the Kubernetes cluster where the entry was discovered.
+    pub cluster: String,
+    /// Hostname of the entry.
+    pub host: String,
+    /// Path of the entry.
+    pub path: String,
+    /// How the path is matched.
+    pub path_type: PathType,
+    /// Kubernetes namespace of the declaring resource.
+    pub namespace: String,
+    /// Name of the `Service` serving the hostname + path.
+    pub service_name: String,
+    /// `true` if the hostname is served over HTTPS.
+    pub tls: bool,
+    /// Prefixed annotations of the declaring resource (without the prefix part).
+    #[serde(default)]
+    pub annotations: BTreeMap<String, String>,
+    /// Prefixed labels of the declaring resource (without the prefix part).
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    /// Client-facing and backend path if the Ingress controller rewrites the path.
+    #[serde(default)]
+    pub rewritten: Option<RewrittenPath>,
+    /// Name of the variant of the hostname + path.
+    #[serde(default = "HostPathState::primary_variant")]
+    pub variant: String,
+    /// Share of traffic (0-100) served by the variant, if declared.
+    #[serde(default)]
+    pub weight: Option<u32>,
+    /// Kind and name of the declaring resource, like `Ingress/checkout`.
+    #[serde(default = "HostPathState::imported_source")]
+    pub source: String,
+    /// Identity and version of the declaring resource, unless imported.
+    #[serde(default)]
+    pub source_object: Option<SourceObject>,
+    /// Creation time of the declaring resource in milliseconds since Unix Epoch.
+    #[serde(default)]
+    pub created_millis: u64,
+}
+
+impl HostPathState {
+    /// Default variant of entries exported before variants were supported.
+    fn primary_variant() -> String {
+        PRIMARY_VARIANT.to_owned()
+    }
+
+    /// Default source of entries exported before sources were tracked.
+    fn imported_source() -> String {
+        "Import/".to_owned()
+    }
+
+    /// Return the owned state of the declaration.
+    pub(super) fn from_declaration(cluster_id: &str, declaration: &HostPathDeclaration) -> Self {
+        Self {
+            cluster: cluster_id.to_owned(),
+            host: declaration.host.to_owned(),
+            path: declaration.path.to_owned(),
+            path_type: declaration.path_type,
+            namespace: declaration.namespace.to_owned(),
+            service_name: declaration.service_name.to_owned(),
+            tls: declaration.tls,
+            annotations: declaration
+                .annotations
+                .iter()
+                .map(|entry| (entry.key().to_owned(), entry.value().to_owned()))
+                .collect(),
+            labels: declaration.labels.to_owned(),
+            rewritten: declaration.rewritten.to_owned(),
+            variant: declaration.variant.name.to_owned(),
+            weight: declaration.variant.weight,
+            source: declaration.source.to_owned(),
+            source_object: Some(declaration.source_object.to_owned()),
+            created_millis: declaration.created_millis,
+        }
+    }
+
+    /// Return the declared annotations.
+    pub(super) fn annotations(&self) -> SkipMap<String, String> {
+        self.annotations
+            .iter()
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect()
+    }
+
+    /// Return the declared variant.
+    pub(super) fn variant(&self) -> Variant {
+        Variant {
+            name: self.variant.to_owned(),
+            weight: self.weight,
+        }
+    }
+}
+
+/// Full monitoring state.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorState {
+    /// Version of the state format.
+    pub format_version: u32,
+    /// All known hostname + path combinations.
+    pub entries: Vec<HostPathState>,
+}
+
+/// Outcome of a state import.
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ImportReport {
+    /// Number of imported entries.
+    pub imported: usize,
+    /// Reasons for each entry that could not be imported.
+    pub rejected: Vec<String>,
+}
+
+impl IngressMonitor {
+    /**
+      Return the declared state of all known hostname + path combinations,
+      including declarations that lost a conflict.
+    */
+    pub async fn export_state(self: &Arc<Self>) -> MonitorState {
+        MonitorState {
+            format_version: STATE_FORMAT_VERSION,
+            entries: self.host_path_claims.get_all(),
+        }
+    }
+
+    /**
+      Add or update all entries of a previously exported state.
+
+      Entries of clusters that are not monitored by this instance are rejected,
+      since the related `Service`s would not be possible to monitor. Imported
+      entries are subject to the regular monitoring and will be updated or
+      removed as the declaring resources change.
+    */
+    pub async fn import_state(self: &Arc<Self>, state: &MonitorState) -> ImportReport {
+        if state.format_version != STATE_FORMAT_VERSION {
+            return ImportReport {
+                imported: 0,
+                rejected: vec![format!(
+                    "Unsupported format version {}. Expected {STATE_FORMAT_VERSION}.",
+                    state.format_version
+                )],
+            };
+        }
+        let mut report = ImportReport {
+            imported: 0,
+            rejected: vec![],
+        };
+        for entry in &state.entries {
+            let Some(cluster) = self
+                .clusters
+                .get(&entry.cluster)
+                .map(|cluster_entry| Arc::clone(cluster_entry.value()))
+            else {
+                report.rejected.push(format!(
+                    "Entry '{}{}' belongs to unknown cluster '{}'.",
+                    entry.host, entry.path, entry.cluster
+                ));
+                continue;
+            };
+            self.claim_host_path(&cluster, entry.clone()).await;
+            report.imported += 1;
+        }
+        log::info!(
+            "Imported {} entries and rejected {}.",
+            report.imported,
+            report.rejected.len()
+        );
+        report
+    }
+}
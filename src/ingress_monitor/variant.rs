@@ -0,0 +1,90 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Detection of canary and other variants of the same hostname + path.
+
+use k8s_openapi::api::networking::v1::Ingress;
+use kube::ResourceExt;
+
+/// Name of the variant of `Ingress`es that are not marked as a variant.
+pub const PRIMARY_VARIANT: &str = "primary";
+/// Annotation (without prefix) naming the variant served by the `Ingress`.
+const VARIANT_ANNOTATION: &str = "variant";
+/// Annotation (without prefix) with the share of traffic (0-100) of the variant.
+const WEIGHT_ANNOTATION: &str = "weight";
+/// Annotation used by the NGINX Ingress controller to mark a canary `Ingress`.
+const NGINX_CANARY_ANNOTATION: &str = "nginx.ingress.kubernetes.io/canary";
+/// Annotation used by the NGINX Ingress controller for the canary traffic share.
+const NGINX_CANARY_WEIGHT_ANNOTATION: &str = "nginx.ingress.kubernetes.io/canary-weight";
+/// Name of the variant of NGINX canary `Ingress`es without explicit variant.
+const CANARY_VARIANT: &str = "canary";
+
+/**
+Variant of a hostname + path served by an `Ingress`.
+
+Progressive delivery setups use multiple `Ingress`es for the same hostname +
+path, where all but one are marked as variants.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Variant {
+    /// Name of the variant.
+    pub name: String,
+    /// Share of traffic (0-100) served by the variant, if declared.
+    pub weight: Option<u32>,
+}
+
+impl Default for Variant {
+    fn default() -> Self {
+        Self {
+            name: PRIMARY_VARIANT.to_owned(),
+            weight: None,
+        }
+    }
+}
+
+impl Variant {
+    /**
+      Detect the variant from the `microfe/variant` and `microfe/weight`
+      annotations (using the configured prefix) or NGINX canary annotations.
+    */
+    pub fn from_ingress(ingress: &Ingress, annotation_prefix: &str) -> Self {
+        let annotations = ingress.annotations();
+        let prefixed = |key: &str| annotations.get(&(annotation_prefix.to_owned() + key));
+        let nginx_canary = annotations
+            .get(NGINX_CANARY_ANNOTATION)
+            .is_some_and(|value| value.eq_ignore_ascii_case("true"));
+        let name = prefixed(VARIANT_ANNOTATION)
+            .filter(|value| !value.is_empty())
+            .map(String::to_owned)
+            .unwrap_or_else(|| {
+                if nginx_canary {
+                    CANARY_VARIANT.to_owned()
+                } else {
+                    PRIMARY_VARIANT.to_owned()
+                }
+            });
+        let weight = prefixed(WEIGHT_ANNOTATION)
+            .or_else(|| {
+                annotations
+                    .get(NGINX_CANARY_WEIGHT_ANNOTATION)
+                    .filter(|_| nginx_canary)
+            })
+            .and_then(|value| value.parse::<u32>().ok())
+            .map(|weight| std::cmp::min(weight, 100));
+        Self { name, weight }
+    }
+}
@@ -22,25 +22,46 @@ use futures::stream;
 use futures::TryStreamExt;
 use kube::runtime::reflector;
 use kube::runtime::reflector::Lookup;
-use kube::runtime::watcher;
 use kube::runtime::watcher::Config;
 use kube::runtime::WatchStreamExt;
 use kube::Api;
 use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
 use std::sync::Arc;
 
+/// Return a watcher configuration with the configured page size and bookmark usage.
+pub fn watcher_config() -> Config {
+    let settings = crate::backoff::settings();
+    let mut config = Config::default();
+    if let Some(page_size) = settings.page_size {
+        config = config.page_size(page_size);
+    }
+    if !settings.bookmarks {
+        config = config.disable_bookmarks();
+    }
+    config
+}
+
 /// Return a stream of existing and future Kubernet resources of type `K`.
 pub async fn reflector_stream<K>(
     api: Api<K>,
     watcher_config: Config,
 ) -> impl futures_util::Stream<Item = Result<Arc<K>, kube::runtime::watcher::Error>>
 where
-    K: std::fmt::Debug + DeserializeOwned + kube::Resource + Clone + std::marker::Send + 'static,
+    K: std::fmt::Debug
+        + DeserializeOwned
+        + Serialize
+        + kube::Resource
+        + Clone
+        + std::marker::Send
+        + 'static,
     <K as kube::Resource>::DynamicType: std::default::Default,
     <K as Lookup>::DynamicType: Eq + Hash + Clone,
 {
     let (reader, writer) = reflector::store();
-    let reflector = reflector(writer, watcher(api, watcher_config));
+    let reflector = reflector(writer, crate::watch_recording::watcher(api, watcher_config));
     let reflector_stream = reflector
         .applied_objects()
         .and_then(|x| async { Ok(Arc::new(x)) });
@@ -54,3 +75,269 @@ where
     });
     stream::select(reflector_stream, store_stream)
 }
+
+/// A single requirement of a [LabelSelector].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LabelRequirement {
+    /// `key=value` or `key==value`
+    Equals(String, String),
+    /// `key!=value`
+    NotEquals(String, String),
+    /// `key in (value1,value2)`
+    In(String, Vec<String>),
+    /// `key notin (value1,value2)`
+    NotIn(String, Vec<String>),
+    /// `key`
+    Exists(String),
+    /// `!key`
+    DoesNotExist(String),
+}
+
+impl LabelRequirement {
+    /// Return `true` if the labels fulfill the requirement.
+    fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        match self {
+            Self::Equals(key, value) => labels.get(key) == Some(value),
+            Self::NotEquals(key, value) => labels.get(key) != Some(value),
+            Self::In(key, values) => labels.get(key).is_some_and(|v| values.contains(v)),
+            Self::NotIn(key, values) => !labels.get(key).is_some_and(|v| values.contains(v)),
+            Self::Exists(key) => labels.contains_key(key),
+            Self::DoesNotExist(key) => !labels.contains_key(key),
+        }
+    }
+}
+
+/**
+Kubernetes label selector that can be evaluated locally against the labels of
+an object.
+
+Supports the same equality-based and set-based syntax as the API server, e.g.
+`microfe=true,env in (prod,stage),!legacy`. An empty selector matches
+everything.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelSelector {
+    requirements: Vec<LabelRequirement>,
+}
+
+impl LabelSelector {
+    /// Return `true` if the labels fulfill all requirements of the selector.
+    pub fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        self.requirements
+            .iter()
+            .all(|requirement| requirement.matches(labels))
+    }
+
+    /// Split on commas that are not enclosed in parenthesis.
+    fn split_requirements(selector: &str) -> Result<Vec<&str>, String> {
+        let mut ret = vec![];
+        let mut depth = 0;
+        let mut start = 0;
+        for (i, c) in selector.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 0 => return Err(format!("Unbalanced ')' in '{selector}'.")),
+                ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    ret.push(&selector[start..i]);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        if depth != 0 {
+            return Err(format!("Unbalanced '(' in '{selector}'."));
+        }
+        ret.push(&selector[start..]);
+        Ok(ret)
+    }
+
+    /// Parse a single requirement like `key!=value` or `key in (a,b)`.
+    fn parse_requirement(requirement: &str) -> Result<LabelRequirement, String> {
+        let requirement = requirement.trim();
+        if requirement.is_empty() {
+            return Err("Empty requirement.".to_string());
+        }
+        if let Some(key) = requirement.strip_prefix('!') {
+            return Ok(LabelRequirement::DoesNotExist(Self::parse_key(
+                key,
+                requirement,
+            )?));
+        }
+        if let Some((key, value)) = requirement.split_once("!=") {
+            return Ok(LabelRequirement::NotEquals(
+                Self::parse_key(key, requirement)?,
+                Self::parse_value(value, requirement)?,
+            ));
+        }
+        if let Some((key, value)) = requirement
+            .split_once("==")
+            .or_else(|| requirement.split_once('='))
+        {
+            return Ok(LabelRequirement::Equals(
+                Self::parse_key(key, requirement)?,
+                Self::parse_value(value, requirement)?,
+            ));
+        }
+        if let Some((key, values)) = requirement.split_once('(') {
+            let values = values
+                .strip_suffix(')')
+                .ok_or(format!("Missing ')' in '{requirement}'."))?
+                .split(',')
+                .map(|value| Self::parse_value(value, requirement))
+                .collect::<Result<Vec<_>, _>>()?;
+            if values.iter().all(String::is_empty) {
+                return Err(format!("Empty set of values in '{requirement}'."));
+            }
+            let mut parts = key.split_whitespace();
+            let (Some(key), Some(operator), None) = (parts.next(), parts.next(), parts.next())
+            else {
+                return Err(format!("Invalid set-based requirement '{requirement}'."));
+            };
+            let key = Self::parse_key(key, requirement)?;
+            return match operator {
+                "in" => Ok(LabelRequirement::In(key, values)),
+                "notin" => Ok(LabelRequirement::NotIn(key, values)),
+                _ => Err(format!("Unknown operator '{operator}' in '{requirement}'.")),
+            };
+        }
+        Ok(LabelRequirement::Exists(Self::parse_key(
+            requirement,
+            requirement,
+        )?))
+    }
+
+    /// Return the trimmed label key unless it is empty or has invalid characters.
+    fn parse_key(key: &str, requirement: &str) -> Result<String, String> {
+        let key = key.trim();
+        let valid = !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+        if !valid {
+            return Err(format!("Invalid key '{key}' in '{requirement}'."));
+        }
+        Ok(key.to_owned())
+    }
+
+    /// Return the trimmed label value unless it has invalid characters. Values may be empty.
+    fn parse_value(value: &str, requirement: &str) -> Result<String, String> {
+        let value = value.trim();
+        if !value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            return Err(format!("Invalid value '{value}' in '{requirement}'."));
+        }
+        Ok(value.to_owned())
+    }
+}
+
+impl FromStr for LabelSelector {
+    type Err = String;
+
+    /// Parse a comma separated list of label requirements.
+    fn from_str(selector: &str) -> Result<Self, Self::Err> {
+        if selector.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let requirements = Self::split_requirements(selector)?
+            .into_iter()
+            .map(Self::parse_requirement)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { requirements })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Return labels from `key=value` pairs.
+    fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// Return `true` if the selector parses and matches the labels.
+    fn matches(selector: &str, pairs: &[(&str, &str)]) -> bool {
+        selector
+            .parse::<LabelSelector>()
+            .unwrap()
+            .matches(&labels(pairs))
+    }
+
+    #[test]
+    fn empty_selector_matches_everything() {
+        assert!(matches("", &[]));
+        assert!(matches(" ", &[("microfe", "true")]));
+    }
+
+    #[test]
+    fn equality() {
+        assert!(matches("microfe=true", &[("microfe", "true")]));
+        assert!(matches("microfe==true", &[("microfe", "true")]));
+        assert!(!matches("microfe=true", &[("microfe", "false")]));
+        assert!(!matches("microfe=true", &[]));
+        assert!(matches(
+            "microfe=true, env = prod",
+            &[("microfe", "true"), ("env", "prod")]
+        ));
+    }
+
+    #[test]
+    fn inequality() {
+        assert!(matches("env!=prod", &[("env", "stage")]));
+        assert!(matches("env!=prod", &[]));
+        assert!(!matches("env!=prod", &[("env", "prod")]));
+    }
+
+    #[test]
+    fn set_based() {
+        assert!(matches("env in (prod,stage)", &[("env", "stage")]));
+        assert!(!matches("env in (prod, stage)", &[("env", "dev")]));
+        assert!(!matches("env in (prod,stage)", &[]));
+        assert!(matches("env notin (prod,stage)", &[("env", "dev")]));
+        assert!(matches("env notin (prod,stage)", &[]));
+        assert!(!matches("env notin (prod,stage)", &[("env", "prod")]));
+        assert!(matches(
+            "microfe=true,env in (prod,stage),!legacy",
+            &[("microfe", "true"), ("env", "prod")]
+        ));
+    }
+
+    #[test]
+    fn existence() {
+        assert!(matches("microfe", &[("microfe", "")]));
+        assert!(!matches("microfe", &[("other", "true")]));
+        assert!(matches("!legacy", &[("microfe", "true")]));
+        assert!(!matches("!legacy", &[("legacy", "true")]));
+    }
+
+    #[test]
+    fn malformed_selectors_are_rejected() {
+        for selector in [
+            "microfe=true,",
+            ",microfe=true",
+            "=true",
+            "!",
+            "!=prod",
+            "env in (prod",
+            "env in prod,stage)",
+            "env in ()",
+            "env within (prod)",
+            "in (prod)",
+            "micro fe",
+            "microfe=tr ue",
+            "microfe=true=false",
+            "env in (prod,st@ge)",
+        ] {
+            assert!(
+                selector.parse::<LabelSelector>().is_err(),
+                "'{selector}' should be rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,63 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+#![warn(missing_docs)]
+#![doc(issue_tracker_base_url = "https://github.com/mydriatech/microfefind/issues/")]
+
+//! # Micro front end discovery on Kubernetes.
+//!
+//! Enable discovery of micro front ends via labeled Kubernetes `Ingress`
+//! declarations.
+//!
+//! The discovery can be embedded in other Rust services:
+//!
+//! ```no_run
+//! use microfefind::conf::AppConfig;
+//! use microfefind::ingress_monitor::IngressMonitor;
+//! use std::sync::Arc;
+//!
+//! # async fn example() -> std::io::Result<()> {
+//! let app_config = Arc::new(AppConfig::new());
+//! let ingress_monitor = IngressMonitor::new(Arc::clone(&app_config));
+//! // Serve the catalogue as part of an existing actix-web application
+//! let app_data = microfefind::rest_api::app_data(app_config, ingress_monitor);
+//! actix_web::HttpServer::new(move || {
+//!     actix_web::App::new()
+//!         .app_data(app_data.clone())
+//!         .configure(microfefind::rest_api::configure)
+//! })
+//! .bind(("0.0.0.0", 8080))?
+//! .run()
+//! .await
+//! # }
+//! ```
+
+mod backoff;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod conf;
+mod error_reporting;
+mod fixtures;
+pub mod ingress_monitor;
+mod kubers_util;
+pub mod log_output;
+pub mod notifier;
+pub mod plugin;
+pub mod rest_api;
+pub mod supervisor;
+pub mod time;
+mod watch_recording;
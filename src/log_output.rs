@@ -0,0 +1,126 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Log outputs for environments where standard output isn't scraped, like
+//! syslog or the systemd journal.
+
+use log::{Log, Metadata, Record, SetLoggerError};
+
+/**
+Applies the filters of an `env_logger` to log messages and forwards matching
+messages to another output.
+
+This keeps `MICROFEFIND_LOG_LEVEL` working regardless of the output.
+ */
+pub struct FilteredLogger {
+    /// Logger that is only used for its filters.
+    filter: env_logger::Logger,
+    /// Output of matching log messages.
+    output: Box<dyn Log>,
+}
+
+impl FilteredLogger {
+    /// Install a new instance as the global logger.
+    pub fn install(filter: env_logger::Logger, output: Box<dyn Log>) -> Result<(), SetLoggerError> {
+        let max_level = filter.filter();
+        log::set_boxed_logger(Box::new(Self { filter, output }))?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+}
+
+impl Log for FilteredLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filter.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.filter.matches(record) {
+            self.output.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.output.flush();
+    }
+}
+
+/**
+Return an output that sends log messages formatted according to RFC 5424 to
+the local syslog daemon.
+ */
+#[cfg(feature = "syslog")]
+pub fn syslog(app_name: &str) -> Result<Box<dyn Log>, String> {
+    let formatter = syslog::Formatter5424 {
+        facility: syslog::Facility::LOG_DAEMON,
+        hostname: None,
+        process: app_name.to_owned(),
+        pid: std::process::id(),
+    };
+    syslog::unix(formatter)
+        .map(|logger| {
+            Box::new(SyslogOutput {
+                logger: std::sync::Mutex::new(logger),
+            }) as Box<dyn Log>
+        })
+        .map_err(|e| format!("Failed to connect to syslog: {e}"))
+}
+
+/// Sends log messages to syslog.
+#[cfg(feature = "syslog")]
+struct SyslogOutput {
+    /// Connection to the local syslog daemon.
+    logger: std::sync::Mutex<syslog::Logger<syslog::LoggerBackend, syslog::Formatter5424>>,
+}
+
+#[cfg(feature = "syslog")]
+impl Log for SyslogOutput {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        // RFC 5424 structured data with the origin of the message
+        let structured_data = std::collections::BTreeMap::from([(
+            "origin".to_owned(),
+            std::collections::BTreeMap::from([("target".to_owned(), record.target().to_owned())]),
+        )]);
+        let message = (0, structured_data, record.args().to_string());
+        let mut logger = self.logger.lock().unwrap();
+        // Nowhere to report failures to log
+        let _ = match record.level() {
+            log::Level::Error => logger.err(message),
+            log::Level::Warn => logger.warning(message),
+            log::Level::Info => logger.info(message),
+            log::Level::Debug | log::Level::Trace => logger.debug(message),
+        };
+    }
+
+    fn flush(&self) {
+        let _ = std::io::Write::flush(&mut self.logger.lock().unwrap().backend);
+    }
+}
+
+/// Return an output that sends log messages to the systemd journal.
+#[cfg(feature = "journald")]
+pub fn journald(app_name: &str) -> Result<Box<dyn Log>, String> {
+    systemd_journal_logger::JournalLog::new()
+        .map(|journal_log| {
+            Box::new(journal_log.with_syslog_identifier(app_name.to_owned())) as Box<dyn Log>
+        })
+        .map_err(|e| format!("Failed to connect to the systemd journal: {e}"))
+}
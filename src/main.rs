@@ -15,47 +15,164 @@
     limitations under the License.
 */
 
-#![warn(missing_docs)]
-#![doc(issue_tracker_base_url = "https://github.com/mydriatech/microfefind/issues/")]
-
 //! # Micro front end discovery on Kubernetes.
 //!
-//! Enable discovery of micro front ends via labeled Kubernetes `Ingress`
-//! declarations.
+//! Binary wrapper of the [microfefind] library.
 //!
 
-pub mod conf;
-mod ingress_monitor;
-mod kubers_util;
-mod rest_api;
-mod time;
-
+use clap::Parser;
+use std::path::PathBuf;
 use std::process::ExitCode;
 use std::sync::Arc;
-use tokio::signal::unix::{signal, SignalKind};
 
-use crate::conf::AppConfig;
-use crate::ingress_monitor::IngressMonitor;
+use microfefind::conf::AppConfig;
+use microfefind::ingress_monitor::IngressMonitor;
+use microfefind::rest_api;
+
+/// Version and build information.
+const VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    " (",
+    env!("BUILD_TARGET"),
+    ", ",
+    env!("BUILD_PROFILE"),
+    ")"
+);
+
+/**
+Command line arguments.
+
+Arguments take precedence over the configuration file and environment
+variables.
+ */
+#[derive(Parser)]
+#[command(about, version = VERSION)]
+struct Cli {
+    /// Configuration file to load instead of `microfefind.json` in the working directory.
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// Print the effective configuration as JSON and exit.
+    #[arg(long)]
+    print_config: bool,
+    /// Validate the configuration and exit.
+    #[arg(long)]
+    validate_config: bool,
+    /// Namespace to monitor. May be repeated.
+    #[arg(long, value_name = "NAMESPACE")]
+    namespace: Vec<String>,
+    /// Kubeconfig file of the cluster to monitor.
+    #[arg(long, value_name = "FILE")]
+    kubeconfig: Option<PathBuf>,
+    /// Context of the kubeconfig to use.
+    #[arg(long, value_name = "CONTEXT")]
+    context: Option<String>,
+    /// Address and port to serve the REST API on, like `127.0.0.1:8083`.
+    #[arg(long, value_name = "ADDRESS:PORT")]
+    bind: Option<String>,
+    /// Serve entries from YAML or JSON manifests in this directory instead of a cluster.
+    #[arg(long, value_name = "DIR")]
+    fixtures: Option<PathBuf>,
+    /// Record all received watch events to this JSONL file.
+    #[arg(long, value_name = "FILE")]
+    record_events: Option<PathBuf>,
+    /// Replay watch events from this JSONL file recorded with `--record-events`.
+    #[arg(long, value_name = "FILE")]
+    replay_events: Option<PathBuf>,
+}
+
+impl Cli {
+    /// Return configuration overrides as pairs of key path and value.
+    fn overrides(&self) -> Vec<(String, String)> {
+        let mut ret = vec![];
+        if !self.namespace.is_empty() {
+            ret.push(("ingress.namespaces".to_owned(), self.namespace.join(",")));
+        }
+        if let Some(kubeconfig) = &self.kubeconfig {
+            ret.push((
+                "kube.kubeconfig".to_owned(),
+                kubeconfig.to_string_lossy().into_owned(),
+            ));
+        }
+        if let Some(context) = &self.context {
+            ret.push(("kube.context".to_owned(), context.to_owned()));
+        }
+        if let Some(bind) = &self.bind {
+            let (address, port) = bind.rsplit_once(':').unwrap_or((bind, ""));
+            let address = address.trim_start_matches('[').trim_end_matches(']');
+            if !address.is_empty() {
+                ret.push(("api.address".to_owned(), address.to_owned()));
+            }
+            if !port.is_empty() {
+                ret.push(("api.port".to_owned(), port.to_owned()));
+            }
+        }
+        if let Some(fixtures) = &self.fixtures {
+            ret.push(("source.kind".to_owned(), "static".to_owned()));
+            ret.push((
+                "source.fixtures".to_owned(),
+                fixtures.to_string_lossy().into_owned(),
+            ));
+        }
+        if let Some(record_events) = &self.record_events {
+            ret.push((
+                "debug.recordevents".to_owned(),
+                record_events.to_string_lossy().into_owned(),
+            ));
+        }
+        if let Some(replay_events) = &self.replay_events {
+            ret.push((
+                "debug.replayevents".to_owned(),
+                replay_events.to_string_lossy().into_owned(),
+            ));
+        }
+        ret
+    }
+}
 
 /// Application entry point.
 fn main() -> ExitCode {
+    let cli = Cli::parse();
     if let Err(e) = init_logger() {
         log::error!("Failed to initialize configuration: {e:?}");
         return ExitCode::FAILURE;
     }
-    let app_config = Arc::new(AppConfig::new());
+    let app_config = match AppConfig::try_with_overrides(cli.config.as_deref(), &cli.overrides()) {
+        Ok(app_config) => Arc::new(app_config),
+        Err(e) => {
+            log::error!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if cli.print_config {
+        println!("{}", app_config.to_json());
+        return ExitCode::SUCCESS;
+    }
+    if cli.validate_config {
+        log::info!("Configuration is valid.");
+        return ExitCode::SUCCESS;
+    }
+    if app_config.debug.console_enabled() {
+        init_console(&app_config);
+    }
     tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .worker_threads(app_config.limits.available_parallelism())
         .build()
         .unwrap()
-        .block_on(run_async(app_config))
+        .block_on(run_async(app_config, cli))
 }
 
-/// Initialize the logging system and apply filters.
+/**
+Initialize the logging system and apply filters.
+
+Logs are written to standard output unless `MICROFEFIND_LOG_OUTPUT` selects
+`syslog` or `journald` (when built with the feature of the same name).
+ */
 fn init_logger() -> Result<(), log::SetLoggerError> {
-    let env_prefex = AppConfig::read_app_name_lowercase().to_uppercase();
-    env_logger::builder()
+    let app_name = AppConfig::read_app_name_lowercase();
+    let env_prefex = app_name.to_uppercase();
+    let mut builder = env_logger::builder();
+    builder
         // Set default log level
         .filter_level(log::LevelFilter::Debug)
         // Customize logging for dependencies
@@ -73,29 +190,59 @@ fn init_logger() -> Result<(), log::SetLoggerError> {
             env_logger::Env::new()
                 .filter(env_prefex.to_owned() + "_LOG_LEVEL")
                 .write_style(env_prefex.to_owned() + "_LOG_STYLE"),
-        )
-        .try_init()
-}
-
-/// Async code entry point.
-async fn run_async(app_config: Arc<AppConfig>) -> ExitCode {
-    // Make a quick check that we have a k8s context that we can use.
-    let client_result = kube::Client::try_default().await;
-    match client_result {
-        Ok(client) => {
-            let info = client.apiserver_version().await.unwrap();
-            log::info!("Kubernetes API version: {info:?}");
+        );
+    let log_output = std::env::var(env_prefex.to_owned() + "_LOG_OUTPUT").unwrap_or_default();
+    let output: Result<Option<Box<dyn log::Log>>, String> = match log_output.to_lowercase().as_str()
+    {
+        "" | "stdout" => Ok(None),
+        #[cfg(feature = "syslog")]
+        "syslog" => microfefind::log_output::syslog(&app_name).map(Some),
+        #[cfg(feature = "journald")]
+        "journald" => microfefind::log_output::journald(&app_name).map(Some),
+        other => Err(format!("Unknown or unsupported log output '{other}'.")),
+    };
+    match output {
+        Ok(Some(output)) => {
+            microfefind::log_output::FilteredLogger::install(builder.build(), output)
         }
+        Ok(None) => builder.try_init(),
         Err(e) => {
-            log::error!("Failed to access Kubernetes API. Is this container deployed? {e:?}");
-            return ExitCode::FAILURE;
+            builder.try_init()?;
+            log::warn!("{e} Logging to standard output instead.");
+            Ok(())
         }
     }
+}
+
+/// Serve instrumentation of all tasks for `tokio-console`.
+fn init_console(app_config: &AppConfig) {
+    #[cfg(all(tokio_unstable, feature = "console"))]
+    if let Some(console_address) = app_config.debug.console_address() {
+        console_subscriber::ConsoleLayer::builder()
+            .server_addr(console_address)
+            .init();
+        log::info!("Serving tokio-console instrumentation on {console_address}.");
+    }
+    #[cfg(not(all(tokio_unstable, feature = "console")))]
+    {
+        let _ = app_config;
+        log::warn!(
+            "Ignoring tokio-console, which requires a build with the 'console' feature and 'tokio_unstable'."
+        );
+    }
+}
+
+/// Async code entry point.
+async fn run_async(app_config: Arc<AppConfig>, cli: Cli) -> ExitCode {
+    // Serve health checks while the Kubernetes API is (re)tried in the background.
     let ingress_monitor = IngressMonitor::new(Arc::clone(&app_config));
     let ingress_monitor_api_future =
-        rest_api::run_http_server(app_config, Arc::clone(&ingress_monitor));
-    let signals_future = block_until_signaled();
+        rest_api::run_http_server(Arc::clone(&app_config), Arc::clone(&ingress_monitor));
+    let signals_future = block_until_signaled(&cli, &ingress_monitor);
     tokio::select! {
+        _ = ingress_monitor.startup_failed() => {
+            return ExitCode::FAILURE;
+        },
         _ = ingress_monitor_api_future => {
             log::trace!("ingress_monitor_api_future finished");
         },
@@ -106,16 +253,130 @@ async fn run_async(app_config: Arc<AppConfig>) -> ExitCode {
     ExitCode::SUCCESS
 }
 
-/// Block until SIGTERM or SIGINT is recieved.
-async fn block_until_signaled() {
-    let mut sigint = signal(SignalKind::interrupt()).unwrap();
-    let mut sigterm = signal(SignalKind::terminate()).unwrap();
-    tokio::select! {
-        _ = sigterm.recv() => {
-            log::debug!("SIGTERM recieved.")
-        },
-        _ = sigint.recv() => {
-            log::debug!("SIGINT recieved.")
-        },
+/// Signal handled by the application.
+enum Signal {
+    /// Stop the application.
+    Shutdown,
+    /// Reload the configuration and reconcile the catalogue.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    Reload,
+}
+
+/// Receiver of SIGTERM, SIGINT and SIGHUP.
+#[cfg(unix)]
+struct Signals {
+    sigint: tokio::signal::unix::Signal,
+    sigterm: tokio::signal::unix::Signal,
+    sighup: tokio::signal::unix::Signal,
+}
+
+#[cfg(unix)]
+impl Signals {
+    /// Start listening for signals.
+    fn new() -> std::io::Result<Self> {
+        use tokio::signal::unix::{signal, SignalKind};
+        Ok(Self {
+            sigint: signal(SignalKind::interrupt())?,
+            sigterm: signal(SignalKind::terminate())?,
+            sighup: signal(SignalKind::hangup())?,
+        })
+    }
+
+    /// Wait for the next signal.
+    async fn recv(&mut self) -> Signal {
+        tokio::select! {
+            _ = self.sigterm.recv() => {
+                log::debug!("SIGTERM recieved.");
+                Signal::Shutdown
+            },
+            _ = self.sigint.recv() => {
+                log::debug!("SIGINT recieved.");
+                Signal::Shutdown
+            },
+            _ = self.sighup.recv() => {
+                log::info!("SIGHUP recieved. Reloading configuration and reconciling.");
+                Signal::Reload
+            },
+        }
+    }
+}
+
+/// Receiver of Ctrl+C on platforms without Unix signals.
+#[cfg(not(unix))]
+struct Signals;
+
+#[cfg(not(unix))]
+impl Signals {
+    /// Start listening for signals.
+    fn new() -> std::io::Result<Self> {
+        Ok(Self)
+    }
+
+    /// Wait for the next signal.
+    async fn recv(&mut self) -> Signal {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            log::warn!("Unable to listen for Ctrl+C: {e:?}");
+            std::future::pending::<()>().await;
+        }
+        log::debug!("Ctrl+C recieved.");
+        Signal::Shutdown
+    }
+}
+
+/**
+Block until SIGTERM or SIGINT (Ctrl+C on other platforms) is recieved.
+
+SIGHUP reloads the configuration and reconciles the catalogue instead.
+ */
+async fn block_until_signaled(cli: &Cli, ingress_monitor: &Arc<IngressMonitor>) {
+    let mut signals = match Signals::new() {
+        Ok(signals) => signals,
+        Err(e) => {
+            log::warn!("Unable to listen for signals: {e:?}");
+            return std::future::pending().await;
+        }
+    };
+    loop {
+        match signals.recv().await {
+            Signal::Shutdown => return,
+            Signal::Reload => {
+                reload_config(ingress_monitor, cli);
+                ingress_monitor.reconcile_all().await;
+            }
+        }
+    }
+}
+
+/**
+Load and validate the configuration again and apply it if only
+[reloadable](microfefind::conf::RELOADABLE_KEYS) values changed.
+
+A reloaded configuration that changes anything else is rejected as a whole, so
+the running configuration never mixes applied and pending changes.
+ */
+fn reload_config(ingress_monitor: &Arc<IngressMonitor>, cli: &Cli) {
+    let reloaded = match AppConfig::try_with_overrides(cli.config.as_deref(), &cli.overrides()) {
+        Ok(reloaded) => reloaded,
+        Err(e) => {
+            log::error!("Keeping the running configuration, since the reloaded configuration is invalid. {e}");
+            return;
+        }
     };
+    match ingress_monitor.reload_config(reloaded) {
+        Ok(changed_keys) if changed_keys.is_empty() => {
+            log::info!("Reloaded configuration is unchanged.");
+        }
+        Ok(changed_keys) => {
+            log::info!(
+                "Reloaded configuration changed {}.",
+                changed_keys.join(", ")
+            );
+        }
+        Err(restart_keys) => {
+            log::error!(
+                "Keeping the running configuration, since {} can only be changed by a restart.",
+                restart_keys.join(", ")
+            );
+        }
+    }
 }
@@ -0,0 +1,148 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+//! Publishing of structured catalogue change events.
+
+mod audit_notifier;
+mod change_feed;
+mod log_notifier;
+#[cfg(feature = "nats")]
+mod nats_notifier;
+#[cfg(feature = "webhook")]
+mod webhook_notifier;
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::conf::AppConfig;
+
+pub use self::audit_notifier::AuditNotifier;
+pub use self::change_feed::ChangeFeed;
+pub use self::change_feed::ChangeFeedPage;
+pub use self::log_notifier::LogNotifier;
+#[cfg(feature = "nats")]
+pub use self::nats_notifier::NatsNotifier;
+#[cfg(feature = "webhook")]
+pub use self::webhook_notifier::WebhookNotifier;
+
+/// Kind of change of a catalogue entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ChangeKind {
+    /// The entry was added to the catalogue.
+    Added,
+    /// A property of the entry changed.
+    Updated,
+    /// The entry was removed from the catalogue.
+    Removed,
+}
+
+/// A structured change of a single catalogue entry.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEvent {
+    /// Kind of change.
+    pub kind: ChangeKind,
+    /// Identifier of the Kubernetes cluster where the entry was discovered.
+    pub cluster: String,
+    /// Kubernetes namespace of the declaring resource.
+    pub namespace: String,
+    /// Combined hostname and path of the entry.
+    pub host_path: String,
+    /// Name of the variant of the entry.
+    pub variant: String,
+    /// Name of the changed property, like `annotations` or `service`, for updates.
+    pub property: Option<String>,
+    /// Kind and name of the resource declaring the entry, like `Ingress/checkout`, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Value of the changed property before the update, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Object>))]
+    pub old: Option<serde_json::Value>,
+    /// Value of the changed property after the update, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<Object>))]
+    pub new: Option<serde_json::Value>,
+    /// Time of the change in milliseconds since Unix Epoch.
+    pub timestamp: u64,
+    /// Monotonically increasing sequence number of the change. See [ChangeFeed].
+    #[serde(default)]
+    pub sequence: u64,
+}
+
+/**
+Sink for catalogue changes.
+
+Implementations must not block, since changes are published from the
+monitoring tasks.
+ */
+pub trait Notifier: Send + Sync {
+    /// Publish a change.
+    fn notify(&self, event: &ChangeEvent);
+}
+
+/// Publishes every change to all contained [Notifier]s.
+pub struct CompositeNotifier {
+    notifiers: Vec<Arc<dyn Notifier>>,
+}
+
+impl CompositeNotifier {
+    /// Return a new instance.
+    pub fn new(notifiers: Vec<Arc<dyn Notifier>>) -> Self {
+        Self { notifiers }
+    }
+}
+
+impl Notifier for CompositeNotifier {
+    fn notify(&self, event: &ChangeEvent) {
+        self.notifiers
+            .iter()
+            .for_each(|notifier| notifier.notify(event));
+    }
+}
+
+/**
+Return the [Notifier] for the configured types.
+
+Unknown or incompletely configured types are logged and ignored.
+ */
+pub fn from_config(app_config: &AppConfig) -> Arc<dyn Notifier> {
+    let mut notifiers: Vec<Arc<dyn Notifier>> = vec![];
+    for notifier_type in app_config.notifier.types() {
+        match notifier_type.as_str() {
+            "log" => notifiers.push(Arc::new(LogNotifier)),
+            "audit" => notifiers.push(Arc::new(AuditNotifier)),
+            #[cfg(feature = "webhook")]
+            "webhook" => match app_config.notifier.webhook_url() {
+                Some(url) => notifiers.push(Arc::new(WebhookNotifier::new(url))),
+                None => log::warn!("Ignoring 'webhook' notifier without URL."),
+            },
+            #[cfg(feature = "nats")]
+            "nats" => match app_config.notifier.nats_url() {
+                Some(url) => notifiers.push(Arc::new(NatsNotifier::new(
+                    url,
+                    app_config.notifier.nats_subject(),
+                ))),
+                None => log::warn!("Ignoring 'nats' notifier without URL."),
+            },
+            other => log::warn!("Ignoring unknown or unsupported notifier '{other}'."),
+        }
+    }
+    Arc::new(CompositeNotifier::new(notifiers))
+}
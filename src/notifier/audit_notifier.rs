@@ -0,0 +1,65 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Notifier that writes an audit trail of changes as JSON lines.
+
+use serde::Serialize;
+use std::io::Write;
+
+use super::ChangeEvent;
+use super::Notifier;
+
+/// Value of the `log` field that distinguishes audit lines from other output.
+pub const AUDIT_LOG: &str = "audit";
+
+/// A single line of the audit trail.
+#[derive(Serialize)]
+struct AuditLine<'a> {
+    /// Always [AUDIT_LOG].
+    log: &'static str,
+    /// The change with the declaring resource and the old and new values.
+    #[serde(flatten)]
+    event: &'a ChangeEvent,
+}
+
+/**
+Writes every change as a single JSON line directly to standard output.
+
+Lines are not prefixed like regular log messages, so log-based SIEM pipelines
+can parse every line with `"log":"audit"` as an audit record of who (the
+declaring resource) changed what (entry and property), when and from which
+old to which new value.
+ */
+pub struct AuditNotifier;
+
+impl Notifier for AuditNotifier {
+    fn notify(&self, event: &ChangeEvent) {
+        let audit_line = AuditLine {
+            log: AUDIT_LOG,
+            event,
+        };
+        match serde_json::to_string(&audit_line) {
+            Ok(json) => {
+                // Lock to not interleave with concurrent log output
+                if let Err(e) = writeln!(std::io::stdout().lock(), "{json}") {
+                    log::warn!("Failed to write audit record: {e}");
+                }
+            }
+            Err(e) => log::warn!("Failed to serialize audit record: {e}"),
+        }
+    }
+}
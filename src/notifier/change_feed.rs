@@ -0,0 +1,162 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Sequenced feed of recent changes that clients can resume from.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use super::ChangeEvent;
+use super::Notifier;
+
+/// Changes after a cursor, as returned by [ChangeFeed::since].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ChangeFeedPage {
+    /// Sequence number of the latest change. Use as cursor of the next request.
+    pub cursor: u64,
+    /// Sequence number of the oldest retained change, if any.
+    pub oldest: Option<u64>,
+    /// `true` if changes after the requested cursor are no longer retained (or
+    /// the cursor is from a previous instance), so the client must
+    /// resynchronize with the full catalogue.
+    pub reset: bool,
+    /// Retained changes after the requested cursor, oldest first.
+    pub events: Vec<ChangeEvent>,
+}
+
+/// Sequence numbers and retained changes guarded by the same lock to keep them ordered.
+#[derive(Default)]
+struct FeedState {
+    sequence: u64,
+    events: VecDeque<ChangeEvent>,
+    /// Approximate memory used by the retained changes in bytes.
+    bytes: usize,
+}
+
+/**
+Assigns a monotonically increasing sequence number to every change, retains
+the most recent changes and forwards the sequenced change to the wrapped
+[Notifier].
+
+Like the `resourceVersion` of a Kubernetes object, clients can use the
+sequence number of the last seen change to detect missed changes.
+ */
+pub struct ChangeFeed {
+    /// Notifier that sequenced changes are forwarded to.
+    inner: Arc<dyn Notifier>,
+    /// Maximum number of retained changes.
+    capacity: usize,
+    /// Maximum memory used by retained changes in bytes, if limited.
+    budget_bytes: Option<usize>,
+    state: Mutex<FeedState>,
+}
+
+impl ChangeFeed {
+    /// Return a new instance that retains up to `capacity` changes.
+    pub fn new(inner: Arc<dyn Notifier>, capacity: usize) -> Self {
+        Self::with_memory_budget(inner, capacity, None)
+    }
+
+    /**
+      Return a new instance that retains up to `capacity` changes, while the
+      retained changes use at most `budget_bytes` (approximately).
+    */
+    pub fn with_memory_budget(
+        inner: Arc<dyn Notifier>,
+        capacity: usize,
+        budget_bytes: Option<usize>,
+    ) -> Self {
+        Self {
+            inner,
+            capacity,
+            budget_bytes,
+            state: Mutex::new(FeedState::default()),
+        }
+    }
+
+    /// Return the approximate memory used by the retained changes in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.state.lock().unwrap().bytes
+    }
+
+    /// Return the approximate memory used by the change in bytes.
+    fn event_bytes(event: &ChangeEvent) -> usize {
+        std::mem::size_of::<ChangeEvent>()
+            + event.cluster.len()
+            + event.namespace.len()
+            + event.host_path.len()
+            + event.variant.len()
+            + event.property.as_ref().map_or(0, String::len)
+            + event.source.as_ref().map_or(0, String::len)
+            + event.old.as_ref().map_or(0, |old| old.to_string().len())
+            + event.new.as_ref().map_or(0, |new| new.to_string().len())
+    }
+
+    /// Return the sequence number of the latest change.
+    pub fn cursor(&self) -> u64 {
+        self.state.lock().unwrap().sequence
+    }
+
+    /// Return the retained changes with a sequence number greater than `cursor`.
+    pub fn since(&self, cursor: u64) -> ChangeFeedPage {
+        let state = self.state.lock().unwrap();
+        let oldest = state.events.front().map(|event| event.sequence);
+        // Changes between the cursor and the oldest retained change were dropped
+        let reset = cursor > state.sequence
+            || oldest.map_or(cursor < state.sequence, |oldest| cursor + 1 < oldest);
+        ChangeFeedPage {
+            cursor: state.sequence,
+            oldest,
+            reset,
+            events: state
+                .events
+                .iter()
+                .filter(|event| event.sequence > cursor)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+impl Notifier for ChangeFeed {
+    fn notify(&self, event: &ChangeEvent) {
+        let mut event = event.clone();
+        {
+            let mut state = self.state.lock().unwrap();
+            state.sequence += 1;
+            event.sequence = state.sequence;
+            if self.capacity > 0 {
+                let event_bytes = Self::event_bytes(&event);
+                while state.events.len() >= self.capacity
+                    || self
+                        .budget_bytes
+                        .is_some_and(|budget| state.bytes + event_bytes > budget)
+                {
+                    let Some(dropped) = state.events.pop_front() else {
+                        break;
+                    };
+                    state.bytes -= Self::event_bytes(&dropped);
+                }
+                state.bytes += event_bytes;
+                state.events.push_back(event.clone());
+            }
+        }
+        self.inner.notify(&event);
+    }
+}
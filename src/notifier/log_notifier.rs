@@ -0,0 +1,41 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+//! Notifier that logs changes.
+
+use super::ChangeEvent;
+use super::Notifier;
+
+/// Logs every change at `info` level.
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+    fn notify(&self, event: &ChangeEvent) {
+        log::info!(
+            "{:?} '{}' ({}) in cluster '{}' and 'ns/{}'{}.",
+            event.kind,
+            event.host_path,
+            event.variant,
+            event.cluster,
+            event.namespace,
+            event
+                .property
+                .as_ref()
+                .map(|property| format!(": {property}"))
+                .unwrap_or_default()
+        );
+    }
+}
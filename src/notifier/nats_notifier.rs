@@ -0,0 +1,71 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+//! Notifier that publishes changes to a NATS message bus.
+
+use futures::lock::Mutex;
+use std::sync::Arc;
+
+use super::ChangeEvent;
+use super::Notifier;
+
+/// Publishes every change as JSON to a NATS subject.
+pub struct NatsNotifier {
+    /// URL of the NATS server.
+    url: String,
+    /// Subject that changes are published to.
+    subject: String,
+    /// Lazily established connection.
+    client: Arc<Mutex<Option<async_nats::Client>>>,
+}
+
+impl NatsNotifier {
+    /// Return a new instance. The connection is established on first use.
+    pub fn new(url: &str, subject: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+            subject: subject.to_owned(),
+            client: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl Notifier for NatsNotifier {
+    fn notify(&self, event: &ChangeEvent) {
+        let payload = serde_json::to_vec(event).unwrap();
+        let url = self.url.to_owned();
+        let subject = self.subject.to_owned();
+        let client = Arc::clone(&self.client);
+        tokio::spawn(async move {
+            let mut client_opt = client.lock().await;
+            if client_opt.is_none() {
+                match async_nats::connect(&url).await {
+                    Ok(client) => {
+                        client_opt.replace(client);
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to connect to NATS server '{url}': {e:?}");
+                        return;
+                    }
+                }
+            }
+            let client = client_opt.as_ref().unwrap();
+            if let Err(e) = client.publish(subject, payload.into()).await {
+                log::warn!("Failed to publish change to NATS: {e:?}");
+            }
+        });
+    }
+}
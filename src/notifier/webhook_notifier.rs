@@ -0,0 +1,54 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+//! Notifier that `POST`s changes to a webhook.
+
+use super::ChangeEvent;
+use super::Notifier;
+
+/// `POST`s every change as JSON to a URL without waiting for the response.
+pub struct WebhookNotifier {
+    /// Shared HTTP client.
+    http_client: reqwest::Client,
+    /// URL to `POST` changes to.
+    url: String,
+}
+
+impl WebhookNotifier {
+    /// Return a new instance.
+    pub fn new(url: &str) -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+            url: url.to_owned(),
+        }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn notify(&self, event: &ChangeEvent) {
+        let request = self.http_client.post(&self.url).json(event);
+        let url = self.url.to_owned();
+        tokio::spawn(async move {
+            let result = request
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = result {
+                log::warn!("Failed to notify webhook '{url}': {e:?}");
+            }
+        });
+    }
+}
@@ -0,0 +1,84 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Plugins transforming entries before they are exposed.
+
+#[cfg(feature = "rhai")]
+mod rhai_plugin;
+#[cfg(feature = "wasm")]
+mod wasm_plugin;
+
+use serde_json::Value;
+use std::sync::Arc;
+
+#[cfg(feature = "rhai")]
+pub use self::rhai_plugin::RhaiPlugin;
+#[cfg(feature = "wasm")]
+pub use self::wasm_plugin::WasmPlugin;
+
+/**
+Custom logic applied to each entry before it is exposed, like rewriting URLs,
+injecting metadata or filtering entries.
+
+Implementations must be fast, since they are invoked for each entry of each
+response.
+ */
+pub trait EntryPlugin: Send + Sync {
+    /**
+      Return the transformed JSON representation of the entry (as returned by
+      `/api/v1/all`) or `None` to omit the entry.
+    */
+    fn transform(&self, entry: Value) -> Option<Value>;
+}
+
+impl std::fmt::Debug for dyn EntryPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EntryPlugin")
+    }
+}
+
+/// [EntryPlugin] applying multiple plugins in order.
+pub struct CompositePlugin {
+    plugins: Vec<Arc<dyn EntryPlugin>>,
+}
+
+impl CompositePlugin {
+    /// Return a new instance applying the plugins in order.
+    pub fn new(plugins: Vec<Arc<dyn EntryPlugin>>) -> Self {
+        Self { plugins }
+    }
+}
+
+impl EntryPlugin for CompositePlugin {
+    fn transform(&self, entry: Value) -> Option<Value> {
+        self.plugins
+            .iter()
+            .try_fold(entry, |entry, plugin| plugin.transform(entry))
+    }
+}
+
+/**
+Return the `plugins` as a single [EntryPlugin] applying them in order or
+`None` if there are no plugins.
+ */
+pub fn compose(mut plugins: Vec<Arc<dyn EntryPlugin>>) -> Option<Arc<dyn EntryPlugin>> {
+    match plugins.len() {
+        0 => None,
+        1 => plugins.pop(),
+        _ => Some(Arc::new(CompositePlugin::new(plugins))),
+    }
+}
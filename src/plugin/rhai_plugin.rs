@@ -0,0 +1,96 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Entry shaping by a Rhai script.
+
+use rhai::{Dynamic, Engine, Scope, AST};
+use serde_json::Value;
+
+use super::EntryPlugin;
+
+/// Name of the script variable holding the entry.
+const ENTRY_VARIABLE: &str = "entry";
+
+/**
+[EntryPlugin] implemented by a Rhai script evaluated for each entry.
+
+The entry (as returned by `/api/v1/all`) is available as the `entry` variable
+and may be modified in place, e.g. `entry.annotations.owner = "web"`. The
+script's result decides what is exposed: `false` omits the entry, a map
+replaces it and anything else exposes the (possibly modified) `entry`.
+For example `!entry.host.ends_with(".internal")` hides internal hosts.
+
+Evaluation is limited by a number of operations, so a misbehaving script
+can't stall the server. Entries are omitted when evaluation fails, so a
+filtering script fails closed.
+ */
+pub struct RhaiPlugin {
+    /// Engine evaluating the script.
+    engine: Engine,
+    /// Compiled script.
+    ast: AST,
+}
+
+impl RhaiPlugin {
+    /// Compile the Rhai script at `path`.
+    pub fn load(path: &str, max_operations: u64) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(max_operations);
+        let ast = engine
+            .compile_file(path.into())
+            .map_err(|e| e.to_string())?;
+        Ok(Self { engine, ast })
+    }
+
+    /// Evaluate the script for the entry.
+    fn evaluate(&self, entry: &Value) -> Result<Option<Value>, String> {
+        let mut scope = Scope::new();
+        scope.push(
+            ENTRY_VARIABLE,
+            rhai::serde::to_dynamic(entry).map_err(|e| e.to_string())?,
+        );
+        let result = self
+            .engine
+            .eval_ast_with_scope::<Dynamic>(&mut scope, &self.ast)
+            .map_err(|e| e.to_string())?;
+        if result.as_bool() == Ok(false) {
+            return Ok(None);
+        }
+        let transformed = if result.is_map() {
+            result
+        } else {
+            scope
+                .get_value::<Dynamic>(ENTRY_VARIABLE)
+                .unwrap_or_default()
+        };
+        rhai::serde::from_dynamic::<Value>(&transformed)
+            .map(Some)
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl EntryPlugin for RhaiPlugin {
+    fn transform(&self, entry: Value) -> Option<Value> {
+        match self.evaluate(&entry) {
+            Ok(transformed) => transformed,
+            Err(e) => {
+                log::warn!("Omitting entry that the script failed to transform: {e}");
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Entry transformation by a sandboxed WebAssembly module.
+
+use serde_json::Value;
+use wasmtime::{Engine, Instance, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use super::EntryPlugin;
+
+/**
+[EntryPlugin] implemented by a WebAssembly module without imports.
+
+The module must export its `memory`, an `alloc(len: i32) -> i32` function
+returning a buffer for the input and a `transform(ptr: i32, len: i32) -> i64`
+function. `transform` receives the JSON representation of the entry and
+returns the position of the JSON output as `ptr << 32 | len`. An output of
+`null` omits the entry.
+
+Each invocation runs in a fresh instance limited by fuel and memory size, so a
+misbehaving plugin can't stall or exhaust the server. Entries are omitted when
+an invocation fails, so a filtering plugin never exposes what it would hide.
+ */
+pub struct WasmPlugin {
+    /// Engine that compiled the module.
+    engine: Engine,
+    /// Compiled module.
+    module: Module,
+    /// Fuel that each invocation may consume.
+    fuel: u64,
+    /// Maximum size of the linear memory of each invocation in bytes.
+    max_memory_bytes: usize,
+}
+
+impl WasmPlugin {
+    /// Compile the WebAssembly module at `path`.
+    pub fn load(path: &str, fuel: u64, max_memory_bytes: usize) -> Result<Self, String> {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(|e| e.to_string())?;
+        let module = Module::from_file(&engine, path).map_err(|e| e.to_string())?;
+        if module.imports().next().is_some() {
+            return Err("Plugins must not import anything.".to_string());
+        }
+        Ok(Self {
+            engine,
+            module,
+            fuel,
+            max_memory_bytes,
+        })
+    }
+
+    /// Invoke `transform` of a new instance with the input and return its output.
+    fn invoke(&self, input: &[u8]) -> wasmtime::Result<Vec<u8>> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.max_memory_bytes)
+            .instances(1)
+            .build();
+        let mut store: Store<StoreLimits> = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(self.fuel)?;
+        let instance = Instance::new(&mut store, &self.module, &[])?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| wasmtime::Error::msg("Missing exported 'memory'."))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let transform = instance.get_typed_func::<(i32, i32), i64>(&mut store, "transform")?;
+        let len = i32::try_from(input.len())?;
+        let ptr = alloc.call(&mut store, len)?;
+        memory.write(&mut store, usize::try_from(ptr)?, input)?;
+        let packed = transform.call(&mut store, (ptr, len))?;
+        let output_ptr = (packed >> 32) as u32 as usize;
+        let output_len = packed as u32 as usize;
+        let mut output = vec![0; output_len];
+        memory.read(&store, output_ptr, &mut output)?;
+        Ok(output)
+    }
+}
+
+impl EntryPlugin for WasmPlugin {
+    fn transform(&self, entry: Value) -> Option<Value> {
+        let result = serde_json::to_vec(&entry)
+            .map_err(|e| e.to_string())
+            .and_then(|input| self.invoke(&input).map_err(|e| e.to_string()))
+            .and_then(|output| serde_json::from_slice::<Value>(&output).map_err(|e| e.to_string()));
+        match result {
+            Ok(Value::Null) => None,
+            Ok(transformed) => Some(transformed),
+            Err(e) => {
+                log::warn!("Omitting entry that the plugin failed to transform: {e}");
+                None
+            }
+        }
+    }
+}
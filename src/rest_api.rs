@@ -17,24 +17,279 @@
 
 //! REST API server and resources.
 
+mod admin_resources;
+mod admission_resources;
 mod api_resources;
+mod binary_snapshot;
+mod change_resources;
+mod config_map_publisher;
+mod dry_run_resources;
+mod error_resources;
+mod graph_resources;
 mod health_resources;
+#[cfg(feature = "http3")]
+mod http3;
+mod load_shedding;
+mod loader_resources;
+pub mod model;
+#[cfg(feature = "openapi")]
+mod openapi_resources;
+mod profiling_resources;
+#[cfg(feature = "proxy")]
+mod proxy_resources;
+mod resolve_resources;
+mod search_resources;
+mod tenant_scope;
+mod ui_resources;
 
-use actix_web::http::header::ContentType;
-use actix_web::{get, web, App, HttpResponse, HttpServer, Responder};
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::{middleware, web, App, HttpMessage, HttpResponse, HttpServer};
+use futures_util::future::{Either, FutureExt, LocalBoxFuture};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
 use std::sync::Arc;
-use utoipa::OpenApi;
 
 use crate::conf::AppConfig;
 use crate::ingress_monitor::IngressMonitor;
+use crate::plugin::EntryPlugin;
 
-/// Number of parallel requests the can be served for each assigned CPU core.
-const WORKERS_PER_CORE: usize = 256;
+pub use self::change_resources::CHANGE_CURSOR_HEADER;
+pub use self::tenant_scope::Tenants;
 
-/// Shared state between requests.
+use self::binary_snapshot::BinarySnapshotCache;
+use self::load_shedding::LoadShedder;
+
+/// Shared state between requests. See [app_data].
 #[derive(Clone)]
-struct AppState {
+pub struct AppState {
+    app_config: Arc<AppConfig>,
     ingress_monitor: Arc<IngressMonitor>,
+    /// Cached CBOR encoding of the catalogue.
+    binary_snapshot: Arc<BinarySnapshotCache>,
+    /// Client for reverse-proxied requests.
+    #[cfg(feature = "proxy")]
+    proxy_client: reqwest::Client,
+    /// Tracking of in-flight requests and event loop lag.
+    load_shedder: Arc<LoadShedder>,
+    /// OpenAPI document, generated on first request.
+    #[cfg(feature = "openapi")]
+    openapi_document: Arc<std::sync::OnceLock<openapi_resources::RenderedDocument>>,
+    /// Runtime running the monitoring, if known.
+    monitor_runtime: Option<tokio::runtime::Handle>,
+}
+
+/// Return the shared state needed by the resources registered by [configure].
+pub fn app_data(
+    app_config: Arc<AppConfig>,
+    ingress_monitor: Arc<IngressMonitor>,
+) -> web::Data<AppState> {
+    #[cfg(feature = "proxy")]
+    let proxy_client = reqwest::Client::builder()
+        .timeout(app_config.api.proxy_timeout())
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap_or_default();
+    let load_shedder = LoadShedder::new(app_config.api.max_in_flight(), app_config.api.max_lag());
+    web::Data::new(AppState {
+        app_config,
+        ingress_monitor,
+        binary_snapshot: Arc::new(BinarySnapshotCache::new()),
+        #[cfg(feature = "proxy")]
+        proxy_client,
+        load_shedder,
+        #[cfg(feature = "openapi")]
+        openapi_document: Arc::default(),
+        // Only known when called from the runtime running the monitoring
+        monitor_runtime: tokio::runtime::Handle::try_current().ok(),
+    })
+}
+
+impl AppState {
+    /**
+      Return the state of the running configuration of the [IngressMonitor],
+      which is replaced as a whole when the configuration is reloaded.
+    */
+    fn reloadable(&self) -> ReloadableState {
+        let running_config = self.ingress_monitor.running_config();
+        ReloadableState {
+            tenants: running_config.api.tenants(),
+            entry_plugin: running_config.plugin.entry_plugin(),
+        }
+    }
+}
+
+/**
+State loaded with the [reloadable](crate::conf::RELOADABLE_KEYS) configuration.
+
+Files are loaded when the configuration is validated, so a reloaded
+configuration with a broken file is rejected and the previous state is kept.
+ */
+struct ReloadableState {
+    /// API keys of tenants when API requests are authenticated.
+    tenants: Option<Arc<Tenants>>,
+    /// Plugin transforming entries before they are exposed.
+    entry_plugin: Option<Arc<dyn EntryPlugin>>,
+}
+
+/**
+Register all API and health resources.
+
+Applications embedding the REST API must also register the [app_data].
+ */
+pub fn configure(service_config: &mut web::ServiceConfig) {
+    let scope = web::scope("/api/v1")
+        .wrap_fn(|req, srv| {
+            let cache_control = req
+                .app_data::<web::Data<AppState>>()
+                .zip(req.path().strip_prefix("/api/v1"))
+                .and_then(|(app_state, endpoint)| app_state.app_config.api.cache_control(endpoint))
+                .and_then(|directives| HeaderValue::from_str(directives).ok());
+            let response = srv.call(req);
+            async move {
+                let mut response = response.await?;
+                if let Some(cache_control) = cache_control {
+                    if response.status().is_success()
+                        && !response.headers().contains_key(header::CACHE_CONTROL)
+                    {
+                        response
+                            .headers_mut()
+                            .insert(header::CACHE_CONTROL, cache_control);
+                    }
+                }
+                Ok(response)
+            }
+        })
+        .wrap_fn(authenticate_tenant)
+        .wrap_fn(|req, srv| {
+            // Outermost, so rejected requests are not even authenticated
+            let admitted = req
+                .app_data::<web::Data<AppState>>()
+                .map(|app_state| app_state.load_shedder.admit());
+            match admitted {
+                Some(None) => Either::Left(std::future::ready(Ok(req.into_response(
+                    HttpResponse::ServiceUnavailable()
+                        .insert_header((
+                            header::RETRY_AFTER,
+                            load_shedding::RETRY_AFTER_SECONDS.to_string(),
+                        ))
+                        .finish(),
+                )))),
+                admitted => Either::Right(srv.call(req).map(move |response| {
+                    // Count the request as in-flight until the response is ready
+                    drop(admitted);
+                    response.map(ServiceResponse::map_into_boxed_body)
+                })),
+            }
+        })
+        .service(api_resources::get_all)
+        .service(api_resources::get_hosts)
+        .service(api_resources::get_groups)
+        .service(change_resources::get_changes)
+        // After the history, since the identifier of an entry may contain slashes
+        .service(change_resources::get_entry_history)
+        .service(api_resources::get_entry)
+        .service(error_resources::get_errors)
+        .service(graph_resources::get_graph)
+        .service(graph_resources::get_compatibility)
+        .service(loader_resources::get_import_map)
+        .service(loader_resources::get_preload)
+        .service(search_resources::search)
+        .service(resolve_resources::resolve)
+        .service(dry_run_resources::dry_run)
+        .service(admin_resources::export_state)
+        .service(admin_resources::import_state)
+        .service(profiling_resources::profile)
+        .service(profiling_resources::heap)
+        .service(profiling_resources::tasks);
+    #[cfg(feature = "openapi")]
+    let scope = scope
+        .service(openapi_resources::openapi)
+        .service(openapi_resources::openapi_yaml);
+    #[cfg(feature = "openapi")]
+    service_config
+        .service(web::redirect("/openapi", "/api/v1/openapi.json"))
+        .service(web::redirect("/openapi.json", "/api/v1/openapi.json"))
+        .service(web::redirect("/openapi.yaml", "/api/v1/openapi.yaml"));
+    #[cfg(feature = "proxy")]
+    service_config.service(
+        web::scope("/proxy")
+            .wrap_fn(authenticate_tenant)
+            .service(proxy_resources::proxy),
+    );
+    service_config
+        .service(scope)
+        .service(
+            web::scope("/admission")
+                .wrap_fn(|req, srv| {
+                    // Only served on the TLS listener of the admission port
+                    if req.app_config().secure() {
+                        Either::Right(
+                            srv.call(req)
+                                .map(|response| response.map(ServiceResponse::map_into_boxed_body)),
+                        )
+                    } else {
+                        Either::Left(std::future::ready(Ok(
+                            req.into_response(HttpResponse::NotFound().finish())
+                        )))
+                    }
+                })
+                .service(admission_resources::validate)
+                .service(admission_resources::mutate),
+        )
+        .service(
+            web::scope("/ui")
+                .wrap_fn(authenticate_tenant)
+                .service(ui_resources::dashboard),
+        )
+        .service(health_resources::health)
+        .service(health_resources::health_live)
+        .service(health_resources::health_ready)
+        .service(health_resources::health_started)
+        .service(health_resources::health_tasks);
+}
+
+/**
+Authenticate the tenant of the request when tenants are configured.
+
+Requests without a known API key are rejected with `401 Unauthorized`, while
+the scope of the authenticated tenant is added to the request for the
+[TenantScope](self::tenant_scope::TenantScope) extractor.
+ */
+fn authenticate_tenant<S, B>(
+    req: ServiceRequest,
+    srv: &S,
+) -> LocalBoxFuture<'static, Result<ServiceResponse, actix_web::Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    // The API description is public even when requests are authenticated
+    let reloadable = req
+        .app_data::<web::Data<AppState>>()
+        .map(|app_state| app_state.reloadable());
+    let tenant_scope = reloadable
+        .as_ref()
+        .and_then(|reloadable| reloadable.tenants.as_ref())
+        .filter(|_| !matches!(req.path(), "/api/v1/openapi.json" | "/api/v1/openapi.yaml"))
+        .map(|tenants| tenants.authenticate(req.request()));
+    match tenant_scope {
+        Some(None) => std::future::ready(Ok(req.into_response(
+            HttpResponse::Unauthorized()
+                .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+                .finish(),
+        )))
+        .boxed_local(),
+        tenant_scope => {
+            if let Some(Some(tenant_scope)) = tenant_scope {
+                req.extensions_mut().insert(tenant_scope);
+            }
+            srv.call(req)
+                .map(|response| response.map(ServiceResponse::map_into_boxed_body))
+                .boxed_local()
+        }
+    }
 }
 
 /// Run HTTP server.
@@ -43,57 +298,131 @@ pub async fn run_http_server(
     ingress_monitor: Arc<IngressMonitor>,
 ) -> std::io::Result<()> {
     let app_config = Arc::clone(&app_config);
-    let workers = app_config.limits.available_parallelism();
-    let max_connections = WORKERS_PER_CORE * workers;
-    log::info!(
-        "API described by http://{}:{}/openapi.json allows {max_connections} concurrent.",
-        &app_config.api.bind_address(),
-        &app_config.api.bind_port(),
-    );
-    let app_state: AppState = AppState { ingress_monitor };
-    let app_data = web::Data::<AppState>::new(app_state);
+    let cpus = app_config.limits.cpus();
+    let workers = app_config.api.workers_for_cpus(cpus);
+    let max_connections = app_config.api.max_connections_for_cpus(cpus);
+    // Actix limits connections and blocking threads per worker
+    let max_connections_per_worker = max_connections.div_ceil(workers);
+    // Default is 2048
+    let backlog = app_config
+        .api
+        .backlog()
+        .unwrap_or(u32::try_from(max_connections / 2).unwrap_or(u32::MAX));
+    let max_payload_bytes = app_config.api.max_payload_bytes();
+    let bind_addresses = app_config.api.bind_addresses();
+    for (address, port) in &bind_addresses {
+        log::info!(
+            "API {} http://{}{} allows {max_connections} concurrent on {workers} workers.",
+            if cfg!(feature = "openapi") {
+                "described by"
+            } else {
+                "served on"
+            },
+            host_port(address, *port),
+            if cfg!(feature = "openapi") {
+                "/openapi.json"
+            } else {
+                "/api/v1"
+            },
+        );
+    }
+    let app_data = app_data(Arc::clone(&app_config), ingress_monitor);
+    config_map_publisher::start_publishing(&app_data);
+    load_shedding::start_lag_probe(&app_data);
+    #[cfg(feature = "http3")]
+    let alt_svc = app_config.api.http3_port().map(http3::alt_svc);
+    #[cfg(not(feature = "http3"))]
+    let alt_svc: Option<String> = None;
 
-    HttpServer::new(move || {
-        let scope = web::scope("/api/v1")
-            .service(openapi)
-            .service(api_resources::get_all);
+    let mut http_server = HttpServer::new(move || {
         App::new()
+            .wrap(middleware::Condition::new(
+                alt_svc.is_some(),
+                middleware::DefaultHeaders::new()
+                    .add((header::ALT_SVC, alt_svc.clone().unwrap_or_default())),
+            ))
             .app_data(app_data.clone())
-            .service(web::redirect("/openapi", "/api/v1/openapi.json"))
-            .service(web::redirect("/openapi.json", "/api/v1/openapi.json"))
-            .service(scope)
-            .service(health_resources::health)
-            .service(health_resources::health_live)
-            .service(health_resources::health_ready)
-            .service(health_resources::health_started)
+            .app_data(web::PayloadConfig::new(max_payload_bytes))
+            .app_data(web::JsonConfig::default().limit(max_payload_bytes))
+            .configure(configure)
     })
     .workers(workers)
-    .backlog(u32::try_from(max_connections / 2).unwrap()) // Default is 2048
-    .worker_max_blocking_threads(max_connections)
-    .max_connections(max_connections)
-    .bind_auto_h2c((app_config.api.bind_address(), app_config.api.bind_port()))?
-    .disable_signals()
-    .shutdown_timeout(5) // Default 30
-    .run()
-    .await
+    .backlog(backlog)
+    .keep_alive(app_config.api.keep_alive())
+    .client_request_timeout(app_config.api.client_request_timeout())
+    .worker_max_blocking_threads(max_connections_per_worker)
+    .max_connections(max_connections_per_worker);
+    for (address, port) in &bind_addresses {
+        http_server = http_server.bind_auto_h2c((address.as_str(), *port))?;
+    }
+    #[cfg(feature = "http3")]
+    if let Some(http3_port) = app_config.api.http3_port() {
+        // Before binding the admission listener, so only API listeners are paired
+        http3::start(&app_config, http3_port, &http_server.addrs())?;
+    }
+    if app_config.admission.enabled() {
+        let tls_config = admission_tls_config(&app_config)?;
+        for (address, _port) in &bind_addresses {
+            log::info!(
+                "Admission webhooks are served on https://{}/admission/.",
+                host_port(address, app_config.admission.port())
+            );
+            http_server = http_server.bind_rustls_0_23(
+                (address.as_str(), app_config.admission.port()),
+                tls_config.clone(),
+            )?;
+        }
+    }
+    http_server
+        .disable_signals()
+        .shutdown_timeout(5) // Default 30
+        .run()
+        .await
 }
 
-/// Serve Open API documentation.
-#[get("/openapi.json")]
-async fn openapi() -> impl Responder {
-    #[derive(OpenApi)]
-    #[openapi(
-        // Use Cargo.toml as source for the "info" section
-        paths(
-            api_resources::get_all,
-            health_resources::health,
-            health_resources::health_live,
-            health_resources::health_ready,
-            health_resources::health_started,
-        )
-    )]
-    struct ApiDoc;
-    HttpResponse::Ok()
-        .content_type(ContentType::json())
-        .body(ApiDoc::openapi().to_pretty_json().unwrap())
+/// Return the `address` and `port` as authority, with brackets around IPv6 addresses.
+fn host_port(address: &str, port: u16) -> String {
+    if address.contains(':') {
+        format!("[{address}]:{port}")
+    } else {
+        format!("{address}:{port}")
+    }
+}
+
+/**
+Return the TLS configuration of the admission webhook listener with the
+configured PEM encoded certificate chain and private key.
+ */
+fn admission_tls_config(app_config: &AppConfig) -> std::io::Result<rustls::ServerConfig> {
+    tls_config(
+        app_config.admission.tls_cert_path(),
+        app_config.admission.tls_key_path(),
+    )
+}
+
+/// Return a TLS configuration with the PEM encoded certificate chain and private key.
+fn tls_config(cert_path: &str, key_path: &str) -> std::io::Result<rustls::ServerConfig> {
+    let invalid_data = |e: String| std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+    let certs = pem::parse_many(std::fs::read(cert_path)?)
+        .map_err(|e| invalid_data(format!("Invalid certificate in '{cert_path}': {e}")))?
+        .into_iter()
+        .filter(|pem| pem.tag() == "CERTIFICATE")
+        .map(|pem| CertificateDer::from(pem.into_contents()))
+        .collect::<Vec<_>>();
+    let key = pem::parse_many(std::fs::read(key_path)?)
+        .map_err(|e| invalid_data(format!("Invalid private key in '{key_path}': {e}")))?
+        .into_iter()
+        .find_map(|pem| match pem.tag() {
+            "PRIVATE KEY" => Some(PrivateKeyDer::Pkcs8(pem.into_contents().into())),
+            "RSA PRIVATE KEY" => Some(PrivateKeyDer::Pkcs1(pem.into_contents().into())),
+            "EC PRIVATE KEY" => Some(PrivateKeyDer::Sec1(pem.into_contents().into())),
+            _ => None,
+        })
+        .ok_or_else(|| invalid_data(format!("No private key found in '{key_path}'.")))?;
+    rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| invalid_data(e.to_string()))?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| invalid_data(e.to_string()))
 }
@@ -0,0 +1,95 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Administrative API resources.
+
+use actix_web::web::{Data, Json};
+use actix_web::{get, post, HttpResponse, Responder};
+
+#[cfg(feature = "openapi")]
+use crate::ingress_monitor::ImportReport;
+use crate::ingress_monitor::MonitorState;
+
+use super::tenant_scope::TenantScope;
+use super::AppState;
+
+/**
+Return a response if administrative resources are not allowed for the request.
+
+Administrative resources must be enabled and, since they expose and replace
+the whole catalogue, API requests must be authenticated by an API key that is
+allowed to see everything.
+ */
+fn forbidden(app_state: &AppState, tenant_scope: &TenantScope) -> Option<HttpResponse> {
+    if !app_state.app_config.api.admin_enabled()
+        || app_state.reloadable().tenants.is_none()
+        || !tenant_scope.is_unrestricted()
+    {
+        return Some(HttpResponse::Forbidden().finish());
+    }
+    None
+}
+
+/**
+Return the full monitoring state as JSON.
+
+The exported state can be restored with [import_state]. Only available when
+administrative resources are enabled and the request is authenticated by an
+unrestricted API key.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Ok", body = MonitorState, content_type = "application/json",),
+        (status = 403, description = "Administrative resources are disabled or the request is not authenticated by an unrestricted API key."),
+    ),
+))]
+#[get("/admin/export")]
+pub async fn export_state(app_state: Data<AppState>, tenant_scope: TenantScope) -> impl Responder {
+    if let Some(response) = forbidden(&app_state, &tenant_scope) {
+        return response;
+    }
+    HttpResponse::Ok().json(app_state.ingress_monitor.export_state().await)
+}
+
+/**
+Restore a monitoring state previously returned by [export_state].
+
+Only available when administrative resources are enabled and the request is
+authenticated by an unrestricted API key.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "admin",
+    request_body(content = MonitorState, content_type = "application/json"),
+    responses(
+        (status = 200, description = "Ok", body = ImportReport, content_type = "application/json",),
+        (status = 403, description = "Administrative resources are disabled or the request is not authenticated by an unrestricted API key."),
+    ),
+))]
+#[post("/admin/import")]
+pub async fn import_state(
+    app_state: Data<AppState>,
+    state: Json<MonitorState>,
+    tenant_scope: TenantScope,
+) -> impl Responder {
+    if let Some(response) = forbidden(&app_state, &tenant_scope) {
+        return response;
+    }
+    HttpResponse::Ok().json(app_state.ingress_monitor.import_state(&state).await)
+}
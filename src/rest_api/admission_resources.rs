@@ -0,0 +1,159 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Admission webhooks for labeled `Ingress`es.
+
+use actix_web::web::{Data, Json};
+use actix_web::{post, HttpResponse, Responder};
+use k8s_openapi::api::networking::v1::Ingress;
+use kube::core::admission::{AdmissionRequest, AdmissionResponse, AdmissionReview};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+use super::AppState;
+
+/**
+Validate a labeled `Ingress` in an `AdmissionReview` from the Kubernetes API
+server.
+
+`Ingress`es with annotations that violate the configured schema, without the
+required annotation keys or declaring a hostname + path that is already
+declared by another resource are rejected before they pollute the catalogue.
+
+Only available on the TLS listener of the admission port when admission
+webhooks are enabled.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    tag = "admission",
+    context_path = "/admission",
+    request_body(content = Object, description = "`AdmissionReview` of an `Ingress`.", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Ok", body = Object, content_type = "application/json",),
+        (status = 400, description = "Invalid `AdmissionReview`.", content_type = "text/plain",),
+        (status = 403, description = "Admission webhooks are disabled."),
+        (status = 404, description = "Not requested on the TLS listener of the admission port."),
+    ),
+))]
+#[post("/validate")]
+pub async fn validate(
+    app_state: Data<AppState>,
+    review: Json<AdmissionReview<Ingress>>,
+) -> impl Responder {
+    if !app_state.app_config.admission.enabled() {
+        return HttpResponse::Forbidden().finish();
+    }
+    let request: AdmissionRequest<Ingress> = match review.into_inner().try_into() {
+        Ok(request) => request,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(format!("Invalid AdmissionReview: {e}"));
+        }
+    };
+    let mut response = AdmissionResponse::from(&request);
+    if let Some(ingress) = &request.object {
+        let problems = app_state.ingress_monitor.admission_problems(ingress);
+        if !problems.is_empty() {
+            log::info!(
+                "Rejecting '{}' in 'ns/{}': {}",
+                request.name,
+                request.namespace.as_deref().unwrap_or_default(),
+                problems.join(" ")
+            );
+            response = response.deny(problems.join(" "));
+        }
+    }
+    HttpResponse::Ok().json(response.into_review())
+}
+
+/**
+Inject default annotations into a labeled `Ingress` in an `AdmissionReview`
+from the Kubernetes API server.
+
+Configured default annotations that the `Ingress` doesn't already declare are
+added with a JSON patch, so tenant manifests can stay minimal.
+
+Only available on the TLS listener of the admission port when admission
+webhooks are enabled.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    tag = "admission",
+    context_path = "/admission",
+    request_body(content = Object, description = "`AdmissionReview` of an `Ingress`.", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Ok", body = Object, content_type = "application/json",),
+        (status = 400, description = "Invalid `AdmissionReview`.", content_type = "text/plain",),
+        (status = 403, description = "Admission webhooks are disabled."),
+        (status = 404, description = "Not requested on the TLS listener of the admission port."),
+    ),
+))]
+#[post("/mutate")]
+pub async fn mutate(
+    app_state: Data<AppState>,
+    review: Json<AdmissionReview<Ingress>>,
+) -> impl Responder {
+    if !app_state.app_config.admission.enabled() {
+        return HttpResponse::Forbidden().finish();
+    }
+    let request: AdmissionRequest<Ingress> = match review.into_inner().try_into() {
+        Ok(request) => request,
+        Err(e) => {
+            return HttpResponse::BadRequest().body(format!("Invalid AdmissionReview: {e}"));
+        }
+    };
+    let mut response = AdmissionResponse::from(&request);
+    if let Some(ingress) = &request.object {
+        let defaults = app_state.ingress_monitor.admission_defaults(ingress);
+        if !defaults.is_empty() {
+            let patch = annotations_patch(ingress, &defaults);
+            response = match serde_json::from_value::<json_patch::Patch>(patch)
+                .map_err(|e| e.to_string())
+                .and_then(|patch| response.with_patch(patch).map_err(|e| e.to_string()))
+            {
+                Ok(response) => response,
+                Err(e) => {
+                    log::warn!("Failed to create patch with default annotations: {e}");
+                    AdmissionResponse::from(&request)
+                }
+            };
+        }
+    }
+    HttpResponse::Ok().json(response.into_review())
+}
+
+/// Return a JSON patch that adds the annotations to the `Ingress`.
+fn annotations_patch(ingress: &Ingress, annotations: &BTreeMap<String, String>) -> Value {
+    if ingress.metadata.annotations.is_none() {
+        return json!([{
+            "op": "add",
+            "path": "/metadata/annotations",
+            "value": annotations,
+        }]);
+    }
+    Value::Array(
+        annotations
+            .iter()
+            .map(|(key, value)| {
+                json!({
+                    "op": "add",
+                    // RFC 6901 escaping of the annotation key
+                    "path": "/metadata/annotations/".to_owned()
+                        + &key.replace('~', "~0").replace('/', "~1"),
+                    "value": value,
+                })
+            })
+            .collect(),
+    )
+}
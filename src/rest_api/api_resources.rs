@@ -18,60 +18,471 @@
 //! API resources
 
 use actix_web::http::StatusCode;
-use actix_web::web::Data;
-use actix_web::{get, Error, HttpResponse};
+use actix_web::web::{Data, Path};
+use actix_web::{get, Error, HttpRequest, HttpResponse};
 use futures::stream;
 use futures_util::StreamExt;
-use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
-use utoipa::ToSchema;
 
 use crate::ingress_monitor::IngressHostPath;
+use crate::ingress_monitor::IngressMonitor;
+use crate::ingress_monitor::LifecycleState;
+use crate::ingress_monitor::GROUP_ANNOTATION;
+use crate::ingress_monitor::PRIORITY_ANNOTATION;
+use crate::ingress_monitor::TEAM_ANNOTATION;
+use crate::plugin::EntryPlugin;
 
+use super::binary_snapshot::BinarySnapshotCache;
+use super::binary_snapshot::CONTENT_TYPE_CBOR;
+use super::change_resources::CHANGE_CURSOR_HEADER;
+use super::model::GroupResponse;
+use super::model::HostResponse;
+use super::model::IngressHostPathResponse;
+use super::model::VariantResponse;
+use super::tenant_scope::TenantScope;
 use super::AppState;
 
-/// HTTP response body object for the [get_all] resource.
-#[derive(ToSchema, Serialize)]
-struct IngressHostPathResponse {
-    /// Combined hostname and path servied via a correctly labeled `Ingress`.
-    host_path: String,
-    /// Last update timestamp in milliseconds sinch Unix Epoch.
-    updated: u64,
-    /// Prefixed annotations of the serving `Ingress` (without the prefix part)
-    annotations: HashMap<String, String>,
-}
-
 impl IngressHostPathResponse {
     /// Convert to a JSON serializable response object
-    async fn from_ingress_host_path(source: Arc<IngressHostPath>) -> Self {
+    async fn from_ingress_host_path(
+        ingress_monitor: &Arc<IngressMonitor>,
+        source: Arc<IngressHostPath>,
+    ) -> Self {
+        let annotations =
+            ingress_monitor.exposed_annotations(ingress_monitor.entry_annotations(&source).await);
+        let validation_errors = ingress_monitor.annotation_validation_errors(&annotations);
+        let (replicas, ready_replicas) = source.replica_counts().await;
+        let (host, path) = source.host_and_path();
         Self {
-            host_path: source.host_path(),
+            cluster: source.cluster_id().to_owned(),
+            origin: None,
+            host_path: source.host_path().to_owned(),
+            host: host.to_owned(),
+            path: path.to_owned(),
+            external_path: source.external_path(),
+            backend_path: source.backend_path(),
+            scheme: source.scheme().to_owned(),
+            url: source.url(),
+            path_type: source.path_type(),
+            wildcard: source.is_wildcard(),
             updated: source.updated_millis().await,
-            annotations: source.annotations_map(),
+            annotations,
+            labels: source.labels_map(),
+            valid: validation_errors.is_empty(),
+            validation_errors,
+            available: source.available(),
+            reachable: source.reachable(),
+            last_probe: source.probe_result(),
+            health: source.health_result(),
+            manifest: ingress_monitor.manifest(&source).await,
+            state: source.lifecycle_state(),
+            last_transition: source.lifecycle_last_transition(),
+            external_name: source.external_name().await,
+            replicas,
+            ready_replicas,
+            deployments: source.deployment_rollouts().await,
+            source_object: source.source_object(),
+            deleted: source.lifecycle_state() == LifecycleState::Removed,
+            variants: vec![VariantResponse {
+                name: source.variant().to_owned(),
+                weight: source.weight(),
+                available: source.available(),
+                state: source.lifecycle_state(),
+            }],
         }
     }
 }
 
-/// Return all currently known labeled micro front end entrypoints. See also [IngressHostPathResponse].
-#[utoipa::path(
+/**
+Return all currently known labeled micro front end entrypoints. See also [IngressHostPathResponse].
+
+Entries are ordered by their `priority` annotation, highest first, so shells
+can mount them in a deterministic order.
+
+Clients sending `Accept: application/cbor` will get a CBOR encoded response
+that is cached until the catalogue changes.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "catalogue",
     responses(
-        (status = 200, description = "Up", body = inline(IngressHostPathResponse), content_type = "application/json",),
+        (status = 200, description = "Up", body = Vec<IngressHostPathResponse>, content_type = ["application/json", "application/cbor"],),
     ),
-)]
+))]
 #[get("/all")]
 pub async fn get_all(
     app_state: Data<AppState>,
-    //req: HttpRequest,
+    req: HttpRequest,
+    tenant_scope: TenantScope,
 ) -> Result<HttpResponse, Error> {
-    let results: Vec<_> = stream::iter(app_state.ingress_monitor.get_all())
-        .then(IngressHostPathResponse::from_ingress_host_path)
-        .collect()
-        .await;
+    if BinarySnapshotCache::is_requested(&req) {
+        return get_all_cbor(&app_state, &tenant_scope).await;
+    }
+    // Read the cursor first, so no change after the snapshot is skipped
+    let cursor = app_state.ingress_monitor.change_cursor();
+    let results = all_responses(&app_state, &tenant_scope).await;
     log::trace!(
         "GET /all -> body: {}",
         serde_json::to_string_pretty(&results).unwrap()
     );
-    let response = HttpResponse::build(StatusCode::OK).json(results);
+    let response = HttpResponse::build(StatusCode::OK)
+        .insert_header((CHANGE_CURSOR_HEADER, cursor.to_string()))
+        .json(results);
     Ok(response)
 }
+
+/**
+Return a single entry like it is returned by `/api/v1/all`, including all of
+its variants. See also [IngressHostPathResponse].
+
+The `id` is the cluster and the combined hostname and path, like
+`local/example.com/checkout`. A recently removed entry is returned as long as
+it is retained as a tombstone.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "catalogue",
+    params(("id" = String, Path, description = "Cluster, hostname and path of the entry, like `local/example.com/checkout`.")),
+    responses(
+        (status = 200, description = "Ok", body = IngressHostPathResponse, content_type = "application/json",),
+        (status = 404, description = "No entry has the identifier."),
+    ),
+))]
+#[get("/entry/{id:.+}")]
+pub async fn get_entry(
+    app_state: Data<AppState>,
+    id: Path<String>,
+    tenant_scope: TenantScope,
+) -> Result<HttpResponse, Error> {
+    let Some((cluster, host_path)) = id.split_once('/') else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    // Live entries come first, so a tombstone is only returned if nothing replaced it
+    let response = all_responses(&app_state, &tenant_scope)
+        .await
+        .into_iter()
+        .find(|response| response.cluster == cluster && response.host_path == host_path);
+    Ok(match response {
+        Some(response) => HttpResponse::build(StatusCode::OK).json(response),
+        None => HttpResponse::NotFound().finish(),
+    })
+}
+
+/**
+Return all currently known entries grouped by hostname. See also [HostResponse].
+
+This matches how shell applications usually mount micro front ends, with one
+shell per host.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "catalogue",
+    responses(
+        (status = 200, description = "Ok", body = Vec<HostResponse>, content_type = "application/json",),
+    ),
+))]
+#[get("/hosts")]
+pub async fn get_hosts(
+    app_state: Data<AppState>,
+    tenant_scope: TenantScope,
+) -> Result<HttpResponse, Error> {
+    let mut hosts: BTreeMap<String, HostResponse> = BTreeMap::new();
+    for response in all_responses(&app_state, &tenant_scope).await {
+        hosts
+            .entry(response.host.to_owned())
+            .or_insert_with(|| HostResponse {
+                host: response.host.to_owned(),
+                annotations: HashMap::new(),
+                paths: vec![],
+            })
+            .paths
+            .push(response);
+    }
+    let results = hosts
+        .into_values()
+        .map(|mut host_response| {
+            // Apply longest paths first, so paths closer to the root take precedence
+            host_response
+                .paths
+                .sort_by(|a, b| b.path.len().cmp(&a.path.len()));
+            host_response.paths.iter().for_each(|path_response| {
+                host_response.annotations.extend(
+                    path_response
+                        .annotations
+                        .iter()
+                        .map(|(key, value)| (key.to_owned(), value.to_owned())),
+                );
+            });
+            host_response.paths.sort_by(|a, b| {
+                response_priority(b)
+                    .cmp(&response_priority(a))
+                    .then_with(|| a.path.cmp(&b.path))
+            });
+            host_response
+        })
+        .collect::<Vec<_>>();
+    Ok(HttpResponse::build(StatusCode::OK).json(results))
+}
+
+/**
+Return all current entries bucketed by the `group` annotation (or `team` when no
+group is declared) with aggregate counts. See also [GroupResponse].
+
+Entries without a group are returned last in a bucket without a `group`.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "catalogue",
+    responses(
+        (status = 200, description = "Ok", body = Vec<GroupResponse>, content_type = "application/json",),
+    ),
+))]
+#[get("/groups")]
+pub async fn get_groups(
+    app_state: Data<AppState>,
+    tenant_scope: TenantScope,
+) -> Result<HttpResponse, Error> {
+    let mut groups: BTreeMap<Option<String>, Vec<IngressHostPathResponse>> = BTreeMap::new();
+    for response in all_responses(&app_state, &tenant_scope).await {
+        if response.deleted {
+            continue;
+        }
+        let group = response_group(&response);
+        groups.entry(group).or_default().push(response);
+    }
+    let ungrouped = groups.remove(&None);
+    let results = groups
+        .into_iter()
+        .chain(ungrouped.map(|entries| (None, entries)))
+        .map(|(group, mut entries)| {
+            entries.sort_by(|a, b| {
+                response_priority(b)
+                    .cmp(&response_priority(a))
+                    .then_with(|| a.host_path.cmp(&b.host_path))
+            });
+            GroupResponse {
+                group,
+                count: entries.len(),
+                available_count: entries
+                    .iter()
+                    .filter(|entry| entry.available == Some(true))
+                    .count(),
+                entries,
+            }
+        })
+        .collect::<Vec<_>>();
+    Ok(HttpResponse::build(StatusCode::OK).json(results))
+}
+
+/// Return the trimmed value of the `group` (or `team`) annotation of the response, if any.
+fn response_group(response: &IngressHostPathResponse) -> Option<String> {
+    [GROUP_ANNOTATION, TEAM_ANNOTATION]
+        .iter()
+        .find_map(|key| response.annotations.get(*key))
+        .map(|value| match value {
+            serde_json::Value::String(value) => value.trim().to_owned(),
+            value => value.to_string(),
+        })
+        .filter(|group| !group.is_empty())
+}
+
+/// Return the mounting priority declared by the `priority` annotation of the response or `0`.
+fn response_priority(response: &IngressHostPathResponse) -> i64 {
+    match response.annotations.get(PRIORITY_ANNOTATION) {
+        Some(serde_json::Value::String(value)) => IngressHostPath::parse_priority(value),
+        Some(value) => value.as_i64().unwrap_or_default(),
+        None => 0,
+    }
+}
+
+/**
+Return all visible entries as a CBOR encoded response, using the cached
+snapshot when still valid and everything is visible.
+ */
+async fn get_all_cbor(
+    app_state: &Data<AppState>,
+    tenant_scope: &TenantScope,
+) -> Result<HttpResponse, Error> {
+    // Read the cursor first, so no change after the snapshot is skipped
+    let cursor = app_state.ingress_monitor.change_cursor();
+    if !tenant_scope.is_unrestricted() {
+        let results = all_responses(app_state, tenant_scope).await;
+        let mut body = vec![];
+        ciborium::into_writer(&results, &mut body)
+            .map_err(actix_web::error::ErrorInternalServerError)?;
+        return Ok(HttpResponse::build(StatusCode::OK)
+            .insert_header((CHANGE_CURSOR_HEADER, cursor.to_string()))
+            .content_type(CONTENT_TYPE_CBOR)
+            .body(body));
+    }
+    let revision = app_state.ingress_monitor.catalogue_revision();
+    let body = match app_state.binary_snapshot.get(revision) {
+        Some(body) => body,
+        None => {
+            let results = all_responses(app_state, tenant_scope).await;
+            app_state
+                .binary_snapshot
+                .put(revision, &results)
+                .map_err(actix_web::error::ErrorInternalServerError)?
+        }
+    };
+    Ok(HttpResponse::build(StatusCode::OK)
+        .insert_header((CHANGE_CURSOR_HEADER, cursor.to_string()))
+        .content_type(CONTENT_TYPE_CBOR)
+        .body(body))
+}
+
+/**
+Return all currently known entries that are not hidden, followed by recently
+deleted entries, as response objects.
+
+Variants of the same hostname and path are grouped into a single response
+object described by the first variant, which is the primary when present.
+
+Entries with annotations that fail schema validation are omitted when
+configured to be rejected, as are entries outside of the tenant's scope.
+Local entries are transformed by the configured plugin, if any.
+ */
+pub(super) async fn all_responses(
+    app_state: &Data<AppState>,
+    tenant_scope: &TenantScope,
+) -> Vec<IngressHostPathResponse> {
+    let ingress_monitor = &app_state.ingress_monitor;
+    let mut visible = vec![];
+    for ingress_host_path in ingress_monitor
+        .get_all()
+        .into_iter()
+        .filter(|ingress_host_path| !ingress_host_path.is_hidden())
+        .chain(ingress_monitor.get_tombstones())
+    {
+        if tenant_scope
+            .allows_entry(ingress_monitor, &ingress_host_path)
+            .await
+        {
+            visible.push(ingress_host_path);
+        }
+    }
+    let reject_invalid = ingress_monitor.reject_invalid_annotations();
+    let responses: Vec<IngressHostPathResponse> = stream::iter(visible)
+        .then(|source| IngressHostPathResponse::from_ingress_host_path(ingress_monitor, source))
+        .filter(|response| std::future::ready(response.valid || !reject_invalid))
+        .collect()
+        .await;
+    let responses = match &app_state.reloadable().entry_plugin {
+        Some(entry_plugin) => responses
+            .into_iter()
+            .filter_map(|response| plugin_transform(entry_plugin, response))
+            .collect(),
+        None => responses,
+    };
+    let mut grouped: Vec<IngressHostPathResponse> = Vec::with_capacity(responses.len());
+    let mut group_index_by_key = HashMap::new();
+    for response in responses {
+        let group_key = response.cluster.to_owned() + "/" + &response.host_path;
+        match group_index_by_key.get(&group_key) {
+            Some(index) => {
+                let group: &mut IngressHostPathResponse = &mut grouped[*index];
+                group.variants.extend(response.variants);
+            }
+            None => {
+                group_index_by_key.insert(group_key, grouped.len());
+                grouped.push(response);
+            }
+        }
+    }
+    grouped.extend(
+        peer_responses(ingress_monitor)
+            .into_iter()
+            .filter(|response| tenant_scope.allows_group(response_group(response).as_deref())),
+    );
+    // Mounting order matters for shells, so live entries with higher priority come first
+    let (mut live, deleted): (Vec<_>, Vec<_>) =
+        grouped.into_iter().partition(|response| !response.deleted);
+    live.sort_by_key(|response| std::cmp::Reverse(response_priority(response)));
+    live.extend(deleted);
+    live
+}
+
+/**
+Return the local entries within the tenant's scope that the configured plugin,
+if any, doesn't omit.
+
+This is the selection behind the import map, preload links, dependency graph
+and compatibility report, so they expose the same entries as `/api/v1/all`.
+ */
+pub(super) async fn selected_entries(
+    app_state: &Data<AppState>,
+    tenant_scope: &TenantScope,
+) -> Vec<Arc<IngressHostPath>> {
+    let mut selected = vec![];
+    for entry in tenant_scope.entries(&app_state.ingress_monitor).await {
+        if plugin_allows(app_state, &entry).await {
+            selected.push(entry);
+        }
+    }
+    selected
+}
+
+/// Return `true` unless the configured plugin omits the entry.
+pub(super) async fn plugin_allows(
+    app_state: &Data<AppState>,
+    entry: &Arc<IngressHostPath>,
+) -> bool {
+    let Some(entry_plugin) = app_state.reloadable().entry_plugin.clone() else {
+        return true;
+    };
+    let response = IngressHostPathResponse::from_ingress_host_path(
+        &app_state.ingress_monitor,
+        Arc::clone(entry),
+    )
+    .await;
+    plugin_transform(&entry_plugin, response).is_some()
+}
+
+/**
+Return the response transformed by the plugin or `None` if the plugin omits it.
+
+The response is exposed unchanged if the plugin returns an invalid response.
+ */
+fn plugin_transform(
+    entry_plugin: &Arc<dyn EntryPlugin>,
+    response: IngressHostPathResponse,
+) -> Option<IngressHostPathResponse> {
+    let Ok(entry) = serde_json::to_value(&response) else {
+        return Some(response);
+    };
+    let transformed = entry_plugin.transform(entry)?;
+    serde_json::from_value(transformed)
+        .map_err(|e| log::warn!("Ignoring invalid entry from plugin: {e}"))
+        .ok()
+        .or(Some(response))
+}
+
+/**
+Return the entries fetched from upstream `microfefind` instances tagged with
+the origin of each instance.
+
+Entries that the upstream instance merged from its own peers keep their origin
+prefixed by the upstream's origin, like `eu/edge`.
+ */
+fn peer_responses(ingress_monitor: &Arc<IngressMonitor>) -> Vec<IngressHostPathResponse> {
+    ingress_monitor
+        .peer_entries()
+        .into_iter()
+        .flat_map(|(origin, entries)| {
+            entries.into_iter().filter_map(move |entry| {
+                serde_json::from_value::<IngressHostPathResponse>(entry)
+                    .map_err(|e| log::debug!("Ignoring invalid entry from peer '{origin}': {e}"))
+                    .ok()
+                    .map(|mut response| {
+                        response.origin = Some(match response.origin {
+                            Some(upstream_origin) => origin.to_owned() + "/" + &upstream_origin,
+                            None => origin.to_owned(),
+                        });
+                        response
+                    })
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,179 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Pre-encoded binary (CBOR) snapshots of API responses.
+
+use actix_web::http::header;
+use actix_web::web::Bytes;
+use actix_web::HttpRequest;
+use serde::Serialize;
+use std::sync::RwLock;
+
+/// Media type of Concise Binary Object Representation (CBOR) responses.
+pub const CONTENT_TYPE_CBOR: &str = "application/cbor";
+
+/**
+Cache of the last CBOR encoded response body.
+
+The cached body is tagged with the revision of the catalogue it was encoded
+from, so it can be served as is until the catalogue changes.
+ */
+pub struct BinarySnapshotCache {
+    /// Revision of the encoded catalogue and the encoded bytes.
+    cached: RwLock<Option<(u64, Bytes)>>,
+}
+
+impl Default for BinarySnapshotCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BinarySnapshotCache {
+    /// Return a new instance.
+    pub fn new() -> Self {
+        Self {
+            cached: RwLock::new(None),
+        }
+    }
+
+    /**
+      Return true if the client prefers a CBOR encoded response.
+
+      CBOR must be explicitly accepted with a higher quality than JSON, so
+      browsers accepting any media type keep getting JSON.
+    */
+    pub fn is_requested(req: &HttpRequest) -> bool {
+        req.headers()
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(Self::prefers_cbor)
+    }
+
+    /// Return true if the `Accept` header value prefers CBOR over JSON.
+    fn prefers_cbor(accept: &str) -> bool {
+        let explicit_cbor = accept
+            .split(',')
+            .filter_map(Self::parse_media_range)
+            .any(|(media_range, quality)| media_range == CONTENT_TYPE_CBOR && quality > 0.0);
+        explicit_cbor
+            && Self::quality(accept, CONTENT_TYPE_CBOR) > Self::quality(accept, "application/json")
+    }
+
+    /**
+      Return the quality of the `media_type` in the `Accept` header value.
+
+      The most specific matching media range wins, so an exact media type
+      takes precedence over a wildcard subtype, which takes precedence over
+      accepting any media type.
+    */
+    fn quality(accept: &str, media_type: &str) -> f32 {
+        let media_type_prefix = media_type.split('/').next().unwrap_or_default().to_owned() + "/*";
+        accept
+            .split(',')
+            .filter_map(Self::parse_media_range)
+            .filter_map(|(media_range, quality)| {
+                let specificity = if media_range == media_type {
+                    2
+                } else if media_range == media_type_prefix {
+                    1
+                } else if media_range == "*/*" {
+                    0
+                } else {
+                    return None;
+                };
+                Some((specificity, quality))
+            })
+            .max_by_key(|(specificity, _)| *specificity)
+            .map(|(_, quality)| quality)
+            .unwrap_or_default()
+    }
+
+    /// Return the lower case media range and its quality (`q`), which defaults to `1`.
+    fn parse_media_range(value: &str) -> Option<(String, f32)> {
+        let mut parts = value.split(';');
+        let media_range = parts.next()?.trim().to_ascii_lowercase();
+        if media_range.is_empty() {
+            return None;
+        }
+        let quality = parts
+            .filter_map(|parameter| parameter.split_once('='))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+            .map_or(Some(1.0), |(_, value)| value.trim().parse::<f32>().ok())?;
+        Some((media_range, quality.clamp(0.0, 1.0)))
+    }
+
+    /// Return the cached encoded bytes if they match the `revision`.
+    pub fn get(&self, revision: u64) -> Option<Bytes> {
+        self.cached
+            .read()
+            .unwrap()
+            .as_ref()
+            .filter(|(cached_revision, _)| *cached_revision == revision)
+            .map(|(_, bytes)| bytes.clone())
+    }
+
+    /// Encode `value` as CBOR and cache the result under the `revision`.
+    pub fn put(
+        &self,
+        revision: u64,
+        value: &impl Serialize,
+    ) -> Result<Bytes, ciborium::ser::Error<std::io::Error>> {
+        let mut buffer = Vec::new();
+        ciborium::into_writer(value, &mut buffer)?;
+        let bytes = Bytes::from(buffer);
+        self.cached
+            .write()
+            .unwrap()
+            .replace((revision, bytes.clone()));
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BinarySnapshotCache;
+
+    #[test]
+    fn cbor_is_negotiated_by_quality() {
+        for (accept, expected) in [
+            ("application/cbor", true),
+            ("application/CBOR", true),
+            ("application/cbor, application/json;q=0.9", true),
+            ("application/json;q=0.5, application/cbor", true),
+            ("application/cbor;q=0.8, */*;q=0.1", true),
+            ("application/cbor;q=0", false),
+            ("application/cbor;q=0.0, application/json", false),
+            ("application/cbor, application/json", false),
+            ("application/json, application/cbor;q=0.9", false),
+            ("*/*", false),
+            (
+                "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
+                false,
+            ),
+            ("application/*", false),
+            ("application/json", false),
+            ("", false),
+        ] {
+            assert_eq!(
+                BinarySnapshotCache::prefers_cbor(accept),
+                expected,
+                "Accept: {accept}"
+            );
+        }
+    }
+}
@@ -0,0 +1,121 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Resources for following changes of the catalogue.
+
+use actix_web::web::{Data, Path, Query};
+use actix_web::{get, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+
+#[cfg(feature = "openapi")]
+use crate::ingress_monitor::EntryChange;
+#[cfg(feature = "openapi")]
+use crate::notifier::ChangeFeedPage;
+
+use super::api_resources::plugin_allows;
+use super::binary_snapshot::BinarySnapshotCache;
+use super::binary_snapshot::CONTENT_TYPE_CBOR;
+use super::tenant_scope::TenantScope;
+use super::AppState;
+
+/// Name of the header with the sequence number of the latest change.
+pub const CHANGE_CURSOR_HEADER: &str = "X-Change-Cursor";
+
+/// Query parameters of the [get_changes] resource.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+struct ChangesQuery {
+    /// Sequence number of the last change seen by the client. Defaults to `0`.
+    cursor: Option<u64>,
+}
+
+/**
+Return retained changes with a sequence number greater than the `cursor`.
+
+Clients start from the `X-Change-Cursor` header of `/api/v1/all` and pass the
+returned `cursor` in the next request. When `reset` is `true`, changes were
+missed (or the server restarted) and the client must reload the full catalogue.
+
+Clients sending `Accept: application/cbor` will get a CBOR encoded response.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "catalogue",
+    params(ChangesQuery),
+    responses(
+        (status = 200, description = "Ok", body = ChangeFeedPage, content_type = ["application/json", "application/cbor"],),
+    ),
+))]
+#[get("/changes")]
+pub async fn get_changes(
+    app_state: Data<AppState>,
+    req: HttpRequest,
+    query: Query<ChangesQuery>,
+    tenant_scope: TenantScope,
+) -> impl Responder {
+    let mut page = app_state
+        .ingress_monitor
+        .changes_since(query.cursor.unwrap_or_default());
+    page.events
+        .retain(|event| tenant_scope.allows_namespace(&event.namespace));
+    if BinarySnapshotCache::is_requested(&req) {
+        let mut body = vec![];
+        return match ciborium::into_writer(&page, &mut body) {
+            Ok(()) => HttpResponse::Ok()
+                .content_type(CONTENT_TYPE_CBOR)
+                .body(body),
+            Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        };
+    }
+    HttpResponse::Ok().json(page)
+}
+
+/**
+Return the recent changes of a single entry, oldest first, including rollouts
+of the `Deployment`s backing its `Service`.
+
+The `id` is the cluster and the combined hostname and path, like
+`local/example.com/checkout`, followed by `#` and the variant name (URL
+encoded as `%23`) for entries of other variants than the primary. Recently
+removed entries are found as long as they are retained as tombstones.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "catalogue",
+    params(("id" = String, Path, description = "Cluster, hostname and path of the entry, like `local/example.com/checkout`.")),
+    responses(
+        (status = 200, description = "Ok", body = Vec<EntryChange>, content_type = "application/json",),
+        (status = 404, description = "No entry has the identifier."),
+    ),
+))]
+#[get("/entry/{id:.+}/history")]
+pub async fn get_entry_history(
+    app_state: Data<AppState>,
+    id: Path<String>,
+    tenant_scope: TenantScope,
+) -> impl Responder {
+    let ingress_monitor = &app_state.ingress_monitor;
+    match ingress_monitor.find_entry(&id) {
+        Some(entry)
+            if tenant_scope.allows_entry(ingress_monitor, &entry).await
+                && plugin_allows(&app_state, &entry).await =>
+        {
+            HttpResponse::Ok().json(entry.history())
+        }
+        _ => HttpResponse::NotFound().finish(),
+    }
+}
@@ -0,0 +1,126 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Publishing of the catalogue to a `ConfigMap`.
+
+use actix_web::web::Data;
+use k8s_openapi::api::core::v1::ConfigMap;
+use kube::api::{Patch, PatchParams};
+use kube::Api;
+use serde_json::json;
+use std::time::Duration;
+
+use super::api_resources::all_responses;
+use super::loader_resources::import_map;
+use super::tenant_scope::TenantScope;
+use super::AppState;
+
+/// Time between checks for changes of the catalogue.
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+/// Maximum size of the data of a `ConfigMap` accepted by the Kubernetes API server.
+const MAX_CONFIG_MAP_SIZE: usize = 1024 * 1024;
+/// Field manager of the server-side apply of the published `ConfigMap`.
+const FIELD_MANAGER: &str = "microfefind";
+
+/**
+Start writing the catalogue (`catalogue.json`) and the native import map
+(`importmap.json`) to the configured `ConfigMap` whenever the catalogue
+changes.
+ */
+pub(super) fn start_publishing(app_state: &Data<AppState>) {
+    let Some(config_map_name) = app_state.app_config.catalogue.publish_config_map() else {
+        return;
+    };
+    let config_map_name = config_map_name.to_owned();
+    let app_state_clone = app_state.clone();
+    app_state
+        .ingress_monitor
+        .supervisor()
+        .spawn("publish-configmap", move || {
+            let app_state = app_state_clone.clone();
+            let config_map_name = config_map_name.clone();
+            async move {
+                let mut published = None;
+                loop {
+                    tokio::time::sleep(CHECK_INTERVAL).await;
+                    let revision = app_state.ingress_monitor.catalogue_revision();
+                    if published == Some(revision) {
+                        continue;
+                    }
+                    if publish(&app_state, &config_map_name).await {
+                        published = Some(revision);
+                    }
+                }
+            }
+        });
+}
+
+/// Write the current catalogue to the `ConfigMap` and return `true` on success.
+async fn publish(app_state: &Data<AppState>, config_map_name: &str) -> bool {
+    let Some(cluster) = app_state.ingress_monitor.local_cluster() else {
+        return false;
+    };
+    let client = cluster.client();
+    let namespace = app_state
+        .app_config
+        .catalogue
+        .publish_namespace()
+        .unwrap_or(client.default_namespace())
+        .to_owned();
+    // The published ConfigMap is not scoped to any tenant
+    let tenant_scope = &TenantScope::unrestricted();
+    let catalogue =
+        serde_json::to_string(&all_responses(app_state, tenant_scope).await).unwrap_or_default();
+    let import_map = serde_json::to_string(&import_map(app_state, tenant_scope, false).await)
+        .unwrap_or_default();
+    if catalogue.len() + import_map.len() > MAX_CONFIG_MAP_SIZE {
+        log::warn!(
+            "Catalogue is too large to be published to 'configmap/{config_map_name}' in 'ns/{namespace}'."
+        );
+        return false;
+    }
+    let patch = json!({
+        "apiVersion": "v1",
+        "kind": "ConfigMap",
+        "metadata": { "name": config_map_name },
+        "data": {
+            "catalogue.json": catalogue,
+            "importmap.json": import_map,
+        },
+    });
+    let result = Api::<ConfigMap>::namespaced(client, &namespace)
+        .patch(
+            config_map_name,
+            &PatchParams::apply(FIELD_MANAGER).force(),
+            &Patch::Apply(&patch),
+        )
+        .await;
+    match result {
+        Ok(_) => {
+            log::debug!(
+                "Published catalogue to 'configmap/{config_map_name}' in 'ns/{namespace}'."
+            );
+            true
+        }
+        Err(e) => {
+            log::warn!(
+                "Failed to publish catalogue to 'configmap/{config_map_name}' in 'ns/{namespace}': {e}"
+            );
+            false
+        }
+    }
+}
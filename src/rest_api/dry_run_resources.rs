@@ -0,0 +1,101 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Resources for verifying manifests before they are deployed.
+
+use actix_web::web::{Bytes, Data, Query};
+use actix_web::{post, HttpResponse, Responder};
+use k8s_openapi::api::networking::v1::Ingress;
+use serde::Deserialize;
+
+use super::tenant_scope::TenantScope;
+use super::AppState;
+#[cfg(feature = "openapi")]
+use crate::ingress_monitor::DryRunReport;
+
+/// Namespace used when neither the manifest nor the query names one.
+const DEFAULT_NAMESPACE: &str = "default";
+
+/// Query parameters of the [dry_run] resource.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+struct DryRunQuery {
+    /// Namespace to evaluate the manifest for when it doesn't declare one.
+    namespace: Option<String>,
+}
+
+/**
+Return whether and how an `Ingress` manifest (JSON or YAML) would be cataloged
+without cataloging it.
+
+The report lists if the labels match, the exposed annotations and any schema
+violations, the entries that would be cataloged and the parts of the `Ingress`
+that would be skipped, so teams can verify manifests in CI before deploying.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "catalogue",
+    params(DryRunQuery),
+    request_body(content = Object, description = "`Ingress` manifest.", content_type = "application/yaml"),
+    responses(
+        (status = 200, description = "Ok", body = DryRunReport, content_type = "application/json",),
+        (status = 400, description = "Invalid or unsupported manifest", content_type = "text/plain",),
+    ),
+))]
+#[post("/dry-run")]
+pub async fn dry_run(
+    app_state: Data<AppState>,
+    query: Query<DryRunQuery>,
+    body: Bytes,
+    tenant_scope: TenantScope,
+) -> impl Responder {
+    // YAML is a superset of JSON
+    let manifest = match serde_yaml::from_slice::<serde_yaml::Value>(&body) {
+        Ok(manifest) => manifest,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Invalid manifest: {e}")),
+    };
+    let kind = manifest
+        .get("kind")
+        .and_then(serde_yaml::Value::as_str)
+        .unwrap_or_default();
+    if kind != "Ingress" {
+        return HttpResponse::BadRequest().body(format!(
+            "Unsupported kind '{kind}'. Only Ingress is supported."
+        ));
+    }
+    let ingress = match serde_yaml::from_value::<Ingress>(manifest) {
+        Ok(ingress) => ingress,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Invalid Ingress: {e}")),
+    };
+    let default_namespace = query
+        .namespace
+        .to_owned()
+        .or_else(|| app_state.app_config.ingress.namespaces().into_iter().next())
+        .unwrap_or_else(|| DEFAULT_NAMESPACE.to_owned());
+    let mut report = app_state
+        .ingress_monitor
+        .dry_run(&ingress, &default_namespace);
+    if !tenant_scope.is_unrestricted() {
+        // Don't reveal resources of other tenants
+        report
+            .entries
+            .iter_mut()
+            .filter(|entry| entry.declared_by.is_some())
+            .for_each(|entry| entry.declared_by = Some("another resource".to_owned()));
+    }
+    HttpResponse::Ok().json(report)
+}
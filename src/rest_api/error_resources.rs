@@ -0,0 +1,72 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+//! Resources for self-diagnosis of resources that are not (fully) cataloged.
+
+use actix_web::web::{Data, Query};
+use actix_web::{get, HttpResponse, Responder};
+use serde::Deserialize;
+
+#[cfg(feature = "openapi")]
+use crate::ingress_monitor::ResourceError;
+
+use super::tenant_scope::TenantScope;
+use super::AppState;
+
+/// Query parameters of the [get_errors] resource.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+struct ErrorsQuery {
+    /// Only return problems of resources in this namespace.
+    namespace: Option<String>,
+}
+
+/**
+Return non-fatal problems of watched resources, like `Ingress` rules without a
+host, annotations that violate the configured schema or `Service`s without a
+selector.
+
+This allows tenants to diagnose why their `Ingress` is not showing up.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "catalogue",
+    params(ErrorsQuery),
+    responses(
+        (status = 200, description = "Ok", body = Vec<ResourceError>, content_type = "application/json",),
+    ),
+))]
+#[get("/errors")]
+pub async fn get_errors(
+    app_state: Data<AppState>,
+    query: Query<ErrorsQuery>,
+    tenant_scope: TenantScope,
+) -> impl Responder {
+    let errors = app_state
+        .ingress_monitor
+        .resource_errors()
+        .await
+        .into_iter()
+        .filter(|resource_error| tenant_scope.allows_namespace(&resource_error.namespace))
+        .filter(|resource_error| {
+            query
+                .namespace
+                .as_ref()
+                .map_or(true, |namespace| &resource_error.namespace == namespace)
+        })
+        .collect::<Vec<_>>();
+    HttpResponse::Ok().json(errors)
+}
@@ -0,0 +1,77 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Resources describing the relations between modules in the catalogue.
+
+use actix_web::web::Data;
+use actix_web::{get, HttpResponse, Responder};
+
+#[cfg(feature = "openapi")]
+use crate::ingress_monitor::CompatibilityReport;
+#[cfg(feature = "openapi")]
+use crate::ingress_monitor::DependencyGraph;
+
+use super::api_resources::selected_entries;
+use super::tenant_scope::TenantScope;
+use super::AppState;
+
+/**
+Return the dependency graph of the modules named by the `name` annotation of
+each entry, with the modules required by the `requires` annotation.
+
+Besides the graph itself, cycles between modules and an order to load the
+modules in (each module after the modules it requires) are detected, so shells
+don't have to compute this client-side.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "catalogue",
+    responses(
+        (status = 200, description = "Ok", body = DependencyGraph, content_type = "application/json",),
+    ),
+))]
+#[get("/graph")]
+pub async fn get_graph(app_state: Data<AppState>, tenant_scope: TenantScope) -> impl Responder {
+    let ingress_monitor = &app_state.ingress_monitor;
+    let entries = selected_entries(&app_state, &tenant_scope).await;
+    HttpResponse::Ok().json(ingress_monitor.dependency_graph_of(entries).await)
+}
+
+/**
+Return requirements like `microfe/requires: cart@^1.2` that are not met by the
+`microfe/version` of any entry providing the required module, and requirements
+of the same module that no single provided version satisfies.
+
+This catches incompatible deployments at discovery time instead of in the
+browser.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "catalogue",
+    responses(
+        (status = 200, description = "Ok", body = CompatibilityReport, content_type = "application/json",),
+    ),
+))]
+#[get("/compatibility")]
+pub async fn get_compatibility(
+    app_state: Data<AppState>,
+    tenant_scope: TenantScope,
+) -> impl Responder {
+    let ingress_monitor = &app_state.ingress_monitor;
+    let entries = selected_entries(&app_state, &tenant_scope).await;
+    HttpResponse::Ok().json(ingress_monitor.compatibility_report_of(entries).await)
+}
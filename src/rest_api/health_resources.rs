@@ -19,11 +19,15 @@
 
 use actix_web::http::StatusCode;
 use actix_web::web::Data;
-use actix_web::{get, HttpResponse, Responder};
+use actix_web::{get, HttpRequest, HttpResponse, Responder};
 use serde::Serialize;
-use utoipa::ToSchema;
+use std::collections::BTreeMap;
 
+use super::tenant_scope::TenantScope;
 use super::AppState;
+use crate::ingress_monitor::count_by_namespace;
+use crate::ingress_monitor::NamespaceHealth;
+use crate::supervisor::TaskState;
 
 /** Helth check status definitions according to Eclipse MicroProfile Health 3.1.
 
@@ -61,46 +65,241 @@ impl HealthStatus {
     }
 
     /// Return the status as [HttpResponse] with correct return code and JSON serialized body.
-    fn as_response(&self) -> impl Responder {
+    fn as_response(&self, data: BTreeMap<String, serde_json::Value>) -> impl Responder {
+        self.as_response_with_checks(vec![], data)
+    }
+
+    /// Return the status and the individual checks as [HttpResponse].
+    fn as_response_with_checks(
+        &self,
+        checks: Vec<HealthCheckResponse>,
+        data: BTreeMap<String, serde_json::Value>,
+    ) -> impl Responder {
         HttpResponse::build(StatusCode::from_u16(self.http_status()).unwrap()).json(
             HealthResponse {
                 status: self.status(),
+                checks,
+                data,
             },
         )
     }
 }
 
 /**
-HTTP response body object for health requests. Only basic status is supported.
+HTTP response body object for health requests.
  */
-#[derive(ToSchema, Serialize)]
-struct HealthResponse {
+#[derive(Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub(super) struct HealthResponse {
     status: String,
+    /// Individual checks that contributed to the status.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    checks: Vec<HealthCheckResponse>,
+    /// Catalogue statistics, so external monitors can detect a healthy, but stale catalogue.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    data: BTreeMap<String, serde_json::Value>,
+}
+
+/**
+Return statistics of the catalogue and the monitoring:
+
+* `entries`: number of cataloged entries.
+* `namespaces`: number of monitored namespaces.
+* `watcherRestarts`: total number of restarts of supervised monitoring tasks.
+* `secondsSinceLastEvent`: time since the last listing or event in any
+  namespace (absent before the first listing).
+* `changeFeedBytes` and `manifestCacheBytes`: approximate memory used by
+  retained changes and cached asset manifests.
+* `memoryBytes`: memory assigned to the app (absent when unlimited).
+* `entryOverflows` and `entryOverflowsTotal`: declared entries that are
+  currently not cataloged and the total since start, due to entry limits.
+* `inFlightRequests` and `eventLoopLagMillis`: current load used to reject
+  API requests when overloaded.
+ */
+fn health_data(app_state: &AppState) -> BTreeMap<String, serde_json::Value> {
+    let ingress_monitor = &app_state.ingress_monitor;
+    let namespace_health = ingress_monitor.namespace_health();
+    let watcher_restarts = ingress_monitor
+        .supervisor()
+        .tasks()
+        .iter()
+        .map(|task| task.restarts())
+        .sum::<u64>();
+    let mut data = BTreeMap::new();
+    data.insert("entries".to_owned(), ingress_monitor.get_all().len().into());
+    data.insert("namespaces".to_owned(), namespace_health.len().into());
+    data.insert("watcherRestarts".to_owned(), watcher_restarts.into());
+    let (change_feed_bytes, manifest_cache_bytes) = ingress_monitor.cache_memory_usage();
+    data.insert("changeFeedBytes".to_owned(), change_feed_bytes.into());
+    data.insert("manifestCacheBytes".to_owned(), manifest_cache_bytes.into());
+    if let Some(memory_bytes) = app_state.app_config.limits.memory_bytes() {
+        data.insert("memoryBytes".to_owned(), memory_bytes.into());
+    }
+    let (entry_overflows, entry_overflows_total) = ingress_monitor.entry_overflow_counts();
+    data.insert("entryOverflows".to_owned(), entry_overflows.into());
+    data.insert(
+        "entryOverflowsTotal".to_owned(),
+        entry_overflows_total.into(),
+    );
+    let load_shedder = &app_state.load_shedder;
+    data.insert(
+        "inFlightRequests".to_owned(),
+        load_shedder.in_flight().into(),
+    );
+    data.insert(
+        "eventLoopLagMillis".to_owned(),
+        u64::try_from(load_shedder.lag().as_millis())
+            .unwrap_or(u64::MAX)
+            .into(),
+    );
+    if let Some(last_event_millis) = namespace_health
+        .iter()
+        .filter_map(|namespace_health| namespace_health.last_event_millis)
+        .max()
+    {
+        data.insert(
+            "secondsSinceLastEvent".to_owned(),
+            (crate::time::now_as_millis().saturating_sub(last_event_millis) / 1000).into(),
+        );
+    }
+    data
+}
+
+/**
+HTTP response body object for a single named health check with optional data.
+ */
+#[derive(Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub(super) struct HealthCheckResponse {
+    name: String,
+    status: String,
+    /// Additional information about the check, like the number of problems.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    data: BTreeMap<String, serde_json::Value>,
+}
+
+impl HealthCheckResponse {
+    /// Return the check of the monitoring of a single namespace.
+    fn from_namespace_health(namespace_health: &NamespaceHealth, now_millis: u64) -> Self {
+        let status = if namespace_health.is_healthy() {
+            HealthStatus::Up
+        } else {
+            HealthStatus::Down
+        };
+        let mut data = BTreeMap::new();
+        data.insert(
+            "cluster".to_owned(),
+            namespace_health.cluster.to_owned().into(),
+        );
+        data.insert(
+            "namespace".to_owned(),
+            namespace_health.namespace.to_owned().into(),
+        );
+        if let Some(last_event_millis) = namespace_health.last_event_millis {
+            data.insert(
+                "lastEventAgeSeconds".to_owned(),
+                (now_millis.saturating_sub(last_event_millis) / 1000).into(),
+            );
+        }
+        if let Some(last_error_millis) = namespace_health.last_error_millis {
+            data.insert(
+                "lastErrorAgeSeconds".to_owned(),
+                (now_millis.saturating_sub(last_error_millis) / 1000).into(),
+            );
+        }
+        if let Some(last_error) = &namespace_health.last_error {
+            data.insert("lastError".to_owned(), last_error.to_owned().into());
+        }
+        Self {
+            name: format!(
+                "namespace/{}/{}",
+                namespace_health.cluster, namespace_health.namespace
+            ),
+            status: status.status(),
+            data,
+        }
+    }
 }
 
 /**
 This endpoint returns the combined status of initialized, readiness and
 liveness of a microservice.
 
-It corresponds to the Kubernetes readiness probe.
+Unmet or conflicting semver requirements between modules are reported as the
+`compatibility` check, which does not affect the combined status.
+
+The monitoring of each namespace is reported as a separate check with the age
+of the last received event and the last error. A namespace that has not been
+listed yet or is failing is `DOWN`, but like the other checks it does not
+affect the combined status, so a single broken tenant namespace doesn't take
+the whole service out of rotation.
+
+When API requests are authenticated, namespace checks and resource problems
+are only reported for namespaces in the scope of the API key of the request.
+
+Kubernetes probes should use the dedicated `/health/ready` and `/health/live`
+resources, like the Helm chart does, or `/health/started`.
  */
-#[utoipa::path(
+#[cfg_attr(feature = "openapi", utoipa::path(
+    tag = "health",
     responses(
-        (status = 200, description = "Up", body = inline(HealthResponse), content_type = "application/json",),
+        (status = 200, description = "Up", body = HealthResponse, content_type = "application/json",),
         (status = 500, description = "Undetermined"),
         (status = 503, description = "Down"),
     ),
-)]
+))]
 #[get("/health")]
-pub async fn health(app_state: Data<AppState>) -> impl Responder {
+pub async fn health(app_state: Data<AppState>, req: HttpRequest) -> impl Responder {
+    let tenant_scope = TenantScope::identify(&req);
+    // Resource problems are reported for self-diagnosis, but does not affect the status
+    let mut resource_errors = app_state.ingress_monitor.resource_errors().await;
+    resource_errors.retain(|error| tenant_scope.allows_namespace(&error.namespace));
+    let resource_errors = HealthCheckResponse {
+        name: "resource-errors".to_owned(),
+        status: HealthStatus::Up.status(),
+        data: count_by_namespace(&resource_errors)
+            .into_iter()
+            .map(|(namespace, count)| (namespace, count.into()))
+            .collect(),
+    };
+    // Incompatible modules are a problem of tenants, so it does not affect the status either
+    let compatibility_report = app_state.ingress_monitor.compatibility_report().await;
+    let compatibility = HealthCheckResponse {
+        name: "compatibility".to_owned(),
+        status: if compatibility_report.is_compatible() {
+            HealthStatus::Up.status()
+        } else {
+            HealthStatus::Down.status()
+        },
+        data: BTreeMap::from([
+            ("unmet".to_owned(), compatibility_report.unmet.len().into()),
+            (
+                "conflicts".to_owned(),
+                compatibility_report.conflicts.len().into(),
+            ),
+        ]),
+    };
+    let now_millis = crate::time::now_as_millis();
+    let namespace_health = app_state.ingress_monitor.namespace_health();
+    let mut checks = vec![resource_errors, compatibility];
+    checks.extend(
+        namespace_health
+            .iter()
+            .filter(|namespace_health| tenant_scope.allows_namespace(&namespace_health.namespace))
+            .map(|namespace_health| {
+                HealthCheckResponse::from_namespace_health(namespace_health, now_millis)
+            }),
+    );
     // Combo: Liveness + Readiness + Startup
     if app_state.ingress_monitor.is_health_started()
         && app_state.ingress_monitor.is_health_ready()
         && app_state.ingress_monitor.is_health_live()
     {
-        HealthStatus::Up.as_response()
+        HealthStatus::Up.as_response_with_checks(checks, health_data(&app_state))
     } else {
-        HealthStatus::Down.as_response()
+        HealthStatus::Down.as_response_with_checks(checks, health_data(&app_state))
     }
 }
 
@@ -110,19 +309,20 @@ to process requests.
 
 It corresponds to the Kubernetes readiness probe.
  */
-#[utoipa::path(
+#[cfg_attr(feature = "openapi", utoipa::path(
+    tag = "health",
     responses(
-        (status = 200, description = "Up", body = inline(HealthResponse), content_type = "application/json",),
+        (status = 200, description = "Up", body = HealthResponse, content_type = "application/json",),
         (status = 500, description = "Undetermined"),
         (status = 503, description = "Down"),
     ),
-)]
+))]
 #[get("/health/ready")]
 pub async fn health_ready(app_state: Data<AppState>) -> impl Responder {
     if app_state.ingress_monitor.is_health_ready() {
-        HealthStatus::Up.as_response()
+        HealthStatus::Up.as_response(health_data(&app_state))
     } else {
-        HealthStatus::Down.as_response()
+        HealthStatus::Down.as_response(health_data(&app_state))
     }
 }
 
@@ -134,19 +334,20 @@ be stopped.
 This endpoint corresponds to the Kubernetes liveness probe, which automatically
 restarts the pod if the check fails.
  */
-#[utoipa::path(
+#[cfg_attr(feature = "openapi", utoipa::path(
+    tag = "health",
     responses(
-        (status = 200, description = "Up", body = inline(HealthResponse), content_type = "application/json",),
+        (status = 200, description = "Up", body = HealthResponse, content_type = "application/json",),
         (status = 500, description = "Undetermined"),
         (status = 503, description = "Down"),
     ),
-)]
+))]
 #[get("/health/live")]
 pub async fn health_live(app_state: Data<AppState>) -> impl Responder {
     if app_state.ingress_monitor.is_health_live() {
-        HealthStatus::Up.as_response()
+        HealthStatus::Up.as_response(health_data(&app_state))
     } else {
-        HealthStatus::Down.as_response()
+        HealthStatus::Down.as_response(health_data(&app_state))
     }
 }
 
@@ -157,18 +358,75 @@ you define.
 
 It corresponds to the Kubernetes startup probe.
  */
-#[utoipa::path(
+#[cfg_attr(feature = "openapi", utoipa::path(
+    tag = "health",
     responses(
-        (status = 200, description = "Up", body = inline(HealthResponse), content_type = "application/json",),
+        (status = 200, description = "Up", body = HealthResponse, content_type = "application/json",),
         (status = 500, description = "Undetermined"),
         (status = 503, description = "Down"),
     ),
-)]
+))]
 #[get("/health/started")]
 pub async fn health_started(app_state: Data<AppState>) -> impl Responder {
     if app_state.ingress_monitor.is_health_started() {
-        HealthStatus::Up.as_response()
+        HealthStatus::Up.as_response(health_data(&app_state))
     } else {
-        HealthStatus::Down.as_response()
+        HealthStatus::Down.as_response(health_data(&app_state))
     }
 }
+
+/**
+HTTP response body object for the health of a single supervised background task.
+ */
+#[derive(Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub(super) struct TaskHealthResponse {
+    /// Unique name of the task.
+    name: String,
+    /// Current state of the task.
+    state: TaskState,
+    /// Number of times the task died and was restarted.
+    restarts: u64,
+    /// `false` if the task keeps dying.
+    healthy: bool,
+}
+
+/**
+This endpoint returns the health of each supervised background monitoring
+task.
+
+A task that keeps dying will also fail the liveness check.
+
+When API requests are authenticated, tasks monitoring a namespace are only
+reported for namespaces in the scope of the API key of the request.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    tag = "health",
+    responses(
+        (status = 200, description = "Ok", body = Vec<TaskHealthResponse>, content_type = "application/json",),
+    ),
+))]
+#[get("/health/tasks")]
+pub async fn health_tasks(app_state: Data<AppState>, req: HttpRequest) -> impl Responder {
+    let tenant_scope = TenantScope::identify(&req);
+    let tasks = app_state
+        .ingress_monitor
+        .supervisor()
+        .tasks()
+        .iter()
+        // Tasks monitoring a namespace are named like `ingresses/{cluster}/{namespace}`
+        .filter(|task| {
+            task.name()
+                .splitn(3, '/')
+                .nth(2)
+                .map_or(true, |namespace| tenant_scope.allows_namespace(namespace))
+        })
+        .map(|task| TaskHealthResponse {
+            name: task.name().to_owned(),
+            state: task.state(),
+            restarts: task.restarts(),
+            healthy: task.is_healthy(),
+        })
+        .collect::<Vec<_>>();
+    HttpResponse::Ok().json(tasks)
+}
@@ -0,0 +1,190 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+//! HTTP/3 (QUIC) listener of the REST API.
+//!
+//! Each HTTP/3 request is forwarded to the HTTP/1.1 listener bound to the
+//! same address, so all resources, authentication and load shedding behave
+//! the same regardless of the protocol. The client's address is passed along
+//! in a `Forwarded` header and bodies are streamed in both directions.
+
+use bytes::{Buf, Bytes};
+use h3::error::ErrorLevel;
+use h3::server::RequestStream;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+use crate::conf::AppConfig;
+
+/// Value of `Alt-Svc` header advertising HTTP/3 on the `port` for a day.
+pub fn alt_svc(port: u16) -> String {
+    format!("h3=\":{port}\"; ma=86400")
+}
+
+/**
+Start serving HTTP/3 on the `port` of the address of every bound HTTP/1.1
+listener in `http_addresses` in the background.
+ */
+pub fn start(
+    app_config: &AppConfig,
+    port: u16,
+    http_addresses: &[SocketAddr],
+) -> std::io::Result<()> {
+    let mut tls_config = super::tls_config(
+        app_config.api.tls_cert_path(),
+        app_config.api.tls_key_path(),
+    )?;
+    tls_config.alpn_protocols = vec![b"h3".to_vec()];
+    let quic_config = quinn::crypto::rustls::QuicServerConfig::try_from(tls_config)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+    let server_config = quinn::ServerConfig::with_crypto(Arc::new(quic_config));
+    let http_client = reqwest::Client::new();
+    for http_address in http_addresses {
+        let socket_address = SocketAddr::new(http_address.ip(), port);
+        let endpoint = quinn::Endpoint::server(server_config.clone(), socket_address)?;
+        let upstream = format!("http://{}", reachable(*http_address));
+        log::info!("API is served over HTTP/3 on udp://{socket_address}.");
+        tokio::spawn(accept_connections(endpoint, http_client.clone(), upstream));
+    }
+    Ok(())
+}
+
+/**
+Return an address that the listener bound to `address` can be connected to,
+which is the loopback address of the same family for unspecified addresses
+like `0.0.0.0`.
+ */
+fn reachable(address: SocketAddr) -> SocketAddr {
+    match address.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => {
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), address.port())
+        }
+        IpAddr::V6(ip) if ip.is_unspecified() => {
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), address.port())
+        }
+        _ => address,
+    }
+}
+
+/// Accept QUIC connections until the endpoint is closed.
+async fn accept_connections(
+    endpoint: quinn::Endpoint,
+    http_client: reqwest::Client,
+    upstream: String,
+) {
+    while let Some(incoming) = endpoint.accept().await {
+        let http_client = http_client.clone();
+        let upstream = upstream.to_owned();
+        tokio::spawn(async move {
+            let connection = match incoming.await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    log::debug!("Failed to establish QUIC connection: {e}");
+                    return;
+                }
+            };
+            let peer_address = connection.remote_address();
+            let mut h3_connection =
+                match h3::server::Connection::new(h3_quinn::Connection::new(connection)).await {
+                    Ok(h3_connection) => h3_connection,
+                    Err(e) => {
+                        log::debug!("Failed to establish HTTP/3 connection: {e}");
+                        return;
+                    }
+                };
+            loop {
+                match h3_connection.accept().await {
+                    Ok(Some((request, stream))) => {
+                        let http_client = http_client.clone();
+                        let upstream = upstream.to_owned();
+                        tokio::spawn(async move {
+                            let result =
+                                forward(&http_client, &upstream, peer_address, request, stream)
+                                    .await;
+                            if let Err(e) = result {
+                                log::debug!("Failed to serve HTTP/3 request: {e}");
+                            }
+                        });
+                    }
+                    Ok(None) => break,
+                    Err(e) => match e.get_error_level() {
+                        ErrorLevel::ConnectionError => break,
+                        ErrorLevel::StreamError => continue,
+                    },
+                }
+            }
+        });
+    }
+}
+
+/**
+Forward the `request` from the client at `peer_address` to the HTTP/1.1
+listener at `upstream` and stream the response back.
+ */
+async fn forward(
+    http_client: &reqwest::Client,
+    upstream: &str,
+    peer_address: SocketAddr,
+    request: http::Request<()>,
+    stream: RequestStream<h3_quinn::BidiStream<Bytes>, Bytes>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let (mut send_stream, recv_stream) = stream.split();
+    let body = futures_util::stream::unfold(recv_stream, |mut recv_stream| async move {
+        match recv_stream.recv_data().await {
+            Ok(Some(mut chunk)) => Some((Ok(chunk.copy_to_bytes(chunk.remaining())), recv_stream)),
+            Ok(None) => None,
+            Err(e) => Some((Err(e), recv_stream)),
+        }
+    });
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map(|path_and_query| path_and_query.as_str())
+        .unwrap_or("/");
+    let mut headers = request.headers().clone();
+    if let Some(authority) = request.uri().authority() {
+        headers.insert(http::header::HOST, authority.as_str().parse()?);
+    }
+    // Only the client's address as seen by this listener is trustworthy
+    headers.remove("x-forwarded-for");
+    headers.remove("x-forwarded-proto");
+    headers.insert(
+        http::header::FORWARDED,
+        format!("for=\"{peer_address}\";proto=https").parse()?,
+    );
+    let mut upstream_response = http_client
+        .request(
+            request.method().clone(),
+            upstream.to_owned() + path_and_query,
+        )
+        .headers(headers)
+        .body(reqwest::Body::wrap_stream(body))
+        .send()
+        .await?;
+    let mut response = http::Response::builder().status(upstream_response.status());
+    for (name, value) in upstream_response.headers() {
+        // Connection specific headers are not allowed in HTTP/3
+        if name != http::header::CONNECTION && name != http::header::TRANSFER_ENCODING {
+            response = response.header(name, value);
+        }
+    }
+    send_stream.send_response(response.body(())?).await?;
+    while let Some(chunk) = upstream_response.chunk().await? {
+        send_stream.send_data(chunk).await?;
+    }
+    send_stream.finish().await?;
+    Ok(())
+}
@@ -0,0 +1,128 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Rejection of API requests while the server is overloaded.
+
+use actix_web::web::Data;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::AppState;
+
+/// Time between measurements of the event loop lag.
+const LAG_PROBE_INTERVAL: Duration = Duration::from_millis(100);
+/// Seconds that rejected clients are asked to wait before retrying.
+pub const RETRY_AFTER_SECONDS: u64 = 1;
+
+/**
+Tracks in-flight API requests and the lag of the event loop running the
+monitoring, to reject requests before the pod is overloaded.
+
+Health resources are never rejected, so probes keep passing during traffic
+spikes.
+ */
+pub struct LoadShedder {
+    /// Number of API requests currently being served.
+    in_flight: AtomicUsize,
+    /// Most recently measured event loop lag in milliseconds.
+    lag_millis: AtomicU64,
+    /// Number of in-flight requests above which new requests are rejected, if limited.
+    max_in_flight: Option<usize>,
+    /// Event loop lag above which new requests are rejected, if limited.
+    max_lag: Option<Duration>,
+}
+
+/// Marks an admitted request as in-flight until dropped.
+pub struct InFlightGuard {
+    load_shedder: Arc<LoadShedder>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.load_shedder.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl LoadShedder {
+    /// Return a new instance.
+    pub fn new(max_in_flight: Option<usize>, max_lag: Option<Duration>) -> Arc<Self> {
+        Arc::new(Self {
+            in_flight: AtomicUsize::new(0),
+            lag_millis: AtomicU64::new(0),
+            max_in_flight,
+            max_lag,
+        })
+    }
+
+    /// Return the number of API requests currently being served.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Return the most recently measured event loop lag.
+    pub fn lag(&self) -> Duration {
+        Duration::from_millis(self.lag_millis.load(Ordering::Relaxed))
+    }
+
+    /**
+      Return a guard that tracks the request as in-flight or `None` if the
+      request should be rejected since a threshold is exceeded.
+    */
+    pub fn admit(self: &Arc<Self>) -> Option<InFlightGuard> {
+        if self.max_lag.is_some_and(|max_lag| self.lag() > max_lag) {
+            return None;
+        }
+        let in_flight = self.in_flight.fetch_add(1, Ordering::Relaxed);
+        // Create the guard first, so the count is restored when rejecting
+        let guard = InFlightGuard {
+            load_shedder: Arc::clone(self),
+        };
+        if self
+            .max_in_flight
+            .is_some_and(|max_in_flight| in_flight >= max_in_flight)
+        {
+            return None;
+        }
+        Some(guard)
+    }
+}
+
+/**
+Start measuring how late a periodic timer fires on the event loop running the
+monitoring, which grows when the runtime is starved.
+ */
+pub(super) fn start_lag_probe(app_state: &Data<AppState>) {
+    let load_shedder = Arc::clone(&app_state.load_shedder);
+    app_state
+        .ingress_monitor
+        .supervisor()
+        .spawn("lag-probe", move || {
+            let load_shedder = Arc::clone(&load_shedder);
+            async move {
+                loop {
+                    let started = Instant::now();
+                    tokio::time::sleep(LAG_PROBE_INTERVAL).await;
+                    let lag = started.elapsed().saturating_sub(LAG_PROBE_INTERVAL);
+                    load_shedder.lag_millis.store(
+                        u64::try_from(lag.as_millis()).unwrap_or(u64::MAX),
+                        Ordering::Relaxed,
+                    );
+                }
+            }
+        });
+}
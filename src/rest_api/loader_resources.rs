@@ -0,0 +1,155 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Resources that help shells load the modules of micro front ends.
+
+use actix_web::web::{Data, Query};
+use actix_web::{get, HttpResponse, Responder};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use super::api_resources::selected_entries;
+use super::model::ImportMapResponse;
+use super::tenant_scope::TenantScope;
+use super::AppState;
+
+/// Query parameters of the [get_import_map] resource.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+struct ImportMapQuery {
+    /// `native` (default) for a browser import map or `systemjs` to include a `depcache`.
+    format: Option<String>,
+}
+
+/**
+Return an import map of the modules named by the `name` annotation of each
+entry to the entry points declared by the `entry` annotation.
+
+The `integrity` annotation of each module is exposed in the `integrity`
+section. With `format=systemjs` the entry points of the modules required by
+each module (according to the `requires` annotation) are also exposed in a
+`depcache` section, for single-spa deployments that still run SystemJS.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "loader",
+    params(ImportMapQuery),
+    responses(
+        (status = 200, description = "Ok", body = ImportMapResponse, content_type = ["application/importmap+json", "application/json"],),
+        (status = 400, description = "Unknown format."),
+    ),
+))]
+#[get("/importmap")]
+pub async fn get_import_map(
+    app_state: Data<AppState>,
+    query: Query<ImportMapQuery>,
+    tenant_scope: TenantScope,
+) -> impl Responder {
+    let systemjs = match query
+        .format
+        .as_deref()
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        None | Some("native") => false,
+        Some("systemjs") => true,
+        Some(format) => {
+            return HttpResponse::BadRequest().body(format!("Unknown format '{format}'."));
+        }
+    };
+    let import_map = import_map(&app_state, &tenant_scope, systemjs).await;
+    if systemjs {
+        HttpResponse::Ok().json(import_map)
+    } else {
+        HttpResponse::Ok()
+            .content_type("application/importmap+json")
+            .json(import_map)
+    }
+}
+
+/// Return the import map of all visible declared modules with a `depcache` section for SystemJS.
+pub(super) async fn import_map(
+    app_state: &Data<AppState>,
+    tenant_scope: &TenantScope,
+    systemjs: bool,
+) -> ImportMapResponse {
+    let ingress_monitor = &app_state.ingress_monitor;
+    let entries = selected_entries(app_state, tenant_scope).await;
+    let declared = ingress_monitor.declared_modules_of(entries).await;
+    let mut import_map = ImportMapResponse::default();
+    for module in &declared {
+        let Some(entry_point) = &module.entry_point else {
+            continue;
+        };
+        if import_map.imports.contains_key(&module.name) {
+            continue;
+        }
+        import_map
+            .imports
+            .insert(module.name.to_owned(), entry_point.to_owned());
+        if let Some(integrity) = module.integrity.as_ref().filter(|i| !i.is_empty()) {
+            import_map
+                .integrity
+                .insert(entry_point.to_owned(), integrity.to_owned());
+        }
+    }
+    if systemjs {
+        let mut depcache = BTreeMap::new();
+        for module in &declared {
+            let Some(entry_point) = import_map.imports.get(&module.name) else {
+                continue;
+            };
+            let dependencies = module
+                .requires
+                .iter()
+                .filter_map(|(required, _)| import_map.imports.get(required).cloned())
+                .collect::<Vec<_>>();
+            if !dependencies.is_empty() {
+                depcache.insert(entry_point.to_owned(), dependencies);
+            }
+        }
+        import_map.depcache = depcache;
+    }
+    import_map
+}
+
+/**
+Return the JavaScript entry points declared by the `entry` annotation of each
+entry as `Link: <url>; rel=modulepreload` response headers and as a JSON list.
+
+Edge proxies can forward these headers as `103 Early Hints`, so browsers can
+start fetching the modules before the shell asks for them.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "loader",
+    responses(
+        (status = 200, description = "Ok", body = Vec<String>, content_type = "application/json",
+            headers(("Link" = String, description = "`<url>; rel=modulepreload` for each entry point."))),
+    ),
+))]
+#[get("/preload")]
+pub async fn get_preload(app_state: Data<AppState>, tenant_scope: TenantScope) -> impl Responder {
+    let ingress_monitor = &app_state.ingress_monitor;
+    let entries = selected_entries(&app_state, &tenant_scope).await;
+    let urls = ingress_monitor.entry_point_urls_of(entries).await;
+    let mut builder = HttpResponse::Ok();
+    for url in &urls {
+        builder.append_header(("Link", format!("<{url}>; rel=modulepreload")));
+    }
+    builder.json(urls)
+}
@@ -0,0 +1,192 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+//! Request and response body objects of the REST API.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::ingress_monitor::DeploymentRollout;
+use crate::ingress_monitor::HealthProbeResult;
+use crate::ingress_monitor::LifecycleState;
+use crate::ingress_monitor::LifecycleTransition;
+use crate::ingress_monitor::PathType;
+use crate::ingress_monitor::ProbeResult;
+use crate::ingress_monitor::SourceObject;
+
+/// HTTP response body object for the `GET /api/v1/all` resource.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct IngressHostPathResponse {
+    /// Identifier of the Kubernetes cluster where the entry was discovered.
+    #[cfg_attr(feature = "openapi", schema(example = "local"))]
+    pub cluster: String,
+    /// Origin of entries merged from upstream `microfefind` instances. Absent for local entries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    /// Combined hostname and path servied via a correctly labeled `Ingress`.
+    #[cfg_attr(feature = "openapi", schema(example = "shop.example.com/checkout"))]
+    pub host_path: String,
+    /// Hostname part of the entry.
+    #[cfg_attr(feature = "openapi", schema(example = "shop.example.com"))]
+    pub host: String,
+    /// Path part of the entry.
+    #[cfg_attr(feature = "openapi", schema(example = "/checkout"))]
+    pub path: String,
+    /// Path that the browser must request, which differs from `path` when it is a regular expression.
+    pub external_path: String,
+    /// Path that the backend receives after rewrites by the Ingress controller.
+    pub backend_path: String,
+    /// `https` if the hostname is served over TLS according to the `Ingress` and `http` otherwise.
+    #[cfg_attr(feature = "openapi", schema(example = "https"))]
+    pub scheme: String,
+    /// Fully qualified URL of the hostname and path.
+    #[cfg_attr(
+        feature = "openapi",
+        schema(example = "https://shop.example.com/checkout")
+    )]
+    pub url: String,
+    /// How the path is matched according to the `pathType` of the `Ingress`.
+    pub path_type: PathType,
+    /// `true` if the hostname is a wildcard like `*.example.com` that matches any subdomain.
+    pub wildcard: bool,
+    /// Last update timestamp in milliseconds sinch Unix Epoch.
+    pub updated: u64,
+    /// Prefixed annotations of the serving `Ingress` (without the prefix part).
+    /// Values are strings or, when enabled, nested JSON structures.
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub annotations: HashMap<String, Value>,
+    /// Prefixed labels of the serving `Ingress` (without the prefix part).
+    pub labels: BTreeMap<String, String>,
+    /// `false` if the annotations do not conform to the configured JSON Schema.
+    pub valid: bool,
+    /// Description of each violation of the configured JSON Schema.
+    pub validation_errors: Vec<String>,
+    /// `true` if the mapped `Service` has at least one ready endpoint. Absent until known.
+    pub available: Option<bool>,
+    /// `true` if the URL responded to the most recent probe. Absent unless probing is enabled.
+    pub reachable: Option<bool>,
+    /// Outcome of the most recent probe of the URL. Absent unless probing is enabled.
+    pub last_probe: Option<ProbeResult>,
+    /// Outcome of the most recent probe of the health endpoint declared by the `health-path` annotation.
+    pub health: Option<HealthProbeResult>,
+    /// Asset manifest referenced by the `manifest` annotation, fetched and cached by the server.
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub manifest: Option<Value>,
+    /// Current lifecycle state of the entry.
+    pub state: LifecycleState,
+    /// Most recent lifecycle state transition of the entry, if any.
+    pub last_transition: Option<LifecycleTransition>,
+    /// External target when the mapped `Service` is of type `ExternalName`.
+    pub external_name: Option<String>,
+    /// Number of endpoints (`Pod`s) backing the mapped `Service`.
+    pub replicas: usize,
+    /// Number of ready endpoints (`Pod`s) backing the mapped `Service`.
+    pub ready_replicas: usize,
+    /// Revision, rollout state, container images and version of each `Deployment` backing the mapped `Service`.
+    pub deployments: Vec<DeploymentRollout>,
+    /// Identity and version of the Kubernetes object declaring the entry. Absent for imported entries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_object: Option<SourceObject>,
+    /// `true` if the entry was recently deleted and is only kept so clients can observe the removal.
+    pub deleted: bool,
+    /// All variants (e.g. primary and canary) serving the same hostname and path.
+    pub variants: Vec<VariantResponse>,
+}
+
+/// Variant of a hostname and path served by a separate `Ingress`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct VariantResponse {
+    /// Name of the variant. `primary` unless marked as a variant.
+    pub name: String,
+    /// Share of traffic (0-100) served by the variant, if declared.
+    pub weight: Option<u32>,
+    /// `true` if the variant's `Service` has at least one ready endpoint. Absent until known.
+    pub available: Option<bool>,
+    /// Current lifecycle state of the variant.
+    pub state: LifecycleState,
+}
+
+/// HTTP response body object for the `GET /api/v1/hosts` resource.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct HostResponse {
+    /// Hostname served via correctly labeled `Ingress`es.
+    pub host: String,
+    /// Annotations of all paths of the host merged, where paths closer to the root take precedence.
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub annotations: HashMap<String, Value>,
+    /// All entries of the host ordered by path.
+    pub paths: Vec<IngressHostPathResponse>,
+}
+
+/// HTTP response body object for the `GET /api/v1/groups` resource.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GroupResponse {
+    /// Value of the `group` (or `team`) annotation. Absent for entries without a group.
+    pub group: Option<String>,
+    /// Number of entries in the group.
+    pub count: usize,
+    /// Number of entries in the group with at least one ready endpoint.
+    pub available_count: usize,
+    /// All entries of the group ordered by host + path.
+    pub entries: Vec<IngressHostPathResponse>,
+}
+
+/// HTTP response body object for the `GET /api/v1/importmap` resource.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ImportMapResponse {
+    /// Entry point URL by module name.
+    pub imports: BTreeMap<String, String>,
+    /// Entry point URLs of the required modules by entry point URL (SystemJS only).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub depcache: BTreeMap<String, Vec<String>>,
+    /// Subresource integrity metadata by entry point URL.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub integrity: BTreeMap<String, String>,
+}
+
+/// Entry to resolve in an HTTP request body of the `POST /api/v1/resolve` resource.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ResolveRequest {
+    /// Hostname of the requested URL. Required unless `module` is present.
+    #[cfg_attr(feature = "openapi", schema(example = "shop.example.com"))]
+    #[serde(default)]
+    pub host: Option<String>,
+    /// Path of the requested URL, matched against the longest entry path. Defaults to `/`.
+    #[cfg_attr(feature = "openapi", schema(example = "/checkout/cart"))]
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Name of a module declared by the `name` annotation.
+    #[cfg_attr(feature = "openapi", schema(example = "@shop/checkout"))]
+    #[serde(default)]
+    pub module: Option<String>,
+}
+
+/// Result of a single [ResolveRequest] in the same order as the request.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ResolveResponse {
+    /// The resolved request.
+    pub request: ResolveRequest,
+    /// Matching entries. Empty if nothing matched.
+    pub entries: Vec<IngressHostPathResponse>,
+}
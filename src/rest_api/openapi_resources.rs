@@ -0,0 +1,217 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Generation and serving of the OpenAPI document describing the REST API.
+
+use actix_web::http::header::ContentType;
+use actix_web::web::Data;
+use actix_web::{get, HttpResponse};
+use utoipa::openapi::{ContactBuilder, LicenseBuilder, ServerBuilder};
+use utoipa::OpenApi;
+
+use super::admin_resources;
+use super::admission_resources;
+use super::api_resources;
+use super::change_resources;
+use super::dry_run_resources;
+use super::error_resources;
+use super::graph_resources;
+use super::health_resources;
+use super::loader_resources;
+use super::profiling_resources;
+#[cfg(feature = "proxy")]
+use super::proxy_resources;
+use super::resolve_resources;
+use super::search_resources;
+use super::ui_resources;
+use super::AppState;
+use crate::conf::AppConfig;
+
+/// Versions of the REST API served by this build, listed as `x-api-versions` in the document.
+pub const API_VERSIONS: &[&str] = &["v1"];
+
+/// The OpenAPI document rendered in all served formats.
+pub struct RenderedDocument {
+    /// The document as pretty printed JSON.
+    json: String,
+    /// The document as YAML.
+    yaml: String,
+}
+
+impl RenderedDocument {
+    /// Render the document for the configuration.
+    fn new(app_config: &AppConfig) -> Self {
+        let mut value = serde_json::to_value(document(app_config)).unwrap();
+        if let Some(object) = value.as_object_mut() {
+            object.insert("x-api-versions".to_owned(), API_VERSIONS.into());
+        }
+        Self {
+            json: serde_json::to_string_pretty(&value).unwrap(),
+            yaml: serde_yaml::to_string(&value).unwrap(),
+        }
+    }
+}
+
+/// Description of all resources and their schemas.
+#[derive(OpenApi)]
+#[openapi(
+    // Use Cargo.toml as source for the "info" section
+    paths(
+        admin_resources::export_state,
+        admin_resources::import_state,
+        admission_resources::validate,
+        admission_resources::mutate,
+        api_resources::get_all,
+        api_resources::get_entry,
+        api_resources::get_groups,
+        api_resources::get_hosts,
+        change_resources::get_changes,
+        change_resources::get_entry_history,
+        dry_run_resources::dry_run,
+        error_resources::get_errors,
+        graph_resources::get_compatibility,
+        graph_resources::get_graph,
+        health_resources::health,
+        health_resources::health_live,
+        health_resources::health_ready,
+        health_resources::health_started,
+        health_resources::health_tasks,
+        loader_resources::get_import_map,
+        loader_resources::get_preload,
+        profiling_resources::heap,
+        profiling_resources::profile,
+        profiling_resources::tasks,
+        resolve_resources::resolve,
+        search_resources::search,
+        ui_resources::dashboard,
+    ),
+    components(schemas(
+        crate::ingress_monitor::CompatibilityReport,
+        crate::ingress_monitor::DependencyGraph,
+        crate::ingress_monitor::DeploymentRollout,
+        crate::ingress_monitor::DryRunEntry,
+        crate::ingress_monitor::DryRunReport,
+        crate::ingress_monitor::DryRunSkipped,
+        crate::ingress_monitor::EntryChange,
+        crate::ingress_monitor::HealthProbeResult,
+        crate::ingress_monitor::HostPathState,
+        crate::ingress_monitor::ImportReport,
+        crate::ingress_monitor::LifecycleEvent,
+        crate::ingress_monitor::LifecycleState,
+        crate::ingress_monitor::LifecycleTransition,
+        crate::ingress_monitor::ModuleNode,
+        crate::ingress_monitor::MonitorState,
+        crate::ingress_monitor::PathType,
+        crate::ingress_monitor::ProbeResult,
+        crate::ingress_monitor::RequirementConflict,
+        crate::ingress_monitor::ResourceError,
+        crate::ingress_monitor::RewrittenPath,
+        crate::ingress_monitor::RolloutState,
+        crate::ingress_monitor::SourceObject,
+        crate::ingress_monitor::UnmetRequirement,
+        crate::notifier::ChangeEvent,
+        crate::notifier::ChangeFeedPage,
+        crate::notifier::ChangeKind,
+        crate::rest_api::model::GroupResponse,
+        crate::rest_api::model::HostResponse,
+        crate::rest_api::model::ImportMapResponse,
+        crate::rest_api::model::IngressHostPathResponse,
+        crate::rest_api::model::ResolveRequest,
+        crate::rest_api::model::ResolveResponse,
+        crate::rest_api::model::VariantResponse,
+        crate::supervisor::TaskState,
+        health_resources::HealthCheckResponse,
+        health_resources::HealthResponse,
+        health_resources::TaskHealthResponse,
+    )),
+    tags(
+        (name = "catalogue", description = "Discovered micro front end entries."),
+        (name = "loader", description = "Import maps and preload hints for module loaders."),
+        (name = "admin", description = "State export and import. Disabled by default."),
+        (name = "admission", description = "Kubernetes admission webhooks for `Ingress`es."),
+        (name = "health", description = "Probes and statistics of the monitoring."),
+        (name = "debug", description = "On-demand profiling. Disabled by default."),
+        (name = "ui", description = "Human readable dashboard."),
+        (name = "proxy", description = "Reverse-proxied assets of entries. Disabled by default."),
+    ),
+)]
+struct ApiDoc;
+
+/**
+Return the OpenAPI document with the server, contact and license information
+from the configuration.
+ */
+fn document(app_config: &AppConfig) -> utoipa::openapi::OpenApi {
+    #[allow(unused_mut)]
+    let mut api_doc = ApiDoc::openapi();
+    #[cfg(feature = "proxy")]
+    {
+        #[derive(OpenApi)]
+        #[openapi(paths(proxy_resources::proxy))]
+        struct ProxyApiDoc;
+        api_doc.merge(ProxyApiDoc::openapi());
+    }
+    let openapi_config = &app_config.openapi;
+    if let Some(server_url) = openapi_config.server_url() {
+        api_doc.servers = Some(vec![ServerBuilder::new().url(server_url).build()]);
+    }
+    let contact_name = openapi_config.contact_name();
+    let contact_email = openapi_config.contact_email();
+    let contact_url = openapi_config.contact_url();
+    if contact_name.is_some() || contact_email.is_some() || contact_url.is_some() {
+        api_doc.info.contact = Some(
+            ContactBuilder::new()
+                .name(contact_name)
+                .email(contact_email)
+                .url(contact_url)
+                .build(),
+        );
+    }
+    if let Some(license_name) = openapi_config.license_name() {
+        api_doc.info.license = Some(
+            LicenseBuilder::new()
+                .name(license_name)
+                .url(openapi_config.license_url())
+                .build(),
+        );
+    }
+    api_doc
+}
+
+/// Return the rendered document, which is generated on the first request.
+fn rendered(app_state: &AppState) -> &RenderedDocument {
+    // The document only depends on the configuration
+    app_state
+        .openapi_document
+        .get_or_init(|| RenderedDocument::new(&app_state.app_config))
+}
+
+/// Serve Open API documentation.
+#[get("/openapi.json")]
+pub async fn openapi(app_state: Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(rendered(&app_state).json.to_owned())
+}
+
+/// Serve Open API documentation as YAML.
+#[get("/openapi.yaml")]
+pub async fn openapi_yaml(app_state: Data<AppState>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/yaml")
+        .body(rendered(&app_state).yaml.to_owned())
+}
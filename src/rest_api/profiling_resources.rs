@@ -0,0 +1,217 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! On-demand profiling resources for diagnosing performance problems.
+
+use actix_web::web::{Data, Query};
+use actix_web::{get, HttpResponse};
+use serde::Deserialize;
+
+use super::tenant_scope::TenantScope;
+use super::AppState;
+
+/// Default duration of a CPU profile in seconds.
+#[cfg(feature = "pprof")]
+const DEFAULT_PROFILE_SECONDS: u64 = 30;
+/// Maximum duration of a CPU profile in seconds.
+#[cfg(feature = "pprof")]
+const MAX_PROFILE_SECONDS: u64 = 300;
+
+/// Query parameters of the [profile] resource.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(not(feature = "pprof"), allow(dead_code))]
+struct ProfileQuery {
+    /// Duration of the profile in seconds. Defaults to `30` and at most `300`.
+    seconds: Option<u64>,
+    /// Sampling frequency in Hz. Defaults to `99`.
+    frequency: Option<i32>,
+}
+
+/**
+Return a response if profiling is not allowed for the request.
+
+Profiling must be enabled and, since profiles expose internals, API requests
+must be authenticated by an API key that is allowed to see everything.
+ */
+fn forbidden(app_state: &AppState, tenant_scope: &TenantScope) -> Option<HttpResponse> {
+    if !app_state.app_config.debug.profiling_enabled()
+        || app_state.reloadable().tenants.is_none()
+        || !tenant_scope.is_unrestricted()
+    {
+        return Some(HttpResponse::Forbidden().finish());
+    }
+    None
+}
+
+/**
+Sample the CPU usage of the whole process for the requested duration and
+return the profile in the protobuf format of `pprof`, e.g. for
+`go tool pprof -http=: profile.pb`.
+
+Requires a build with the `pprof` feature. Only one profile can be recorded
+at a time.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "debug",
+    params(ProfileQuery),
+    responses(
+        (status = 200, description = "Ok", content_type = "application/octet-stream",),
+        (status = 403, description = "Profiling is disabled or the request is not authenticated by an unrestricted API key."),
+        (status = 409, description = "Another profile is being recorded."),
+        (status = 501, description = "Not built with the `pprof` feature."),
+    ),
+))]
+#[get("/debug/pprof/profile")]
+pub async fn profile(
+    app_state: Data<AppState>,
+    query: Query<ProfileQuery>,
+    tenant_scope: TenantScope,
+) -> HttpResponse {
+    if let Some(response) = forbidden(&app_state, &tenant_scope) {
+        return response;
+    }
+    #[cfg(feature = "pprof")]
+    {
+        use pprof::protos::Message;
+
+        let seconds = query
+            .seconds
+            .unwrap_or(DEFAULT_PROFILE_SECONDS)
+            .clamp(1, MAX_PROFILE_SECONDS);
+        let guard = match pprof::ProfilerGuardBuilder::default()
+            .frequency(query.frequency.unwrap_or(99).clamp(1, 1000))
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()
+        {
+            Ok(guard) => guard,
+            Err(e) => return HttpResponse::Conflict().body(e.to_string()),
+        };
+        log::info!("Recording CPU profile for {seconds} seconds.");
+        tokio::time::sleep(std::time::Duration::from_secs(seconds)).await;
+        let profile = guard
+            .report()
+            .build()
+            .and_then(|report| report.pprof())
+            .map_err(|e| e.to_string())
+            .and_then(|profile| profile.write_to_bytes().map_err(|e| e.to_string()));
+        match profile {
+            Ok(body) => HttpResponse::Ok()
+                .content_type("application/octet-stream")
+                .insert_header(("Content-Disposition", "attachment; filename=\"profile.pb\""))
+                .body(body),
+            Err(e) => HttpResponse::InternalServerError().body(e),
+        }
+    }
+    #[cfg(not(feature = "pprof"))]
+    {
+        let _ = query;
+        HttpResponse::NotImplemented().body("Requires a build with the 'pprof' feature.")
+    }
+}
+
+/**
+Return memory statistics of the process: resident, peak resident and virtual
+memory from `/proc/self/status` (absent on other platforms), the memory
+assigned to the app and the memory used by the largest caches.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "debug",
+    responses(
+        (status = 200, description = "Ok", body = Object, content_type = "application/json",),
+        (status = 403, description = "Profiling is disabled or the request is not authenticated by an unrestricted API key."),
+    ),
+))]
+#[get("/debug/pprof/heap")]
+pub async fn heap(app_state: Data<AppState>, tenant_scope: TenantScope) -> HttpResponse {
+    if let Some(response) = forbidden(&app_state, &tenant_scope) {
+        return response;
+    }
+    let mut data = serde_json::Map::new();
+    let status = std::fs::read_to_string("/proc/self/status").unwrap_or_default();
+    for (field, name) in [
+        ("VmRSS:", "residentBytes"),
+        ("VmHWM:", "peakResidentBytes"),
+        ("VmSize:", "virtualBytes"),
+    ] {
+        // Values are reported like "VmRSS:     1234 kB"
+        let kibibytes = status
+            .lines()
+            .find_map(|line| line.strip_prefix(field))
+            .and_then(|value| {
+                value
+                    .trim()
+                    .trim_end_matches("kB")
+                    .trim()
+                    .parse::<u64>()
+                    .ok()
+            });
+        if let Some(kibibytes) = kibibytes {
+            data.insert(name.to_owned(), (kibibytes * 1024).into());
+        }
+    }
+    if let Some(memory_bytes) = app_state.app_config.limits.memory_bytes() {
+        data.insert("memoryBytes".to_owned(), memory_bytes.into());
+    }
+    let (change_feed_bytes, manifest_cache_bytes) = app_state.ingress_monitor.cache_memory_usage();
+    data.insert("changeFeedBytes".to_owned(), change_feed_bytes.into());
+    data.insert("manifestCacheBytes".to_owned(), manifest_cache_bytes.into());
+    HttpResponse::Ok().json(data)
+}
+
+/**
+Return a dump of the runtime running the monitoring: the number of worker
+threads, alive tasks and the state of each supervised task.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "debug",
+    responses(
+        (status = 200, description = "Ok", body = Object, content_type = "application/json",),
+        (status = 403, description = "Profiling is disabled or the request is not authenticated by an unrestricted API key."),
+    ),
+))]
+#[get("/debug/pprof/tasks")]
+pub async fn tasks(app_state: Data<AppState>, tenant_scope: TenantScope) -> HttpResponse {
+    if let Some(response) = forbidden(&app_state, &tenant_scope) {
+        return response;
+    }
+    let mut data = serde_json::Map::new();
+    if let Some(runtime) = &app_state.monitor_runtime {
+        let metrics = runtime.metrics();
+        data.insert("workers".to_owned(), metrics.num_workers().into());
+        data.insert("aliveTasks".to_owned(), metrics.num_alive_tasks().into());
+    }
+    let tasks = app_state
+        .ingress_monitor
+        .supervisor()
+        .tasks()
+        .iter()
+        .map(|task| {
+            serde_json::json!({
+                "name": task.name(),
+                "state": task.state(),
+                "restarts": task.restarts(),
+                "healthy": task.is_healthy(),
+            })
+        })
+        .collect::<Vec<_>>();
+    data.insert("tasks".to_owned(), tasks.into());
+    HttpResponse::Ok().json(data)
+}
@@ -0,0 +1,141 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Reverse-proxying of asset requests to the `Service` of each entry.
+
+use actix_web::http::Method;
+use actix_web::http::StatusCode;
+use actix_web::web::{Data, Path};
+use actix_web::{route, HttpRequest, HttpResponse};
+
+use super::api_resources::plugin_allows;
+use super::tenant_scope::TenantScope;
+use super::AppState;
+
+/// Request headers that are forwarded to the `Service`.
+const FORWARDED_REQUEST_HEADERS: &[&str] = &[
+    "accept",
+    "accept-encoding",
+    "accept-language",
+    "if-modified-since",
+    "if-none-match",
+    "range",
+];
+
+/// Response headers that are not forwarded to the client.
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "content-length",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/**
+Forward a request for an asset of an entry, like
+`/proxy/shop.example.com/checkout/main.js`, to the in-cluster `Service` mapped
+by the entry with the longest matching hostname and path.
+
+This allows shells to load all bundles from a single origin, avoiding CORS
+and cookie domain issues. Only `GET` and `HEAD` requests for entries in the
+local cluster are forwarded and only when proxying is enabled. When tenants
+are configured, requests are authenticated like API requests and only
+entries visible to the tenant are forwarded.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/proxy",
+    tag = "proxy",
+    params(
+        ("host_path" = String, Path, description = "Hostname and path of the asset, like `shop.example.com/checkout/main.js`."),
+    ),
+    responses(
+        (status = 200, description = "Response of the `Service`."),
+        (status = 401, description = "Tenants are configured and the request has no known API key."),
+        (status = 403, description = "Proxying is disabled."),
+        (status = 404, description = "No entry visible to the tenant matches the hostname and path."),
+        (status = 502, description = "The `Service` did not respond."),
+        (status = 503, description = "The `Service` of the entry is not known yet."),
+    ),
+))]
+#[route("/{host_path:.*}", method = "GET", method = "HEAD")]
+pub async fn proxy(
+    app_state: Data<AppState>,
+    host_path: Path<String>,
+    req: HttpRequest,
+    tenant_scope: TenantScope,
+) -> HttpResponse {
+    if !app_state.app_config.api.proxy_enabled() {
+        return HttpResponse::Forbidden().finish();
+    }
+    let host_path = host_path.into_inner();
+    let ingress_monitor = &app_state.ingress_monitor;
+    let Some(entry) = ingress_monitor.find_local_entry(&host_path) else {
+        return HttpResponse::NotFound().finish();
+    };
+    // Don't reveal entries of other tenants or entries omitted by the plugin
+    if !tenant_scope.allows_entry(ingress_monitor, &entry).await
+        || !plugin_allows(&app_state, &entry).await
+    {
+        return HttpResponse::NotFound().finish();
+    }
+    let Some(base_url) = entry.service_base_url().await else {
+        return HttpResponse::ServiceUnavailable().finish();
+    };
+    let (host, _) = entry.host_and_path();
+    let mut url = base_url + &host_path[host.len()..];
+    if !req.query_string().is_empty() {
+        url.push('?');
+        url.push_str(req.query_string());
+    }
+    let method = if req.method() == Method::HEAD {
+        reqwest::Method::HEAD
+    } else {
+        reqwest::Method::GET
+    };
+    let mut request = app_state
+        .proxy_client
+        .request(method, &url)
+        .header("host", host);
+    for name in FORWARDED_REQUEST_HEADERS {
+        if let Some(value) = req.headers().get(*name) {
+            request = request.header(*name, value.as_bytes());
+        }
+    }
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            log::warn!(
+                "Failed to proxy '{host_path}' to '{url}': {}",
+                e.without_url()
+            );
+            return HttpResponse::BadGateway().finish();
+        }
+    };
+    let status =
+        StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut builder = HttpResponse::build(status);
+    for (name, value) in response.headers() {
+        if !HOP_BY_HOP_HEADERS.contains(&name.as_str()) {
+            builder.append_header((name.as_str(), value.as_bytes()));
+        }
+    }
+    builder.streaming(response.bytes_stream())
+}
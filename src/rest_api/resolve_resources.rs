@@ -0,0 +1,128 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Resources for resolving many entries in one round trip.
+
+use actix_web::web::{Data, Json};
+use actix_web::{post, HttpResponse, Responder};
+use serde_json::Value;
+
+use super::api_resources::all_responses;
+use super::model::IngressHostPathResponse;
+use super::model::ResolveRequest;
+use super::model::ResolveResponse;
+use super::tenant_scope::TenantScope;
+use super::AppState;
+
+/// Maximum number of entries to resolve in a single request.
+const MAX_RESOLVE_REQUESTS: usize = 1000;
+
+/**
+Return the matching entries of each requested host and path or module name.
+
+A host and path resolves to the entries with the longest path that is a prefix
+of the requested path, like an Ingress controller would route the request. A
+module resolves to the entries declaring it with the `name` annotation.
+
+This lets server-side renderers resolve all fragments of a page in one round
+trip.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "catalogue",
+    request_body(content = Vec<ResolveRequest>, content_type = "application/json"),
+    responses(
+        (status = 200, description = "Ok", body = Vec<ResolveResponse>, content_type = "application/json",),
+        (status = 400, description = "Too many or incomplete requests", content_type = "text/plain",),
+    ),
+))]
+#[post("/resolve")]
+pub async fn resolve(
+    app_state: Data<AppState>,
+    requests: Json<Vec<ResolveRequest>>,
+    tenant_scope: TenantScope,
+) -> impl Responder {
+    if requests.len() > MAX_RESOLVE_REQUESTS {
+        return HttpResponse::BadRequest().body(format!(
+            "At most {MAX_RESOLVE_REQUESTS} entries can be resolved per request."
+        ));
+    }
+    if let Some(index) = requests
+        .iter()
+        .position(|request| request.host.is_none() && request.module.is_none())
+    {
+        return HttpResponse::BadRequest()
+            .body(format!("Request {index} must have a 'host' or a 'module'."));
+    }
+    let responses = all_responses(&app_state, &tenant_scope)
+        .await
+        .into_iter()
+        .filter(|response| !response.deleted)
+        .collect::<Vec<_>>();
+    let results = requests
+        .into_inner()
+        .into_iter()
+        .map(|request| {
+            let entries = resolve_one(&responses, &request);
+            ResolveResponse { request, entries }
+        })
+        .collect::<Vec<_>>();
+    HttpResponse::Ok().json(results)
+}
+
+/// Return the entries matching a single request.
+fn resolve_one(
+    responses: &[IngressHostPathResponse],
+    request: &ResolveRequest,
+) -> Vec<IngressHostPathResponse> {
+    let candidates = responses.iter().filter(|response| {
+        request
+            .host
+            .as_ref()
+            .map_or(true, |host| response.host.eq_ignore_ascii_case(host))
+            && request.module.as_ref().map_or(true, |module| {
+                response.annotations.get("name") == Some(&Value::String(module.to_owned()))
+            })
+    });
+    let Some(requested_path) = request
+        .path
+        .as_deref()
+        .or(request.host.as_ref().map(|_| "/"))
+    else {
+        return candidates.cloned().collect();
+    };
+    let candidates = candidates
+        .filter(|response| is_path_prefix(&response.external_path, requested_path))
+        .collect::<Vec<_>>();
+    let longest = candidates
+        .iter()
+        .map(|response| response.external_path.len())
+        .max()
+        .unwrap_or_default();
+    candidates
+        .into_iter()
+        .filter(|response| response.external_path.len() == longest)
+        .cloned()
+        .collect()
+}
+
+/// Return `true` if the entry `path` routes requests for the `requested_path`.
+fn is_path_prefix(path: &str, requested_path: &str) -> bool {
+    requested_path
+        .strip_prefix(path)
+        .is_some_and(|rest| rest.is_empty() || path.ends_with('/') || rest.starts_with('/'))
+}
@@ -0,0 +1,136 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Resources for exploring large catalogues.
+
+use actix_web::web::{Data, Query};
+use actix_web::{get, HttpResponse, Responder};
+use regex::RegexBuilder;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::api_resources::all_responses;
+#[cfg(feature = "openapi")]
+use super::model::IngressHostPathResponse;
+use super::tenant_scope::TenantScope;
+use super::AppState;
+
+/// Maximum length of a search query.
+const MAX_QUERY_LENGTH: usize = 256;
+/// Maximum size of a compiled regular expression in bytes.
+const MAX_REGEX_SIZE: usize = 256 * 1024;
+
+/// Query parameters of the [search] resource.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+struct SearchQuery {
+    /// Glob pattern (`*` matches any characters, `?` a single character) or
+    /// regular expression to match.
+    q: String,
+    /// `true` to interpret `q` as a regular expression instead of a glob pattern.
+    #[serde(default)]
+    regex: bool,
+}
+
+/**
+Return entries where the host + path or any annotation value matches the query.
+
+Queries are case-insensitive glob patterns like `shop*` that must match the
+whole value. With `regex=true` the query is an unanchored regular expression
+that is evaluated in linear time with limits on the query and compiled size.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/api/v1",
+    tag = "catalogue",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Ok", body = Vec<IngressHostPathResponse>, content_type = "application/json",),
+        (status = 400, description = "Invalid query", content_type = "text/plain",),
+    ),
+))]
+#[get("/search")]
+pub async fn search(
+    app_state: Data<AppState>,
+    query: Query<SearchQuery>,
+    tenant_scope: TenantScope,
+) -> impl Responder {
+    if query.q.len() > MAX_QUERY_LENGTH {
+        return HttpResponse::BadRequest().body(format!(
+            "Query must not be longer than {MAX_QUERY_LENGTH} characters."
+        ));
+    }
+    let matcher: Box<dyn Fn(&str) -> bool> = if query.regex {
+        match RegexBuilder::new(&query.q)
+            .case_insensitive(true)
+            .size_limit(MAX_REGEX_SIZE)
+            .dfa_size_limit(MAX_REGEX_SIZE)
+            .build()
+        {
+            Ok(regex) => Box::new(move |value| regex.is_match(value)),
+            Err(e) => return HttpResponse::BadRequest().body(format!("Invalid regex: {e}")),
+        }
+    } else {
+        let pattern = query.q.to_lowercase().chars().collect::<Vec<_>>();
+        Box::new(move |value| {
+            glob_matches(&pattern, &value.to_lowercase().chars().collect::<Vec<_>>())
+        })
+    };
+    let results = all_responses(&app_state, &tenant_scope)
+        .await
+        .into_iter()
+        .filter(|response| !response.deleted)
+        .filter(|response| {
+            matcher(&response.host_path)
+                || response.annotations.values().any(|value| match value {
+                    Value::String(value) => matcher(value),
+                    value => matcher(&value.to_string()),
+                })
+        })
+        .collect::<Vec<_>>();
+    HttpResponse::Ok().json(results)
+}
+
+/**
+Return `true` if the whole `value` matches the glob `pattern`.
+
+Uses backtracking to the last `*` only, which runs in `O(pattern * value)` time.
+ */
+fn glob_matches(pattern: &[char], value: &[char]) -> bool {
+    let (mut p, mut v) = (0, 0);
+    let mut last_star = None;
+    while v < value.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                last_star = Some((p, v));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == value[v] => {
+                p += 1;
+                v += 1;
+            }
+            _ => match last_star {
+                Some((star_p, star_v)) => {
+                    p = star_p + 1;
+                    v = star_v + 1;
+                    last_star = Some((star_p, star_v + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
@@ -0,0 +1,245 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Scoping of API responses to the namespaces and groups of a tenant.
+
+use actix_web::dev::Payload;
+use actix_web::http::header;
+use actix_web::web::Data;
+use actix_web::{FromRequest, HttpMessage, HttpRequest};
+use std::future::{ready, Ready};
+use std::sync::Arc;
+
+use super::AppState;
+use crate::ingress_monitor::IngressHostPath;
+use crate::ingress_monitor::IngressMonitor;
+use crate::ingress_monitor::GROUP_ANNOTATION;
+use crate::ingress_monitor::TEAM_ANNOTATION;
+
+/// Header with an API key as an alternative to `Authorization: Bearer <key>`.
+pub const API_KEY_HEADER: &str = "X-API-Key";
+/// Scope prefix matching entries by their `group` (or `team`) annotation.
+const GROUP_SCOPE_PREFIX: &str = "group:";
+
+/**
+Namespaces and groups whose entries an authenticated tenant may see.
+
+Extracted from requests by resources that expose entries or artifacts derived
+from them. Unrestricted when API requests are not authenticated.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TenantScope {
+    /// `true` if everything is visible.
+    unrestricted: bool,
+    /// Visible namespaces.
+    namespaces: Vec<String>,
+    /// Visible values of the `group` (or `team`) annotation.
+    groups: Vec<String>,
+}
+
+impl TenantScope {
+    /// Return a scope where everything is visible.
+    pub fn unrestricted() -> Self {
+        Self {
+            unrestricted: true,
+            ..Self::default()
+        }
+    }
+
+    /// Parse a comma separated list of namespaces, `group:<name>` and `*`.
+    fn parse(value: &str) -> Self {
+        let mut ret = Self::default();
+        for item in value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+        {
+            if item == "*" {
+                ret.unrestricted = true;
+            } else if let Some(group) = item.strip_prefix(GROUP_SCOPE_PREFIX) {
+                ret.groups.push(group.trim().to_owned());
+            } else {
+                ret.namespaces.push(item.to_owned());
+            }
+        }
+        ret
+    }
+
+    /// Return `true` if everything is visible.
+    pub fn is_unrestricted(&self) -> bool {
+        self.unrestricted
+    }
+
+    /**
+      Return `true` if resources in the namespace are visible.
+
+      Tenants that are only scoped to groups can't see namespaced resources
+      that are not entries, like change events and resource errors.
+    */
+    pub fn allows_namespace(&self, namespace: &str) -> bool {
+        self.unrestricted || self.namespaces.iter().any(|allowed| allowed == namespace)
+    }
+
+    /// Return `true` if entries with the `group` (or `team`) annotation value are visible.
+    pub fn allows_group(&self, group: Option<&str>) -> bool {
+        self.unrestricted
+            || group.is_some_and(|group| self.groups.iter().any(|allowed| allowed == group))
+    }
+
+    /// Return `true` if the local entry is visible.
+    pub async fn allows_entry(
+        &self,
+        ingress_monitor: &Arc<IngressMonitor>,
+        entry: &Arc<IngressHostPath>,
+    ) -> bool {
+        if self.unrestricted || self.allows_namespace(entry.namespace()) {
+            return true;
+        }
+        if self.groups.is_empty() {
+            return false;
+        }
+        let annotations = ingress_monitor.entry_annotations(entry).await;
+        let group = [GROUP_ANNOTATION, TEAM_ANNOTATION]
+            .iter()
+            .find_map(|key| annotations.get(*key))
+            .map(|value| value.trim());
+        self.allows_group(group)
+    }
+
+    /**
+      Return the scope of the tenant identified by the API key of the request
+      to a resource that does not require authentication.
+
+      The scope is empty for requests without a known API key and unrestricted
+      if API requests are not authenticated.
+    */
+    pub fn identify(req: &HttpRequest) -> Self {
+        let Some(app_state) = req.app_data::<Data<AppState>>() else {
+            return Self::unrestricted();
+        };
+        match &app_state.reloadable().tenants {
+            Some(tenants) => tenants.authenticate(req).unwrap_or_default(),
+            None => Self::unrestricted(),
+        }
+    }
+
+    /// Return the visible local entries.
+    pub async fn entries(
+        &self,
+        ingress_monitor: &Arc<IngressMonitor>,
+    ) -> Vec<Arc<IngressHostPath>> {
+        let mut ret = vec![];
+        for entry in ingress_monitor.get_all() {
+            if self.allows_entry(ingress_monitor, &entry).await {
+                ret.push(entry);
+            }
+        }
+        ret
+    }
+}
+
+impl FromRequest for TenantScope {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    /**
+      Return the scope of the authenticated tenant, an unrestricted scope if
+      API requests are not authenticated or an empty scope for requests that
+      were not authenticated.
+    */
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let authenticated = req
+            .app_data::<Data<AppState>>()
+            .is_some_and(|app_state| app_state.reloadable().tenants.is_some());
+        ready(Ok(if authenticated {
+            req.extensions()
+                .get::<TenantScope>()
+                .cloned()
+                .unwrap_or_default()
+        } else {
+            Self::unrestricted()
+        }))
+    }
+}
+
+/// API keys and the [TenantScope] of each tenant.
+#[derive(Debug, Default)]
+pub struct Tenants {
+    /// API key and scope of each tenant.
+    scopes: Vec<(String, TenantScope)>,
+}
+
+impl Tenants {
+    /**
+      Load the tenants file at `path`. See
+      [ApiConfig::tenants_path](crate::conf::ApiConfig::tenants_path).
+    */
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read tenants from '{path}': {e}"))?;
+        let mut scopes = vec![];
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((api_key, scope)) = line.split_once(char::is_whitespace) else {
+                return Err(format!(
+                    "Line {} of '{path}' must be formatted as '<api-key> <scope>[,<scope>...]'.",
+                    index + 1
+                ));
+            };
+            scopes.push((api_key.to_owned(), TenantScope::parse(scope)));
+        }
+        Ok(Self { scopes })
+    }
+
+    /**
+      Return the scope of the API key in the `Authorization: Bearer` or
+      `X-API-Key` header of the request or `None` if the key is missing or
+      unknown.
+    */
+    pub fn authenticate(&self, req: &HttpRequest) -> Option<TenantScope> {
+        let api_key = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .or_else(|| {
+                req.headers()
+                    .get(API_KEY_HEADER)
+                    .and_then(|value| value.to_str().ok())
+            })?
+            .trim();
+        // Compare with every key to not reveal which keys exist by timing
+        self.scopes
+            .iter()
+            .fold(None, |found, (candidate, scope)| {
+                if Self::constant_time_eq(candidate.as_bytes(), api_key.as_bytes()) {
+                    Some(scope)
+                } else {
+                    found
+                }
+            })
+            .cloned()
+    }
+
+    /// Return `true` if the byte slices are equal, in time only dependent on their lengths.
+    fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+    }
+}
@@ -0,0 +1,190 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Embedded HTML dashboard for a quick visual check of the catalogue.
+
+use actix_web::http::header::ContentType;
+use actix_web::web::Data;
+use actix_web::{get, HttpResponse, Responder};
+use k8s_openapi::chrono::{DateTime, SecondsFormat};
+use serde_json::Value;
+use std::fmt::Write;
+
+use super::api_resources::all_responses;
+use super::model::IngressHostPathResponse;
+use super::tenant_scope::TenantScope;
+use super::AppState;
+use crate::ingress_monitor::NamespaceHealth;
+
+/// Style sheet of the dashboard.
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:2em;width:100%}\
+th,td{border:1px solid #ccc;padding:.3em .6em;text-align:left;vertical-align:top}\
+th{background:#eee}.up{color:#070}.down{color:#b00}\
+ul{margin:0;padding-left:1.2em}code{font-size:.9em}";
+
+/**
+Return a read-only HTML dashboard of all cataloged entries with their
+annotations, availability, state and last update, and of the health of each
+monitored namespace.
+
+The dashboard is rendered from the same data as the API. When tenants are
+configured, requests are authenticated like API requests and only show what
+is visible to the tenant.
+ */
+#[cfg_attr(feature = "openapi", utoipa::path(
+    context_path = "/ui",
+    tag = "ui",
+    responses(
+        (status = 200, description = "Ok", content_type = "text/html",),
+        (status = 401, description = "Tenants are configured and the request has no known API key."),
+    ),
+))]
+#[get("")]
+pub async fn dashboard(app_state: Data<AppState>, tenant_scope: TenantScope) -> impl Responder {
+    let entries = all_responses(&app_state, &tenant_scope).await;
+    let mut namespace_health = app_state.ingress_monitor.namespace_health();
+    namespace_health
+        .retain(|namespace_health| tenant_scope.allows_namespace(&namespace_health.namespace));
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{name}</title>\
+         <style>{STYLE}</style></head><body><h1>{name}</h1>",
+        name = escape(env!("CARGO_PKG_NAME")),
+    );
+    render_entries(&mut html, &entries);
+    render_namespaces(&mut html, &namespace_health);
+    html.push_str("</body></html>");
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(html)
+}
+
+/// Append a table of the `entries` to the `html`.
+fn render_entries(html: &mut String, entries: &[IngressHostPathResponse]) {
+    let _ = write!(html, "<h2>Entries ({})</h2>", entries.len());
+    html.push_str(
+        "<table><tr><th>URL</th><th>Cluster</th><th>State</th><th>Available</th>\
+         <th>Replicas</th><th>Updated</th><th>Annotations</th></tr>",
+    );
+    for entry in entries {
+        let available = match entry.available {
+            Some(true) => "<span class=\"up\">yes</span>",
+            Some(false) => "<span class=\"down\">no</span>",
+            None => "unknown",
+        };
+        let _ = write!(
+            html,
+            "<tr><td>{url}</td><td>{cluster}</td><td>{state:?}</td>\
+             <td>{available}</td><td>{ready}/{replicas}</td><td>{updated}</td><td><ul>",
+            url = render_url(&entry.url),
+            cluster = escape(&entry.cluster),
+            state = entry.state,
+            ready = entry.ready_replicas,
+            replicas = entry.replicas,
+            updated = format_millis(entry.updated),
+        );
+        let mut annotations = entry.annotations.iter().collect::<Vec<_>>();
+        annotations.sort_by(|a, b| a.0.cmp(b.0));
+        for (key, value) in annotations {
+            let value = match value {
+                Value::String(value) => value.to_owned(),
+                value => value.to_string(),
+            };
+            let _ = write!(
+                html,
+                "<li><code>{}</code>: {}</li>",
+                escape(key),
+                escape(&value)
+            );
+        }
+        html.push_str("</ul></td></tr>");
+    }
+    html.push_str("</table>");
+}
+
+/// Append a table of the health of each monitored namespace to the `html`.
+fn render_namespaces(html: &mut String, namespace_health: &[NamespaceHealth]) {
+    let _ = write!(html, "<h2>Namespaces ({})</h2>", namespace_health.len());
+    html.push_str(
+        "<table><tr><th>Cluster</th><th>Namespace</th><th>Status</th>\
+         <th>Last event</th><th>Last error</th></tr>",
+    );
+    for namespace_health in namespace_health {
+        let status = if namespace_health.is_healthy() {
+            "<span class=\"up\">UP</span>"
+        } else {
+            "<span class=\"down\">DOWN</span>"
+        };
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{status}</td><td>{}</td><td>{}</td></tr>",
+            escape(&namespace_health.cluster),
+            escape(&namespace_health.namespace),
+            namespace_health
+                .last_event_millis
+                .map(format_millis)
+                .unwrap_or_default(),
+            escape(namespace_health.last_error.as_deref().unwrap_or_default()),
+        );
+    }
+    html.push_str("</table>");
+}
+
+/// Return the milliseconds since Unix Epoch as an RFC 3339 timestamp.
+fn format_millis(millis: u64) -> String {
+    i64::try_from(millis)
+        .ok()
+        .and_then(DateTime::from_timestamp_millis)
+        .map(|date_time| date_time.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_default()
+}
+
+/**
+Return the `url` as a link if it is an `http` or `https` URL.
+
+Any other URL, e.g. a `javascript:` URL announced by a federated peer, is
+rendered as escaped text.
+ */
+fn render_url(url: &str) -> String {
+    let scheme = url.split_once("://").map(|(scheme, _)| scheme);
+    if scheme.is_some_and(|scheme| {
+        scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https")
+    }) {
+        let url = escape(url);
+        format!("<a href=\"{url}\">{url}</a>")
+    } else {
+        escape(url)
+    }
+}
+
+/// Return the `text` with HTML special characters escaped.
+fn escape(text: &str) -> String {
+    let mut ret = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\'' => ret.push_str("&#39;"),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
@@ -0,0 +1,292 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Supervision of long running background tasks.
+
+use crossbeam_skiplist::SkipMap;
+use futures::FutureExt;
+use serde::Serialize;
+use std::any::Any;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::task::{AbortHandle, JoinHandle};
+
+use crate::backoff::ExponentialBackoff;
+use crate::error_reporting::ErrorReport;
+
+/// Number of consecutive failures after which a task is considered unhealthy.
+const MAX_CONSECUTIVE_FAILURES: u64 = 5;
+
+tokio::task_local! {
+    /// Name of the supervised task that is currently running.
+    static CURRENT_TASK: String;
+}
+
+/// State of a supervised task.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TaskState {
+    /// The task is running.
+    Running,
+    /// The task died and is waiting to be restarted.
+    Restarting,
+    /// The task finished on its own.
+    Completed,
+    /// The task was aborted on request.
+    Aborted,
+}
+
+/// Book keeping of a single supervised task.
+pub struct SupervisedTask {
+    /// Unique name of the task.
+    name: String,
+    /// Current state of the task.
+    state: Mutex<TaskState>,
+    /// Total number of restarts.
+    restarts: AtomicU64,
+    /// Number of failures since the task last ran successfully for a while.
+    consecutive_failures: AtomicU64,
+    /// Handle used to abort the supervision of the task.
+    supervision_abort_handle: Mutex<Option<AbortHandle>>,
+    /// Handle used to abort the currently running incarnation of the task.
+    abort_handle: Mutex<Option<AbortHandle>>,
+}
+
+impl SupervisedTask {
+    /// Return a new instance.
+    fn new(name: &str) -> Arc<Self> {
+        Arc::new(Self {
+            name: name.to_owned(),
+            state: Mutex::new(TaskState::Running),
+            restarts: AtomicU64::new(0),
+            consecutive_failures: AtomicU64::new(0),
+            supervision_abort_handle: Mutex::new(None),
+            abort_handle: Mutex::new(None),
+        })
+    }
+
+    /// Return the unique name of the task.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the current state of the task.
+    pub fn state(&self) -> TaskState {
+        *self.state.lock().unwrap()
+    }
+
+    /// Return the total number of restarts of the task.
+    pub fn restarts(&self) -> u64 {
+        self.restarts.load(Ordering::Relaxed)
+    }
+
+    /// Return `true` unless the task keeps dying.
+    pub fn is_healthy(&self) -> bool {
+        self.consecutive_failures.load(Ordering::Relaxed) < MAX_CONSECUTIVE_FAILURES
+    }
+
+    /// Update the state of the task.
+    fn set_state(&self, state: TaskState) {
+        *self.state.lock().unwrap() = state;
+    }
+
+    /// Abort supervision and the currently running task.
+    fn abort(&self) {
+        self.set_state(TaskState::Aborted);
+        [&self.supervision_abort_handle, &self.abort_handle]
+            .iter()
+            .filter_map(|abort_handle| abort_handle.lock().unwrap().take())
+            .for_each(|abort_handle| abort_handle.abort());
+    }
+}
+
+/**
+Owner of all long running background tasks.
+
+A task that dies (panics or is cancelled by the runtime) is restarted with
+exponential backoff, while a task that finishes on its own is left alone.
+ */
+#[derive(Default)]
+pub struct Supervisor {
+    /// Supervised tasks by unique name.
+    tasks: SkipMap<String, Arc<SupervisedTask>>,
+}
+
+impl Supervisor {
+    /// Return a new instance.
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /**
+    Spawn a supervised task named `name` that will be (re-)created by
+    `task_factory`.
+
+    Any previous task with the same name is aborted.
+     */
+    pub fn spawn<F, Fut>(self: &Arc<Self>, name: &str, task_factory: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let task = SupervisedTask::new(name);
+        if let Some(previous) = self.tasks.get(name).map(|entry| Arc::clone(entry.value())) {
+            previous.abort();
+        }
+        self.tasks.insert(name.to_owned(), Arc::clone(&task));
+        let task_clone = Arc::clone(&task);
+        let join_handle = spawn_named(&format!("supervise {name}"), async move {
+            let task = task_clone;
+            let mut backoff = ExponentialBackoff::default();
+            loop {
+                let started = Instant::now();
+                let join_handle = spawn_named(
+                    &task.name,
+                    CURRENT_TASK.scope(task.name.to_owned(), task_factory()),
+                );
+                task.abort_handle
+                    .lock()
+                    .unwrap()
+                    .replace(join_handle.abort_handle());
+                task.set_state(TaskState::Running);
+                match join_handle.await {
+                    Ok(()) => {
+                        log::debug!("Supervised task '{}' completed.", task.name);
+                        task.set_state(TaskState::Completed);
+                        return;
+                    }
+                    Err(e) => {
+                        if task.state() == TaskState::Aborted {
+                            return;
+                        }
+                        if started.elapsed() > backoff.max_delay() {
+                            backoff.reset();
+                            task.consecutive_failures.store(0, Ordering::Relaxed);
+                        }
+                        task.set_state(TaskState::Restarting);
+                        task.restarts.fetch_add(1, Ordering::Relaxed);
+                        task.consecutive_failures.fetch_add(1, Ordering::Relaxed);
+                        let delay = backoff.next_delay();
+                        log::error!(
+                            "Supervised task '{}' died and will be restarted in {delay:?}: {e:?}",
+                            task.name
+                        );
+                        if !e.is_panic() {
+                            // Panics are reported by the panic hook with more details
+                            crate::error_reporting::report(ErrorReport {
+                                kind: "task_died",
+                                message: format!("{e}"),
+                                task: Some(task.name.to_owned()),
+                                ..ErrorReport::default()
+                            });
+                        }
+                        tokio::time::sleep(delay).await;
+                    }
+                }
+            }
+        });
+        task.supervision_abort_handle
+            .lock()
+            .unwrap()
+            .replace(join_handle.abort_handle());
+    }
+
+    /// Abort the supervised task named `name` and stop tracking it.
+    pub fn abort(&self, name: &str) {
+        if let Some(entry) = self.tasks.remove(name) {
+            entry.value().abort();
+        }
+    }
+
+    /**
+    Abort the currently running incarnation of the task named `name`, so it is
+    restarted like a task that died.
+
+    Returns `false` if there is no such task.
+     */
+    pub fn restart(&self, name: &str) -> bool {
+        let Some(entry) = self.tasks.get(name) else {
+            return false;
+        };
+        if let Some(abort_handle) = entry.value().abort_handle.lock().unwrap().as_ref() {
+            abort_handle.abort();
+        }
+        true
+    }
+
+    /// Return all currently supervised tasks.
+    pub fn tasks(&self) -> Vec<Arc<SupervisedTask>> {
+        self.tasks
+            .iter()
+            .map(|entry| Arc::clone(entry.value()))
+            .collect()
+    }
+
+    /// Return `true` unless any of the supervised tasks keeps dying.
+    pub fn is_healthy(&self) -> bool {
+        self.tasks.iter().all(|entry| entry.value().is_healthy())
+    }
+}
+
+/// Return the name of the supervised task that is currently running, if any.
+pub fn current_task_name() -> Option<String> {
+    CURRENT_TASK.try_with(Clone::clone).ok()
+}
+
+/**
+Run the `future` and return the message of any panic as an error instead of
+letting it end the task.
+ */
+pub async fn catch_panic<Fut: Future>(future: Fut) -> Result<Fut::Output, String> {
+    AssertUnwindSafe(future)
+        .catch_unwind()
+        .await
+        .map_err(|payload| panic_message(payload.as_ref()))
+}
+
+/// Return the message of a panic `payload`.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_default()
+}
+
+/**
+Spawn the future as a task that is named in `tokio-console` when built with
+the `console` feature and `--cfg tokio_unstable`.
+ */
+fn spawn_named<Fut>(name: &str, future: Fut) -> JoinHandle<()>
+where
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    #[cfg(all(tokio_unstable, feature = "console"))]
+    return tokio::task::Builder::new()
+        .name(name)
+        .spawn(future)
+        .expect("Tasks are only spawned from a running Tokio runtime.");
+    #[cfg(not(all(tokio_unstable, feature = "console")))]
+    {
+        let _ = name;
+        tokio::spawn(future)
+    }
+}
@@ -16,8 +16,94 @@
 */
 
 //! Time utilities.
+//!
+//! Timestamps exposed to API clients use the wall clock, while elapsed time
+//! (like expiry of tombstones) is measured with monotonic [Instant]s, so wall
+//! clock jumps (e.g. NTP corrections) can't expire or extend anything early.
+//!
+//! Monitors get the time from an injected [Clock], so tests can control it
+//! with a [MockClock].
 
-use std::time::{Duration, SystemTime};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Source of the current time.
+pub trait Clock: Send + Sync {
+    /// Return elapsed milliseconds since Unix Epoch time according to the wall clock.
+    fn now_as_millis(&self) -> u64;
+
+    /// Return the current monotonic instant for measuring elapsed time.
+    fn instant(&self) -> Instant;
+}
+
+/// [Clock] backed by the operating system.
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_as_millis(&self) -> u64 {
+        now_as_millis()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Return the [Clock] backed by the operating system.
+pub fn system_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+/**
+[Clock] that only moves when told to.
+
+The wall clock can also be set independently of the monotonic time to
+simulate wall clock jumps.
+ */
+#[derive(Debug)]
+pub struct MockClock {
+    /// Monotonic instant when the clock was created.
+    started: Instant,
+    /// Elapsed monotonic milliseconds since `started`.
+    elapsed_millis: AtomicU64,
+    /// Current wall clock time in milliseconds since Unix Epoch.
+    wall_millis: AtomicU64,
+}
+
+impl MockClock {
+    /// Return a new instance where the wall clock starts at `wall_millis`.
+    pub fn new(wall_millis: u64) -> Arc<Self> {
+        Arc::new(Self {
+            started: Instant::now(),
+            elapsed_millis: AtomicU64::new(0),
+            wall_millis: AtomicU64::new(wall_millis),
+        })
+    }
+
+    /// Move both the wall clock and the monotonic time forward.
+    pub fn advance(&self, duration: Duration) {
+        let millis = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+        self.elapsed_millis.fetch_add(millis, Ordering::Relaxed);
+        self.wall_millis.fetch_add(millis, Ordering::Relaxed);
+    }
+
+    /// Set the wall clock without affecting the monotonic time.
+    pub fn set_wall_millis(&self, wall_millis: u64) {
+        self.wall_millis.store(wall_millis, Ordering::Relaxed);
+    }
+}
+
+impl Clock for MockClock {
+    fn now_as_millis(&self) -> u64 {
+        self.wall_millis.load(Ordering::Relaxed)
+    }
+
+    fn instant(&self) -> Instant {
+        self.started + Duration::from_millis(self.elapsed_millis.load(Ordering::Relaxed))
+    }
+}
 
 /// Return elapsed milliseconds since Unix Epoch time.
 pub fn now_as_millis() -> u64 {
@@ -0,0 +1,305 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Recording and replay of watch events.
+//!
+//! Race conditions seen in production can be reproduced locally by recording
+//! all received watch events to a JSONL file and feeding them back through
+//! the monitors with the same relative timing.
+
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use kube::api::PartialObjectMeta;
+use kube::core::ErrorResponse;
+use kube::runtime::watcher;
+use kube::runtime::watcher::{Config, Event};
+use kube::{Api, Resource};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Recording or replay of all watches. See [configure].
+static MODE: OnceLock<Mode> = OnceLock::new();
+
+/// What happens to the events of all watches.
+enum Mode {
+    /// Events are appended to a file.
+    Record(Mutex<BufWriter<File>>),
+    /// Events are read from a file instead of watching.
+    Replay(Replay),
+}
+
+/// A single line of a recording.
+#[derive(Debug, Deserialize, Serialize)]
+struct RecordedEvent {
+    /// Time when the event was received in milliseconds since Unix Epoch.
+    time: u64,
+    /// Resource URL and selectors of the watch.
+    watch: String,
+    /// `APPLIED`, `DELETED`, `RESTARTED` or `ERROR`.
+    event: String,
+    /// Objects of the event.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    objects: Vec<Value>,
+    /// Description of a failed watch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl RecordedEvent {
+    /// Return the recording of a received event.
+    fn from_result<T: Serialize>(watch: &str, result: &watcher::Result<Event<T>>) -> Self {
+        let to_value = |object: &T| serde_json::to_value(object).unwrap_or_default();
+        let (event, objects, error) = match result {
+            Ok(Event::Applied(object)) => ("APPLIED", vec![to_value(object)], None),
+            Ok(Event::Deleted(object)) => ("DELETED", vec![to_value(object)], None),
+            Ok(Event::Restarted(objects)) => {
+                ("RESTARTED", objects.iter().map(to_value).collect(), None)
+            }
+            Err(e) => ("ERROR", vec![], Some(e.to_string())),
+        };
+        Self {
+            time: crate::time::now_as_millis(),
+            watch: watch.to_owned(),
+            event: event.to_owned(),
+            objects,
+            error,
+        }
+    }
+
+    /// Return the recorded event or `None` if it can't be replayed.
+    fn into_result<T: DeserializeOwned>(self) -> Option<watcher::Result<Event<T>>> {
+        let objects = self
+            .objects
+            .into_iter()
+            .map(serde_json::from_value::<T>)
+            .collect::<Result<Vec<_>, _>>();
+        let mut objects = match objects {
+            Ok(objects) => objects,
+            Err(e) => {
+                log::warn!("Skipping recorded event of '{}': {e}", self.watch);
+                return None;
+            }
+        };
+        match self.event.as_str() {
+            "APPLIED" => objects.pop().map(|object| Ok(Event::Applied(object))),
+            "DELETED" => objects.pop().map(|object| Ok(Event::Deleted(object))),
+            "RESTARTED" => Some(Ok(Event::Restarted(objects))),
+            "ERROR" => Some(Err(watcher::Error::WatchError(ErrorResponse {
+                status: "Failure".to_owned(),
+                message: self.error.unwrap_or_default(),
+                reason: "Replayed".to_owned(),
+                code: 500,
+            }))),
+            other => {
+                log::warn!("Skipping recorded event of unknown type '{other}'.");
+                None
+            }
+        }
+    }
+}
+
+/// Recorded events that are fed back through the monitors.
+struct Replay {
+    /// Monotonic time when the replay started.
+    started: Instant,
+    /// Time when the first event was recorded in milliseconds since Unix Epoch.
+    first_millis: u64,
+    /// Events that are not replayed yet by watch.
+    pending: Mutex<HashMap<String, VecDeque<RecordedEvent>>>,
+}
+
+impl Replay {
+    /// Load all events from the recording at `path`.
+    fn load(path: &Path) -> Result<Self, std::io::Error> {
+        let mut pending = HashMap::<String, VecDeque<RecordedEvent>>::new();
+        let mut first_millis = u64::MAX;
+        let mut count = 0;
+        for (index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<RecordedEvent>(&line) {
+                Ok(recorded) => {
+                    first_millis = first_millis.min(recorded.time);
+                    count += 1;
+                    pending
+                        .entry(recorded.watch.to_owned())
+                        .or_default()
+                        .push_back(recorded);
+                }
+                Err(e) => log::warn!("Skipping invalid line {} of recording: {e}", index + 1),
+            }
+        }
+        log::info!(
+            "Replaying {count} events of {} watches from '{}'.",
+            pending.len(),
+            path.display()
+        );
+        Ok(Self {
+            started: Instant::now(),
+            first_millis,
+            pending: Mutex::new(pending),
+        })
+    }
+
+    /// Remove and return the next event of the `watch` that is not replayed yet.
+    fn next(&self, watch: &str) -> Option<RecordedEvent> {
+        self.pending
+            .lock()
+            .unwrap()
+            .get_mut(watch)
+            .and_then(VecDeque::pop_front)
+    }
+
+    /**
+      Return a stream of the recorded events of the `watch` that are due
+      relative to the start of the replay.
+
+      A restarted watch continues where the previous one stopped and the
+      stream never ends, just like a watch without any changes.
+    */
+    fn stream<T>(&'static self, watch: String) -> BoxStream<'static, watcher::Result<Event<T>>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        futures::stream::unfold(watch, move |watch| async move {
+            loop {
+                let recorded = self.next(&watch)?;
+                let offset = Duration::from_millis(recorded.time.saturating_sub(self.first_millis));
+                tokio::time::sleep_until(tokio::time::Instant::from_std(self.started + offset))
+                    .await;
+                if let Some(result) = recorded.into_result() {
+                    return Some((result, watch));
+                }
+            }
+        })
+        .chain(futures::stream::pending())
+        .boxed()
+    }
+}
+
+/**
+Record events of all watches to the file at `record_path` or replay the
+recording at `replay_path` instead of watching.
+
+Only the first call has any effect, since recording and replay is process wide.
+ */
+pub fn configure(record_path: Option<&Path>, replay_path: Option<&Path>) {
+    let mode = if let Some(path) = replay_path {
+        match Replay::load(path) {
+            Ok(replay) => Mode::Replay(replay),
+            Err(e) => {
+                log::error!("Unable to replay '{}': {e}", path.display());
+                return;
+            }
+        }
+    } else if let Some(path) = record_path {
+        match File::create(path) {
+            Ok(file) => {
+                log::info!("Recording all watch events to '{}'.", path.display());
+                Mode::Record(Mutex::new(BufWriter::new(file)))
+            }
+            Err(e) => {
+                log::error!("Unable to record to '{}': {e}", path.display());
+                return;
+            }
+        }
+    } else {
+        return;
+    };
+    if MODE.set(mode).is_err() {
+        log::debug!("Recording or replay of watch events is already configured.");
+    }
+}
+
+/// Return `true` if recorded events are replayed instead of watching.
+pub fn is_replaying() -> bool {
+    matches!(MODE.get(), Some(Mode::Replay(_)))
+}
+
+/// Like [kube::runtime::watcher], but subject to recording or replay.
+pub fn watcher<K>(
+    api: Api<K>,
+    watcher_config: Config,
+) -> BoxStream<'static, watcher::Result<Event<K>>>
+where
+    K: Resource + Clone + DeserializeOwned + Serialize + Debug + Send + 'static,
+{
+    let watch = watch_key(api.resource_url(), &watcher_config);
+    tap(watch, move || kube::runtime::watcher(api, watcher_config))
+}
+
+/// Like [kube::runtime::metadata_watcher], but subject to recording or replay.
+pub fn metadata_watcher<K>(
+    api: Api<K>,
+    watcher_config: Config,
+) -> BoxStream<'static, watcher::Result<Event<PartialObjectMeta<K>>>>
+where
+    K: Resource + Clone + DeserializeOwned + Debug + Send + 'static,
+    PartialObjectMeta<K>: Serialize + DeserializeOwned,
+{
+    let watch = watch_key(api.resource_url(), &watcher_config);
+    tap(watch, move || {
+        kube::runtime::metadata_watcher(api, watcher_config)
+    })
+}
+
+/// Return the identity of a watch in recordings.
+fn watch_key(resource_url: &str, watcher_config: &Config) -> String {
+    format!(
+        "{resource_url}?labelSelector={}&fieldSelector={}",
+        watcher_config.label_selector.as_deref().unwrap_or_default(),
+        watcher_config.field_selector.as_deref().unwrap_or_default()
+    )
+}
+
+/// Return the `live` stream of events with recording or the replayed events.
+fn tap<T, S>(
+    watch: String,
+    live: impl FnOnce() -> S,
+) -> BoxStream<'static, watcher::Result<Event<T>>>
+where
+    T: Serialize + DeserializeOwned + Send + 'static,
+    S: futures::Stream<Item = watcher::Result<Event<T>>> + Send + 'static,
+{
+    match MODE.get() {
+        Some(Mode::Replay(replay)) => replay.stream(watch),
+        Some(Mode::Record(writer)) => live()
+            .inspect(move |result| {
+                let recorded = RecordedEvent::from_result(&watch, result);
+                let line = serde_json::to_string(&recorded).unwrap_or_default() + "\n";
+                let mut writer = writer.lock().unwrap();
+                if let Err(e) = writer
+                    .write_all(line.as_bytes())
+                    .and_then(|()| writer.flush())
+                {
+                    log::warn!("Failed to record watch event: {e}");
+                }
+            })
+            .boxed(),
+        None => live().boxed(),
+    }
+}
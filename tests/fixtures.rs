@@ -0,0 +1,105 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Monitoring of `Ingress`es declared in local fixture files.
+
+mod harness;
+
+use microfefind::conf::AppConfig;
+use microfefind::ingress_monitor::IngressMonitor;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use self::harness::eventually;
+
+/// Return a new empty directory for fixture files that is unique to the test.
+fn fixture_directory(test_name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!(
+        "microfefind-fixtures-{test_name}-{}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// Write an `Ingress` manifest that maps `host` and `path` to the `app` `Service`.
+fn write_ingress(directory: &Path, host: &str, path: &str) {
+    let manifest = format!(
+        r#"---
+apiVersion: networking.k8s.io/v1
+kind: Ingress
+metadata:
+  name: app
+  labels:
+    microfe: "true"
+spec:
+  rules:
+  - host: {host}
+    http:
+      paths:
+      - path: {path}
+        pathType: Prefix
+        backend:
+          service:
+            name: app
+            port:
+              number: 80
+"#
+    );
+    std::fs::write(directory.join("app.yaml"), manifest).unwrap();
+}
+
+/// Start monitoring of the fixture files in the `directory`.
+fn start_monitoring(directory: &Path) -> Arc<IngressMonitor> {
+    let overrides = [
+        ("source.kind".to_owned(), "static".to_owned()),
+        (
+            "source.fixtures".to_owned(),
+            directory.to_string_lossy().into_owned(),
+        ),
+    ];
+    let app_config = AppConfig::try_with_overrides(None, &overrides).unwrap();
+    IngressMonitor::new(Arc::new(app_config))
+}
+
+/// Return `true` if the host and path is currently in the catalogue.
+fn is_cataloged(ingress_monitor: &Arc<IngressMonitor>, host: &str, path: &str) -> bool {
+    ingress_monitor
+        .get_all()
+        .iter()
+        .any(|entry| entry.host_and_path() == (host, path))
+}
+
+#[tokio::test]
+async fn fixture_ingress_is_cataloged_and_follows_changes() {
+    let directory = fixture_directory("changes");
+    write_ingress(&directory, "app.example.com", "/app");
+    let ingress_monitor = start_monitoring(&directory);
+    eventually("Ingress from fixture is cataloged", || async {
+        is_cataloged(&ingress_monitor, "app.example.com", "/app")
+    })
+    .await;
+    assert!(ingress_monitor.is_health_ready());
+    write_ingress(&directory, "app.example.com", "/moved");
+    eventually("changed fixture is reloaded", || async {
+        is_cataloged(&ingress_monitor, "app.example.com", "/moved")
+            && !is_cataloged(&ingress_monitor, "app.example.com", "/app")
+    })
+    .await;
+    std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,386 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! In-memory stand-in for the Kubernetes API server used by integration tests.
+
+// Each test binary uses a different subset of the harness
+#![allow(dead_code)]
+
+use bytes::Bytes;
+use futures::StreamExt;
+use http::{Request, Response, StatusCode};
+use http_body_util::combinators::UnsyncBoxBody;
+use http_body_util::{BodyExt, Full, StreamBody};
+use kube::ResourceExt;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+
+/// Response body of the fake API server.
+type Body = UnsyncBoxBody<Bytes, Infallible>;
+
+/// A change of an object in a collection.
+#[derive(Clone, Debug)]
+enum Change {
+    /// Object stored before and after the change (`None` if absent).
+    Object {
+        collection: String,
+        resource_version: u64,
+        old: Option<Value>,
+        new: Option<Value>,
+    },
+    /// All watches of the collection are expired with `410 Gone`.
+    Expired { collection: String },
+}
+
+/// Objects and the history of changes.
+#[derive(Default)]
+struct Store {
+    /// Objects by name in each collection path.
+    objects: BTreeMap<String, BTreeMap<String, Value>>,
+    /// All changes so far, used to replay changes to watches that start late.
+    history: Vec<Change>,
+}
+
+/**
+Fake Kubernetes API server that serves list and watch requests from memory.
+
+Objects are stored by the collection path of the resource, so any resource
+kind can be served. Lists and watches support equality based label selectors
+and the `metadata.name` field selector, which is all that the monitoring uses.
+ */
+pub struct FakeApiServer {
+    store: Mutex<Store>,
+    changes: broadcast::Sender<Change>,
+    resource_version: AtomicU64,
+}
+
+impl FakeApiServer {
+    /// Return a new instance without any objects.
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            store: Mutex::new(Store::default()),
+            changes: broadcast::channel(1024).0,
+            resource_version: AtomicU64::new(1),
+        })
+    }
+
+    /// Return a client that sends all requests to this fake API server.
+    pub fn client(self: &Arc<Self>) -> kube::Client {
+        let self_clone = Arc::clone(self);
+        let service = tower::service_fn(move |request: Request<kube::client::Body>| {
+            let self_clone = Arc::clone(&self_clone);
+            async move { Ok::<_, Infallible>(self_clone.handle(request)) }
+        });
+        kube::Client::new(service, "default")
+    }
+
+    /// Create or replace the object and notify watches.
+    pub fn apply<K>(&self, object: &K)
+    where
+        K: kube::Resource<DynamicType = ()> + Serialize,
+    {
+        let collection = K::url_path(&(), object.namespace().as_deref());
+        let mut value = serde_json::to_value(object).unwrap();
+        let mut store = self.store.lock().unwrap();
+        let old = store
+            .objects
+            .get(&collection)
+            .and_then(|objects| objects.get(&object.name_any()))
+            .cloned();
+        let resource_version = self.next_resource_version();
+        let metadata = value["metadata"].as_object_mut().unwrap();
+        metadata.insert(
+            "resourceVersion".into(),
+            resource_version.to_string().into(),
+        );
+        let uid = old
+            .as_ref()
+            .map(|old| old["metadata"]["uid"].clone())
+            .unwrap_or_else(|| format!("uid-{resource_version}").into());
+        metadata.entry("uid").or_insert(uid);
+        metadata
+            .entry("creationTimestamp")
+            .or_insert("2024-01-01T00:00:00Z".into());
+        store
+            .objects
+            .entry(collection.clone())
+            .or_default()
+            .insert(object.name_any(), value.clone());
+        self.publish(
+            &mut store,
+            Change::Object {
+                collection,
+                resource_version,
+                old,
+                new: Some(value),
+            },
+        );
+    }
+
+    /// Delete the object and notify watches.
+    pub fn delete<K>(&self, object: &K)
+    where
+        K: kube::Resource<DynamicType = ()>,
+    {
+        let collection = K::url_path(&(), object.namespace().as_deref());
+        let mut store = self.store.lock().unwrap();
+        let old = Self::remove(&mut store, &collection, &object.name_any());
+        let resource_version = self.next_resource_version();
+        self.publish(
+            &mut store,
+            Change::Object {
+                collection,
+                resource_version,
+                old,
+                new: None,
+            },
+        );
+    }
+
+    /**
+      Delete the object without notifying watches, like a deletion that
+      happens while a watch is disconnected.
+    */
+    pub fn delete_unobserved<K>(&self, object: &K)
+    where
+        K: kube::Resource<DynamicType = ()>,
+    {
+        let collection = K::url_path(&(), object.namespace().as_deref());
+        let mut store = self.store.lock().unwrap();
+        Self::remove(&mut store, &collection, &object.name_any());
+        self.next_resource_version();
+    }
+
+    /// Fail all current watches of the resource kind in the namespace with `410 Gone`.
+    pub fn expire_watches<K>(&self, namespace: Option<&str>)
+    where
+        K: kube::Resource<DynamicType = ()>,
+    {
+        let collection = K::url_path(&(), namespace);
+        let mut store = self.store.lock().unwrap();
+        self.publish(&mut store, Change::Expired { collection });
+    }
+
+    fn next_resource_version(&self) -> u64 {
+        self.resource_version.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn remove(store: &mut Store, collection: &str, name: &str) -> Option<Value> {
+        store
+            .objects
+            .get_mut(collection)
+            .and_then(|objects| objects.remove(name))
+    }
+
+    fn publish(&self, store: &mut Store, change: Change) {
+        store.history.push(change.clone());
+        // No receivers just means that nothing is watching yet
+        let _ = self.changes.send(change);
+    }
+
+    /// Serve a single request.
+    fn handle(&self, request: Request<kube::client::Body>) -> Response<Body> {
+        if request.method() != http::Method::GET {
+            return Self::status_response(StatusCode::NOT_FOUND);
+        }
+        let collection = request.uri().path().to_owned();
+        let query = form_urlencoded::parse(request.uri().query().unwrap_or("").as_bytes())
+            .into_owned()
+            .collect::<BTreeMap<String, String>>();
+        let selector = Selector {
+            labels: query.get("labelSelector").cloned().unwrap_or_default(),
+            fields: query.get("fieldSelector").cloned().unwrap_or_default(),
+        };
+        if query.get("watch").map(String::as_str) == Some("true") {
+            let resource_version = query
+                .get("resourceVersion")
+                .and_then(|resource_version| resource_version.parse().ok())
+                .unwrap_or(u64::MAX);
+            self.watch(collection, selector, resource_version)
+        } else {
+            self.list(&collection, &selector)
+        }
+    }
+
+    /// Return all matching objects in the collection.
+    fn list(&self, collection: &str, selector: &Selector) -> Response<Body> {
+        let store = self.store.lock().unwrap();
+        let items = store
+            .objects
+            .get(collection)
+            .map(|objects| {
+                objects
+                    .values()
+                    .filter(|object| selector.matches(object))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let list = json!({
+            "apiVersion": "v1",
+            "kind": "List",
+            "metadata": {
+                "resourceVersion": (self.resource_version.load(Ordering::Relaxed) - 1).to_string(),
+            },
+            "items": items,
+        });
+        Response::new(Full::new(Bytes::from(list.to_string())).boxed_unsync())
+    }
+
+    /// Stream changes of matching objects after the `resource_version`.
+    fn watch(
+        &self,
+        collection: String,
+        selector: Selector,
+        resource_version: u64,
+    ) -> Response<Body> {
+        let store = self.store.lock().unwrap();
+        let receiver = self.changes.subscribe();
+        let missed = store
+            .history
+            .iter()
+            .filter(|change| match change {
+                Change::Object {
+                    resource_version: change_version,
+                    ..
+                } => *change_version > resource_version,
+                Change::Expired { .. } => false,
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        drop(store);
+        let changes = futures::stream::iter(missed)
+            .chain(BroadcastStream::new(receiver).filter_map(|change| async { change.ok() }));
+        let events = changes
+            .filter_map(move |change| {
+                let event = selector.watch_event(&collection, change);
+                async move { event }
+            })
+            .map(|event| {
+                Ok::<_, Infallible>(http_body::Frame::data(Bytes::from(
+                    event.to_string() + "\n",
+                )))
+            });
+        Response::new(StreamBody::new(events).boxed_unsync())
+    }
+
+    fn status_response(status: StatusCode) -> Response<Body> {
+        let mut response = Response::new(Full::new(Bytes::from("{}")).boxed_unsync());
+        *response.status_mut() = status;
+        response
+    }
+}
+
+/// Label and field selector of a list or watch request.
+struct Selector {
+    labels: String,
+    fields: String,
+}
+
+impl Selector {
+    /// Return `true` if the object matches both selectors.
+    fn matches(&self, object: &Value) -> bool {
+        let labels_match = Self::requirements(&self.labels).all(|(key, value)| {
+            let label = &object["metadata"]["labels"][key];
+            match value {
+                Some(value) => label.as_str() == Some(value),
+                None => !label.is_null(),
+            }
+        });
+        let fields_match = Self::requirements(&self.fields).all(|(key, value)| {
+            let field = key
+                .split('.')
+                .fold(object, |object, segment| &object[segment]);
+            field.as_str() == value
+        });
+        labels_match && fields_match
+    }
+
+    /// Return `key=value` or `key` (exists) requirements of a selector.
+    fn requirements(selector: &str) -> impl Iterator<Item = (&str, Option<&str>)> {
+        selector
+            .split(',')
+            .filter(|requirement| !requirement.is_empty())
+            .map(|requirement| match requirement.split_once('=') {
+                Some((key, value)) => (key, Some(value.trim_start_matches('='))),
+                None => (requirement, None),
+            })
+    }
+
+    /// Return the watch event of the change as seen through this selector.
+    fn watch_event(&self, collection: &str, change: Change) -> Option<Value> {
+        match change {
+            Change::Expired {
+                collection: expired,
+            } if expired == collection => Some(json!({
+                "type": "ERROR",
+                "object": {
+                    "apiVersion": "v1",
+                    "kind": "Status",
+                    "metadata": {},
+                    "status": "Failure",
+                    "message": "too old resource version",
+                    "reason": "Expired",
+                    "code": 410,
+                },
+            })),
+            Change::Object {
+                collection: changed,
+                old,
+                new,
+                ..
+            } if changed == collection => {
+                let old_matches = old.as_ref().is_some_and(|old| self.matches(old));
+                let new_matches = new.as_ref().is_some_and(|new| self.matches(new));
+                let (event_type, object) = match (old_matches, new_matches) {
+                    (false, true) => ("ADDED", new?),
+                    (true, true) => ("MODIFIED", new?),
+                    (true, false) => ("DELETED", new.or(old)?),
+                    (false, false) => return None,
+                };
+                Some(json!({"type": event_type, "object": object}))
+            }
+            _ => None,
+        }
+    }
+}
+
+/**
+Poll `condition` until it holds or fail the test after a while.
+
+Monitoring happens in background tasks, so results are not immediate.
+ */
+pub async fn eventually<F, Fut>(description: &str, mut condition: F)
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = bool>,
+{
+    for _ in 0..100 {
+        if condition().await {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("Timed out waiting for: {description}");
+}
@@ -0,0 +1,299 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Monitoring of `Ingress`es and mapped `Service`s against a fake API server.
+
+mod harness;
+
+use k8s_openapi::api::discovery::v1::EndpointSlice;
+use k8s_openapi::api::networking::v1::Ingress;
+use microfefind::conf::AppConfig;
+use microfefind::ingress_monitor::{IngressMonitor, KubeCluster};
+use microfefind::notifier::ChangeKind;
+use microfefind::time::{Clock, MockClock};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+use self::harness::{eventually, FakeApiServer};
+
+/// Start monitoring of the fake API server's cluster with the default configuration.
+fn start_monitoring() -> (Arc<FakeApiServer>, Arc<IngressMonitor>) {
+    start_monitoring_with_clock(microfefind::time::system_clock())
+}
+
+/// Start monitoring of the fake API server's cluster using the `clock`.
+fn start_monitoring_with_clock(clock: Arc<dyn Clock>) -> (Arc<FakeApiServer>, Arc<IngressMonitor>) {
+    let server = FakeApiServer::new();
+    let cluster = KubeCluster::from_client("test", server.client());
+    let ingress_monitor =
+        IngressMonitor::with_clusters_and_clock(Arc::new(AppConfig::new()), &[cluster], clock);
+    (server, ingress_monitor)
+}
+
+/// Return an `Ingress` that maps `host` and `path` to the `Service` named `service_name`.
+fn ingress(name: &str, label_value: &str, host: &str, path: &str, service_name: &str) -> Ingress {
+    serde_json::from_value(json!({
+        "apiVersion": "networking.k8s.io/v1",
+        "kind": "Ingress",
+        "metadata": {
+            "name": name,
+            "namespace": "default",
+            "labels": { "microfe": label_value },
+        },
+        "spec": {
+            "rules": [{
+                "host": host,
+                "http": {
+                    "paths": [{
+                        "path": path,
+                        "pathType": "Prefix",
+                        "backend": { "service": { "name": service_name, "port": { "number": 80 } } },
+                    }],
+                },
+            }],
+        },
+    }))
+    .unwrap()
+}
+
+/// Return an `EndpointSlice` of the `Service` with the given endpoint readiness.
+fn endpoint_slice(service_name: &str, ready: &[bool]) -> EndpointSlice {
+    let endpoints = ready
+        .iter()
+        .enumerate()
+        .map(|(i, ready)| json!({ "addresses": [format!("10.0.0.{i}")], "conditions": { "ready": ready } }))
+        .collect::<Vec<_>>();
+    serde_json::from_value(json!({
+        "apiVersion": "discovery.k8s.io/v1",
+        "kind": "EndpointSlice",
+        "metadata": {
+            "name": service_name.to_owned() + "-abcde",
+            "namespace": "default",
+            "labels": { "kubernetes.io/service-name": service_name },
+        },
+        "addressType": "IPv4",
+        "endpoints": endpoints,
+    }))
+    .unwrap()
+}
+
+/// Return `true` if the host and path is currently in the catalogue.
+fn is_cataloged(ingress_monitor: &Arc<IngressMonitor>, host: &str, path: &str) -> bool {
+    ingress_monitor
+        .get_all()
+        .iter()
+        .any(|entry| entry.host_and_path() == (host, path))
+}
+
+#[tokio::test]
+async fn labeled_ingress_is_cataloged() {
+    let (server, ingress_monitor) = start_monitoring();
+    server.apply(&ingress("app", "true", "app.example.com", "/app", "app"));
+    server.apply(&ingress(
+        "other",
+        "false",
+        "app.example.com",
+        "/other",
+        "other",
+    ));
+    eventually("labeled Ingress is cataloged", || async {
+        is_cataloged(&ingress_monitor, "app.example.com", "/app")
+    })
+    .await;
+    assert!(!is_cataloged(&ingress_monitor, "app.example.com", "/other"));
+}
+
+#[tokio::test]
+async fn relabeled_ingress_is_removed() {
+    let (server, ingress_monitor) = start_monitoring();
+    server.apply(&ingress("app", "true", "app.example.com", "/app", "app"));
+    eventually("labeled Ingress is cataloged", || async {
+        is_cataloged(&ingress_monitor, "app.example.com", "/app")
+    })
+    .await;
+    server.apply(&ingress("app", "false", "app.example.com", "/app", "app"));
+    eventually("relabeled Ingress is removed", || async {
+        !is_cataloged(&ingress_monitor, "app.example.com", "/app")
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn deleted_ingress_is_removed() {
+    let (server, ingress_monitor) = start_monitoring();
+    let app = ingress("app", "true", "app.example.com", "/app", "app");
+    server.apply(&app);
+    eventually("labeled Ingress is cataloged", || async {
+        is_cataloged(&ingress_monitor, "app.example.com", "/app")
+    })
+    .await;
+    server.delete(&app);
+    eventually("deleted Ingress is removed", || async {
+        !is_cataloged(&ingress_monitor, "app.example.com", "/app")
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn changes_are_sequenced_and_resumable() {
+    let (server, ingress_monitor) = start_monitoring();
+    server.apply(&ingress("app", "true", "app.example.com", "/app", "app"));
+    eventually("labeled Ingress is cataloged", || async {
+        is_cataloged(&ingress_monitor, "app.example.com", "/app")
+    })
+    .await;
+    let page = ingress_monitor.changes_since(0);
+    assert!(!page.reset);
+    assert_eq!(page.events[0].kind, ChangeKind::Added);
+    let sequences = page.events.iter().map(|event| event.sequence);
+    assert!(sequences.eq(1..=page.cursor));
+    let resumed = ingress_monitor.changes_since(page.cursor);
+    assert!(!resumed.reset);
+    assert!(resumed
+        .events
+        .iter()
+        .all(|event| event.sequence > page.cursor));
+    // A cursor from the future (e.g. a previous instance) forces a resync
+    assert!(ingress_monitor.changes_since(u64::MAX).reset);
+}
+
+#[tokio::test]
+async fn ingress_deleted_during_watch_outage_is_removed_on_restart() {
+    let (server, ingress_monitor) = start_monitoring();
+    let app = ingress("app", "true", "app.example.com", "/app", "app");
+    server.apply(&app);
+    server.apply(&ingress(
+        "other",
+        "true",
+        "app.example.com",
+        "/other",
+        "other",
+    ));
+    eventually("labeled Ingresses are cataloged", || async {
+        is_cataloged(&ingress_monitor, "app.example.com", "/app")
+            && is_cataloged(&ingress_monitor, "app.example.com", "/other")
+    })
+    .await;
+    server.delete_unobserved(&app);
+    server.expire_watches::<Ingress>(Some("default"));
+    eventually("Ingress missing after re-list is removed", || async {
+        !is_cataloged(&ingress_monitor, "app.example.com", "/app")
+    })
+    .await;
+    assert!(is_cataloged(&ingress_monitor, "app.example.com", "/other"));
+}
+
+#[tokio::test]
+async fn ready_endpoints_make_entry_available() {
+    let (server, ingress_monitor) = start_monitoring();
+    server.apply(&ingress("app", "true", "app.example.com", "/app", "app"));
+    eventually("labeled Ingress is cataloged", || async {
+        is_cataloged(&ingress_monitor, "app.example.com", "/app")
+    })
+    .await;
+    let entry = ingress_monitor.get_all().pop().unwrap();
+    server.apply(&endpoint_slice("app", &[true, false]));
+    eventually("entry is available", || async {
+        entry.available() == Some(true)
+    })
+    .await;
+    assert_eq!(entry.replica_counts().await, (2, 1));
+    server.apply(&endpoint_slice("app", &[false, false]));
+    eventually("entry is unavailable", || async {
+        entry.available() == Some(false)
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn tombstone_expires_by_monotonic_time_only() {
+    let clock = MockClock::new(1_700_000_000_000);
+    let (server, ingress_monitor) = start_monitoring_with_clock(clock.clone());
+    let app = ingress("app", "true", "app.example.com", "/app", "app");
+    server.apply(&app);
+    eventually("labeled Ingress is cataloged", || async {
+        is_cataloged(&ingress_monitor, "app.example.com", "/app")
+    })
+    .await;
+    server.delete(&app);
+    eventually("deleted Ingress leaves a tombstone", || async {
+        ingress_monitor.get_tombstones().len() == 1
+    })
+    .await;
+    // A wall clock jump must not expire the tombstone
+    clock.set_wall_millis(1_800_000_000_000);
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(ingress_monitor.get_tombstones().len(), 1);
+    clock.advance(Duration::from_secs(61));
+    eventually("tombstone expires", || async {
+        ingress_monitor.get_tombstones().is_empty()
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn reloaded_host_filter_applies_to_existing_entries() {
+    let (server, ingress_monitor) = start_monitoring();
+    server.apply(&ingress(
+        "admin",
+        "true",
+        "internal.example.com",
+        "/admin",
+        "admin",
+    ));
+    eventually("labeled Ingress is cataloged", || async {
+        is_cataloged(&ingress_monitor, "internal.example.com", "/admin")
+    })
+    .await;
+    let reloaded = AppConfig::try_with_overrides(
+        None,
+        &[("ingress.hostdeny".to_owned(), "internal.*".to_owned())],
+    )
+    .unwrap();
+    assert_eq!(
+        ingress_monitor.reload_config(reloaded),
+        Ok(vec!["ingress.hostdeny".to_owned()])
+    );
+    ingress_monitor.reconcile_all().await;
+    eventually("denied host is removed", || async {
+        !is_cataloged(&ingress_monitor, "internal.example.com", "/admin")
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn reload_requiring_restart_is_rejected() {
+    let (_server, ingress_monitor) = start_monitoring();
+    let reloaded = AppConfig::try_with_overrides(
+        None,
+        &[
+            ("ingress.hostdeny".to_owned(), "internal.*".to_owned()),
+            ("catalogue.tombstonettl".to_owned(), "1".to_owned()),
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        ingress_monitor.reload_config(reloaded),
+        Err(vec!["catalogue.tombstonettl".to_owned()])
+    );
+    // Nothing is applied, not even the reloadable change
+    assert!(ingress_monitor
+        .running_config()
+        .ingress
+        .is_host_exposed("internal.example.com"));
+}
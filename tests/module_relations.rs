@@ -0,0 +1,154 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Dependency graph and semver compatibility of modules declared by `Ingress`es.
+
+mod harness;
+
+use k8s_openapi::api::networking::v1::Ingress;
+use microfefind::conf::AppConfig;
+use microfefind::ingress_monitor::{IngressMonitor, KubeCluster};
+use serde_json::json;
+use std::sync::Arc;
+
+use self::harness::{eventually, FakeApiServer};
+
+/// Start monitoring of the fake API server's cluster with the default configuration.
+fn start_monitoring() -> (Arc<FakeApiServer>, Arc<IngressMonitor>) {
+    let server = FakeApiServer::new();
+    let cluster = KubeCluster::from_client("test", server.client());
+    let ingress_monitor = IngressMonitor::with_clusters(Arc::new(AppConfig::new()), &[cluster]);
+    (server, ingress_monitor)
+}
+
+/// Return a labeled `Ingress` at `/{name}` providing the `module` with the given annotations.
+fn module_ingress(name: &str, module: &str, version: &str, requires: &str) -> Ingress {
+    serde_json::from_value(json!({
+        "apiVersion": "networking.k8s.io/v1",
+        "kind": "Ingress",
+        "metadata": {
+            "name": name,
+            "namespace": "default",
+            "labels": { "microfe": "true" },
+            "annotations": {
+                "microfe/name": module,
+                "microfe/version": version,
+                "microfe/requires": requires,
+            },
+        },
+        "spec": {
+            "rules": [{
+                "host": "shop.example.com",
+                "http": {
+                    "paths": [{
+                        "path": format!("/{name}"),
+                        "pathType": "Prefix",
+                        "backend": { "service": { "name": name, "port": { "number": 80 } } },
+                    }],
+                },
+            }],
+        },
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn modules_are_ordered_and_cycles_detected() {
+    let (server, ingress_monitor) = start_monitoring();
+    server.apply(&module_ingress("shell", "shell", "1.0.0", "checkout,cart"));
+    server.apply(&module_ingress("checkout", "checkout", "1.0.0", "cart"));
+    server.apply(&module_ingress("cart", "cart", "1.0.0", "pricing"));
+    server.apply(&module_ingress("ping", "ping", "1.0.0", "pong"));
+    server.apply(&module_ingress("pong", "pong", "1.0.0", "ping"));
+    eventually("all modules are cataloged", || async {
+        ingress_monitor.dependency_graph().await.modules.len() == 5
+    })
+    .await;
+    let graph = ingress_monitor.dependency_graph().await;
+    assert_eq!(graph.load_order, vec!["cart", "checkout", "shell"]);
+    assert_eq!(graph.cycles, vec![vec!["ping", "pong"]]);
+    let cart = graph.modules.iter().find(|module| module.name == "cart");
+    assert_eq!(cart.unwrap().missing, vec!["pricing"]);
+}
+
+#[tokio::test]
+async fn unmet_and_conflicting_requirements_are_reported() {
+    let (server, ingress_monitor) = start_monitoring();
+    server.apply(&module_ingress("cart", "cart", "1.2.5", ""));
+    server.apply(&module_ingress("cart-next", "cart", "1.4.2", ""));
+    server.apply(&module_ingress(
+        "checkout",
+        "checkout",
+        "1.0.0",
+        "cart@~1.2",
+    ));
+    server.apply(&module_ingress(
+        "wishlist",
+        "wishlist",
+        "1.0.0",
+        "cart@~1.4",
+    ));
+    server.apply(&module_ingress("legacy", "legacy", "1.0.0", "cart@^0.9"));
+    eventually("all modules are cataloged", || async {
+        ingress_monitor.dependency_graph().await.modules.len() == 4
+            && ingress_monitor.get_all().len() == 5
+    })
+    .await;
+    let report = ingress_monitor.compatibility_report().await;
+    assert_eq!(report.unmet.len(), 1);
+    assert_eq!(report.unmet[0].module, "legacy");
+    assert_eq!(report.unmet[0].available, vec!["1.2.5", "1.4.2"]);
+    assert_eq!(report.conflicts.len(), 1);
+    assert_eq!(report.conflicts[0].required, "cart");
+    assert_eq!(
+        report.conflicts[0].ranges.keys().collect::<Vec<_>>(),
+        vec!["checkout", "wishlist"]
+    );
+}
+
+#[tokio::test]
+async fn modules_are_ordered_by_priority() {
+    let (server, ingress_monitor) = start_monitoring();
+    let with_priority = |mut ingress: Ingress, priority: &str| {
+        ingress
+            .metadata
+            .annotations
+            .get_or_insert_with(Default::default)
+            .insert("microfe/priority".to_owned(), priority.to_owned());
+        ingress
+    };
+    server.apply(&module_ingress("cart", "cart", "1.0.0", ""));
+    server.apply(&with_priority(
+        module_ingress("checkout", "checkout", "1.0.0", ""),
+        "10",
+    ));
+    server.apply(&with_priority(
+        module_ingress("wishlist", "wishlist", "1.0.0", ""),
+        "-1",
+    ));
+    eventually("all modules are cataloged", || async {
+        ingress_monitor.declared_modules().await.len() == 3
+    })
+    .await;
+    let names = ingress_monitor
+        .declared_modules()
+        .await
+        .into_iter()
+        .map(|module| module.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["checkout", "cart", "wishlist"]);
+}
@@ -0,0 +1,108 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Entries omitted by the Rhai script are left out of everything derived from the catalogue.
+
+#![cfg(feature = "rhai")]
+
+mod harness;
+
+use actix_web::test::{call_and_read_body_json, init_service, TestRequest};
+use actix_web::App;
+use k8s_openapi::api::networking::v1::Ingress;
+use microfefind::conf::AppConfig;
+use microfefind::ingress_monitor::{IngressMonitor, KubeCluster};
+use microfefind::rest_api;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use self::harness::{eventually, FakeApiServer};
+
+/// Script omitting everything below `/internal`.
+const SCRIPT: &str = r#"!entry.path.starts_with("/internal")"#;
+
+/// Return a labeled `Ingress` at `/{name}` declaring the module `name` with an entry point.
+fn module_ingress(name: &str) -> Ingress {
+    serde_json::from_value(json!({
+        "apiVersion": "networking.k8s.io/v1",
+        "kind": "Ingress",
+        "metadata": {
+            "name": name,
+            "namespace": "default",
+            "labels": { "microfe": "true" },
+            "annotations": {
+                "microfe/name": name,
+                "microfe/entry": "remoteEntry.js",
+            },
+        },
+        "spec": {
+            "rules": [{
+                "host": "shop.example.com",
+                "http": {
+                    "paths": [{
+                        "path": format!("/{name}"),
+                        "pathType": "Prefix",
+                        "backend": { "service": { "name": name, "port": { "number": 80 } } },
+                    }],
+                },
+            }],
+        },
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn omitted_entries_are_missing_from_import_map() {
+    let script_path = std::env::temp_dir().join(format!("microfefind-{}.rhai", std::process::id()));
+    std::fs::write(&script_path, SCRIPT).unwrap();
+    let app_config = Arc::new(
+        AppConfig::try_with_overrides(
+            None,
+            &[(
+                "plugin.script".to_owned(),
+                script_path.to_string_lossy().into_owned(),
+            )],
+        )
+        .unwrap(),
+    );
+    let server = FakeApiServer::new();
+    let cluster = KubeCluster::from_client("test", server.client());
+    let ingress_monitor = IngressMonitor::with_clusters(Arc::clone(&app_config), &[cluster]);
+    server.apply(&module_ingress("shop"));
+    server.apply(&module_ingress("internal"));
+    eventually("both modules are cataloged", || async {
+        ingress_monitor.declared_modules().await.len() == 2
+    })
+    .await;
+    let app = init_service(
+        App::new()
+            .app_data(rest_api::app_data(app_config, Arc::clone(&ingress_monitor)))
+            .configure(rest_api::configure),
+    )
+    .await;
+    let import_map: Value = call_and_read_body_json(
+        &app,
+        TestRequest::get().uri("/api/v1/importmap").to_request(),
+    )
+    .await;
+    let _ = std::fs::remove_file(&script_path);
+    assert_eq!(
+        import_map["imports"]["shop"],
+        "http://shop.example.com/shop/remoteEntry.js"
+    );
+    assert!(import_map["imports"].get("internal").is_none());
+}
@@ -0,0 +1,103 @@
+/*
+    Copyright 2024 MydriaTech AB
+
+    Licensed under the Apache License 2.0 with Free world makers exception
+    1.0.0 (the "License"); you may not use this file except in compliance with
+    the License. You should have obtained a copy of the License with the source
+    or binary distribution in file named
+
+        LICENSE-Apache-2.0-with-FWM-Exception-1.0.0
+
+    Unless required by applicable law or agreed to in writing, software
+    distributed under the License is distributed on an "AS IS" BASIS,
+    WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+    See the License for the specific language governing permissions and
+    limitations under the License.
+*/
+
+//! Replay of recorded watch events through the monitors.
+
+mod harness;
+
+use microfefind::conf::AppConfig;
+use microfefind::ingress_monitor::IngressMonitor;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use self::harness::eventually;
+
+/// Watch of labeled `Ingress`es in the default namespace with the default configuration.
+const INGRESS_WATCH: &str =
+    "/apis/networking.k8s.io/v1/namespaces/default/ingresses?labelSelector=microfe=true&fieldSelector=";
+
+/// Return a labeled `Ingress` named `app` that maps `host` and `path` to the `app` `Service`.
+fn ingress(host: &str, path: &str) -> Value {
+    json!({
+        "apiVersion": "networking.k8s.io/v1",
+        "kind": "Ingress",
+        "metadata": {
+            "name": "app",
+            "namespace": "default",
+            "uid": "uid-app",
+            "labels": { "microfe": "true" },
+        },
+        "spec": {
+            "rules": [{
+                "host": host,
+                "http": {
+                    "paths": [{
+                        "path": path,
+                        "pathType": "Prefix",
+                        "backend": { "service": { "name": "app", "port": { "number": 80 } } },
+                    }],
+                },
+            }],
+        },
+    })
+}
+
+/// Return `true` if the host and path is currently in the catalogue.
+fn is_cataloged(ingress_monitor: &Arc<IngressMonitor>, host: &str, path: &str) -> bool {
+    ingress_monitor
+        .get_all()
+        .iter()
+        .any(|entry| entry.host_and_path() == (host, path))
+}
+
+#[tokio::test]
+async fn recorded_ingress_events_are_replayed_in_order() {
+    let recording = [
+        json!({
+            "time": 1_000,
+            "watch": INGRESS_WATCH,
+            "event": "RESTARTED",
+            "objects": [ingress("app.example.com", "/app")],
+        }),
+        json!({
+            "time": 1_200,
+            "watch": INGRESS_WATCH,
+            "event": "APPLIED",
+            "objects": [ingress("app.example.com", "/moved")],
+        }),
+    ]
+    .iter()
+    .map(|event| event.to_string() + "\n")
+    .collect::<String>();
+    let path = std::env::temp_dir().join(format!(
+        "microfefind-recording-{}.jsonl",
+        std::process::id()
+    ));
+    std::fs::write(&path, recording).unwrap();
+    let overrides = [(
+        "debug.replayevents".to_owned(),
+        path.to_string_lossy().into_owned(),
+    )];
+    let app_config = AppConfig::try_with_overrides(None, &overrides).unwrap();
+    let ingress_monitor = IngressMonitor::new(Arc::new(app_config));
+    eventually("replayed update is applied", || async {
+        is_cataloged(&ingress_monitor, "app.example.com", "/moved")
+            && !is_cataloged(&ingress_monitor, "app.example.com", "/app")
+    })
+    .await;
+    std::fs::remove_file(&path).unwrap();
+}